@@ -3,24 +3,70 @@
 #[macro_use]
 mod macros;
 
+/// Defines `$mod_name::$reg_name`, a 64-bit system register accessed
+/// through mrs/msr with `$reg_literal`, which has no bitfields.
+#[macro_export]
+macro_rules! define_sys_register {
+    ($mod_name:ident, $reg_name:ident, $reg_literal:tt) => {
+        pub mod $mod_name {
+            use tock_registers::interfaces::{Readable, Writeable};
+            pub struct Reg;
+
+            impl Readable for Reg {
+                type T = u64;
+                type R = ();
+
+                sys_coproc_read_raw!(u64, $reg_literal, "x");
+            }
+
+            impl Writeable for Reg {
+                type T = u64;
+                type R = ();
+
+                sys_coproc_write_raw!(u64, $reg_literal, "x");
+            }
+
+            pub const $reg_name: Reg = Reg {};
+        }
+    };
+}
+
 mod cptr_el2;
+pub mod debug;
+mod id_aa64mmfr3_el1;
 mod id_aa64pfr1_el1;
+mod id_aa64smfr0_el1;
 mod id_aa64zfr0_el1;
 mod mdcr_el2;
+mod mecid_a0_el2;
+mod mecidr_el2;
+pub mod mte;
 mod pmcr_el0;
 pub mod pmu;
+mod sctlr2_el2;
+mod smcr_el1;
 mod smcr_el2;
 mod svcr;
+mod tpidr2_el0;
+mod vmecid_p_el2;
 mod zcr_el1;
 mod zcr_el2;
 
 pub use cptr_el2::CPTR_EL2;
+pub use id_aa64mmfr3_el1::ID_AA64MMFR3_EL1;
 pub use id_aa64pfr1_el1::ID_AA64PFR1_SME_EL1;
+pub use id_aa64smfr0_el1::ID_AA64SMFR0_EL1;
 pub use id_aa64zfr0_el1::ID_AA64ZFR0_EL1;
 pub use mdcr_el2::MDCR_EL2;
+pub use mecid_a0_el2::MECID_A0_EL2;
+pub use mecidr_el2::MECIDR_EL2;
 pub use pmcr_el0::PMCR_EL0;
+pub use sctlr2_el2::SCTLR2_EL2;
+pub use smcr_el1::SMCR_EL1;
 pub use smcr_el2::SMCR_EL2;
 pub use svcr::SVCR;
+pub use tpidr2_el0::TPIDR2_EL0;
+pub use vmecid_p_el2::VMECID_P_EL2;
 pub use zcr_el1::ZCR_EL1;
 pub use zcr_el2::ZCR_EL2;
 
@@ -163,6 +209,8 @@ define_iss_id!(ISS_ID_AA64MMFR2_EL1, 3, 0, 0, 7, 2);
 
 define_iss_id!(ISS_ID_AA64ZFR0_EL1, 3, 0, 0, 4, 4);
 
+define_iss_id!(ISS_ID_AA64SMFR0_EL1, 3, 0, 0, 4, 5);
+
 define_iss_id!(ISS_ID_ICC_DIR_EL1, 3, 0, 0xc, 0xb, 1);
 
 define_iss_id!(ISS_ID_ICC_SGI1R_EL1, 3, 0, 0xc, 0xb, 5);
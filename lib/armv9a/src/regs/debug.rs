@@ -0,0 +1,138 @@
+#![allow(unused_imports)]
+#![allow(unused_attributes)]
+
+//! Self-hosted debug registers
+
+use tock_registers::interfaces::{Readable, Writeable};
+
+crate::define_sys_register!(mdscr_el1, MDSCR_EL1, "MDSCR_EL1");
+crate::define_sys_register!(dbgbcr0_el1, DBGBCR0_EL1, "DBGBCR0_EL1");
+crate::define_sys_register!(dbgbcr1_el1, DBGBCR1_EL1, "DBGBCR1_EL1");
+crate::define_sys_register!(dbgbcr2_el1, DBGBCR2_EL1, "DBGBCR2_EL1");
+crate::define_sys_register!(dbgbcr3_el1, DBGBCR3_EL1, "DBGBCR3_EL1");
+crate::define_sys_register!(dbgbcr4_el1, DBGBCR4_EL1, "DBGBCR4_EL1");
+crate::define_sys_register!(dbgbcr5_el1, DBGBCR5_EL1, "DBGBCR5_EL1");
+crate::define_sys_register!(dbgbcr6_el1, DBGBCR6_EL1, "DBGBCR6_EL1");
+crate::define_sys_register!(dbgbcr7_el1, DBGBCR7_EL1, "DBGBCR7_EL1");
+crate::define_sys_register!(dbgbcr8_el1, DBGBCR8_EL1, "DBGBCR8_EL1");
+crate::define_sys_register!(dbgbcr9_el1, DBGBCR9_EL1, "DBGBCR9_EL1");
+crate::define_sys_register!(dbgbcr10_el1, DBGBCR10_EL1, "DBGBCR10_EL1");
+crate::define_sys_register!(dbgbcr11_el1, DBGBCR11_EL1, "DBGBCR11_EL1");
+crate::define_sys_register!(dbgbcr12_el1, DBGBCR12_EL1, "DBGBCR12_EL1");
+crate::define_sys_register!(dbgbcr13_el1, DBGBCR13_EL1, "DBGBCR13_EL1");
+crate::define_sys_register!(dbgbcr14_el1, DBGBCR14_EL1, "DBGBCR14_EL1");
+crate::define_sys_register!(dbgbcr15_el1, DBGBCR15_EL1, "DBGBCR15_EL1");
+crate::define_sys_register!(dbgbvr0_el1, DBGBVR0_EL1, "DBGBVR0_EL1");
+crate::define_sys_register!(dbgbvr1_el1, DBGBVR1_EL1, "DBGBVR1_EL1");
+crate::define_sys_register!(dbgbvr2_el1, DBGBVR2_EL1, "DBGBVR2_EL1");
+crate::define_sys_register!(dbgbvr3_el1, DBGBVR3_EL1, "DBGBVR3_EL1");
+crate::define_sys_register!(dbgbvr4_el1, DBGBVR4_EL1, "DBGBVR4_EL1");
+crate::define_sys_register!(dbgbvr5_el1, DBGBVR5_EL1, "DBGBVR5_EL1");
+crate::define_sys_register!(dbgbvr6_el1, DBGBVR6_EL1, "DBGBVR6_EL1");
+crate::define_sys_register!(dbgbvr7_el1, DBGBVR7_EL1, "DBGBVR7_EL1");
+crate::define_sys_register!(dbgbvr8_el1, DBGBVR8_EL1, "DBGBVR8_EL1");
+crate::define_sys_register!(dbgbvr9_el1, DBGBVR9_EL1, "DBGBVR9_EL1");
+crate::define_sys_register!(dbgbvr10_el1, DBGBVR10_EL1, "DBGBVR10_EL1");
+crate::define_sys_register!(dbgbvr11_el1, DBGBVR11_EL1, "DBGBVR11_EL1");
+crate::define_sys_register!(dbgbvr12_el1, DBGBVR12_EL1, "DBGBVR12_EL1");
+crate::define_sys_register!(dbgbvr13_el1, DBGBVR13_EL1, "DBGBVR13_EL1");
+crate::define_sys_register!(dbgbvr14_el1, DBGBVR14_EL1, "DBGBVR14_EL1");
+crate::define_sys_register!(dbgbvr15_el1, DBGBVR15_EL1, "DBGBVR15_EL1");
+crate::define_sys_register!(dbgwcr0_el1, DBGWCR0_EL1, "DBGWCR0_EL1");
+crate::define_sys_register!(dbgwcr1_el1, DBGWCR1_EL1, "DBGWCR1_EL1");
+crate::define_sys_register!(dbgwcr2_el1, DBGWCR2_EL1, "DBGWCR2_EL1");
+crate::define_sys_register!(dbgwcr3_el1, DBGWCR3_EL1, "DBGWCR3_EL1");
+crate::define_sys_register!(dbgwcr4_el1, DBGWCR4_EL1, "DBGWCR4_EL1");
+crate::define_sys_register!(dbgwcr5_el1, DBGWCR5_EL1, "DBGWCR5_EL1");
+crate::define_sys_register!(dbgwcr6_el1, DBGWCR6_EL1, "DBGWCR6_EL1");
+crate::define_sys_register!(dbgwcr7_el1, DBGWCR7_EL1, "DBGWCR7_EL1");
+crate::define_sys_register!(dbgwcr8_el1, DBGWCR8_EL1, "DBGWCR8_EL1");
+crate::define_sys_register!(dbgwcr9_el1, DBGWCR9_EL1, "DBGWCR9_EL1");
+crate::define_sys_register!(dbgwcr10_el1, DBGWCR10_EL1, "DBGWCR10_EL1");
+crate::define_sys_register!(dbgwcr11_el1, DBGWCR11_EL1, "DBGWCR11_EL1");
+crate::define_sys_register!(dbgwcr12_el1, DBGWCR12_EL1, "DBGWCR12_EL1");
+crate::define_sys_register!(dbgwcr13_el1, DBGWCR13_EL1, "DBGWCR13_EL1");
+crate::define_sys_register!(dbgwcr14_el1, DBGWCR14_EL1, "DBGWCR14_EL1");
+crate::define_sys_register!(dbgwcr15_el1, DBGWCR15_EL1, "DBGWCR15_EL1");
+crate::define_sys_register!(dbgwvr0_el1, DBGWVR0_EL1, "DBGWVR0_EL1");
+crate::define_sys_register!(dbgwvr1_el1, DBGWVR1_EL1, "DBGWVR1_EL1");
+crate::define_sys_register!(dbgwvr2_el1, DBGWVR2_EL1, "DBGWVR2_EL1");
+crate::define_sys_register!(dbgwvr3_el1, DBGWVR3_EL1, "DBGWVR3_EL1");
+crate::define_sys_register!(dbgwvr4_el1, DBGWVR4_EL1, "DBGWVR4_EL1");
+crate::define_sys_register!(dbgwvr5_el1, DBGWVR5_EL1, "DBGWVR5_EL1");
+crate::define_sys_register!(dbgwvr6_el1, DBGWVR6_EL1, "DBGWVR6_EL1");
+crate::define_sys_register!(dbgwvr7_el1, DBGWVR7_EL1, "DBGWVR7_EL1");
+crate::define_sys_register!(dbgwvr8_el1, DBGWVR8_EL1, "DBGWVR8_EL1");
+crate::define_sys_register!(dbgwvr9_el1, DBGWVR9_EL1, "DBGWVR9_EL1");
+crate::define_sys_register!(dbgwvr10_el1, DBGWVR10_EL1, "DBGWVR10_EL1");
+crate::define_sys_register!(dbgwvr11_el1, DBGWVR11_EL1, "DBGWVR11_EL1");
+crate::define_sys_register!(dbgwvr12_el1, DBGWVR12_EL1, "DBGWVR12_EL1");
+crate::define_sys_register!(dbgwvr13_el1, DBGWVR13_EL1, "DBGWVR13_EL1");
+crate::define_sys_register!(dbgwvr14_el1, DBGWVR14_EL1, "DBGWVR14_EL1");
+crate::define_sys_register!(dbgwvr15_el1, DBGWVR15_EL1, "DBGWVR15_EL1");
+
+pub use dbgbcr0_el1::DBGBCR0_EL1;
+pub use dbgbcr10_el1::DBGBCR10_EL1;
+pub use dbgbcr11_el1::DBGBCR11_EL1;
+pub use dbgbcr12_el1::DBGBCR12_EL1;
+pub use dbgbcr13_el1::DBGBCR13_EL1;
+pub use dbgbcr14_el1::DBGBCR14_EL1;
+pub use dbgbcr15_el1::DBGBCR15_EL1;
+pub use dbgbcr1_el1::DBGBCR1_EL1;
+pub use dbgbcr2_el1::DBGBCR2_EL1;
+pub use dbgbcr3_el1::DBGBCR3_EL1;
+pub use dbgbcr4_el1::DBGBCR4_EL1;
+pub use dbgbcr5_el1::DBGBCR5_EL1;
+pub use dbgbcr6_el1::DBGBCR6_EL1;
+pub use dbgbcr7_el1::DBGBCR7_EL1;
+pub use dbgbcr8_el1::DBGBCR8_EL1;
+pub use dbgbcr9_el1::DBGBCR9_EL1;
+pub use dbgbvr0_el1::DBGBVR0_EL1;
+pub use dbgbvr10_el1::DBGBVR10_EL1;
+pub use dbgbvr11_el1::DBGBVR11_EL1;
+pub use dbgbvr12_el1::DBGBVR12_EL1;
+pub use dbgbvr13_el1::DBGBVR13_EL1;
+pub use dbgbvr14_el1::DBGBVR14_EL1;
+pub use dbgbvr15_el1::DBGBVR15_EL1;
+pub use dbgbvr1_el1::DBGBVR1_EL1;
+pub use dbgbvr2_el1::DBGBVR2_EL1;
+pub use dbgbvr3_el1::DBGBVR3_EL1;
+pub use dbgbvr4_el1::DBGBVR4_EL1;
+pub use dbgbvr5_el1::DBGBVR5_EL1;
+pub use dbgbvr6_el1::DBGBVR6_EL1;
+pub use dbgbvr7_el1::DBGBVR7_EL1;
+pub use dbgbvr8_el1::DBGBVR8_EL1;
+pub use dbgbvr9_el1::DBGBVR9_EL1;
+pub use dbgwcr0_el1::DBGWCR0_EL1;
+pub use dbgwcr10_el1::DBGWCR10_EL1;
+pub use dbgwcr11_el1::DBGWCR11_EL1;
+pub use dbgwcr12_el1::DBGWCR12_EL1;
+pub use dbgwcr13_el1::DBGWCR13_EL1;
+pub use dbgwcr14_el1::DBGWCR14_EL1;
+pub use dbgwcr15_el1::DBGWCR15_EL1;
+pub use dbgwcr1_el1::DBGWCR1_EL1;
+pub use dbgwcr2_el1::DBGWCR2_EL1;
+pub use dbgwcr3_el1::DBGWCR3_EL1;
+pub use dbgwcr4_el1::DBGWCR4_EL1;
+pub use dbgwcr5_el1::DBGWCR5_EL1;
+pub use dbgwcr6_el1::DBGWCR6_EL1;
+pub use dbgwcr7_el1::DBGWCR7_EL1;
+pub use dbgwcr8_el1::DBGWCR8_EL1;
+pub use dbgwcr9_el1::DBGWCR9_EL1;
+pub use dbgwvr0_el1::DBGWVR0_EL1;
+pub use dbgwvr10_el1::DBGWVR10_EL1;
+pub use dbgwvr11_el1::DBGWVR11_EL1;
+pub use dbgwvr12_el1::DBGWVR12_EL1;
+pub use dbgwvr13_el1::DBGWVR13_EL1;
+pub use dbgwvr14_el1::DBGWVR14_EL1;
+pub use dbgwvr15_el1::DBGWVR15_EL1;
+pub use dbgwvr1_el1::DBGWVR1_EL1;
+pub use dbgwvr2_el1::DBGWVR2_EL1;
+pub use dbgwvr3_el1::DBGWVR3_EL1;
+pub use dbgwvr4_el1::DBGWVR4_EL1;
+pub use dbgwvr5_el1::DBGWVR5_EL1;
+pub use dbgwvr6_el1::DBGWVR6_EL1;
+pub use dbgwvr7_el1::DBGWVR7_EL1;
+pub use dbgwvr8_el1::DBGWVR8_EL1;
+pub use dbgwvr9_el1::DBGWVR9_EL1;
+pub use mdscr_el1::MDSCR_EL1;
@@ -0,0 +1,24 @@
+//! AArch64 Memory Model Feature Register 3 - EL1
+//!
+//! Provides information about the implemented memory model and memory management support.
+
+use tock_registers::{interfaces::Readable, register_bitfields};
+
+register_bitfields! {u64,
+    pub ID_AA64MMFR3_EL1 [
+        /// Support for the Memory Encryption Contexts (FEAT_MEC).
+        MEC OFFSET(28) NUMBITS(4) [],
+    ]
+}
+
+pub struct Reg;
+
+impl Readable for Reg {
+    type T = u64;
+    type R = ID_AA64MMFR3_EL1::Register;
+
+    //sys_coproc_read_raw!(u64, "ID_AA64MMFR3_EL1", "x");
+    sys_coproc_read_raw!(u64, "S3_0_C0_C7_3", "x");
+}
+
+pub const ID_AA64MMFR3_EL1: Reg = Reg {};
@@ -0,0 +1,26 @@
+//! SME Feature ID Register 0
+//!
+//! Provides information about the implemented features of the SME.
+
+use tock_registers::{interfaces::Readable, register_bitfields};
+
+register_bitfields! {u64,
+    pub ID_AA64SMFR0_EL1 [
+        /// Support for execution of the full A64 instruction set in Streaming SVE mode
+        FA64 OFFSET(63) NUMBITS(1) [],
+        /// Version of the SME instructions (SME2 and later)
+        SMEver OFFSET(56) NUMBITS(4) [],
+    ]
+}
+
+pub struct Reg;
+
+impl Readable for Reg {
+    type T = u64;
+    type R = ID_AA64SMFR0_EL1::Register;
+
+    //sys_coproc_read_raw!(u64, "ID_AA64SMFR0_EL1", "x");
+    sys_coproc_read_raw!(u64, "S3_0_C0_C4_5", "x");
+}
+
+pub const ID_AA64SMFR0_EL1: Reg = Reg {};
@@ -36,7 +36,8 @@ impl Readable for Reg {
     type T = u64;
     type R = ID_AA64ZFR0_EL1::Register;
 
-    sys_coproc_read_raw!(u64, "ID_AA64PFR1_EL1", "x");
+    //sys_coproc_read_raw!(u64, "ID_AA64ZFR0_EL1", "x");
+    sys_coproc_read_raw!(u64, "S3_0_C0_C4_4", "x");
 }
 
 pub const ID_AA64ZFR0_EL1: Reg = Reg {};
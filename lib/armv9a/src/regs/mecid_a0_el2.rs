@@ -0,0 +1,34 @@
+//! Alternate MECID for the EL2 translation regime - EL2
+//!
+//! MECID used for the accesses through the EL2 stage 1 descriptors with AMEC set.
+
+use tock_registers::{
+    interfaces::{Readable, Writeable},
+    register_bitfields,
+};
+
+register_bitfields! {u64,
+    pub MECID_A0_EL2 [
+        MECID OFFSET(0) NUMBITS(16) []
+    ]
+}
+
+pub struct Reg;
+
+impl Readable for Reg {
+    type T = u64;
+    type R = MECID_A0_EL2::Register;
+
+    //sys_coproc_read_raw!(u64, "MECID_A0_EL2", "x");
+    sys_coproc_read_raw!(u64, "S3_4_C10_C8_1", "x");
+}
+
+impl Writeable for Reg {
+    type T = u64;
+    type R = MECID_A0_EL2::Register;
+
+    //sys_coproc_write_raw!(u64, "MECID_A0_EL2", "x");
+    sys_coproc_write_raw!(u64, "S3_4_C10_C8_1", "x");
+}
+
+pub const MECID_A0_EL2: Reg = Reg {};
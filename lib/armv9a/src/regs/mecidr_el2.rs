@@ -0,0 +1,22 @@
+//! MEC Identification Register - EL2
+
+use tock_registers::{interfaces::Readable, register_bitfields};
+
+register_bitfields! {u64,
+    pub MECIDR_EL2 [
+        /// Number of bits of MECID minus 1
+        MECIDWidthm1 OFFSET(0) NUMBITS(4) []
+    ]
+}
+
+pub struct Reg;
+
+impl Readable for Reg {
+    type T = u64;
+    type R = MECIDR_EL2::Register;
+
+    //sys_coproc_read_raw!(u64, "MECIDR_EL2", "x");
+    sys_coproc_read_raw!(u64, "S3_4_C10_C8_7", "x");
+}
+
+pub const MECIDR_EL2: Reg = Reg {};
@@ -0,0 +1,16 @@
+#![allow(unused_imports)]
+#![allow(unused_attributes)]
+
+//! Memory Tagging Extension registers
+
+use tock_registers::interfaces::{Readable, Writeable};
+
+crate::define_sys_register!(tfsr_el1, TFSR_EL1, "S3_0_C5_C6_0");
+crate::define_sys_register!(tfsre0_el1, TFSRE0_EL1, "S3_0_C5_C6_1");
+crate::define_sys_register!(gcr_el1, GCR_EL1, "S3_0_C1_C0_6");
+crate::define_sys_register!(rgsr_el1, RGSR_EL1, "S3_0_C1_C0_5");
+
+pub use gcr_el1::GCR_EL1;
+pub use rgsr_el1::RGSR_EL1;
+pub use tfsr_el1::TFSR_EL1;
+pub use tfsre0_el1::TFSRE0_EL1;
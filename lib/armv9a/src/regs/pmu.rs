@@ -6,26 +6,7 @@ use tock_registers::interfaces::{Readable, Writeable};
 #[macro_export]
 macro_rules! define_pmu_register {
     ($mod_name:ident, $reg_name:ident, $reg_literal:tt) => {
-        pub mod $mod_name {
-            use tock_registers::interfaces::{Readable, Writeable};
-            pub struct Reg;
-
-            impl Readable for Reg {
-                type T = u64;
-                type R = ();
-
-                sys_coproc_read_raw!(u64, $reg_literal, "x");
-            }
-
-            impl Writeable for Reg {
-                type T = u64;
-                type R = ();
-
-                sys_coproc_write_raw!(u64, $reg_literal, "x");
-            }
-
-            pub const $reg_name: Reg = Reg {};
-        }
+        $crate::define_sys_register!($mod_name, $reg_name, $reg_literal);
     };
 }
 
@@ -0,0 +1,33 @@
+//! System Control Register 2 - EL2
+
+use tock_registers::{
+    interfaces::{Readable, Writeable},
+    register_bitfields,
+};
+
+register_bitfields! {u64,
+    pub SCTLR2_EL2 [
+        /// Enables the alternate MECID (MECID_A0_EL2) of the EL2 translation regime
+        EMEC OFFSET(1) NUMBITS(1) []
+    ]
+}
+
+pub struct Reg;
+
+impl Readable for Reg {
+    type T = u64;
+    type R = SCTLR2_EL2::Register;
+
+    //sys_coproc_read_raw!(u64, "SCTLR2_EL2", "x");
+    sys_coproc_read_raw!(u64, "S3_4_C1_C0_3", "x");
+}
+
+impl Writeable for Reg {
+    type T = u64;
+    type R = SCTLR2_EL2::Register;
+
+    //sys_coproc_write_raw!(u64, "SCTLR2_EL2", "x");
+    sys_coproc_write_raw!(u64, "S3_4_C1_C0_3", "x");
+}
+
+pub const SCTLR2_EL2: Reg = Reg {};
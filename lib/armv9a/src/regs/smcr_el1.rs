@@ -0,0 +1,42 @@
+//! SME Control Register - EL1
+
+use tock_registers::{
+    interfaces::{Readable, Writeable},
+    register_bitfields,
+};
+
+register_bitfields! {u64,
+    pub SMCR_EL1 [
+        /// When FEAT_SME_FA64 is implemented:
+        /// Controls whether execution of an A64 instruction is
+        /// considered legal when the PE is in Streaming SVE mode
+        FA64 OFFSET(31) NUMBITS(1) [],
+        /// When FEAT_SME2 is implemented:
+        /// Traps execution of instructions which access ZT0
+        EZT0 OFFSET(30) NUMBITS(1) [],
+        /// Reserved
+        RAZWI   OFFSET(4) NUMBITS(5) [],
+        /// Requested Streaming SVE Vector Length (SVL)
+        LEN OFFSET(0) NUMBITS(4) []
+    ]
+}
+
+pub struct Reg;
+
+impl Readable for Reg {
+    type T = u64;
+    type R = SMCR_EL1::Register;
+
+    //sys_coproc_read_raw!(u64, "SMCR_EL1", "x");
+    sys_coproc_read_raw!(u64, "S3_0_C1_C2_6", "x");
+}
+
+impl Writeable for Reg {
+    type T = u64;
+    type R = SMCR_EL1::Register;
+
+    //sys_coproc_write_raw!(u64, "SMCR_EL1", "x");
+    sys_coproc_write_raw!(u64, "S3_0_C1_C2_6", "x");
+}
+
+pub const SMCR_EL1: Reg = Reg {};
@@ -11,6 +11,9 @@ register_bitfields! {u64,
         /// Controls whether execution of an A64 instruction is
         /// considered legal when the PE is in Streaming SVE mode
         FA64 OFFSET(31) NUMBITS(1) [],
+        /// When FEAT_SME2 is implemented:
+        /// Traps execution of instructions which access ZT0
+        EZT0 OFFSET(30) NUMBITS(1) [],
         /// Reserved
         RAZWI   OFFSET(4) NUMBITS(5) [],
         /// Effective Streaming SVE Vector Length (SVL)
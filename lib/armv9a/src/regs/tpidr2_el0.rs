@@ -0,0 +1,35 @@
+//! EL0 Read/Write Software Thread ID Register 2
+//!
+//! Used by the SME lazy saving scheme of the ZA storage.
+
+use tock_registers::{
+    interfaces::{Readable, Writeable},
+    register_bitfields,
+};
+
+register_bitfields! {u64,
+    pub TPIDR2_EL0 [
+        /// Thread ID
+        ThreadID OFFSET(0) NUMBITS(64) []
+    ]
+}
+
+pub struct Reg;
+
+impl Readable for Reg {
+    type T = u64;
+    type R = TPIDR2_EL0::Register;
+
+    //sys_coproc_read_raw!(u64, "TPIDR2_EL0", "x");
+    sys_coproc_read_raw!(u64, "S3_3_C13_C0_5", "x");
+}
+
+impl Writeable for Reg {
+    type T = u64;
+    type R = TPIDR2_EL0::Register;
+
+    //sys_coproc_write_raw!(u64, "TPIDR2_EL0", "x");
+    sys_coproc_write_raw!(u64, "S3_3_C13_C0_5", "x");
+}
+
+pub const TPIDR2_EL0: Reg = Reg {};
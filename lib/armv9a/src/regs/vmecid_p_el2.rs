@@ -0,0 +1,34 @@
+//! Realm VM Primary MECID Register - EL2
+//!
+//! MECID used for the stage 2 translations of the EL1&0 regime in the Realm state.
+
+use tock_registers::{
+    interfaces::{Readable, Writeable},
+    register_bitfields,
+};
+
+register_bitfields! {u64,
+    pub VMECID_P_EL2 [
+        MECID OFFSET(0) NUMBITS(16) []
+    ]
+}
+
+pub struct Reg;
+
+impl Readable for Reg {
+    type T = u64;
+    type R = VMECID_P_EL2::Register;
+
+    //sys_coproc_read_raw!(u64, "VMECID_P_EL2", "x");
+    sys_coproc_read_raw!(u64, "S3_4_C10_C9_0", "x");
+}
+
+impl Writeable for Reg {
+    type T = u64;
+    type R = VMECID_P_EL2::Register;
+
+    //sys_coproc_write_raw!(u64, "VMECID_P_EL2", "x");
+    sys_coproc_write_raw!(u64, "S3_4_C10_C9_0", "x");
+}
+
+pub const VMECID_P_EL2: Reg = Reg {};
@@ -0,0 +1,93 @@
+use super::{
+    Hasher, Measurement, MeasurementError, RealmParams, RecParams, MEASURE_DESC_TYPE_DATA,
+    MEASURE_DESC_TYPE_REC, MEASURE_DESC_TYPE_RIPAS, RMI_MEASURE_CONTENT,
+};
+
+/// Extends a measurement slot of a realm, which is the RIM
+/// except for `extend_measurement`.
+pub struct HashContext<'a> {
+    hasher: Hasher,
+    measurement: &'a mut Measurement,
+}
+
+impl<'a> HashContext<'a> {
+    pub fn new(hash_algo: u8, measurement: &'a mut Measurement) -> Result<Self, MeasurementError> {
+        Ok(Self {
+            hasher: Hasher::from_hash_algo(hash_algo)?,
+            measurement,
+        })
+    }
+
+    pub fn measure_realm_create(&mut self, params: &RealmParams) -> Result<(), MeasurementError> {
+        self.hasher.hash_object_into(params, &mut *self.measurement)
+    }
+
+    pub fn extend_measurement(&mut self, buffer: &[u8]) -> Result<(), MeasurementError> {
+        let old_value = *self.measurement;
+
+        self.hasher.hash_fields_into(&mut *self.measurement, |h| {
+            h.hash(&old_value.as_ref()[0..self.hasher.output_size()]);
+            h.hash(buffer);
+        })
+    }
+
+    /// Allocation tags of the granule (FEAT_MTE) are not measured.
+    /// They are always zero at DATA_CREATE, as tags are wiped along with
+    /// the contents on delegation and the host can't copy tags into it.
+    /// Whether the realm may use tags is covered by the realm flags.
+    pub fn measure_data_granule(
+        &mut self,
+        data: &[u8],
+        ipa: usize,
+        flags: usize,
+    ) -> Result<(), MeasurementError> {
+        let mut data_measurement = Measurement::empty();
+
+        if flags == RMI_MEASURE_CONTENT {
+            self.hasher.hash_fields_into(&mut data_measurement, |h| {
+                h.hash(data);
+            })?;
+        }
+
+        let oldrim = *self.measurement;
+        self.hasher.hash_fields_into(&mut *self.measurement, |h| {
+            h.hash_u8(MEASURE_DESC_TYPE_DATA); // desc type
+            h.hash([0u8; 7]); // padding
+            h.hash_u64(0x100); // desc struct size
+            h.hash(oldrim); // old RIM value
+            h.hash_usize(ipa); // ipa
+            h.hash_usize(flags); // flags
+            h.hash(data_measurement); // data granule hash
+            h.hash([0u8; 0x100 - 0xa0]); // padding
+        })
+    }
+
+    pub fn measure_rec_params(&mut self, params: &RecParams) -> Result<(), MeasurementError> {
+        let mut params_measurement = Measurement::empty();
+        self.hasher
+            .hash_object_into(params, &mut params_measurement)?;
+
+        let oldrim = *self.measurement;
+        self.hasher.hash_fields_into(&mut *self.measurement, |h| {
+            h.hash_u8(MEASURE_DESC_TYPE_REC); // desc type
+            h.hash([0u8; 7]); // padding
+            h.hash_u64(0x100); // desc struct size
+            h.hash(oldrim); // old RIM value
+            h.hash(params_measurement); // REC params hash
+            h.hash([0u8; 0x100 - 0x90]); // padding
+        })
+    }
+
+    pub fn measure_ripas_granule(&mut self, base: u64, top: u64) -> Result<(), MeasurementError> {
+        let oldrim = *self.measurement;
+        self.hasher.hash_fields_into(&mut *self.measurement, |h| {
+            h.hash_u8(MEASURE_DESC_TYPE_RIPAS); // desc type
+            h.hash([0u8; 7]); // padding
+            h.hash_u64(0x100); // desc struct size
+            h.hash(oldrim); // old RIM value
+            h.hash_u64(base); // ipa
+            h.hash_u64(top); // level
+            h.hash([0u8; 0x100 - 0x60]); // padding to 0x100 size
+        })
+    }
+}
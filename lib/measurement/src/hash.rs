@@ -1,11 +1,8 @@
 use alloc::boxed::Box;
 use sha2::Digest;
-use sha2::{digest::DynDigest, Sha256, Sha512};
+use sha2::{digest::DynDigest, Sha256, Sha384, Sha512};
 
-use crate::{
-    measurement::MeasurementError,
-    rmi::{HASH_ALGO_SHA256, HASH_ALGO_SHA512},
-};
+use crate::{MeasurementError, HASH_ALGO_SHA256, HASH_ALGO_SHA384, HASH_ALGO_SHA512};
 
 pub struct HashWrapper {
     pub hash_func: Box<dyn DynDigest>,
@@ -59,12 +56,14 @@ impl Hasher {
         let factory: Box<dyn Fn() -> Box<dyn DynDigest>> = match hash_algo {
             HASH_ALGO_SHA256 => Box::new(|| Box::new(Sha256::new())),
             HASH_ALGO_SHA512 => Box::new(|| Box::new(Sha512::new())),
+            HASH_ALGO_SHA384 => Box::new(|| Box::new(Sha384::new())),
             _ => return Err(MeasurementError::InvalidHashAlgorithmValue(hash_algo)),
         };
 
         let block_size = match hash_algo {
             HASH_ALGO_SHA256 => <Sha256 as Digest>::output_size(),
             HASH_ALGO_SHA512 => <Sha512 as Digest>::output_size(),
+            HASH_ALGO_SHA384 => <Sha384 as Digest>::output_size(),
             _ => return Err(MeasurementError::InvalidHashAlgorithmValue(hash_algo)),
         };
 
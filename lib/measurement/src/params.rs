@@ -0,0 +1,136 @@
+use super::{Hashable, Hasher, MeasurementError};
+
+use rmm_spec::rmi::realm::Params as RmiRealmParams;
+use rmm_spec::rmi::rec::params::Params as RmiRecParams;
+
+/// The measured fields of RmiRealmParams.
+/// The others are hashed as zero, along with the padding up to a granule.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RealmParams {
+    pub flags: u64,
+    pub s2sz: u8,
+    pub sve_vl: u8,
+    pub num_bps: u8,
+    pub num_wps: u8,
+    pub pmu_num_ctrs: u8,
+    pub hash_algo: u8,
+    pub num_aux_planes: u8,
+    pub mec_policy: u8,
+    pub sme_vl: u8,
+}
+
+impl From<&RmiRealmParams> for RealmParams {
+    fn from(params: &RmiRealmParams) -> Self {
+        Self {
+            flags: params.flags,
+            s2sz: params.s2sz,
+            sve_vl: params.sve_vl,
+            num_bps: params.num_bps,
+            num_wps: params.num_wps,
+            pmu_num_ctrs: params.pmu_num_ctrs,
+            hash_algo: params.hash_algo,
+            num_aux_planes: params.num_aux_planes,
+            mec_policy: params.mec_policy,
+            sme_vl: params.sme_vl,
+        }
+    }
+}
+
+impl Hashable for RealmParams {
+    fn hash(&self, hasher: &Hasher, out: &mut [u8]) -> Result<(), MeasurementError> {
+        hasher.hash_fields_into(out, |alg| {
+            alg.hash_u64(self.flags);
+            // From 0x8 to 0x48, each in an 8-byte slot
+            for field in [
+                self.s2sz,
+                self.sve_vl,
+                self.num_bps,
+                self.num_wps,
+                self.pmu_num_ctrs,
+                self.hash_algo,
+                self.num_aux_planes,
+                self.mec_policy,
+                self.sme_vl,
+            ] {
+                alg.hash_u8(field);
+                alg.hash([0u8; 7]);
+            }
+            // rpv, vmid, rtt_base, rtt_level_start, rtt_num_start,
+            // aux_vmid and aux_rtt_base are not used
+            alg.hash([0u8; 0x1000 - 0x50]);
+        })
+    }
+}
+
+/// The measured fields of RmiRecParams.
+/// The others are hashed as zero, along with the padding up to a granule.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RecParams {
+    pub flags: u64,
+    pub pc: u64,
+    pub gprs: [u64; 8],
+}
+
+impl From<&RmiRecParams> for RecParams {
+    fn from(params: &RmiRecParams) -> Self {
+        Self {
+            flags: params.flags,
+            pc: params.pc,
+            gprs: params.gprs,
+        }
+    }
+}
+
+impl Hashable for RecParams {
+    fn hash(&self, hasher: &Hasher, out: &mut [u8]) -> Result<(), MeasurementError> {
+        hasher.hash_fields_into(out, |h| {
+            h.hash_u64(self.flags);
+            h.hash([0u8; 0x200 - 0x8]); // mpidr is not used
+            h.hash_u64(self.pc);
+            h.hash([0u8; 0x300 - 0x208]);
+            h.hash_u64_array(self.gprs.as_slice());
+            h.hash([0u8; 0x1000 - 0x340]); // num_aux and aux are not used
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{HASH_ALGO_SHA256, MEASUREMENTS_SLOT_MAX_SIZE};
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn measured_params_layout() {
+        let hasher = Hasher::from_hash_algo(HASH_ALGO_SHA256).unwrap();
+        let mut out = [0u8; MEASUREMENTS_SLOT_MAX_SIZE];
+
+        let params = RealmParams {
+            flags: 0x4,
+            s2sz: 40,
+            hash_algo: HASH_ALGO_SHA256,
+            sme_vl: 3,
+            ..Default::default()
+        };
+        let mut granule = [0u8; 0x1000];
+        granule[0x0] = 0x4;
+        granule[0x8] = 40;
+        granule[0x48] = 3;
+        params.hash(&hasher, &mut out).unwrap();
+        assert_eq!(&out[..32], &Sha256::digest(granule)[..]);
+
+        let params = RecParams {
+            flags: 0x1,
+            pc: 0x8000_0000,
+            gprs: [0x11; 8],
+        };
+        let mut granule = [0u8; 0x1000];
+        granule[0x0] = 0x1;
+        granule[0x200..0x208].copy_from_slice(&0x8000_0000u64.to_le_bytes());
+        for i in 0..8 {
+            granule[0x300 + i * 8] = 0x11;
+        }
+        params.hash(&hasher, &mut out).unwrap();
+        assert_eq!(&out[..32], &Sha256::digest(granule)[..]);
+    }
+}
@@ -0,0 +1,11 @@
+//! The RMI and RSI ABI of the RMM: command IDs, return codes and the
+//! structures exchanged through memory, shared by the RMM, the realms,
+//! the SDK and the host tools so that they all agree on the layout.
+#![no_std]
+#![warn(rust_2018_idioms)]
+
+#[macro_use]
+mod r#macro;
+
+pub mod rmi;
+pub mod rsi;
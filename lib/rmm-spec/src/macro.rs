@@ -0,0 +1,18 @@
+// TODO: Expands to cover args, ret
+macro_rules! define_interface {
+    (command {$($variant:ident = $val:expr),*,}) => {
+        $(pub const $variant: usize = $val;)*
+        pub fn to_str(code: usize) -> &'static str {
+            match code {
+                $($variant => stringify!($variant)),*,
+                _ => "UNDEFINED"
+            }
+        }
+    };
+}
+
+macro_rules! const_assert_size {
+    ($struct:ty, $size:expr) => {
+        const _: () = assert!(core::mem::size_of::<$struct>() == $size);
+    };
+}
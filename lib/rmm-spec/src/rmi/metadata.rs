@@ -0,0 +1,298 @@
+use crate::rmi::RmiStatus;
+
+use core::ffi::CStr;
+use core::fmt;
+
+// v1 is signed by the key embedded in the metadata itself.
+// v2 also carries the certificate chain of the key, rooted in the vendor key.
+pub const FMT_VERSION_1: usize = 1;
+pub const FMT_VERSION_2: usize = 2;
+pub const REALM_ID_SIZE: usize = 128;
+// The size of the RIM slot, which fits SHA-512
+pub const RIM_MAX_SIZE: usize = 64;
+pub const P384_PUBLIC_KEY_SIZE: usize = 96;
+pub const P384_SIGNATURE_SIZE: usize = P384_PUBLIC_KEY_SIZE;
+
+/// The maximum number of certificates in the signer chain of v2 metadata
+pub const MAX_CHAIN_LEN: usize = 3;
+// The maximum size of a certificate, which is a tagged COSE_Sign1 whose payload
+// is the COSE_Key of the subject, signed with ES384 by the issuer.
+pub const CERT_MAX_SIZE: usize = 512;
+
+pub const METADATA_HASH_SHA_256: usize = 0x01;
+pub const METADATA_HASH_SHA_512: usize = 0x02;
+pub const METADATA_HASH_SHA_384: usize = 0x03;
+
+const REALM_METADATA_HEADER_SIZE: usize = 0x150;
+#[allow(dead_code)]
+const REALM_METADATA_SIGNED_SIZE: usize = 0x1B0;
+const REALM_METADATA_UNUSED_SIZE: usize = 0x830;
+#[allow(dead_code)]
+const REALM_METADATA_SIZE: usize = 0x1000;
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct MetadataCert {
+    len: usize,
+    cose_sign1: [u8; CERT_MAX_SIZE],
+}
+
+impl MetadataCert {
+    /// Returns the tagged COSE_Sign1 of the certificate
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        self.cose_sign1.get(..self.len)
+    }
+}
+
+/// The realm metadata, which the host passes with RMI_ISLET_REALM_SET_METADATA
+/// and the signer tools create.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct IsletRealmMetadata {
+    fmt_version: usize,
+    realm_id: [u8; REALM_ID_SIZE],
+    rim: [u8; RIM_MAX_SIZE],
+    hash_algo: usize,
+    svn: usize,
+    version_major: usize,
+    version_minor: usize,
+    version_patch: usize,
+    public_key: [u8; P384_PUBLIC_KEY_SIZE],
+    signature: [u8; P384_SIGNATURE_SIZE],
+    // v2 only, chain[0] is issued by the vendor key and
+    // the subject of the last one is `public_key`
+    chain_len: usize,
+    chain: [MetadataCert; MAX_CHAIN_LEN],
+    _unused: [u8; REALM_METADATA_UNUSED_SIZE],
+}
+
+const _: () = assert!(core::mem::size_of::<IsletRealmMetadata>() == REALM_METADATA_SIZE);
+const _: () = assert!(core::mem::size_of::<IsletRealmMetadata>() >= REALM_METADATA_SIGNED_SIZE);
+
+const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, fmt_version) == 0x00);
+const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, realm_id) == 0x08);
+const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, rim) == 0x88);
+const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, hash_algo) == 0xc8);
+const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, svn) == 0xd0);
+const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, version_major) == 0xd8);
+const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, version_minor) == 0xe0);
+const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, version_patch) == 0xe8);
+const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, public_key) == 0xf0);
+const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, signature) == 0x150);
+const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, chain_len) == 0x1b0);
+const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, chain) == 0x1b8);
+
+impl IsletRealmMetadata {
+    pub fn new(
+        realm_id: &str,
+        rim: &[u8],
+        hash_algo: usize,
+        svn: usize,
+        version: (usize, usize, usize),
+    ) -> Result<Self, RmiStatus> {
+        // Keep the terminating NUL in realm_id
+        if realm_id.len() >= REALM_ID_SIZE || rim.len() > RIM_MAX_SIZE {
+            return Err(RmiStatus::ErrorInput);
+        }
+
+        let mut metadata: Self = unsafe { core::mem::zeroed() };
+        metadata.fmt_version = FMT_VERSION_1;
+        metadata.realm_id[..realm_id.len()].copy_from_slice(realm_id.as_bytes());
+        metadata.rim[..rim.len()].copy_from_slice(rim);
+        metadata.hash_algo = hash_algo;
+        metadata.svn = svn;
+        (
+            metadata.version_major,
+            metadata.version_minor,
+            metadata.version_patch,
+        ) = version;
+        metadata.validate()?;
+        Ok(metadata)
+    }
+
+    /// Sets the signing key and its chain of COSE_Sign1 certificates,
+    /// which makes the metadata v2 if the chain is not empty.
+    /// The signer fills them in before signing `signed_data()`.
+    pub fn set_signer(
+        &mut self,
+        public_key: &[u8; P384_PUBLIC_KEY_SIZE],
+        chain: &[&[u8]],
+    ) -> Result<(), RmiStatus> {
+        if chain.len() > MAX_CHAIN_LEN || chain.iter().any(|c| c.len() > CERT_MAX_SIZE) {
+            return Err(RmiStatus::ErrorInput);
+        }
+
+        self.public_key = *public_key;
+        self.fmt_version = if chain.is_empty() {
+            FMT_VERSION_1
+        } else {
+            FMT_VERSION_2
+        };
+        self.chain_len = chain.len();
+        for (dst, src) in self.chain.iter_mut().zip(chain) {
+            dst.len = src.len();
+            dst.cose_sign1 = [0; CERT_MAX_SIZE];
+            dst.cose_sign1[..src.len()].copy_from_slice(src);
+        }
+        Ok(())
+    }
+
+    /// Returns the header which the signature covers.
+    pub fn signed_data(&self) -> &[u8] {
+        &self.as_bytes()[..REALM_METADATA_HEADER_SIZE]
+    }
+
+    pub fn set_signature(&mut self, signature: &[u8; P384_SIGNATURE_SIZE]) {
+        self.signature = *signature;
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe {
+            core::slice::from_raw_parts(
+                (self as *const Self) as *const u8,
+                core::mem::size_of::<Self>(),
+            )
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != core::mem::size_of::<Self>() {
+            return None;
+        }
+        // All the fields are plain integers and bytes
+        Some(unsafe { core::ptr::read_unaligned(bytes.as_ptr() as *const Self) })
+    }
+
+    /// Checks the fields other than the signature and its chain
+    pub fn validate(&self) -> Result<(), RmiStatus> {
+        if ![FMT_VERSION_1, FMT_VERSION_2].contains(&self.fmt_version) {
+            return Err(RmiStatus::ErrorInput);
+        }
+
+        if self.svn == 0 {
+            return Err(RmiStatus::ErrorInput);
+        }
+
+        if ![
+            METADATA_HASH_SHA_256,
+            METADATA_HASH_SHA_384,
+            METADATA_HASH_SHA_512,
+        ]
+        .contains(&self.hash_algo)
+        {
+            return Err(RmiStatus::ErrorInput);
+        }
+
+        let is_printable_ascii = |&c| c >= b' ' && c <= b'~';
+
+        if !self
+            .realm_id
+            .iter()
+            .take_while(|&c| *c != b'\0')
+            .all(is_printable_ascii)
+        {
+            return Err(RmiStatus::ErrorInput);
+        }
+
+        Ok(())
+    }
+
+    pub fn fmt_version(&self) -> usize {
+        self.fmt_version
+    }
+
+    pub fn realm_id(&self) -> &[u8; REALM_ID_SIZE] {
+        &self.realm_id
+    }
+
+    pub fn realm_id_as_str(&self) -> Option<&str> {
+        let Ok(cstr) = CStr::from_bytes_until_nul(&self.realm_id) else {
+            return None;
+        };
+        let Ok(s) = cstr.to_str() else {
+            return None;
+        };
+        Some(s)
+    }
+
+    pub fn rim(&self) -> &[u8; RIM_MAX_SIZE] {
+        &self.rim
+    }
+
+    pub fn hash_algo(&self) -> usize {
+        self.hash_algo
+    }
+
+    pub fn svn(&self) -> usize {
+        self.svn
+    }
+
+    pub fn version(&self) -> (usize, usize, usize) {
+        (self.version_major, self.version_minor, self.version_patch)
+    }
+
+    pub fn public_key(&self) -> &[u8; P384_PUBLIC_KEY_SIZE] {
+        &self.public_key
+    }
+
+    pub fn signature(&self) -> &[u8; P384_SIGNATURE_SIZE] {
+        &self.signature
+    }
+
+    /// Returns the signer chain of v2 metadata, which is empty for v1.
+    /// Returns `None` if its length is out of range.
+    pub fn chain(&self) -> Option<&[MetadataCert]> {
+        self.chain.get(..self.chain_len)
+    }
+}
+
+// Prints bytes in lowercase hex, without allocating
+struct Hex<'a>(&'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+impl fmt::Display for IsletRealmMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "fmt_version: {:#010x}", self.fmt_version)?;
+        writeln!(
+            f,
+            "realm_id: {}",
+            self.realm_id_as_str().unwrap_or("INVALID REALM ID")
+        )?;
+        writeln!(f, "rim: {}", Hex(&self.rim))?;
+        writeln!(f, "hash_algo: {:#010x}", self.hash_algo)?;
+        writeln!(f, "svn: {:#010x}", self.svn)?;
+        writeln!(f, "version_major: {:#010x}", self.version_major)?;
+        writeln!(f, "version_minor: {:#010x}", self.version_minor)?;
+        writeln!(f, "version_patch: {:#010x}", self.version_patch)?;
+        writeln!(f, "public_key: {}", Hex(&self.public_key))?;
+        write!(f, "signature: {}", Hex(&self.signature))?;
+        if self.fmt_version == FMT_VERSION_2 {
+            write!(f, "\nchain_len: {}", self.chain_len)?;
+        }
+        Ok(())
+    }
+}
+
+// The RMM keeps the metadata in a granule
+impl vmsa::guard::Content for IsletRealmMetadata {}
+
+impl safe_abstraction::raw_ptr::RawPtr for IsletRealmMetadata {}
+
+impl safe_abstraction::raw_ptr::SafetyChecked for IsletRealmMetadata {}
+
+impl safe_abstraction::raw_ptr::SafetyAssured for IsletRealmMetadata {
+    fn is_initialized(&self) -> bool {
+        // All the fields are plain integers and bytes,
+        // whose values are checked by `validate()` and the signature.
+        true
+    }
+
+    fn verify_ownership(&self) -> bool {
+        true
+    }
+}
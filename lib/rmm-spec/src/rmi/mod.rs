@@ -0,0 +1,114 @@
+pub mod metadata;
+pub mod realm;
+pub mod rec;
+
+define_interface! {
+    command {
+         VERSION                = 0xc400_0150,
+         GRANULE_DELEGATE       = 0xc400_0151,
+         GRANULE_UNDELEGATE     = 0xc400_0152,
+         DATA_CREATE            = 0xc400_0153,
+         DATA_CREATE_UNKNOWN    = 0xc400_0154,
+         DATA_DESTROY           = 0xc400_0155,
+         REALM_ACTIVATE         = 0xc400_0157,
+         REALM_CREATE           = 0xc400_0158,
+         REALM_DESTROY          = 0xc400_0159,
+         REC_CREATE             = 0xc400_015a,
+         REC_DESTROY            = 0xc400_015b,
+         REC_ENTER              = 0xc400_015c,
+         RTT_CREATE             = 0xc400_015d,
+         RTT_DESTROY            = 0xc400_015e,
+         RTT_MAP_UNPROTECTED    = 0xc400_015f,
+         RTT_UNMAP_UNPROTECTED  = 0xc400_0162,
+         RTT_READ_ENTRY         = 0xc400_0161,
+         PSCI_COMPLETE          = 0xc400_0164,
+         FEATURES               = 0xc400_0165,
+         RTT_FOLD               = 0xc400_0166,
+         REC_AUX_COUNT          = 0xc400_0167,
+         RTT_INIT_RIPAS         = 0xc400_0168,
+         RTT_SET_RIPAS          = 0xc400_0169,
+         RTT_AUX_CREATE         = 0xc400_017d,
+         RTT_AUX_DESTROY        = 0xc400_017e,
+         RTT_AUX_MAP_PROTECTED  = 0xc400_0180,
+         RTT_AUX_UNMAP_PROTECTED = 0xc400_0183,
+         PDEV_AUX_COUNT         = 0xc400_0156,
+         DEV_MEM_MAP            = 0xc400_0172,
+         DEV_MEM_UNMAP          = 0xc400_0173,
+         PDEV_ABORT             = 0xc400_0174,
+         PDEV_COMMUNICATE       = 0xc400_0175,
+         PDEV_CREATE            = 0xc400_0176,
+         PDEV_DESTROY           = 0xc400_0177,
+         PDEV_GET_STATE         = 0xc400_0178,
+         PDEV_IDE_RESET         = 0xc400_0179,
+         PDEV_NOTIFY            = 0xc400_017a,
+         PDEV_SET_PUBKEY        = 0xc400_017b,
+         PDEV_STOP              = 0xc400_017c,
+         VDEV_ABORT             = 0xc400_0185,
+         VDEV_COMMUNICATE       = 0xc400_0186,
+         VDEV_CREATE            = 0xc400_0187,
+         VDEV_DESTROY           = 0xc400_0188,
+         VDEV_GET_STATE         = 0xc400_0189,
+         VDEV_STOP              = 0xc400_018a,
+         // vendor calls
+         ISLET_REALM_SET_METADATA = 0xc700_0150,
+         ISLET_STATS_READ       = 0xc700_0151,
+         ISLET_STATS_RESET      = 0xc700_0152,
+         ISLET_TRACE_READ       = 0xc700_0153,
+         ISLET_LOG_SET_FILTER   = 0xc700_0154,
+         ISLET_LOG_SET_SINKS    = 0xc700_0155,
+         ISLET_LOG_DRAIN        = 0xc700_0156,
+         ISLET_REPLAY_READ      = 0xc700_0157,
+         ISLET_REPLAY_RESET     = 0xc700_0158,
+    }
+}
+
+pub const REQ_COMPLETE: usize = 0xc400_018f;
+
+pub const ABI_MAJOR_VERSION: usize = 1;
+pub const ABI_MINOR_VERSION: usize = 0;
+
+// B3.4.1 RmiCommandReturnCode type, in the low 8 bits of X0.
+// The upper bits carry the index of some errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(usize)]
+pub enum RmiStatus {
+    Success = 0,
+    ErrorInput = 1,
+    ErrorRealm = 2,
+    ErrorRec = 3,
+    ErrorRtt = 4,
+    ErrorInUse = 5,
+    ErrorCount = 6,
+}
+
+pub const SUCCESS: usize = RmiStatus::Success as usize;
+pub const ERROR_INPUT: usize = RmiStatus::ErrorInput as usize;
+pub const ERROR_REC: usize = RmiStatus::ErrorRec as usize;
+pub const ERROR_IN_USE: usize = RmiStatus::ErrorInUse as usize;
+
+pub const MEC_POLICY_SHARED: u8 = 0;
+pub const MEC_POLICY_PRIVATE: u8 = 1;
+
+pub const PMU_OVERFLOW_NOT_ACTIVE: u8 = 0;
+pub const PMU_OVERFLOW_ACTIVE: u8 = 1;
+
+// RmiRttEntryState represents the state of an RTTE
+pub mod rtt_entry_state {
+    pub const RMI_UNASSIGNED: usize = 0;
+    pub const RMI_ASSIGNED: usize = 1;
+    pub const RMI_TABLE: usize = 2;
+    pub const RMI_ASSIGNED_DEV: usize = 3;
+}
+
+pub const MAX_REC_AUX_GRANULES: usize = 16;
+
+// The number of auxiliary planes a Realm can have (RMM 1.1, Planes)
+pub const MAX_AUX_PLANES: usize = 3;
+
+pub const EXIT_SYNC: u8 = 0;
+pub const EXIT_IRQ: u8 = 1;
+pub const EXIT_FIQ: u8 = 2;
+pub const EXIT_PSCI: u8 = 3;
+pub const EXIT_RIPAS_CHANGE: u8 = 4;
+pub const EXIT_HOST_CALL: u8 = 5;
+pub const EXIT_SERROR: u8 = 6;
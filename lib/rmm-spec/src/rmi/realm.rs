@@ -0,0 +1,144 @@
+use super::MAX_AUX_PLANES;
+
+use armv9a::{define_bitfield, define_bits, define_mask};
+use autopadding::*;
+
+pub const RPV_SIZE: usize = 64;
+
+define_bits!(
+    RmiRealmFlags,
+    Lpa2[0 - 0],
+    Sve[1 - 1],
+    Pmu[2 - 2],
+    Da[3 - 3],
+    Sme[4 - 4],
+    Mte[5 - 5],
+    Reserved[63 - 6]
+);
+
+// RmiRealmParams, passed by the Host on REALM_CREATE.
+pad_struct_and_impl_default!(
+pub struct Params {
+    0x0    pub flags: u64,
+    0x8    pub s2sz: u8,
+    0x10   pub sve_vl: u8,
+    0x18   pub num_bps: u8,
+    0x20   pub num_wps: u8,
+    0x28   pub pmu_num_ctrs: u8,
+    0x30   pub hash_algo: u8,
+    0x38   pub num_aux_planes: u8,
+    0x40   pub mec_policy: u8,
+    0x48   pub sme_vl: u8,
+    0x400  pub rpv: [u8; RPV_SIZE],
+    0x800  pub vmid: u16,
+    0x808  pub rtt_base: u64,
+    0x810  pub rtt_level_start: i64,
+    0x818  pub rtt_num_start: u32,
+    0x900  pub aux_vmid: [u16; MAX_AUX_PLANES],
+    0x980  pub aux_rtt_base: [u64; MAX_AUX_PLANES],
+    0x1000 => @END,
+}
+);
+
+const_assert_size!(Params, 0x1000);
+const SUPPORTED: u64 = 1;
+
+impl core::fmt::Debug for Params {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Params")
+            .field(
+                "flags",
+                &format_args!(
+                    "lpa2: {:?} sve: {:?} pmu: {:?} da: {:?} sme: {:?} mte: {:?}",
+                    RmiRealmFlags::new(self.flags).get_masked_value(RmiRealmFlags::Lpa2),
+                    RmiRealmFlags::new(self.flags).get_masked_value(RmiRealmFlags::Sve),
+                    RmiRealmFlags::new(self.flags).get_masked_value(RmiRealmFlags::Pmu),
+                    RmiRealmFlags::new(self.flags).get_masked_value(RmiRealmFlags::Da),
+                    RmiRealmFlags::new(self.flags).get_masked_value(RmiRealmFlags::Sme),
+                    RmiRealmFlags::new(self.flags).get_masked_value(RmiRealmFlags::Mte)
+                ),
+            )
+            .field("s2sz", &self.s2sz)
+            .field("sve_vl", &self.sve_vl)
+            .field("num_bps", &self.num_bps)
+            .field("num_wps", &self.num_wps)
+            .field("pmu_num_ctrs", &self.pmu_num_ctrs)
+            .field("hash_algo", &self.hash_algo)
+            .field("num_aux_planes", &self.num_aux_planes)
+            .field("mec_policy", &self.mec_policy)
+            .field("sme_vl", &self.sme_vl)
+            .field("rpv", &self.rpv)
+            .field("vmid", &self.vmid)
+            .field("rtt_base", &format_args!("{:#X}", &self.rtt_base))
+            .field("rtt_level_start", &self.rtt_level_start)
+            .field("rtt_num_start", &self.rtt_num_start)
+            .field("aux_vmid", &self.aux_vmid)
+            .field("aux_rtt_base", &format_args!("{:#X?}", &self.aux_rtt_base))
+            .finish()
+    }
+}
+
+impl Params {
+    pub fn ipa_bits(&self) -> usize {
+        self.s2sz as usize
+    }
+
+    pub fn lpa2(&self) -> bool {
+        let flags = RmiRealmFlags::new(self.flags);
+        flags.get_masked_value(RmiRealmFlags::Lpa2) == SUPPORTED
+    }
+
+    pub fn sve_en(&self) -> bool {
+        let flags = RmiRealmFlags::new(self.flags);
+        flags.get_masked_value(RmiRealmFlags::Sve) == SUPPORTED
+    }
+
+    pub fn pmu_en(&self) -> bool {
+        let flags = RmiRealmFlags::new(self.flags);
+        flags.get_masked_value(RmiRealmFlags::Pmu) == SUPPORTED
+    }
+
+    pub fn da_en(&self) -> bool {
+        let flags = RmiRealmFlags::new(self.flags);
+        flags.get_masked_value(RmiRealmFlags::Da) == SUPPORTED
+    }
+
+    pub fn sme_en(&self) -> bool {
+        let flags = RmiRealmFlags::new(self.flags);
+        flags.get_masked_value(RmiRealmFlags::Sme) == SUPPORTED
+    }
+
+    pub fn mte_en(&self) -> bool {
+        let flags = RmiRealmFlags::new(self.flags);
+        flags.get_masked_value(RmiRealmFlags::Mte) == SUPPORTED
+    }
+
+    pub fn num_aux_planes(&self) -> usize {
+        self.num_aux_planes as usize
+    }
+}
+
+impl safe_abstraction::raw_ptr::RawPtr for Params {}
+
+impl safe_abstraction::raw_ptr::SafetyChecked for Params {}
+
+impl safe_abstraction::raw_ptr::SafetyAssured for Params {
+    fn is_initialized(&self) -> bool {
+        // Given the fact that this memory is initialized by the Host,
+        // it's not possible to unequivocally guarantee
+        // that the values have been initialized from the perspective of the RMM.
+        // However, any values, whether correctly initialized or not, will undergo
+        // verification during the Measurement phase.
+        // Consequently, this function returns `true`.
+        true
+    }
+
+    fn verify_ownership(&self) -> bool {
+        // This memory has permissions from the Host's perspective,
+        // which inherently implies that exclusive ownership cannot be guaranteed by the RMM alone.
+        // However, since the RMM only performs read operations and any incorrect values will be
+        // verified during the Measurement phase.
+        // Consequently, this function returns `true`.
+        true
+    }
+}
@@ -0,0 +1,56 @@
+use autopadding::*;
+
+pub const NR_AUX: usize = 16;
+pub const NR_GPRS: usize = 8;
+
+// RmiRecParams, passed by the Host on REC_CREATE.
+pad_struct_and_impl_default!(
+pub struct Params {
+    0x0    pub flags: u64,
+    0x100  pub mpidr: u64,
+    0x200  pub pc: u64,
+    0x300  pub gprs: [u64; NR_GPRS],
+    0x800  pub num_aux: u64,
+    0x808  pub aux: [u64; NR_AUX],
+    0x1000 => @END,
+}
+);
+const_assert_size!(Params, 0x1000);
+
+impl core::fmt::Debug for Params {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Params")
+            .field("flags", &format_args!("{:#X}", &self.flags))
+            .field("mpidr", &format_args!("{:#X}", &self.mpidr))
+            .field("pc", &format_args!("{:#X}", &self.pc))
+            .field("gprs", &format_args!("{:#X?}", &self.gprs))
+            .field("num_aux", &self.num_aux)
+            .field("aux", &self.aux)
+            .finish()
+    }
+}
+
+impl safe_abstraction::raw_ptr::RawPtr for Params {}
+
+impl safe_abstraction::raw_ptr::SafetyChecked for Params {}
+
+impl safe_abstraction::raw_ptr::SafetyAssured for Params {
+    fn is_initialized(&self) -> bool {
+        // Given the fact that this memory is initialized by the Host,
+        // it's not possible to unequivocally guarantee
+        // that the values have been initialized from the perspective of the RMM.
+        // However, any values, whether correctly initialized or not, will undergo
+        // verification during the Measurement phase.
+        // Consequently, this function returns `true`.
+        true
+    }
+
+    fn verify_ownership(&self) -> bool {
+        // This memory has permissions from the Host's perspective,
+        // which inherently implies that exclusive ownership cannot be guaranteed by the RMM alone.
+        // However, since the RMM only performs read operations and any incorrect values will be
+        // verified during the Measurement phase.
+        // Consequently, this function returns `true`.
+        true
+    }
+}
@@ -0,0 +1,53 @@
+use crate::rmi::realm::RPV_SIZE;
+
+use autopadding::*;
+
+// RsiRealmConfig, filled by the RMM on REALM_CONFIG
+pad_struct_and_impl_default!(
+pub struct RealmConfig {
+    0x0    pub ipa_width: usize,    // Offset 0x0
+    0x8    pub hash_algo: u8,       // Offset 0x8
+    0x10   pub num_aux_planes: u64, // Offset 0x10
+    0x200  pub rpv: [u8; RPV_SIZE], // Offset 0x200
+    0x1000 => @END,                 // The width of the RealmConfig structure is 4096 (0x1000) bytes.
+}
+);
+
+impl RealmConfig {
+    // The below `init()` fills the object allocated in the Realm kernel with the proper
+    // value (ipa_width), which helps to redirect the accesses to decrypted pages.
+    //
+    // For some reason, using 33 for ipa_width causes a problem (format string bug?)
+    // in parsing the following kernel cmdline argument:
+    // `console=ttyS0 root=/dev/vda rw  console=pl011,mmio,0x1c0a0000 console=ttyAMA0 printk.devkmsg=on`.
+    // So, we get back to use the same kernel argument with TF-RMM's one (uart0 & uart3).
+    pub fn init(&mut self, ipa_width: usize, hash_algo: u8, num_aux_planes: usize, rpv: &[u8]) {
+        self.ipa_width = ipa_width;
+        self.hash_algo = hash_algo;
+        self.num_aux_planes = num_aux_planes as u64;
+        self.rpv.copy_from_slice(rpv);
+    }
+}
+
+impl safe_abstraction::raw_ptr::RawPtr for RealmConfig {}
+
+impl safe_abstraction::raw_ptr::SafetyChecked for RealmConfig {}
+
+impl safe_abstraction::raw_ptr::SafetyAssured for RealmConfig {
+    fn is_initialized(&self) -> bool {
+        // The initialization of this memory is guaranteed
+        // according to the RMM Specification A2.2.4 Granule Wiping.
+        // This instance belongs to a Data Granule and has been initialized.
+        true
+    }
+
+    fn verify_ownership(&self) -> bool {
+        // The instance's ownership is guaranteed while being processed by the RMM.
+        // While the Realm holds RW permissions for the instance,
+        // it cannot exercise these permissions from the moment an SMC request is made
+        // until the request is completed. Even in multi-core environments,
+        // the designated areas are protected by Stage 2 Table,
+        // ensuring that there are no adverse effects on RMM's memory safety.
+        true
+    }
+}
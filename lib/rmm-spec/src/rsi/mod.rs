@@ -0,0 +1,59 @@
+pub mod config;
+pub mod hostcall;
+
+define_interface! {
+    command {
+        ABI_VERSION             = 0xc400_0190,
+        FEATURES                = 0xc400_0191,
+        MEASUREMENT_READ        = 0xc400_0192,
+        MEASUREMENT_EXTEND      = 0xc400_0193,
+        ATTEST_TOKEN_INIT       = 0xc400_0194,
+        ATTEST_TOKEN_CONTINUE   = 0xc400_0195,
+        REALM_CONFIG            = 0xc400_0196,
+        IPA_STATE_SET           = 0xc400_0197,
+        IPA_STATE_GET           = 0xc400_0198,
+        HOST_CALL               = 0xc400_0199,
+        PLANE_ENTER             = 0xc400_01a3,
+        PLANE_REG_READ          = 0xc400_01ae,
+        PLANE_REG_WRITE         = 0xc400_01af,
+        VDEV_GET_INFO           = 0xc400_019e,
+        VDEV_VALIDATE_MAPPING   = 0xc400_019f,
+        // PSCI smcs
+        // XXX: SMCCC_VERSION is not defined in the spec, so remove it if it is not used now
+        SMCCC_VERSION           = 0x8000_0000,
+        PSCI_VERSION            = 0x8400_0000,
+        PSCI_CPU_SUSPEND        = 0xC400_0001,
+        PSCI_CPU_OFF            = 0x8400_0002,
+        PSCI_CPU_ON             = 0xC400_0003,
+        PSCI_AFFINITY_INFO      = 0xC400_0004,
+        PSCI_SYSTEM_OFF         = 0x8400_0008,
+        PSCI_SYSTEM_RESET       = 0x8400_0009,
+        PSCI_FEATURES           = 0x8400_000A,
+        // vendor calls
+        ISLET_REALM_SEALING_KEY = 0xC700_0191,
+    }
+}
+
+// B4.4.2 RsiCommandReturnCode type
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(usize)]
+pub enum RsiStatus {
+    Success = 0,
+    ErrorInput = 1,
+    ErrorState = 2,
+    Incomplete = 3,
+}
+
+pub const SUCCESS: usize = RsiStatus::Success as usize;
+pub const ERROR_INPUT: usize = RsiStatus::ErrorInput as usize;
+pub const ERROR_STATE: usize = RsiStatus::ErrorState as usize;
+pub const INCOMPLETE: usize = RsiStatus::Incomplete as usize;
+
+pub const ABI_VERSION_MAJOR: usize = 1;
+pub const ABI_VERSION_MINOR: usize = 0;
+
+// Flags of ISLET_REALM_SEALING_KEY, selecting the material the key derives from
+pub const RSI_ISLET_USE_VHUK_M: usize = 0x1 << 0;
+pub const RSI_ISLET_SLK_RIM: usize = 0x1 << 1;
+pub const RSI_ISLET_SLK_REALM_ID: usize = 0x1 << 2;
+pub const RSI_ISLET_SLK_SVN: usize = 0x1 << 3;
@@ -4,11 +4,27 @@ use spinning_top::SpinlockGuard;
 use super::error::Error;
 use safe_abstraction::raw_ptr;
 
+/// InPlaceLock is implemented by entries which are locked by a bit of their own value
+/// instead of a separate spinlock, for the entries shared with hardware (e.g., RTT entries).
+pub trait InPlaceLock<E> {
+    /// Writes back `value`, which the guard may have modified, releasing the lock.
+    fn unlock(&self, value: &E);
+}
+
+enum Lock<'a, E> {
+    Spinlock(SpinlockGuard<'a, E>),
+    /// a copy of the entry, written back to `entry` on drop
+    InPlace {
+        value: E,
+        entry: &'a dyn InPlaceLock<E>,
+    },
+}
+
 /// EntryGuard provides a secure interface to access Entry while holding the corresponding lock.
 /// Also, it is used as a means of accessing "content" placed at the address of Entry under the lock.
 pub struct EntryGuard<'a, E> {
     /// inner type for Entry, which corresponds to Entry::Inner
-    inner: SpinlockGuard<'a, E>,
+    inner: Lock<'a, E>,
     /// address that this Entry holds
     addr: usize,
     /// flags of Entry
@@ -18,7 +34,21 @@ pub struct EntryGuard<'a, E> {
 
 impl<'a, E> EntryGuard<'a, E> {
     pub fn new(inner: SpinlockGuard<'a, E>, addr: usize, flags: u64) -> Self {
-        Self { inner, addr, flags }
+        Self {
+            inner: Lock::Spinlock(inner),
+            addr,
+            flags,
+        }
+    }
+
+    /// Creates a guard of the entry which the caller has locked in place.
+    /// `value` is the entry as of the locking, and the lock is released on drop.
+    pub fn in_place(value: E, entry: &'a dyn InPlaceLock<E>, addr: usize, flags: u64) -> Self {
+        Self {
+            inner: Lock::InPlace { value, entry },
+            addr,
+            flags,
+        }
     }
 
     /// content placed at the `addr`. (e.g., Rec, DataPage, ...)
@@ -53,13 +83,27 @@ impl<'a, E> Deref for EntryGuard<'a, E> {
     type Target = E;
 
     fn deref(&self) -> &Self::Target {
-        &self.inner
+        match &self.inner {
+            Lock::Spinlock(inner) => inner,
+            Lock::InPlace { value, .. } => value,
+        }
     }
 }
 
 impl<'a, E> DerefMut for EntryGuard<'a, E> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.inner
+        match &mut self.inner {
+            Lock::Spinlock(inner) => inner,
+            Lock::InPlace { value, .. } => value,
+        }
+    }
+}
+
+impl<'a, E> Drop for EntryGuard<'a, E> {
+    fn drop(&mut self) {
+        if let Lock::InPlace { value, entry } = &self.inner {
+            entry.unlock(value);
+        }
     }
 }
 
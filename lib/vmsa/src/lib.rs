@@ -13,7 +13,8 @@ pub mod page_table;
 use armv9a::{define_bitfield, define_bits, define_mask};
 
 define_bits!(
-    RawGPA, // ref. K6.1.2
+    RawGPA,            // ref. K6.1.2
+    LM1Index[51 - 48], // level -1 with FEAT_LPA2
     L0Index[47 - 39],
     L1Index[38 - 30],
     L2Index[29 - 21],
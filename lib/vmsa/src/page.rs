@@ -11,7 +11,7 @@ pub trait PageSize: Copy {
     const SIZE: usize;
 
     /// The page table level at which a page of this size is mapped
-    const MAP_TABLE_LEVEL: usize;
+    const MAP_TABLE_LEVEL: isize;
 
     /// Any extra flag that needs to be set to map a page of this size.
     const MAP_EXTRA_FLAG: u64;
@@ -9,12 +9,15 @@ use alloc::alloc::Layout;
 use core::marker::PhantomData;
 use core::slice::Iter;
 
-// Safety/TODO:
-//  - As of now, concurrency safety for RTT and Realm page table is achieved by a big lock.
-//  - If we want to use entry-level locking for a better efficiency, several pieces of codes in this file should be modified accordingly.
+// Safety:
+//  - Methods taking `&mut self` rely on the caller for exclusive access to the whole tree
+//    (e.g., the RMM page table is behind a big lock).
+//  - table_entries_locked() walks the tree with the lock of each table, taken through
+//    Entry::lock() of the entry pointing to it. Entries which implement the lock can be
+//    shared between CPUs, with each table modified only under its lock.
 
 pub trait Level {
-    const THIS_LEVEL: usize;
+    const THIS_LEVEL: isize;
     const TABLE_SIZE: usize;
     const TABLE_ALIGN: usize;
     const NUM_ENTRIES: usize;
@@ -34,7 +37,7 @@ pub trait Entry {
 
     fn pte(&self) -> u64;
     fn mut_pte(&mut self) -> &mut Self::Inner;
-    fn address(&self, level: usize) -> Option<PhysAddr>;
+    fn address(&self, level: isize) -> Option<PhysAddr>;
 
     fn set(&mut self, addr: PhysAddr, flags: u64) -> Result<(), Error>;
     fn point_to_subtable(&mut self, index: usize, addr: PhysAddr) -> Result<(), Error>;
@@ -47,7 +50,7 @@ pub trait Entry {
     fn index<L: Level>(addr: usize) -> usize;
 
     // This duplicates with address()
-    fn as_subtable(&self, _index: usize, level: usize) -> Result<usize, Error> {
+    fn as_subtable(&self, _index: usize, level: isize) -> Result<usize, Error> {
         match self.address(level) {
             Some(addr) => Ok(addr.as_usize()),
             _ => Err(Error::MmInvalidAddr),
@@ -57,6 +60,9 @@ pub trait Entry {
     fn points_to_table_or_page(&self) -> bool;
 }
 
+/// Entries of a table along with its lock, and the level of the table
+pub type LockedEntries<'a, E> = (&'a [E], EntryGuard<'a, <E as Entry>::Inner>, isize);
+
 pub trait MemAlloc {
     /// Allocates memory according to the given layout.
     ///
@@ -130,7 +136,7 @@ pub trait PageTableMethods<A: Address, L, E: Entry, const N: usize> {
     /// (output)
     ///    if exists,
     ///      A tuple of
-    ///        ((EntryGuard), the lastly reached page-table level (usize))
+    ///        ((EntryGuard), the lastly reached page-table level (isize))
     ///    else,
     ///      None
     fn entry<
@@ -140,10 +146,10 @@ pub trait PageTableMethods<A: Address, L, E: Entry, const N: usize> {
     >(
         &'a mut self,
         guest: Page<S, A>,
-        level: usize,
+        level: isize,
         no_valid_check: bool,
         func: F,
-    ) -> Result<(Option<EntryGuard<'_, E::Inner>>, usize), Error>;
+    ) -> Result<(Option<EntryGuard<'_, E::Inner>>, isize), Error>;
     /// Traverses page tables from the root and locate the page table at a specific level.
     ///
     /// (input)
@@ -153,14 +159,31 @@ pub trait PageTableMethods<A: Address, L, E: Entry, const N: usize> {
     /// (output)
     ///    if exists,
     ///      A tuple of
-    ///        (entry array iterartor, the lastly reached page-table level (usize))
+    ///        (entry array iterartor, the lastly reached page-table level (isize))
     ///    else,
     ///      None
     fn table_entries<'a, S: PageSize + 'a>(
         &'a self,
         page: Page<S, A>,
-        level: usize,
-    ) -> Result<(Iter<'a, E>, usize), Error>;
+        level: isize,
+    ) -> Result<(Iter<'a, E>, isize), Error>;
+    /// Traverses page tables as table_entries() does, locking them hand-over-hand:
+    /// the lock of the next table is taken before the lock of this table is released.
+    ///
+    /// (input)
+    ///    page: a target page to translate
+    ///    level: the intended page-table level to reach
+    ///    lock: the lock of this table
+    ///
+    /// (output)
+    ///    A tuple of
+    ///      (entry array, the lock of the table holding it, the lastly reached page-table level (isize))
+    fn table_entries_locked<'a, S: PageSize + 'a>(
+        &'a self,
+        page: Page<S, A>,
+        level: isize,
+        lock: EntryGuard<'a, E::Inner>,
+    ) -> Result<LockedEntries<'a, E>, Error>;
     fn drop(&mut self);
     fn unset_page<S: PageSize>(&mut self, guest: Page<S, A>);
 }
@@ -254,10 +277,10 @@ impl<A: Address, L: Level, E: Entry, const N: usize> PageTableMethods<A, L, E, N
     >(
         &'a mut self,
         guest: Page<S, A>,
-        level: usize,
+        level: isize,
         no_valid_check: bool,
         mut func: F,
-    ) -> Result<(Option<EntryGuard<'_, E::Inner>>, usize), Error> {
+    ) -> Result<(Option<EntryGuard<'_, E::Inner>>, isize), Error> {
         assert!(L::THIS_LEVEL == S::MAP_TABLE_LEVEL);
         if level > S::MAP_TABLE_LEVEL {
             return Err(Error::MmInvalidLevel);
@@ -283,11 +306,20 @@ impl<A: Address, L: Level, E: Entry, const N: usize> PageTableMethods<A, L, E, N
     default fn table_entries<'a, S: PageSize + 'a>(
         &'a self,
         _page: Page<S, A>,
-        _level: usize,
-    ) -> Result<(Iter<'a, E>, usize), Error> {
+        _level: isize,
+    ) -> Result<(Iter<'a, E>, isize), Error> {
         Ok((self.entries.iter(), L::THIS_LEVEL))
     }
 
+    default fn table_entries_locked<'a, S: PageSize + 'a>(
+        &'a self,
+        _page: Page<S, A>,
+        _level: isize,
+        lock: EntryGuard<'a, E::Inner>,
+    ) -> Result<LockedEntries<'a, E>, Error> {
+        Ok((&self.entries, lock, L::THIS_LEVEL))
+    }
+
     default fn drop(&mut self) {
         unsafe {
             // FIXME: need to use allocator that is used at new_in()
@@ -326,10 +358,10 @@ where
     >(
         &'a mut self,
         page: Page<S, A>,
-        level: usize,
+        level: isize,
         no_valid_check: bool,
         mut func: F,
-    ) -> Result<(Option<EntryGuard<'_, E::Inner>>, usize), Error> {
+    ) -> Result<(Option<EntryGuard<'_, E::Inner>>, isize), Error> {
         assert!(L::THIS_LEVEL <= S::MAP_TABLE_LEVEL);
         if level > S::MAP_TABLE_LEVEL {
             return Err(Error::MmInvalidLevel);
@@ -369,8 +401,8 @@ where
     default fn table_entries<'a, S: PageSize + 'a>(
         &'a self,
         page: Page<S, A>,
-        level: usize,
-    ) -> Result<(Iter<'a, E>, usize), Error> {
+        level: isize,
+    ) -> Result<(Iter<'a, E>, isize), Error> {
         assert!(L::THIS_LEVEL <= S::MAP_TABLE_LEVEL);
         if level > S::MAP_TABLE_LEVEL {
             return Err(Error::MmInvalidLevel);
@@ -386,6 +418,31 @@ where
         }
     }
 
+    default fn table_entries_locked<'a, S: PageSize + 'a>(
+        &'a self,
+        page: Page<S, A>,
+        level: isize,
+        lock: EntryGuard<'a, E::Inner>,
+    ) -> Result<LockedEntries<'a, E>, Error> {
+        assert!(L::THIS_LEVEL <= S::MAP_TABLE_LEVEL);
+        if level > S::MAP_TABLE_LEVEL {
+            return Err(Error::MmInvalidLevel);
+        }
+        let index = E::index::<L>(page.address().into());
+
+        // The entry doesn't change while this table is locked,
+        // and the subtable doesn't go away while it is locked.
+        if L::THIS_LEVEL < level && self.entries[index].points_to_table_or_page() {
+            if let Some(sublock) = self.entries[index].lock()? {
+                core::mem::drop(lock);
+                return self
+                    .subtable::<S>(page)?
+                    .table_entries_locked(page, level, sublock);
+            }
+        }
+        Ok((&self.entries, lock, L::THIS_LEVEL))
+    }
+
     fn set_page<S: PageSize>(
         &mut self,
         guest: Page<S, A>,
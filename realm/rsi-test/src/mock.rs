@@ -1,20 +1,18 @@
 use core::arch::asm;
-use core::ptr::addr_of;
+use core::ptr::addr_of_mut;
+
+use rmm_spec::rsi::hostcall::HostCall;
+use rmm_spec::rsi::HOST_CALL as RSI_HOST_CALL;
 
-// TODO:
-//   Detach rmm-spec(data structures & commands) to newly crate.
-//   And use it both rmm and realm
-const RSI_HOST_CALL: usize = 0xC400_0199;
 const CMD_GET_SHARED_BUF: u16 = 1;
 const CMD_SUCCESS: u16 = 2;
 
-#[repr(C)]
-struct HostCall {
-    pub imm: u16,
-    pub padding: u16,
-}
+// The RMM requires RsiHostCall to be aligned to its size
+#[repr(C, align(256))]
+struct AlignedHostCall(HostCall);
 
-static mut HOST_CALL: HostCall = HostCall { imm: 0, padding: 0 };
+// SAFETY: HostCall consists of integers only, for which zero is valid
+static mut HOST_CALL: AlignedHostCall = AlignedHostCall(unsafe { core::mem::zeroed() });
 
 unsafe fn smc(cmd: usize, arg: [usize; 4]) -> [usize; 8] {
     let mut ret: [usize; 8] = [0usize; 8];
@@ -36,11 +34,12 @@ pub unsafe fn get_ns_buffer() {
     // CHECK:
     //   HOST_CALL is not initialized when use tf-rmm
     //   HOST_CALL is initialized when use islet-rmm
-    HOST_CALL.padding = 0;
-    HOST_CALL.imm = CMD_GET_SHARED_BUF;
+    let host_call = &mut *addr_of_mut!(HOST_CALL.0);
+    *host_call = HostCall::default();
+    host_call.set_imm(CMD_GET_SHARED_BUF);
     let arg = [
-        addr_of!(HOST_CALL) as *const _ as usize,
-        HOST_CALL.imm as usize,
+        host_call as *const _ as usize,
+        CMD_GET_SHARED_BUF as usize,
         0,
         0,
     ];
@@ -48,12 +47,8 @@ pub unsafe fn get_ns_buffer() {
 }
 
 pub unsafe fn exit_to_host() {
-    HOST_CALL.imm = CMD_SUCCESS;
-    let arg = [
-        addr_of!(HOST_CALL) as *const _ as usize,
-        HOST_CALL.imm as usize,
-        0,
-        0,
-    ];
+    let host_call = &mut *addr_of_mut!(HOST_CALL.0);
+    host_call.set_imm(CMD_SUCCESS);
+    let arg = [host_call as *const _ as usize, CMD_SUCCESS as usize, 0, 0];
     let _ = smc(RSI_HOST_CALL, arg);
 }
@@ -18,6 +18,8 @@ struct RealmParamsFuzz {
     num_wps: u8,
     pmu_num_ctrs: u8,
     hash_algo: u8,
+    mec_policy: u8,
+    sme_vl: u8,
     rpv: [u8; 64],
     vmid: u16,
     rtt_level_start: i64,
@@ -44,6 +46,8 @@ fuzz_target!(|data: RealmParamsFuzz| {
         params.num_wps = data.num_wps;
         params.pmu_num_ctrs = data.pmu_num_ctrs;
         params.hash_algo = data.hash_algo;
+        params.mec_policy = data.mec_policy;
+        params.sme_vl = data.sme_vl;
         params.rpv = data.rpv;
         params.vmid = data.vmid;
         params.rtt_base = rtt as u64;
@@ -0,0 +1,562 @@
+#![no_main]
+
+/* Fuzz sequences of RMI and RSI commands over a small pool of granules,
+ * checking the RMM's view of the granules against the commands that succeeded
+ * after each step. Unlike the other targets, which fuzz a single command from a
+ * fixed prelude, this reaches the interleavings of commands (e.g., RTT_FOLD
+ * after a partial DATA_DESTROY, REC_DESTROY with a PSCI request pending).
+ */
+
+use islet_rmm::granule::{GranuleState, GRANULE_SIZE};
+use islet_rmm::rmi::realm::params::Params as RealmParams;
+use islet_rmm::rmi::rec::params::Params as RecParams;
+use islet_rmm::rmi::rtt_entry_state::{RMI_ASSIGNED, RMI_TABLE};
+use islet_rmm::rmi::{
+    DATA_CREATE, DATA_CREATE_UNKNOWN, DATA_DESTROY, GRANULE_DELEGATE, GRANULE_UNDELEGATE,
+    MAX_REC_AUX_GRANULES, PSCI_COMPLETE, REALM_ACTIVATE, REALM_CREATE, REALM_DESTROY, REC_CREATE,
+    REC_DESTROY, REC_ENTER, RTT_CREATE, RTT_DESTROY, RTT_FOLD, RTT_INIT_RIPAS, RTT_MAP_UNPROTECTED,
+    RTT_READ_ENTRY, RTT_SET_RIPAS, RTT_UNMAP_UNPROTECTED, SUCCESS,
+};
+use islet_rmm::rsi::{HOST_CALL, IPA_STATE_SET, PSCI_CPU_OFF, PSCI_CPU_ON};
+use islet_rmm::test_utils::inspect::{granule_state, num_children, rec_owner};
+use islet_rmm::test_utils::*;
+
+use libfuzzer_sys::{arbitrary, fuzz_target};
+
+/* The pool starts past the granules of test_utils (IDX_*) */
+const POOL_START: usize = 64;
+const POOL_SIZE: usize = 48;
+const MAX_OPS: usize = 64;
+const MAX_PAGES: usize = 8;
+
+const EXIT_IRQ: usize = 1;
+const EXIT_DATA_ABORT: usize = 2 << 4;
+const PSCI_DENIED: usize = -3isize as usize;
+
+fn pool(idx: u8) -> usize {
+    alloc_granule(POOL_START + idx as usize % POOL_SIZE)
+}
+
+fn pool_granules() -> impl Iterator<Item = usize> {
+    (0..POOL_SIZE).map(|idx| alloc_granule(POOL_START + idx))
+}
+
+fn block_size(level: usize) -> usize {
+    match level {
+        0 => L0_SIZE,
+        1 => L1_SIZE,
+        2 => L2_SIZE,
+        _ => L3_SIZE,
+    }
+}
+
+#[derive(Debug, Clone, Copy, arbitrary::Arbitrary)]
+struct Ipa {
+    ns: bool,
+    block: bool,
+    page: u16,
+}
+
+impl Ipa {
+    fn addr(&self) -> usize {
+        let base = if self.ns { 1 << (IPA_WIDTH - 1) } else { 0 };
+        base + self.block as usize * L2_SIZE + (self.page as usize % 512) * L3_SIZE
+    }
+
+    fn aligned(&self, size: usize) -> usize {
+        self.addr() / size * size
+    }
+
+    fn range(&self, pages: u8) -> (usize, usize) {
+        let base = self.addr();
+        (base, base + (pages as usize % MAX_PAGES + 1) * L3_SIZE)
+    }
+}
+
+#[derive(Debug, arbitrary::Arbitrary)]
+enum Exit {
+    Irq,
+    PsciCpuOn { target: u8 },
+    PsciCpuOff,
+    IpaStateSet { ipa: Ipa, pages: u8, ram: bool },
+    HostCall { ipa: Ipa },
+    DataAbort { ipa: Ipa, esr: u32 },
+}
+
+impl Exit {
+    /* The arguments of REC_ENTER beyond rec and run (see mock::realm::emulate_realm) */
+    fn args(&self) -> Vec<usize> {
+        match self {
+            Exit::Irq => vec![REC_ENTER_EXIT_CMD, EXIT_IRQ, 0, 0, 0],
+            Exit::PsciCpuOn { target } => vec![PSCI_CPU_ON, *target as usize, 0, 0],
+            Exit::PsciCpuOff => vec![PSCI_CPU_OFF],
+            Exit::IpaStateSet { ipa, pages, ram } => {
+                let (base, top) = ipa.range(*pages);
+                vec![IPA_STATE_SET, base, top, *ram as usize, 0]
+            }
+            Exit::HostCall { ipa } => vec![HOST_CALL, ipa.addr()],
+            Exit::DataAbort { ipa, esr } => {
+                let hpfar = (ipa.addr() >> 12) << 4;
+                vec![
+                    REC_ENTER_EXIT_CMD,
+                    EXIT_DATA_ABORT,
+                    *esr as usize,
+                    hpfar,
+                    ipa.addr(),
+                ]
+            }
+        }
+    }
+}
+
+/* Granules are indices to the pool, and levels are those of RTTs to create */
+#[derive(Debug, arbitrary::Arbitrary)]
+enum Op {
+    Delegate {
+        granule: u8,
+    },
+    Undelegate {
+        granule: u8,
+    },
+    RealmCreate {
+        rd: u8,
+        rtt: u8,
+    },
+    RealmActivate {
+        rd: u8,
+    },
+    RealmDestroy {
+        rd: u8,
+    },
+    RecCreate {
+        rd: u8,
+        rec: u8,
+        aux: u8,
+    },
+    RecDestroy {
+        rec: u8,
+    },
+    RecEnter {
+        rec: u8,
+        exit: Exit,
+    },
+    PsciComplete {
+        rec: u8,
+        target: u8,
+        accept: bool,
+    },
+    RttCreate {
+        rd: u8,
+        rtt: u8,
+        ipa: Ipa,
+        level: u8,
+    },
+    RttDestroy {
+        rd: u8,
+        ipa: Ipa,
+        level: u8,
+    },
+    RttFold {
+        rd: u8,
+        ipa: Ipa,
+        level: u8,
+    },
+    InitRipas {
+        rd: u8,
+        ipa: Ipa,
+        pages: u8,
+    },
+    SetRipas {
+        rd: u8,
+        rec: u8,
+        ipa: Ipa,
+        pages: u8,
+    },
+    DataCreate {
+        rd: u8,
+        data: u8,
+        ipa: Ipa,
+        unknown: bool,
+    },
+    DataDestroy {
+        rd: u8,
+        ipa: Ipa,
+    },
+    MapUnprotected {
+        rd: u8,
+        ipa: Ipa,
+    },
+    UnmapUnprotected {
+        rd: u8,
+        ipa: Ipa,
+    },
+}
+
+/* What the RMM should hold, from the commands that succeeded */
+#[derive(Debug)]
+struct Realm {
+    rd: usize,
+    rtt: usize,
+    num_recs: usize,
+}
+
+#[derive(Debug)]
+struct Rec {
+    rec: usize,
+    rd: usize,
+    aux: Vec<usize>,
+}
+
+#[derive(Debug)]
+struct Rtt {
+    rtt: usize,
+    rd: usize,
+    ipa: usize,
+    level: usize,
+}
+
+#[derive(Debug)]
+struct Data {
+    data: usize,
+    rd: usize,
+    ipa: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct Unprotected {
+    rd: usize,
+    ipa: usize,
+}
+
+#[derive(Debug, Default)]
+struct Model {
+    realms: Vec<Realm>,
+    recs: Vec<Rec>,
+    rtts: Vec<Rtt>,
+    data: Vec<Data>,
+    unprotected: Vec<Unprotected>,
+}
+
+fn rtt_level(level: u8) -> usize {
+    level as usize % 3 + 1
+}
+
+fn step(model: &mut Model, op: &Op) {
+    match *op {
+        Op::Delegate { granule } => {
+            rmi_call(GRANULE_DELEGATE, &[pool(granule)]);
+        }
+        Op::Undelegate { granule } => {
+            rmi_call(GRANULE_UNDELEGATE, &[pool(granule)]);
+        }
+        Op::RealmCreate { rd, rtt } => {
+            let (rd, rtt) = (pool(rd), pool(rtt));
+            let params_ptr = alloc_granule(IDX_REALM_PARAMS);
+            unsafe {
+                core::ptr::write_bytes(params_ptr as *mut u8, 0, GRANULE_SIZE);
+                let params = &mut *(params_ptr as *mut RealmParams);
+                params.s2sz = IPA_WIDTH as u8;
+                params.rtt_num_start = 1;
+                params.rtt_level_start = 0;
+                params.rtt_base = rtt as u64;
+            }
+            if rmi_call(REALM_CREATE, &[rd, params_ptr])[0] == SUCCESS {
+                model.realms.push(Realm {
+                    rd,
+                    rtt,
+                    num_recs: 0,
+                });
+            }
+        }
+        Op::RealmActivate { rd } => {
+            rmi_call(REALM_ACTIVATE, &[pool(rd)]);
+        }
+        Op::RealmDestroy { rd } => {
+            let rd = pool(rd);
+            if rmi_call(REALM_DESTROY, &[rd])[0] == SUCCESS {
+                assert!(
+                    !model.recs.iter().any(|rec| rec.rd == rd)
+                        && !model.rtts.iter().any(|rtt| rtt.rd == rd)
+                        && !model.data.iter().any(|data| data.rd == rd)
+                        && !model.unprotected.iter().any(|ns| ns.rd == rd),
+                    "REALM_DESTROY succeeded on a live realm {:#x}: {:x?}",
+                    rd,
+                    model
+                );
+                model.realms.retain(|realm| realm.rd != rd);
+            }
+        }
+        Op::RecCreate { rd, rec, aux } => {
+            let (rd, rec) = (pool(rd), pool(rec));
+            let aux: Vec<usize> = (0..MAX_REC_AUX_GRANULES)
+                .map(|idx| pool(aux.wrapping_add(idx as u8)))
+                .collect();
+            // The host delegates the auxiliary granules along with the REC
+            for granule in aux.iter() {
+                if granule_state(*granule) == Some(GranuleState::Undelegated) {
+                    rmi_call(GRANULE_DELEGATE, &[*granule]);
+                }
+            }
+
+            let mpidr = model
+                .realms
+                .iter()
+                .find(|realm| realm.rd == rd)
+                .map_or(0, |realm| realm.num_recs);
+            let params_ptr = alloc_granule(IDX_REC1_PARAMS);
+            unsafe {
+                core::ptr::write_bytes(params_ptr as *mut u8, 0, GRANULE_SIZE);
+                let params = &mut *(params_ptr as *mut RecParams);
+                params.flags = 1; // RMI_RUNNABLE
+                params.mpidr = mpidr as u64;
+                params.num_aux = MAX_REC_AUX_GRANULES as u64;
+                for (idx, granule) in aux.iter().enumerate() {
+                    params.aux[idx] = *granule as u64;
+                }
+            }
+            if rmi_call(REC_CREATE, &[rd, rec, params_ptr])[0] == SUCCESS {
+                let realm = model.realms.iter_mut().find(|realm| realm.rd == rd);
+                realm.expect("REC_CREATE on an unknown realm").num_recs += 1;
+                model.recs.push(Rec { rec, rd, aux });
+            }
+        }
+        Op::RecDestroy { rec } => {
+            let rec = pool(rec);
+            if rmi_call(REC_DESTROY, &[rec])[0] == SUCCESS {
+                model.recs.retain(|r| r.rec != rec);
+            }
+        }
+        Op::RecEnter { rec, ref exit } => {
+            let run = alloc_granule(IDX_REC1_RUN);
+            unsafe { core::ptr::write_bytes(run as *mut u8, 0, GRANULE_SIZE) };
+            let mut args = vec![pool(rec), run];
+            args.extend(exit.args());
+            rmi_call(REC_ENTER, &args);
+        }
+        Op::PsciComplete {
+            rec,
+            target,
+            accept,
+        } => {
+            let status = if accept { SUCCESS } else { PSCI_DENIED };
+            rmi_call(PSCI_COMPLETE, &[pool(rec), pool(target), status]);
+        }
+        Op::RttCreate {
+            rd,
+            rtt,
+            ipa,
+            level,
+        } => {
+            let (rd, rtt, level) = (pool(rd), pool(rtt), rtt_level(level));
+            let ipa = ipa.aligned(block_size(level - 1));
+            if rmi_call(RTT_CREATE, &[rd, rtt, ipa, level])[0] == SUCCESS {
+                model.rtts.push(Rtt {
+                    rtt,
+                    rd,
+                    ipa,
+                    level,
+                });
+            }
+        }
+        Op::RttDestroy { rd, ipa, level } | Op::RttFold { rd, ipa, level } => {
+            let (rd, level) = (pool(rd), rtt_level(level));
+            let ipa = ipa.aligned(block_size(level - 1));
+            let cmd = match op {
+                Op::RttDestroy { .. } => RTT_DESTROY,
+                _ => RTT_FOLD,
+            };
+            let ret = rmi_call(cmd, &[rd, ipa, level]);
+            if ret[0] == SUCCESS {
+                let idx = model
+                    .rtts
+                    .iter()
+                    .position(|rtt| rtt.rd == rd && rtt.ipa == ipa && rtt.level == level);
+                let rtt = model.rtts.swap_remove(idx.expect("Removed an unknown RTT"));
+                assert_eq!(ret[1], rtt.rtt, "Removed another RTT than {:x?}", rtt);
+            }
+        }
+        Op::InitRipas { rd, ipa, pages } => {
+            let (base, top) = ipa.range(pages);
+            rmi_call(RTT_INIT_RIPAS, &[pool(rd), base, top]);
+        }
+        Op::SetRipas {
+            rd,
+            rec,
+            ipa,
+            pages,
+        } => {
+            let (base, top) = ipa.range(pages);
+            rmi_call(RTT_SET_RIPAS, &[pool(rd), pool(rec), base, top]);
+        }
+        Op::DataCreate {
+            rd,
+            data,
+            ipa,
+            unknown,
+        } => {
+            const RMI_NO_MEASURE_CONTENT: usize = 0;
+
+            let (rd, data, ipa) = (pool(rd), pool(data), ipa.addr());
+            let ret = if unknown {
+                rmi_call(DATA_CREATE_UNKNOWN, &[rd, data, ipa])
+            } else {
+                let src = alloc_granule(IDX_SRC1);
+                rmi_call(DATA_CREATE, &[rd, data, ipa, src, RMI_NO_MEASURE_CONTENT])
+            };
+            if ret[0] == SUCCESS {
+                model.data.push(Data { data, rd, ipa });
+            }
+        }
+        Op::DataDestroy { rd, ipa } => {
+            let (rd, ipa) = (pool(rd), ipa.addr());
+            let ret = rmi_call(DATA_DESTROY, &[rd, ipa]);
+            if ret[0] == SUCCESS {
+                let idx = model
+                    .data
+                    .iter()
+                    .position(|data| data.rd == rd && data.ipa == ipa);
+                let data = model.data.swap_remove(idx.expect("Destroyed unknown data"));
+                assert_eq!(ret[1], data.data, "Destroyed other data than {:x?}", data);
+            }
+        }
+        Op::MapUnprotected { rd, ipa } => {
+            let (rd, ipa) = (pool(rd), ipa.addr());
+            let ns = alloc_granule(IDX_NS_DESC);
+            if rmi_call(RTT_MAP_UNPROTECTED, &[rd, ipa, MAP_LEVEL, ns])[0] == SUCCESS {
+                model.unprotected.push(Unprotected { rd, ipa });
+            }
+        }
+        Op::UnmapUnprotected { rd, ipa } => {
+            let (rd, ipa) = (pool(rd), ipa.addr());
+            if rmi_call(RTT_UNMAP_UNPROTECTED, &[rd, ipa, MAP_LEVEL])[0] == SUCCESS {
+                let mapping = Unprotected { rd, ipa };
+                assert!(
+                    model.unprotected.contains(&mapping),
+                    "Unmapped an unknown mapping {:x?}",
+                    mapping
+                );
+                model.unprotected.retain(|ns| *ns != mapping);
+            }
+        }
+    }
+}
+
+fn check(model: &Model) {
+    for granule in pool_granules() {
+        let state = granule_state(granule).unwrap();
+        let known = match state {
+            GranuleState::RD => {
+                let recs = pool_granules()
+                    .filter(|rec| rec_owner(*rec) == Some(granule))
+                    .count();
+                assert_eq!(
+                    num_children(granule),
+                    Some(recs),
+                    "RD {:#x} doesn't count its RECs",
+                    granule
+                );
+                model.realms.iter().any(|realm| realm.rd == granule)
+            }
+            GranuleState::Rec => model.recs.iter().any(|rec| rec.rec == granule),
+            GranuleState::RecAux => model.recs.iter().any(|rec| rec.aux.contains(&granule)),
+            GranuleState::RTT => {
+                model.realms.iter().any(|realm| realm.rtt == granule)
+                    || model.rtts.iter().any(|rtt| rtt.rtt == granule)
+            }
+            GranuleState::Data => model.data.iter().any(|data| data.data == granule),
+            _ => true,
+        };
+        assert!(
+            known,
+            "Granule {:#x} is left in state {}: {:x?}",
+            granule, state, model
+        );
+    }
+
+    // RTTs aren't counted in the granule status table, so their entries are walked instead
+    for rtt in model.rtts.iter() {
+        assert_eq!(
+            granule_state(rtt.rtt),
+            Some(GranuleState::RTT),
+            "{:x?}",
+            rtt
+        );
+        let ret = rmi_call(RTT_READ_ENTRY, &[rtt.rd, rtt.ipa, rtt.level - 1]);
+        assert_eq!(
+            ret[..4],
+            [SUCCESS, rtt.level - 1, RMI_TABLE, rtt.rtt],
+            "Unlinked {:x?}",
+            rtt
+        );
+    }
+    for data in model.data.iter() {
+        assert_eq!(
+            granule_state(data.data),
+            Some(GranuleState::Data),
+            "{:x?}",
+            data
+        );
+        let ret = rmi_call(RTT_READ_ENTRY, &[data.rd, data.ipa, MAP_LEVEL]);
+        let (level, state, desc) = (ret[1], ret[2], ret[3]);
+        assert_eq!(
+            (ret[0], state),
+            (SUCCESS, RMI_ASSIGNED),
+            "Unmapped {:x?}",
+            data
+        );
+        assert_eq!(
+            desc + data.ipa % block_size(level),
+            data.data,
+            "Mismapped {:x?}",
+            data
+        );
+    }
+}
+
+/* Granule states outlive an input, so everything goes back to the host */
+fn teardown(mut model: Model) {
+    for ns in model.unprotected.iter() {
+        let ret = rmi_call(RTT_UNMAP_UNPROTECTED, &[ns.rd, ns.ipa, MAP_LEVEL]);
+        assert_eq!(ret[0], SUCCESS, "{:x?}", ns);
+    }
+    for data in model.data.iter() {
+        let ret = rmi_call(DATA_DESTROY, &[data.rd, data.ipa]);
+        assert_eq!(ret[0], SUCCESS, "{:x?}", data);
+    }
+    model.rtts.sort_by_key(|rtt| core::cmp::Reverse(rtt.level));
+    for rtt in model.rtts.iter() {
+        let ret = rmi_call(RTT_DESTROY, &[rtt.rd, rtt.ipa, rtt.level]);
+        assert_eq!(ret[0], SUCCESS, "{:x?}", rtt);
+    }
+    for rec in model.recs.iter() {
+        let ret = rmi_call(REC_DESTROY, &[rec.rec]);
+        assert_eq!(ret[0], SUCCESS, "{:x?}", rec);
+    }
+    for realm in model.realms.iter() {
+        let ret = rmi_call(REALM_DESTROY, &[realm.rd]);
+        assert_eq!(ret[0], SUCCESS, "{:x?}", realm);
+    }
+
+    for granule in pool_granules() {
+        if granule_state(granule) == Some(GranuleState::Delegated) {
+            let ret = rmi_call(GRANULE_UNDELEGATE, &[granule]);
+            assert_eq!(ret[0], SUCCESS);
+        }
+        assert_eq!(
+            granule_state(granule),
+            Some(GranuleState::Undelegated),
+            "Granule {:#x} is leaked",
+            granule
+        );
+    }
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let mut model = Model::default();
+
+    for op in ops.iter().take(MAX_OPS) {
+        step(&mut model, op);
+        check(&model);
+    }
+    teardown(model);
+});
@@ -0,0 +1,330 @@
+//! Creates, signs, verifies and dumps the realm metadata which the RMM
+//! checks at RMI_ISLET_REALM_SET_METADATA.
+//!
+//!   rmm_metadata create --realm-id <ID> --rim <HEX> --hash-algo <sha256|sha384|sha512>
+//!                       --svn <N> --version <MAJOR.MINOR.PATCH> --key <signer.pem>
+//!                       [--cert <cert.cose>]... --out <metadata.bin>
+//!   rmm_metadata cert --issuer-key <issuer.pem> --subject <subject.pem> --out <cert.cose>
+//!   rmm_metadata verify <metadata.bin> [--vendor-key <vendor.pem>]
+//!   rmm_metadata dump <metadata.bin>
+//!
+//! Keys are P-384 in PEM. A subject or a vendor key can be either public or private.
+//! Certificates make the v2 metadata, from the one issued by the vendor key
+//! to the one of the signing key. `verify` checks them as the RMM does,
+//! except for the revoked keys, which only the platform knows.
+//!
+//! It's built for the host, as the workspace defaults to the RMM's target:
+//!   cargo run -p rmm_metadata --target x86_64-unknown-linux-gnu -- dump metadata.bin
+
+use coset::{
+    iana, CborSerializable, CoseKey, CoseKeyBuilder, CoseSign1, CoseSign1Builder, HeaderBuilder,
+    Label, TaggedCborSerializable,
+};
+use p384::ecdsa::signature::{Signer, Verifier};
+use p384::ecdsa::{Signature, SigningKey, VerifyingKey};
+use p384::elliptic_curve::sec1::{FromEncodedPoint, ToEncodedPoint};
+use p384::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use p384::{EncodedPoint, PublicKey, SecretKey};
+use rmm_spec::rmi::metadata::{
+    IsletRealmMetadata, MetadataCert, FMT_VERSION_2, METADATA_HASH_SHA_256, METADATA_HASH_SHA_384,
+    METADATA_HASH_SHA_512, P384_PUBLIC_KEY_SIZE,
+};
+
+use std::collections::HashMap;
+use std::process::ExitCode;
+
+type Result<T> = std::result::Result<T, String>;
+
+const USAGE: &str = "usage: rmm_metadata <create|cert|verify|dump> [options]";
+
+// Options which take multiple values
+const MULTI_OPTIONS: [&str; 1] = ["--cert"];
+
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, Vec<String>>,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut parsed = Args {
+            positional: Vec::new(),
+            options: HashMap::new(),
+        };
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                parsed.positional.push(arg);
+                continue;
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{} needs a value", arg))?;
+            let values = parsed.options.entry(arg.clone()).or_default();
+            if !values.is_empty() && !MULTI_OPTIONS.contains(&arg.as_str()) {
+                return Err(format!("{} is given more than once", arg));
+            }
+            values.push(value);
+        }
+        Ok(parsed)
+    }
+
+    fn get(&self, option: &str) -> Option<&str> {
+        self.options
+            .get(option)
+            .and_then(|values| values.first())
+            .map(String::as_str)
+    }
+
+    fn required(&self, option: &str) -> Result<&str> {
+        self.get(option)
+            .ok_or_else(|| format!("{} is required", option))
+    }
+
+    fn all(&self, option: &str) -> &[String] {
+        self.options.get(option).map_or(&[], Vec::as_slice)
+    }
+}
+
+fn read(path: &str) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn write(path: &str, data: &[u8]) -> Result<()> {
+    std::fs::write(path, data).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn secret_key(path: &str) -> Result<SecretKey> {
+    let pem = String::from_utf8(read(path)?).map_err(|e| format!("{}: {}", path, e))?;
+    SecretKey::from_pkcs8_pem(&pem)
+        .or_else(|_| SecretKey::from_sec1_pem(&pem))
+        .map_err(|_| format!("{}: not a P-384 private key", path))
+}
+
+// Takes the public key out of a private key if needed
+fn public_key(path: &str) -> Result<[u8; P384_PUBLIC_KEY_SIZE]> {
+    let pem = String::from_utf8(read(path)?).map_err(|e| format!("{}: {}", path, e))?;
+    let key = match PublicKey::from_public_key_pem(&pem) {
+        Ok(key) => key,
+        Err(_) => secret_key(path)?.public_key(),
+    };
+    Ok(raw_public_key(&key))
+}
+
+// The RMM takes the public keys as (x || y), without the SEC1 tag
+fn raw_public_key(key: &PublicKey) -> [u8; P384_PUBLIC_KEY_SIZE] {
+    let point = key.to_encoded_point(false);
+    let mut raw = [0u8; P384_PUBLIC_KEY_SIZE];
+    raw.copy_from_slice(&point.as_bytes()[1..]);
+    raw
+}
+
+fn sign(key: &SecretKey, data: &[u8]) -> Signature {
+    SigningKey::from(key).sign(data)
+}
+
+fn verifying_key(raw: &[u8; P384_PUBLIC_KEY_SIZE]) -> Result<VerifyingKey> {
+    let point = EncodedPoint::from_untagged_bytes(raw.as_slice().into());
+    Option::from(PublicKey::from_encoded_point(&point))
+        .map(|key: PublicKey| VerifyingKey::from(&key))
+        .ok_or_else(|| "Not a P-384 public key".to_string())
+}
+
+// The payload of a certificate is the P-384 COSE_Key of the subject
+fn cert_payload(subject: &[u8; P384_PUBLIC_KEY_SIZE]) -> Result<Vec<u8>> {
+    let (x, y) = subject.split_at(P384_PUBLIC_KEY_SIZE / 2);
+    CoseKeyBuilder::new_ec2_pub_key(iana::EllipticCurve::P_384, x.to_vec(), y.to_vec())
+        .build()
+        .to_vec()
+        .map_err(|e| format!("Failed to encode the subject: {}", e))
+}
+
+// Returns the public key of the subject if `cert` is signed by `issuer`
+fn verify_cert(
+    cert: &MetadataCert,
+    issuer: &[u8; P384_PUBLIC_KEY_SIZE],
+) -> Result<[u8; P384_PUBLIC_KEY_SIZE]> {
+    let data = cert.as_bytes().ok_or("Certificate is too long")?;
+    let sign1 = CoseSign1::from_tagged_slice(data)
+        .map_err(|e| format!("Certificate is not a COSE_Sign1: {}", e))?;
+    if sign1.protected.header.alg != Some(coset::Algorithm::Assigned(iana::Algorithm::ES384)) {
+        return Err("Certificate should be signed with ES384".to_string());
+    }
+
+    let verifying_key = verifying_key(issuer)?;
+    sign1
+        .verify_signature(b"", |sig, data| {
+            let signature = Signature::from_slice(sig)?;
+            verifying_key.verify(data, &signature)
+        })
+        .map_err(|_| "Certificate is not signed by its issuer".to_string())?;
+
+    let payload = sign1.payload.ok_or("Certificate has no subject")?;
+    let key =
+        CoseKey::from_slice(&payload).map_err(|e| format!("Subject is not a COSE_Key: {}", e))?;
+    let param = |label: iana::Ec2KeyParameter| {
+        key.params
+            .iter()
+            .find(|(l, _)| *l == Label::Int(label as i64))
+            .and_then(|(_, value)| value.as_bytes())
+    };
+    let (Some(x), Some(y)) = (
+        param(iana::Ec2KeyParameter::X),
+        param(iana::Ec2KeyParameter::Y),
+    ) else {
+        return Err("Subject is not an EC2 key".to_string());
+    };
+
+    [x.as_slice(), y.as_slice()]
+        .concat()
+        .try_into()
+        .map_err(|_| "Subject is not a P-384 key".to_string())
+}
+
+// Checks that the signing key is certified by `vendor_key` through the chain
+fn verify_chain(
+    metadata: &IsletRealmMetadata,
+    vendor_key: Option<&[u8; P384_PUBLIC_KEY_SIZE]>,
+) -> Result<()> {
+    let vendor_key = vendor_key.ok_or("--vendor-key is required for the metadata v2")?;
+    let chain = match metadata.chain() {
+        Some(chain) if !chain.is_empty() => chain,
+        _ => return Err("Invalid length of the signer chain".to_string()),
+    };
+
+    let mut issuer = *vendor_key;
+    for cert in chain {
+        issuer = verify_cert(cert, &issuer)?;
+    }
+    if issuer != *metadata.public_key() {
+        return Err("The signer chain doesn't end with the signing key".to_string());
+    }
+    Ok(())
+}
+
+fn hash_algo(name: &str) -> Result<usize> {
+    match name {
+        "sha256" => Ok(METADATA_HASH_SHA_256),
+        "sha384" => Ok(METADATA_HASH_SHA_384),
+        "sha512" => Ok(METADATA_HASH_SHA_512),
+        _ => Err(format!("Unknown hash algorithm: {}", name)),
+    }
+}
+
+fn version(triple: &str) -> Result<(usize, usize, usize)> {
+    let parts: Vec<usize> = triple
+        .split('.')
+        .map(|part| part.parse::<usize>())
+        .collect::<std::result::Result<_, _>>()
+        .map_err(|_| format!("Invalid version: {}", triple))?;
+    match parts[..] {
+        [major, minor, patch] => Ok((major, minor, patch)),
+        _ => Err(format!("Version should be MAJOR.MINOR.PATCH: {}", triple)),
+    }
+}
+
+fn load(path: &str) -> Result<IsletRealmMetadata> {
+    IsletRealmMetadata::from_bytes(&read(path)?)
+        .ok_or_else(|| format!("{}: not a realm metadata", path))
+}
+
+fn create(args: &Args) -> Result<()> {
+    let rim = hex::decode(args.required("--rim")?).map_err(|e| format!("--rim: {}", e))?;
+    let svn = args
+        .required("--svn")?
+        .parse()
+        .map_err(|_| "--svn should be a number".to_string())?;
+    let mut metadata = IsletRealmMetadata::new(
+        args.required("--realm-id")?,
+        &rim,
+        hash_algo(args.required("--hash-algo")?)?,
+        svn,
+        version(args.required("--version")?)?,
+    )
+    .map_err(|e| format!("Invalid metadata: {:?}", e))?;
+
+    let key = secret_key(args.required("--key")?)?;
+    let chain = args
+        .all("--cert")
+        .iter()
+        .map(|path| read(path))
+        .collect::<Result<Vec<_>>>()?;
+    let chain: Vec<&[u8]> = chain.iter().map(Vec::as_slice).collect();
+    metadata
+        .set_signer(&raw_public_key(&key.public_key()), &chain)
+        .map_err(|e| format!("Invalid signer chain: {:?}", e))?;
+
+    let signature = sign(&key, metadata.signed_data());
+    let signature = signature.to_bytes();
+    metadata.set_signature(signature[..].try_into().expect("P-384 signature"));
+
+    write(args.required("--out")?, metadata.as_bytes())
+}
+
+fn cert(args: &Args) -> Result<()> {
+    let issuer = secret_key(args.required("--issuer-key")?)?;
+    let subject = public_key(args.required("--subject")?)?;
+
+    let cert = CoseSign1Builder::new()
+        .protected(
+            HeaderBuilder::new()
+                .algorithm(iana::Algorithm::ES384)
+                .build(),
+        )
+        .payload(cert_payload(&subject)?)
+        .create_signature(b"", |data| sign(&issuer, data).to_vec())
+        .build()
+        .to_tagged_vec()
+        .map_err(|e| format!("Failed to encode the certificate: {}", e))?;
+
+    write(args.required("--out")?, &cert)
+}
+
+fn verify(args: &Args) -> Result<()> {
+    let path = args.positional.first().ok_or(USAGE)?;
+    let metadata = load(path)?;
+
+    let vendor_key = args.get("--vendor-key").map(public_key).transpose()?;
+
+    metadata
+        .validate()
+        .map_err(|e| format!("Invalid metadata: {:?}", e))?;
+    if metadata.fmt_version() == FMT_VERSION_2 {
+        verify_chain(&metadata, vendor_key.as_ref())?;
+    }
+    let signature =
+        Signature::from_slice(metadata.signature()).map_err(|_| "Invalid signature".to_string())?;
+    verifying_key(metadata.public_key())?
+        .verify(metadata.signed_data(), &signature)
+        .map_err(|_| "Invalid signature".to_string())?;
+    println!("{}: OK", path);
+    Ok(())
+}
+
+fn dump(args: &Args) -> Result<()> {
+    let path = args.positional.first().ok_or(USAGE)?;
+    println!("{}", load(path)?);
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let Some(command) = args.next() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+
+    let result = Args::parse(args).and_then(|args| match command.as_str() {
+        "create" => create(&args),
+        "cert" => cert(&args),
+        "verify" => verify(&args),
+        "dump" => dump(&args),
+        _ => Err(USAGE.to_string()),
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Calculates the Realm Initial Measurement (RIM) offline,
+//! with the same measurement code as the RMM.
+//!
+//!   rmm_rim <ops.txt>
+//!
+//! The input lists the RMI commands which build the realm, one per line,
+//! in the order the host issues them. Numbers are decimal or 0x-prefixed hex,
+//! and '#' starts a comment.
+//!
+//!   realm hash_algo=<sha256|sha384|sha512> [flags=N] [s2sz=N] [sve_vl=N] [num_bps=N]
+//!         [num_wps=N] [pmu_num_ctrs=N] [num_aux_planes=N] [mec_policy=N] [sme_vl=N]
+//!   ripas <base> <top> [map_size]         RMI_RTT_INIT_RIPAS, 4KB mappings by default
+//!   data <ipa> <image> [unmeasured]       RMI_DATA_CREATE for each granule of the image
+//!   rec [flags=N] [pc=N] [x0=N] ... [x7=N]  RMI_REC_CREATE
+//!
+//! `realm` comes first, as RMI_REALM_CREATE. Paths of the images are relative
+//! to the input file. The RIM is printed in hex.
+
+use islet_measurement::{
+    HashContext, Hasher, Measurement, HASH_ALGO_SHA256, HASH_ALGO_SHA384, HASH_ALGO_SHA512,
+    RMI_MEASURE_CONTENT,
+};
+use rmm_spec::rmi::realm::Params as RealmParams;
+use rmm_spec::rmi::rec::params::Params as RecParams;
+
+use std::path::Path;
+use std::process::ExitCode;
+
+type Result<T> = std::result::Result<T, String>;
+
+const GRANULE_SIZE: usize = 4096;
+const RMI_NO_MEASURE_CONTENT: usize = 0;
+
+fn number(value: &str) -> Result<u64> {
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|_| format!("Invalid number: {}", value))
+}
+
+fn byte(value: &str) -> Result<u8> {
+    u8::try_from(number(value)?).map_err(|_| format!("Too big for a byte: {}", value))
+}
+
+fn hash_algo(name: &str) -> Result<u8> {
+    match name {
+        "sha256" => Ok(HASH_ALGO_SHA256),
+        "sha384" => Ok(HASH_ALGO_SHA384),
+        "sha512" => Ok(HASH_ALGO_SHA512),
+        _ => Err(format!("Unknown hash algorithm: {}", name)),
+    }
+}
+
+// Splits "key=value" arguments
+fn options<'a>(args: &[&'a str]) -> Result<Vec<(&'a str, &'a str)>> {
+    args.iter()
+        .map(|arg| {
+            arg.split_once('=')
+                .ok_or_else(|| format!("Expected key=value: {}", arg))
+        })
+        .collect()
+}
+
+fn realm_params(args: &[&str]) -> Result<RealmParams> {
+    let mut params = RealmParams::default();
+    let mut algo = None;
+    for (key, value) in options(args)? {
+        match key {
+            "hash_algo" => algo = Some(hash_algo(value)?),
+            "flags" => params.flags = number(value)?,
+            "s2sz" => params.s2sz = byte(value)?,
+            "sve_vl" => params.sve_vl = byte(value)?,
+            "num_bps" => params.num_bps = byte(value)?,
+            "num_wps" => params.num_wps = byte(value)?,
+            "pmu_num_ctrs" => params.pmu_num_ctrs = byte(value)?,
+            "num_aux_planes" => params.num_aux_planes = byte(value)?,
+            "mec_policy" => params.mec_policy = byte(value)?,
+            "sme_vl" => params.sme_vl = byte(value)?,
+            _ => return Err(format!("Unknown realm param: {}", key)),
+        }
+    }
+    params.hash_algo = algo.ok_or("realm needs hash_algo")?;
+    Ok(params)
+}
+
+fn rec_params(args: &[&str]) -> Result<RecParams> {
+    let mut params = RecParams::default();
+    for (key, value) in options(args)? {
+        match key {
+            "flags" => params.flags = number(value)?,
+            "pc" => params.pc = number(value)?,
+            _ => {
+                let gpr = key
+                    .strip_prefix('x')
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .filter(|n| *n < params.gprs.len())
+                    .ok_or_else(|| format!("Unknown REC param: {}", key))?;
+                params.gprs[gpr] = number(value)?;
+            }
+        }
+    }
+    Ok(params)
+}
+
+fn measure_data(ctx: &mut HashContext<'_>, args: &[&str], dir: &Path) -> Result<()> {
+    let (ipa, path, flags) = match args {
+        [ipa, path] => (ipa, path, RMI_MEASURE_CONTENT),
+        [ipa, path, "unmeasured"] => (ipa, path, RMI_NO_MEASURE_CONTENT),
+        _ => return Err("Usage: data <ipa> <image> [unmeasured]".to_string()),
+    };
+    let ipa = number(ipa)? as usize;
+    if ipa % GRANULE_SIZE != 0 {
+        return Err(format!("IPA is not aligned: {:#x}", ipa));
+    }
+    let path = dir.join(path);
+    let image = std::fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    // The last granule is padded with zeros
+    for (i, chunk) in image.chunks(GRANULE_SIZE).enumerate() {
+        let mut granule = [0u8; GRANULE_SIZE];
+        granule[..chunk.len()].copy_from_slice(chunk);
+        ctx.measure_data_granule(&granule, ipa + i * GRANULE_SIZE, flags)
+            .map_err(|e| format!("{:?}", e))?;
+    }
+    Ok(())
+}
+
+fn measure_ripas(ctx: &mut HashContext<'_>, args: &[&str]) -> Result<()> {
+    let (base, top, map_size) = match args {
+        [base, top] => (number(base)?, number(top)?, GRANULE_SIZE as u64),
+        [base, top, map_size] => (number(base)?, number(top)?, number(map_size)?),
+        _ => return Err("Usage: ripas <base> <top> [map_size]".to_string()),
+    };
+    if !map_size.is_power_of_two() || base % map_size != 0 || top % map_size != 0 {
+        return Err(format!("Range is not aligned to {:#x}", map_size));
+    }
+
+    // The RMM measures each of the mappings in the range
+    for addr in (base..top).step_by(map_size as usize) {
+        ctx.measure_ripas_granule(addr, addr + map_size)
+            .map_err(|e| format!("{:?}", e))?;
+    }
+    Ok(())
+}
+
+fn calculate(ops: &str, dir: &Path) -> Result<Vec<u8>> {
+    let mut rim = Measurement::empty();
+    let mut lines = ops
+        .lines()
+        .enumerate()
+        .map(|(n, line)| (n + 1, line.split('#').next().unwrap_or("").trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    let (n, first) = lines.next().ok_or("No realm is created")?;
+    let words: Vec<&str> = first.split_whitespace().collect();
+    if words[0] != "realm" {
+        return Err(format!("line {}: realm should come first", n));
+    }
+    let params = realm_params(&words[1..]).map_err(|e| format!("line {}: {}", n, e))?;
+    let mut ctx = HashContext::new(params.hash_algo, &mut rim).map_err(|e| format!("{:?}", e))?;
+    ctx.measure_realm_create(&(&params).into())
+        .map_err(|e| format!("{:?}", e))?;
+
+    for (n, line) in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let result = match words[0] {
+            "ripas" => measure_ripas(&mut ctx, &words[1..]),
+            "data" => measure_data(&mut ctx, &words[1..], dir),
+            "rec" => rec_params(&words[1..]).and_then(|params| {
+                ctx.measure_rec_params(&(&params).into())
+                    .map_err(|e| format!("{:?}", e))
+            }),
+            op => Err(format!("Unknown operation: {}", op)),
+        };
+        result.map_err(|e| format!("line {}: {}", n, e))?;
+    }
+
+    let size = Hasher::from_hash_algo(params.hash_algo)
+        .map_err(|e| format!("{:?}", e))?
+        .output_size();
+    Ok(rim.as_slice()[..size].to_vec())
+}
+
+fn main() -> ExitCode {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("usage: rmm_rim <ops.txt>");
+        return ExitCode::FAILURE;
+    };
+
+    let result = std::fs::read_to_string(&path)
+        .map_err(|e| format!("{}: {}", path, e))
+        .and_then(|ops| {
+            let dir = Path::new(&path).parent().unwrap_or(Path::new("."));
+            calculate(&ops, dir)
+        });
+    match result {
+        Ok(rim) => {
+            let hex: String = rim.iter().map(|b| format!("{:02x}", b)).collect();
+            println!("{}", hex);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! Runs the rmm in userspace and serves RMI over a Unix socket,
+//! to develop the host side without FVP.
+//!
+//! Like the fuzz targets, it links the rmm as a hosted library. It's built
+//! for the host (e.g., x86), where the aarch64 instructions are left out:
+//!   ./scripts/sim.sh /tmp/rmm.sock
+//!
+//! The physical memory is an anonymous mapping of 256MB, all of it NS DRAM.
+//! Physical addresses are the addresses of the mapping in this process,
+//! so clients ask for the base with INFO. The realm doesn't run: REC_ENTER
+//! returns the realm exits which the client scripted for the REC beforehand.
+//!
+//! # Wire format
+//!
+//! Clients send requests and get a response for each, in order.
+//! A connection is served at a time, and the state of the rmm outlives it.
+//! Every message starts with two u32, all integers being little-endian.
+//!
+//!   request:  op: u32, len: u32, payload: [u8; len]
+//!   response: status: u32, len: u32, payload: [u8; len]
+//!
+//!   op  request payload                response payload
+//!   1   RMI     fid: u64, x1..: [u64]  x0..x4: [u64; 5]
+//!   2   READ    pa: u64, size: u64     data: [u8; size]
+//!   3   WRITE   pa: u64, data: [u8]    (empty)
+//!   4   INFO    (empty)                base: u64, size: u64
+//!   5   SCRIPT  rec: u64, exit: [u64]  (empty)
+//!
+//!   status  0 OK, 1 INVALID (malformed request), 2 FAULT (the range is out of
+//!           the memory or delegated to realms)
+//!
+//! RMI takes up to 7 arguments, and the ones beyond the command's are ignored.
+//! Arguments to hold in memory (e.g., RmiRealmParams) are written beforehand.
+//!
+//! SCRIPT queues a realm exit for the next REC_ENTER of the REC, one of
+//!   0, exit_reason, esr, hpfar, far   the realm exits to the RMM as given
+//!   fid, x1, x2, ...                  the realm calls RSI with the arguments
+//! where exit_reason is the one of the RMM exception handler
+//! (1: IRQ, 0x10: RSI, 0x20: data abort, 0x30: instruction abort, 0x40: WFx).
+//! The RMM handles the exit as it would on the real machine, and REC_ENTER
+//! returns after one exit. Without a script, the REC exits with an IRQ.
+
+mod memory;
+
+use islet_rmm::test_utils::{rmi_call, REC_ENTER_EXIT_CMD};
+use memory::Memory;
+use rmm_spec::rmi::REC_ENTER;
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::process::ExitCode;
+
+const OP_RMI: u32 = 1;
+const OP_READ: u32 = 2;
+const OP_WRITE: u32 = 3;
+const OP_INFO: u32 = 4;
+const OP_SCRIPT: u32 = 5;
+
+const STATUS_OK: u32 = 0;
+const STATUS_INVALID: u32 = 1;
+const STATUS_FAULT: u32 = 2;
+
+const MAX_PAYLOAD: usize = 16 * 1024 * 1024;
+const MAX_RMI_ARGS: usize = 7;
+const MAX_EXIT_WORDS: usize = 32;
+const EXIT_IRQ: usize = 1;
+
+struct Simulator {
+    memory: Memory,
+    // Realm exits to return on REC_ENTER, for each REC
+    scripts: HashMap<usize, VecDeque<Vec<usize>>>,
+}
+
+fn words(payload: &[u8]) -> Option<Vec<usize>> {
+    if payload.len() % 8 != 0 {
+        return None;
+    }
+    let words = payload
+        .chunks(8)
+        .map(|word| u64::from_le_bytes(word.try_into().unwrap()) as usize)
+        .collect();
+    Some(words)
+}
+
+fn bytes(words: &[usize]) -> Vec<u8> {
+    words
+        .iter()
+        .flat_map(|word| (*word as u64).to_le_bytes())
+        .collect()
+}
+
+impl Simulator {
+    fn rmi(&mut self, payload: &[u8]) -> Result<Vec<u8>, u32> {
+        let request = words(payload).ok_or(STATUS_INVALID)?;
+        let (&fid, args) = request.split_first().ok_or(STATUS_INVALID)?;
+        if args.len() > MAX_RMI_ARGS {
+            return Err(STATUS_INVALID);
+        }
+
+        let mut args = args.to_vec();
+        if fid == REC_ENTER {
+            // The arguments beyond rec and run emulate the realm
+            let rec = args.first().copied().unwrap_or(0);
+            let exit = self
+                .scripts
+                .get_mut(&rec)
+                .and_then(|script| script.pop_front())
+                .unwrap_or_else(|| vec![REC_ENTER_EXIT_CMD, EXIT_IRQ, 0, 0, 0]);
+            args.resize(2, 0);
+            args.extend(exit);
+        }
+        Ok(bytes(&rmi_call(fid, &args)))
+    }
+
+    fn read(&self, payload: &[u8]) -> Result<Vec<u8>, u32> {
+        let [pa, size] = words(payload)
+            .and_then(|w| <[usize; 2]>::try_from(w).ok())
+            .ok_or(STATUS_INVALID)?;
+        if size > MAX_PAYLOAD {
+            return Err(STATUS_INVALID);
+        }
+        self.memory.read(pa, size).ok_or(STATUS_FAULT)
+    }
+
+    fn write(&self, payload: &[u8]) -> Result<Vec<u8>, u32> {
+        if payload.len() < 8 {
+            return Err(STATUS_INVALID);
+        }
+        let (pa, data) = payload.split_at(8);
+        let pa = u64::from_le_bytes(pa.try_into().unwrap()) as usize;
+        self.memory.write(pa, data).ok_or(STATUS_FAULT)?;
+        Ok(Vec::new())
+    }
+
+    fn script(&mut self, payload: &[u8]) -> Result<Vec<u8>, u32> {
+        let request = words(payload).ok_or(STATUS_INVALID)?;
+        let (&rec, exit) = request.split_first().ok_or(STATUS_INVALID)?;
+        let valid = match exit.first() {
+            Some(&REC_ENTER_EXIT_CMD) => exit.len() == 5,
+            Some(_) => exit.len() <= MAX_EXIT_WORDS,
+            None => false,
+        };
+        if !valid {
+            return Err(STATUS_INVALID);
+        }
+        self.scripts
+            .entry(rec)
+            .or_default()
+            .push_back(exit.to_vec());
+        Ok(Vec::new())
+    }
+
+    fn handle(&mut self, op: u32, payload: &[u8]) -> Result<Vec<u8>, u32> {
+        match op {
+            OP_RMI => self.rmi(payload),
+            OP_READ => self.read(payload),
+            OP_WRITE => self.write(payload),
+            OP_INFO => Ok(bytes(&[self.memory.base(), self.memory.size()])),
+            OP_SCRIPT => self.script(payload),
+            _ => Err(STATUS_INVALID),
+        }
+    }
+
+    fn serve(&mut self, mut stream: UnixStream) -> io::Result<()> {
+        loop {
+            let mut header = [0u8; 8];
+            match stream.read_exact(&mut header) {
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                result => result?,
+            }
+            let op = u32::from_le_bytes(header[..4].try_into().unwrap());
+            let len = u32::from_le_bytes(header[4..].try_into().unwrap()) as usize;
+            if len > MAX_PAYLOAD {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Too large request: {}", len),
+                ));
+            }
+            let mut payload = vec![0u8; len];
+            stream.read_exact(&mut payload)?;
+
+            let (status, response) = match self.handle(op, &payload) {
+                Ok(response) => (STATUS_OK, response),
+                Err(status) => (status, Vec::new()),
+            };
+            stream.write_all(&status.to_le_bytes())?;
+            stream.write_all(&(response.len() as u32).to_le_bytes())?;
+            stream.write_all(&response)?;
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("usage: rmm_sim <socket>");
+        return ExitCode::FAILURE;
+    };
+
+    let memory = match Memory::map() {
+        Ok(memory) => memory,
+        Err(e) => {
+            eprintln!("Failed to map the memory: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    // A socket left by the previous run can't be bound again
+    let _ = std::fs::remove_file(&path);
+    let listener = match UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    eprintln!(
+        "Listening on {}, memory at {:#x}..{:#x}",
+        path,
+        memory.base(),
+        memory.base() + memory.size()
+    );
+
+    let mut sim = Simulator {
+        memory,
+        scripts: HashMap::new(),
+    };
+    for stream in listener.incoming() {
+        let result = stream.and_then(|stream| sim.serve(stream));
+        if let Err(e) = result {
+            eprintln!("Connection closed: {}", e);
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,80 @@
+use islet_rmm::config::NS_DRAM_REGIONS;
+use islet_rmm::granule::{is_not_in_realm, GRANULE_SIZE, GRANULE_STATUS_TABLE_SIZE};
+
+use std::io;
+
+/// The physical memory of the simulated machine, an anonymous mapping
+/// whose addresses are used as physical addresses as they are.
+/// All of it is NS DRAM which the host can delegate to realms.
+pub struct Memory {
+    base: usize,
+    size: usize,
+}
+
+impl Memory {
+    pub const SIZE: usize = GRANULE_STATUS_TABLE_SIZE * GRANULE_SIZE;
+
+    pub fn map() -> io::Result<Self> {
+        // Safety: a new private anonymous mapping doesn't alias anything
+        let ptr = unsafe {
+            libc::mmap(
+                core::ptr::null_mut(),
+                Self::SIZE,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_NORESERVE,
+                -1,
+                0,
+            )
+        };
+        if core::ptr::eq(ptr, libc::MAP_FAILED) {
+            return Err(io::Error::last_os_error());
+        }
+
+        let base = ptr as usize;
+        NS_DRAM_REGIONS.lock().push(base..base + Self::SIZE);
+        Ok(Self {
+            base,
+            size: Self::SIZE,
+        })
+    }
+
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // The host can't access the granules delegated to realms,
+    // which are in realm PAS on the real machine.
+    fn host_accessible(&self, pa: usize, len: usize) -> bool {
+        let Some(end) = pa.checked_add(len) else {
+            return false;
+        };
+        if pa < self.base || end > self.base + self.size {
+            return false;
+        }
+        let first = pa & !(GRANULE_SIZE - 1);
+        (first..end).step_by(GRANULE_SIZE).all(is_not_in_realm)
+    }
+
+    pub fn read(&self, pa: usize, len: usize) -> Option<Vec<u8>> {
+        if !self.host_accessible(pa, len) {
+            return None;
+        }
+        // Safety: the range is within the mapping
+        let src = unsafe { core::slice::from_raw_parts(pa as *const u8, len) };
+        Some(src.to_vec())
+    }
+
+    pub fn write(&self, pa: usize, data: &[u8]) -> Option<()> {
+        if !self.host_accessible(pa, data.len()) {
+            return None;
+        }
+        // Safety: the range is within the mapping
+        let dst = unsafe { core::slice::from_raw_parts_mut(pa as *mut u8, data.len()) };
+        dst.copy_from_slice(data);
+        Some(())
+    }
+}
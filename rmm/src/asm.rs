@@ -23,7 +23,12 @@ pub fn smc(cmd: usize, args: &[usize]) -> [usize; 8] {
     put(&mut ret);
     put(&mut padded_args);
     #[cfg(any(kani, miri, test, fuzzing))]
-    if cmd == crate::rmi::gpt::MARK_REALM {
+    if (cmd == crate::rmi::gpt::MARK_REALM || cmd == crate::rmi::gpt::MARK_NONSECURE)
+        && !crate::granule::validate_addr(args[0])
+    {
+        // Device memory, which isn't tracked as granules
+        ret[0] = SMC_SUCCESS;
+    } else if cmd == crate::rmi::gpt::MARK_REALM {
         use crate::get_granule;
         use crate::granule::entry::GranuleGpt;
         let addr = args[0];
@@ -34,6 +39,8 @@ pub fn smc(cmd: usize, args: &[usize]) -> [usize; 8] {
             let _ = get_granule!(addr).map(|mut guard| guard.set_gpt(GranuleGpt::GPT_REALM));
             ret[0] = SMC_SUCCESS;
         }
+    } else if cmd == crate::rmi::RMM_MEC_REFRESH {
+        ret[0] = SMC_SUCCESS;
     } else if cmd == crate::rmi::gpt::MARK_NONSECURE {
         use crate::get_granule;
         use crate::granule::entry::GranuleGpt;
@@ -68,6 +75,38 @@ pub fn smc(cmd: usize, args: &[usize]) -> [usize; 8] {
     ret
 }
 
+/// Reads a random number from RNDR (FEAT_RNG).
+/// Returns None if no entropy is available at the moment.
+pub fn rndr() -> Option<u64> {
+    #[cfg(not(any(kani, miri, test, fuzzing)))]
+    {
+        let (value, valid): (u64, u64);
+        unsafe {
+            // RNDR sets NZCV to 0b0100 on failure
+            asm!(
+                "mrs {value}, s3_3_c2_c4_0",
+                "cset {valid}, ne",
+                value = out(reg) value,
+                valid = out(reg) valid,
+                options(nomem, nostack),
+            );
+        }
+        (valid != 0).then_some(value)
+    }
+    #[cfg(any(kani, miri, test, fuzzing))]
+    {
+        use core::sync::atomic::{AtomicU64, Ordering};
+        static SEED: AtomicU64 = AtomicU64::new(0);
+        // splitmix64, to keep the numbers distinct across calls
+        let mut z = SEED
+            .fetch_add(0x9e37_79b9_7f4a_7c15, Ordering::Relaxed)
+            .wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Some(z ^ (z >> 31))
+    }
+}
+
 #[inline(always)]
 pub fn dcache_flush(addr: usize, len: usize) {
     let mut cur_addr = addr;
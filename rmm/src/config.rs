@@ -50,3 +50,11 @@ pub fn is_ns_dram(addr: usize) -> bool {
 
     false
 }
+
+/// Whether [base, top) overlaps any of the NS DRAM regions
+pub fn overlaps_ns_dram(base: usize, top: usize) -> bool {
+    NS_DRAM_REGIONS
+        .lock()
+        .iter()
+        .any(|range| base < range.end && top > range.start)
+}
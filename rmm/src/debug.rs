@@ -0,0 +1,380 @@
+use aarch64_cpu::registers::*;
+use armv9a::regs::debug::*;
+
+// The architecture allows up to 16 breakpoints and 16 watchpoints
+pub const MAX_BPS: usize = 16;
+pub const MAX_WPS: usize = 16;
+
+// The number of breakpoints and watchpoints implemented, minus one
+// as encoded in ID_AA64DFR0_EL1.{BRPs, WRPs}
+pub fn max_num_bps() -> u64 {
+    #[cfg(not(any(miri, test, fuzzing)))]
+    let ret = ID_AA64DFR0_EL1.read(ID_AA64DFR0_EL1::BRPs);
+    #[cfg(any(miri, test, fuzzing))]
+    let ret = 5;
+    trace!("Debug # breakpoints: {:?}", ret + 1);
+    ret
+}
+
+pub fn max_num_wps() -> u64 {
+    #[cfg(not(any(miri, test, fuzzing)))]
+    let ret = ID_AA64DFR0_EL1.read(ID_AA64DFR0_EL1::WRPs);
+    #[cfg(any(miri, test, fuzzing))]
+    let ret = 3;
+    trace!("Debug # watchpoints: {:?}", ret + 1);
+    ret
+}
+
+pub fn validate(num_bps: u8, num_wps: u8) -> bool {
+    num_bps as u64 <= max_num_bps() && num_wps as u64 <= max_num_wps()
+}
+
+fn store_bp(n: usize, dbgbcr: &mut [u64; MAX_BPS], dbgbvr: &mut [u64; MAX_BPS]) {
+    match n {
+        0 => {
+            dbgbcr[0] = DBGBCR0_EL1.get();
+            dbgbvr[0] = DBGBVR0_EL1.get();
+        }
+        1 => {
+            dbgbcr[1] = DBGBCR1_EL1.get();
+            dbgbvr[1] = DBGBVR1_EL1.get();
+        }
+        2 => {
+            dbgbcr[2] = DBGBCR2_EL1.get();
+            dbgbvr[2] = DBGBVR2_EL1.get();
+        }
+        3 => {
+            dbgbcr[3] = DBGBCR3_EL1.get();
+            dbgbvr[3] = DBGBVR3_EL1.get();
+        }
+        4 => {
+            dbgbcr[4] = DBGBCR4_EL1.get();
+            dbgbvr[4] = DBGBVR4_EL1.get();
+        }
+        5 => {
+            dbgbcr[5] = DBGBCR5_EL1.get();
+            dbgbvr[5] = DBGBVR5_EL1.get();
+        }
+        6 => {
+            dbgbcr[6] = DBGBCR6_EL1.get();
+            dbgbvr[6] = DBGBVR6_EL1.get();
+        }
+        7 => {
+            dbgbcr[7] = DBGBCR7_EL1.get();
+            dbgbvr[7] = DBGBVR7_EL1.get();
+        }
+        8 => {
+            dbgbcr[8] = DBGBCR8_EL1.get();
+            dbgbvr[8] = DBGBVR8_EL1.get();
+        }
+        9 => {
+            dbgbcr[9] = DBGBCR9_EL1.get();
+            dbgbvr[9] = DBGBVR9_EL1.get();
+        }
+        10 => {
+            dbgbcr[10] = DBGBCR10_EL1.get();
+            dbgbvr[10] = DBGBVR10_EL1.get();
+        }
+        11 => {
+            dbgbcr[11] = DBGBCR11_EL1.get();
+            dbgbvr[11] = DBGBVR11_EL1.get();
+        }
+        12 => {
+            dbgbcr[12] = DBGBCR12_EL1.get();
+            dbgbvr[12] = DBGBVR12_EL1.get();
+        }
+        13 => {
+            dbgbcr[13] = DBGBCR13_EL1.get();
+            dbgbvr[13] = DBGBVR13_EL1.get();
+        }
+        14 => {
+            dbgbcr[14] = DBGBCR14_EL1.get();
+            dbgbvr[14] = DBGBVR14_EL1.get();
+        }
+        15 => {
+            dbgbcr[15] = DBGBCR15_EL1.get();
+            dbgbvr[15] = DBGBVR15_EL1.get();
+        }
+        _ => {}
+    }
+}
+
+fn load_bp(n: usize, dbgbcr: u64, dbgbvr: u64) {
+    match n {
+        0 => {
+            DBGBCR0_EL1.set(dbgbcr);
+            DBGBVR0_EL1.set(dbgbvr);
+        }
+        1 => {
+            DBGBCR1_EL1.set(dbgbcr);
+            DBGBVR1_EL1.set(dbgbvr);
+        }
+        2 => {
+            DBGBCR2_EL1.set(dbgbcr);
+            DBGBVR2_EL1.set(dbgbvr);
+        }
+        3 => {
+            DBGBCR3_EL1.set(dbgbcr);
+            DBGBVR3_EL1.set(dbgbvr);
+        }
+        4 => {
+            DBGBCR4_EL1.set(dbgbcr);
+            DBGBVR4_EL1.set(dbgbvr);
+        }
+        5 => {
+            DBGBCR5_EL1.set(dbgbcr);
+            DBGBVR5_EL1.set(dbgbvr);
+        }
+        6 => {
+            DBGBCR6_EL1.set(dbgbcr);
+            DBGBVR6_EL1.set(dbgbvr);
+        }
+        7 => {
+            DBGBCR7_EL1.set(dbgbcr);
+            DBGBVR7_EL1.set(dbgbvr);
+        }
+        8 => {
+            DBGBCR8_EL1.set(dbgbcr);
+            DBGBVR8_EL1.set(dbgbvr);
+        }
+        9 => {
+            DBGBCR9_EL1.set(dbgbcr);
+            DBGBVR9_EL1.set(dbgbvr);
+        }
+        10 => {
+            DBGBCR10_EL1.set(dbgbcr);
+            DBGBVR10_EL1.set(dbgbvr);
+        }
+        11 => {
+            DBGBCR11_EL1.set(dbgbcr);
+            DBGBVR11_EL1.set(dbgbvr);
+        }
+        12 => {
+            DBGBCR12_EL1.set(dbgbcr);
+            DBGBVR12_EL1.set(dbgbvr);
+        }
+        13 => {
+            DBGBCR13_EL1.set(dbgbcr);
+            DBGBVR13_EL1.set(dbgbvr);
+        }
+        14 => {
+            DBGBCR14_EL1.set(dbgbcr);
+            DBGBVR14_EL1.set(dbgbvr);
+        }
+        15 => {
+            DBGBCR15_EL1.set(dbgbcr);
+            DBGBVR15_EL1.set(dbgbvr);
+        }
+        _ => {}
+    }
+}
+
+fn store_wp(n: usize, dbgwcr: &mut [u64; MAX_WPS], dbgwvr: &mut [u64; MAX_WPS]) {
+    match n {
+        0 => {
+            dbgwcr[0] = DBGWCR0_EL1.get();
+            dbgwvr[0] = DBGWVR0_EL1.get();
+        }
+        1 => {
+            dbgwcr[1] = DBGWCR1_EL1.get();
+            dbgwvr[1] = DBGWVR1_EL1.get();
+        }
+        2 => {
+            dbgwcr[2] = DBGWCR2_EL1.get();
+            dbgwvr[2] = DBGWVR2_EL1.get();
+        }
+        3 => {
+            dbgwcr[3] = DBGWCR3_EL1.get();
+            dbgwvr[3] = DBGWVR3_EL1.get();
+        }
+        4 => {
+            dbgwcr[4] = DBGWCR4_EL1.get();
+            dbgwvr[4] = DBGWVR4_EL1.get();
+        }
+        5 => {
+            dbgwcr[5] = DBGWCR5_EL1.get();
+            dbgwvr[5] = DBGWVR5_EL1.get();
+        }
+        6 => {
+            dbgwcr[6] = DBGWCR6_EL1.get();
+            dbgwvr[6] = DBGWVR6_EL1.get();
+        }
+        7 => {
+            dbgwcr[7] = DBGWCR7_EL1.get();
+            dbgwvr[7] = DBGWVR7_EL1.get();
+        }
+        8 => {
+            dbgwcr[8] = DBGWCR8_EL1.get();
+            dbgwvr[8] = DBGWVR8_EL1.get();
+        }
+        9 => {
+            dbgwcr[9] = DBGWCR9_EL1.get();
+            dbgwvr[9] = DBGWVR9_EL1.get();
+        }
+        10 => {
+            dbgwcr[10] = DBGWCR10_EL1.get();
+            dbgwvr[10] = DBGWVR10_EL1.get();
+        }
+        11 => {
+            dbgwcr[11] = DBGWCR11_EL1.get();
+            dbgwvr[11] = DBGWVR11_EL1.get();
+        }
+        12 => {
+            dbgwcr[12] = DBGWCR12_EL1.get();
+            dbgwvr[12] = DBGWVR12_EL1.get();
+        }
+        13 => {
+            dbgwcr[13] = DBGWCR13_EL1.get();
+            dbgwvr[13] = DBGWVR13_EL1.get();
+        }
+        14 => {
+            dbgwcr[14] = DBGWCR14_EL1.get();
+            dbgwvr[14] = DBGWVR14_EL1.get();
+        }
+        15 => {
+            dbgwcr[15] = DBGWCR15_EL1.get();
+            dbgwvr[15] = DBGWVR15_EL1.get();
+        }
+        _ => {}
+    }
+}
+
+fn load_wp(n: usize, dbgwcr: u64, dbgwvr: u64) {
+    match n {
+        0 => {
+            DBGWCR0_EL1.set(dbgwcr);
+            DBGWVR0_EL1.set(dbgwvr);
+        }
+        1 => {
+            DBGWCR1_EL1.set(dbgwcr);
+            DBGWVR1_EL1.set(dbgwvr);
+        }
+        2 => {
+            DBGWCR2_EL1.set(dbgwcr);
+            DBGWVR2_EL1.set(dbgwvr);
+        }
+        3 => {
+            DBGWCR3_EL1.set(dbgwcr);
+            DBGWVR3_EL1.set(dbgwvr);
+        }
+        4 => {
+            DBGWCR4_EL1.set(dbgwcr);
+            DBGWVR4_EL1.set(dbgwvr);
+        }
+        5 => {
+            DBGWCR5_EL1.set(dbgwcr);
+            DBGWVR5_EL1.set(dbgwvr);
+        }
+        6 => {
+            DBGWCR6_EL1.set(dbgwcr);
+            DBGWVR6_EL1.set(dbgwvr);
+        }
+        7 => {
+            DBGWCR7_EL1.set(dbgwcr);
+            DBGWVR7_EL1.set(dbgwvr);
+        }
+        8 => {
+            DBGWCR8_EL1.set(dbgwcr);
+            DBGWVR8_EL1.set(dbgwvr);
+        }
+        9 => {
+            DBGWCR9_EL1.set(dbgwcr);
+            DBGWVR9_EL1.set(dbgwvr);
+        }
+        10 => {
+            DBGWCR10_EL1.set(dbgwcr);
+            DBGWVR10_EL1.set(dbgwvr);
+        }
+        11 => {
+            DBGWCR11_EL1.set(dbgwcr);
+            DBGWVR11_EL1.set(dbgwvr);
+        }
+        12 => {
+            DBGWCR12_EL1.set(dbgwcr);
+            DBGWVR12_EL1.set(dbgwvr);
+        }
+        13 => {
+            DBGWCR13_EL1.set(dbgwcr);
+            DBGWVR13_EL1.set(dbgwvr);
+        }
+        14 => {
+            DBGWCR14_EL1.set(dbgwcr);
+            DBGWVR14_EL1.set(dbgwvr);
+        }
+        15 => {
+            DBGWCR15_EL1.set(dbgwcr);
+            DBGWVR15_EL1.set(dbgwvr);
+        }
+        _ => {}
+    }
+}
+
+/// Returns DBGBCR<n>_EL1 and DBGBVR<n>_EL1.
+pub fn get_bp(n: usize) -> (u64, u64) {
+    if n >= MAX_BPS {
+        error!("Index out of bounds");
+        return (0, 0);
+    }
+    let (mut dbgbcr, mut dbgbvr) = ([0; MAX_BPS], [0; MAX_BPS]);
+    store_bp(n, &mut dbgbcr, &mut dbgbvr);
+    (dbgbcr[n], dbgbvr[n])
+}
+
+pub fn set_bp(n: usize, dbgbcr: u64, dbgbvr: u64) {
+    load_bp(n, dbgbcr, dbgbvr);
+}
+
+/// Returns DBGWCR<n>_EL1 and DBGWVR<n>_EL1.
+pub fn get_wp(n: usize) -> (u64, u64) {
+    if n >= MAX_WPS {
+        error!("Index out of bounds");
+        return (0, 0);
+    }
+    let (mut dbgwcr, mut dbgwvr) = ([0; MAX_WPS], [0; MAX_WPS]);
+    store_wp(n, &mut dbgwcr, &mut dbgwvr);
+    (dbgwcr[n], dbgwvr[n])
+}
+
+pub fn set_wp(n: usize, dbgwcr: u64, dbgwvr: u64) {
+    load_wp(n, dbgwcr, dbgwvr);
+}
+
+pub fn set_bp_regs(cnt: usize, dbgbcr: &[u64; MAX_BPS], dbgbvr: &[u64; MAX_BPS]) {
+    if cnt > MAX_BPS {
+        error!("Index out of bounds");
+        return;
+    }
+    for i in 0..cnt {
+        load_bp(i, dbgbcr[i], dbgbvr[i]);
+    }
+}
+
+pub fn get_bp_regs(cnt: usize, dbgbcr: &mut [u64; MAX_BPS], dbgbvr: &mut [u64; MAX_BPS]) {
+    if cnt > MAX_BPS {
+        error!("Index out of bounds");
+        return;
+    }
+    for i in 0..cnt {
+        store_bp(i, dbgbcr, dbgbvr);
+    }
+}
+
+pub fn set_wp_regs(cnt: usize, dbgwcr: &[u64; MAX_WPS], dbgwvr: &[u64; MAX_WPS]) {
+    if cnt > MAX_WPS {
+        error!("Index out of bounds");
+        return;
+    }
+    for i in 0..cnt {
+        load_wp(i, dbgwcr[i], dbgwvr[i]);
+    }
+}
+
+pub fn get_wp_regs(cnt: usize, dbgwcr: &mut [u64; MAX_WPS], dbgwvr: &mut [u64; MAX_WPS]) {
+    if cnt > MAX_WPS {
+        error!("Index out of bounds");
+        return;
+    }
+    for i in 0..cnt {
+        store_wp(i, dbgwcr, dbgwvr);
+    }
+}
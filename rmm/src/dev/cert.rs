@@ -0,0 +1,77 @@
+//! The certificate chain of a device, as retrieved through SPDM GET_CERTIFICATE
+//!
+//! Only what it takes to find the public key of the leaf certificate is
+//! parsed here. Whether the chain is rooted in a trusted CA is up to the host
+//! and to the realm, which gets its digest through the VDEV.
+
+use crate::rmi::error::Error;
+
+// SPDM certificate chain header: Length (2), Reserved (2), then RootHash
+const CHAIN_HEADER_SIZE: usize = 4;
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_SEQUENCE: u8 = 0x30;
+// [0] EXPLICIT Version of TBSCertificate
+const TAG_VERSION: u8 = 0xA0;
+
+/// Splits the DER element at the start of `der` into (tag, content, rest).
+fn element(der: &[u8]) -> Result<(u8, &[u8], &[u8]), Error> {
+    let (&tag, der) = der.split_first().ok_or(Error::RmiErrorInput)?;
+    let (&first, der) = der.split_first().ok_or(Error::RmiErrorInput)?;
+    let (len, der) = match first {
+        0..=0x7f => (first as usize, der),
+        0x81..=0x83 => {
+            let n = (first & 0x7f) as usize;
+            let bytes = der.get(..n).ok_or(Error::RmiErrorInput)?;
+            let len = bytes.iter().fold(0, |len, b| (len << 8) | *b as usize);
+            (len, &der[n..])
+        }
+        _ => return Err(Error::RmiErrorInput),
+    };
+    let content = der.get(..len).ok_or(Error::RmiErrorInput)?;
+    Ok((tag, content, &der[len..]))
+}
+
+/// Returns the content of the element at the start of `der`
+/// if it has `tag`, along with the rest.
+fn expect(der: &[u8], tag: u8) -> Result<(&[u8], &[u8]), Error> {
+    match element(der)? {
+        (t, content, rest) if t == tag => Ok((content, rest)),
+        _ => Err(Error::RmiErrorInput),
+    }
+}
+
+/// Returns the public key of the leaf certificate in `chain`,
+/// as the SEC1 encoded point of subjectPublicKey.
+pub fn leaf_pubkey(chain: &[u8], hash_size: usize) -> Result<&[u8], Error> {
+    let mut certs = chain
+        .get(CHAIN_HEADER_SIZE + hash_size..)
+        .ok_or(Error::RmiErrorInput)?;
+    let leaf = loop {
+        let (cert, rest) = expect(certs, TAG_SEQUENCE)?;
+        if rest.is_empty() {
+            break cert;
+        }
+        certs = rest;
+    };
+
+    let (tbs, _) = expect(leaf, TAG_SEQUENCE)?;
+    let mut tbs = tbs;
+    if tbs.first() == Some(&TAG_VERSION) {
+        tbs = element(tbs)?.2;
+    }
+    tbs = expect(tbs, TAG_INTEGER)?.1; // serialNumber
+    for _ in 0..4 {
+        // signature, issuer, validity, subject
+        tbs = expect(tbs, TAG_SEQUENCE)?.1;
+    }
+    let (spki, _) = expect(tbs, TAG_SEQUENCE)?;
+    let (_algorithm, spki) = expect(spki, TAG_SEQUENCE)?;
+    let (key, _) = expect(spki, TAG_BIT_STRING)?;
+    // No unused bits
+    match key.split_first() {
+        Some((0, key)) if !key.is_empty() => Ok(key),
+        _ => Err(Error::RmiErrorInput),
+    }
+}
@@ -0,0 +1,49 @@
+//! Device memory which realms map through DEV_MEM_MAP
+//!
+//! The non-coherent memory of a PDEV is out of the NS DRAM which the granule
+//! status table covers, so its granules mapped to realms are tracked here.
+//! A granule is delegated while it's mapped. This keeps the host away from it
+//! and lets it be mapped at a single IPA of a single realm.
+
+use crate::asm::{smc, SMC_SUCCESS};
+use crate::rmi::error::Error;
+use crate::rmi::gpt::{MARK_NONSECURE, MARK_REALM};
+use alloc::collections::BTreeSet;
+use spinning_top::Spinlock;
+
+static MAPPED: Spinlock<BTreeSet<usize>> = Spinlock::new(BTreeSet::new());
+
+/// Delegates the device granule at `pa` to be mapped.
+/// Fails if the granule is mapped already.
+pub fn delegate(pa: usize) -> Result<(), Error> {
+    let mut mapped = MAPPED.lock();
+    if mapped.contains(&pa) {
+        warn!("Device granule {:X} is mapped already", pa);
+        return Err(Error::RmiErrorInput);
+    }
+    if smc(MARK_REALM, &[pa])[0] != SMC_SUCCESS {
+        return Err(Error::RmiErrorInput);
+    }
+    mapped.insert(pa);
+    Ok(())
+}
+
+/// Gives the device granule at `pa` back to the host once it's unmapped.
+pub fn undelegate(pa: usize) -> Result<(), Error> {
+    let mut mapped = MAPPED.lock();
+    if !mapped.remove(&pa) {
+        return Err(Error::RmiErrorInput);
+    }
+    if smc(MARK_NONSECURE, &[pa])[0] != SMC_SUCCESS {
+        panic!(
+            "A delegated device granule should only be undelegated on request from RMM. {:X}",
+            pa
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub fn is_mapped(pa: usize) -> bool {
+    MAPPED.lock().contains(&pa)
+}
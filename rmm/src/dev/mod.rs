@@ -0,0 +1,118 @@
+//! Realm device assignment (RMM 1.1, DA)
+//!
+//! A PDEV represents a physical device (a PCIe function) which the host
+//! has put under the control of the RMM. A VDEV is an interface (TDI) of
+//! a PDEV assigned to a realm.
+//!
+//! The RMM authenticates devices and manages their interfaces by
+//! talking SPDM/TDISP to them. Messages are relayed by the host through
+//! PDEV_COMMUNICATE and VDEV_COMMUNICATE, so each device object keeps
+//! the progress of its operation in a `Requester`.
+
+pub mod cert;
+pub mod mem;
+pub mod pdev;
+#[cfg(any(test, miri, fuzzing))]
+pub mod soft;
+pub mod spdm;
+pub mod tdisp;
+pub mod vdev;
+
+use crate::rmi::error::Error;
+use spdm::{Operation, Progress, Requester, Step};
+
+// RmiDevCommStatus: what the host reports on entry to *_COMMUNICATE
+pub const COMM_STATUS_NONE: u64 = 0;
+pub const COMM_STATUS_RESPONSE: u64 = 1;
+pub const COMM_STATUS_ERROR: u64 = 2;
+
+// RmiDevCommExitFlags: what the RMM asks the host on exit from *_COMMUNICATE
+pub const COMM_EXIT_FLAG_SEND: u64 = 1 << 0;
+
+/// A request to be relayed to the device by the host
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Exchange {
+    pub protocol: u8,
+    pub len: usize,
+}
+
+/// Common interface of the device objects driven by *_COMMUNICATE
+pub trait DevObject {
+    fn requester(&mut self) -> &mut Requester;
+
+    /// Returns the operation to run in the current state.
+    fn operation(&self) -> Operation;
+
+    /// Writes the request for `step` into `buf` and returns its length.
+    fn build_request(&self, step: Step, offset: usize, buf: &mut [u8]) -> usize;
+
+    /// Consumes the response to `step`.
+    fn handle_response(
+        &mut self,
+        step: Step,
+        offset: usize,
+        resp: &[u8],
+    ) -> Result<Progress, Error>;
+
+    /// Moves the object to the state following `op`.
+    fn on_complete(&mut self, op: Operation) -> Result<(), Error>;
+
+    /// Moves the object to its error state.
+    fn on_error(&mut self);
+}
+
+/// Drives the operation of `obj` by one exchange.
+///
+/// `status` and `resp` are the ones reported by the host for the request
+/// issued on the previous call, if any. Returns the next request written
+/// into `req`, or `None` if the operation has finished (or failed).
+pub fn communicate<T: DevObject>(
+    obj: &mut T,
+    status: u64,
+    resp: &[u8],
+    req: &mut [u8],
+) -> Result<Option<Exchange>, Error> {
+    if obj.requester().is_idle() {
+        let op = obj.operation();
+        if op == Operation::None {
+            return Err(Error::RmiErrorInput);
+        }
+        obj.requester().start(op);
+    }
+
+    if let Some(step) = obj.requester().pending() {
+        let offset = obj.requester().offset();
+        let result = match status {
+            COMM_STATUS_RESPONSE => obj.handle_response(step, offset, resp),
+            // The host has not delivered the request. Issue it again.
+            COMM_STATUS_NONE => Ok(Progress::Again),
+            _ => Err(Error::RmiErrorInput),
+        };
+        match result {
+            Ok(progress) => obj.requester().advance(progress),
+            Err(_) => {
+                warn!("Device communication failed at {:?}", step);
+                obj.requester().abort();
+                obj.on_error();
+                return Ok(None);
+            }
+        }
+    }
+
+    if obj.requester().is_done() {
+        let op = obj.requester().op();
+        obj.requester().abort();
+        obj.on_complete(op)?;
+        return Ok(None);
+    }
+
+    let requester = obj.requester();
+    let step = requester.current().ok_or(Error::RmiErrorInput)?;
+    let offset = requester.offset();
+    requester.set_pending(true);
+    let len = obj.build_request(step, offset, req);
+    Ok(Some(Exchange {
+        protocol: step.protocol(),
+        len,
+    }))
+}
@@ -0,0 +1,374 @@
+use super::cert;
+use super::spdm::{self, Operation, Progress, Requester, Step, SPDM_HEADER_SIZE};
+use super::DevObject;
+use crate::asm::rndr;
+use crate::granule::{GranuleState, GRANULE_SIZE};
+use crate::host::DataPage;
+use crate::measurement::Hasher;
+use crate::rmi::error::Error;
+use crate::{get_granule, get_granule_if};
+
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use vmsa::guard::Content;
+
+// The number of auxiliary granules of a PDEV.
+// aux[0] keeps the certificate chain of the device.
+pub const PDEV_NUM_AUX: usize = 1;
+pub const PDEV_MAX_NCOH_RANGES: usize = 16;
+pub const PDEV_MAX_PUBKEY_SIZE: usize = 512;
+pub const PDEV_MAX_DIGEST_SIZE: usize = 64;
+// GET_VERSION, GET_CAPABILITIES and NEGOTIATE_ALGORITHMS with their responses,
+// which the signature of KEY_EXCHANGE_RSP covers
+pub const PDEV_MAX_VCA_SIZE: usize = 1024;
+// The largest request built by a PDEV (KEY_EXCHANGE)
+const MAX_REQUEST_SIZE: usize = 64;
+
+// RmiSignatureAlgorithm
+pub const PUBKEY_ALGO_ECDSA_P256: u8 = 0;
+pub const PUBKEY_ALGO_ECDSA_P384: u8 = 1;
+pub const PUBKEY_ALGO_RSASSA_3072: u8 = 2;
+
+// RmiPdevState
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum State {
+    New = 0,
+    NeedsKey = 1,
+    HasKey = 2,
+    Ready = 3,
+    IdeResetting = 4,
+    Stopping = 5,
+    Stopped = 6,
+    Error = 7,
+}
+
+#[derive(Debug)]
+pub struct Pdev {
+    state: State,
+    pdev_id: u64,
+    segment_id: u64,
+    ecam_addr: u64,
+    root_id: u64,
+    hash_algo: u8,
+    aux: [usize; PDEV_NUM_AUX],
+    num_ncoh: usize,
+    ncoh_base: [usize; PDEV_MAX_NCOH_RANGES],
+    ncoh_top: [usize; PDEV_MAX_NCOH_RANGES],
+    cert_len: usize,
+    cert_digest: [u8; PDEV_MAX_DIGEST_SIZE],
+    pubkey_algo: u8,
+    pubkey_len: usize,
+    pubkey: [u8; PDEV_MAX_PUBKEY_SIZE],
+    vca_len: usize,
+    vca: [u8; PDEV_MAX_VCA_SIZE],
+    random: [u8; spdm::RANDOM_SIZE],
+    num_vdevs: usize,
+    requester: Requester,
+}
+
+// Pdev is placed in the PDEV granule, which the VCA transcript takes a good part of
+const _: () = {
+    crate::const_assert!(core::mem::size_of::<Pdev>() <= GRANULE_SIZE);
+};
+
+impl Pdev {
+    #[allow(clippy::too_many_arguments)]
+    pub fn init(
+        &mut self,
+        pdev_id: u64,
+        segment_id: u64,
+        ecam_addr: u64,
+        root_id: u64,
+        hash_algo: u8,
+        aux: &[usize],
+        ncoh_base: &[usize],
+        ncoh_top: &[usize],
+    ) {
+        self.state = State::New;
+        self.pdev_id = pdev_id;
+        self.segment_id = segment_id;
+        self.ecam_addr = ecam_addr;
+        self.root_id = root_id;
+        self.hash_algo = hash_algo;
+        self.aux[..aux.len()].copy_from_slice(aux);
+        self.num_ncoh = ncoh_base.len();
+        self.ncoh_base[..ncoh_base.len()].copy_from_slice(ncoh_base);
+        self.ncoh_top[..ncoh_top.len()].copy_from_slice(ncoh_top);
+        self.cert_len = 0;
+        self.cert_digest = [0; PDEV_MAX_DIGEST_SIZE];
+        self.pubkey_algo = 0;
+        self.pubkey_len = 0;
+        self.pubkey = [0; PDEV_MAX_PUBKEY_SIZE];
+        self.vca_len = 0;
+        self.vca = [0; PDEV_MAX_VCA_SIZE];
+        self.random = [0; spdm::RANDOM_SIZE];
+        self.num_vdevs = 0;
+        self.requester = Requester::default();
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    pub fn set_state(&mut self, state: State) {
+        self.state = state;
+    }
+
+    pub fn pdev_id(&self) -> u64 {
+        self.pdev_id
+    }
+
+    pub fn hash_algo(&self) -> u8 {
+        self.hash_algo
+    }
+
+    pub fn aux(&self) -> &[usize] {
+        &self.aux
+    }
+
+    pub fn cert_digest(&self) -> &[u8] {
+        &self.cert_digest
+    }
+
+    pub fn num_vdevs(&self) -> usize {
+        self.num_vdevs
+    }
+
+    pub fn inc_vdevs(&mut self) {
+        self.num_vdevs += 1;
+    }
+
+    pub fn dec_vdevs(&mut self) {
+        self.num_vdevs -= 1;
+    }
+
+    /// Returns true if [base, top) lies within one of the
+    /// non-coherent address ranges of the device.
+    pub fn ncoh_contains(&self, base: usize, top: usize) -> bool {
+        base < top
+            && self.ncoh_base[..self.num_ncoh]
+                .iter()
+                .zip(self.ncoh_top[..self.num_ncoh].iter())
+                .any(|(b, t)| *b <= base && top <= *t)
+    }
+
+    /// Sets the public key which the device signs KEY_EXCHANGE_RSP with.
+    ///
+    /// The key must be the one of the leaf certificate retrieved from the device.
+    /// RSASSA-3072 isn't supported, as there is no RSA verifier in the RMM.
+    pub fn set_pubkey(&mut self, algo: u8, key: &[u8]) -> Result<(), Error> {
+        match algo {
+            PUBKEY_ALGO_ECDSA_P256 | PUBKEY_ALGO_ECDSA_P384 => {}
+            _ => return Err(Error::RmiErrorInput),
+        }
+        if key.is_empty() || key.len() > PDEV_MAX_PUBKEY_SIZE {
+            return Err(Error::RmiErrorInput);
+        }
+
+        let aux_granule = get_granule_if!(self.aux[0], GranuleState::PdevAux)?;
+        let cert = aux_granule.content::<DataPage>()?;
+        let hash_size = Hasher::from_hash_algo(self.hash_algo)?.output_size();
+        if cert::leaf_pubkey(&cert.as_slice()[..self.cert_len], hash_size)? != key {
+            warn!("The public key isn't the one of the device certificate");
+            return Err(Error::RmiErrorInput);
+        }
+
+        self.pubkey_algo = algo;
+        self.pubkey_len = key.len();
+        self.pubkey[..key.len()].copy_from_slice(key);
+        self.refresh_random()
+    }
+
+    pub fn abort(&mut self) {
+        self.requester.abort();
+        // A KEY_EXCHANGE issued again must not accept the response to the aborted one
+        let _ = self.refresh_random();
+    }
+
+    fn refresh_random(&mut self) -> Result<(), Error> {
+        for chunk in self.random.chunks_mut(core::mem::size_of::<u64>()) {
+            let random = rndr().ok_or(Error::RmiErrorInput)?;
+            chunk.copy_from_slice(&random.to_le_bytes()[..chunk.len()]);
+        }
+        Ok(())
+    }
+
+    // Keeps a request and its response of VCA for the transcript of KEY_EXCHANGE
+    fn record_vca(&mut self, step: Step, resp: &[u8]) -> Result<(), Error> {
+        if step == Step::GetVersion {
+            self.vca_len = 0;
+        }
+        let mut req = [0u8; MAX_REQUEST_SIZE];
+        let req_len = self.build_request(step, 0, &mut req);
+        let end = self.vca_len + req_len + resp.len();
+        if end > PDEV_MAX_VCA_SIZE {
+            return Err(Error::RmiErrorInput);
+        }
+        let vca = &mut self.vca[self.vca_len..end];
+        vca[..req_len].copy_from_slice(&req[..req_len]);
+        vca[req_len..].copy_from_slice(resp);
+        self.vca_len = end;
+        Ok(())
+    }
+
+    /// Verifies the signature of KEY_EXCHANGE_RSP, which covers the transcript
+    /// VCA || Hash(certificate chain) || KEY_EXCHANGE || KEY_EXCHANGE_RSP
+    /// up to the signature.
+    fn verify_key_exchange(&self, resp: &[u8]) -> Result<(), Error> {
+        let sig_size = match self.pubkey_algo {
+            PUBKEY_ALGO_ECDSA_P256 => 64,
+            PUBKEY_ALGO_ECDSA_P384 => 96,
+            _ => return Err(Error::RmiErrorInput),
+        };
+        let body = spdm::response_body(Step::KeyExchange, resp)?;
+        let (sig_offset, sig) = spdm::key_exchange_signature(body, sig_size)?;
+        let signed = &resp[..SPDM_HEADER_SIZE + sig_offset];
+
+        let mut req = [0u8; MAX_REQUEST_SIZE];
+        let req_len = self.build_request(Step::KeyExchange, 0, &mut req);
+
+        let hasher = Hasher::from_hash_algo(self.hash_algo)?;
+        let hash_size = hasher.output_size();
+        let mut th = [0u8; PDEV_MAX_DIGEST_SIZE];
+        hasher.hash_fields_into(&mut th, |h| {
+            h.hash(&self.vca[..self.vca_len]);
+            h.hash(&self.cert_digest[..hash_size]);
+            h.hash(&req[..req_len]);
+            h.hash(signed);
+        })?;
+        let mut digest = [0u8; PDEV_MAX_DIGEST_SIZE];
+        hasher.hash_fields_into(&mut digest, |h| {
+            h.hash(spdm::signing_prefix(spdm::KEY_EXCHANGE_RSP_CONTEXT));
+            h.hash(&th[..hash_size]);
+        })?;
+
+        let key = &self.pubkey[..self.pubkey_len];
+        let digest = &digest[..hash_size];
+        let verified = match self.pubkey_algo {
+            PUBKEY_ALGO_ECDSA_P256 => {
+                p256::ecdsa::VerifyingKey::from_sec1_bytes(key).and_then(|key| {
+                    key.verify_prehash(digest, &p256::ecdsa::Signature::from_slice(sig)?)
+                })
+            }
+            _ => p384::ecdsa::VerifyingKey::from_sec1_bytes(key).and_then(|key| {
+                key.verify_prehash(digest, &p384::ecdsa::Signature::from_slice(sig)?)
+            }),
+        };
+        verified.map_err(|_| {
+            warn!("The signature of KEY_EXCHANGE_RSP doesn't match");
+            Error::RmiErrorInput
+        })
+    }
+
+    fn store_cert_portion(&mut self, offset: usize, portion: &[u8]) -> Result<usize, Error> {
+        let end = offset + portion.len();
+        if end > GRANULE_SIZE {
+            return Err(Error::RmiErrorInput);
+        }
+        let mut aux_granule = get_granule_if!(self.aux[0], GranuleState::PdevAux)?;
+        let mut cert = aux_granule.content_mut::<DataPage>()?;
+        cert.as_mut_slice()[offset..end].copy_from_slice(portion);
+        Ok(end)
+    }
+
+    fn measure_cert(&mut self) -> Result<(), Error> {
+        let aux_granule = get_granule_if!(self.aux[0], GranuleState::PdevAux)?;
+        let cert = aux_granule.content::<DataPage>()?;
+        let chain = &cert.as_slice()[..self.cert_len];
+        Hasher::from_hash_algo(self.hash_algo)?
+            .hash_fields_into(&mut self.cert_digest, |h| h.hash(chain))?;
+        Ok(())
+    }
+}
+
+impl DevObject for Pdev {
+    fn requester(&mut self) -> &mut Requester {
+        &mut self.requester
+    }
+
+    fn operation(&self) -> Operation {
+        match self.state {
+            State::New => Operation::Connect,
+            State::HasKey => Operation::Session,
+            State::IdeResetting => Operation::KeyUpdate,
+            State::Stopping => Operation::EndSession,
+            _ => Operation::None,
+        }
+    }
+
+    fn build_request(&self, step: Step, offset: usize, buf: &mut [u8]) -> usize {
+        spdm::build_request(step, offset, 0, &self.random, buf)
+    }
+
+    fn handle_response(
+        &mut self,
+        step: Step,
+        offset: usize,
+        resp: &[u8],
+    ) -> Result<Progress, Error> {
+        let body = spdm::response_body(step, resp)?;
+        match step {
+            Step::GetVersion | Step::GetCapabilities | Step::NegotiateAlgorithms => {
+                self.record_vca(step, resp)?;
+                return Ok(Progress::Next);
+            }
+            Step::KeyExchange => {
+                self.verify_key_exchange(resp)?;
+                return Ok(Progress::Next);
+            }
+            Step::GetCertificate => {}
+            // TODO: set up the secure session keys, which FINISH and the
+            // messages after it are protected with.
+            _ => return Ok(Progress::Next),
+        }
+
+        let (portion, remainder) = spdm::certificate_portion(body)?;
+        let end = self.store_cert_portion(offset, portion)?;
+        if remainder > 0 {
+            if portion.is_empty() {
+                return Err(Error::RmiErrorInput);
+            }
+            self.requester.set_offset(end);
+            return Ok(Progress::Again);
+        }
+        self.cert_len = end;
+        self.measure_cert()?;
+        Ok(Progress::Next)
+    }
+
+    fn on_complete(&mut self, op: Operation) -> Result<(), Error> {
+        self.state = match op {
+            Operation::Connect => State::NeedsKey,
+            Operation::Session | Operation::KeyUpdate => State::Ready,
+            Operation::EndSession => State::Stopped,
+            _ => return Err(Error::RmiErrorInput),
+        };
+        Ok(())
+    }
+
+    fn on_error(&mut self) {
+        self.state = State::Error;
+    }
+}
+
+impl Content for Pdev {}
+
+impl safe_abstraction::raw_ptr::RawPtr for Pdev {}
+
+impl safe_abstraction::raw_ptr::SafetyChecked for Pdev {}
+
+impl safe_abstraction::raw_ptr::SafetyAssured for Pdev {
+    fn is_initialized(&self) -> bool {
+        // The initialization of this memory is guaranteed
+        // according to the RMM Specification A2.2.4 Granule Wiping.
+        // This instance belongs to a PDEV Granule and has been initialized.
+        true
+    }
+
+    fn verify_ownership(&self) -> bool {
+        // The ownership of this instance is exclusively ensured by the RMM.
+        // It is accessed only within the lock scope of the `get_granule*` macros
+        // through the `content*` functions.
+        true
+    }
+}
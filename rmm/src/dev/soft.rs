@@ -0,0 +1,349 @@
+//! A software PDEV which answers SPDM/TDISP requests in place of a device.
+//!
+//! This lets the whole device assignment flow be exercised by the mock host
+//! in `test_utils` without hardware. It authenticates itself by signing
+//! KEY_EXCHANGE_RSP with the key of its leaf certificate (with SHA-256), but
+//! it doesn't set up a secure session. The certificates themselves aren't
+//! signed, as the RMM leaves the trust of the chain to the host and the realm.
+
+use super::spdm::{self, code, SPDM_HEADER_SIZE, SPDM_VERSION};
+use super::tdisp::{self, msg, START_NONCE_SIZE};
+
+extern crate alloc;
+use alloc::vec::Vec;
+use p256::ecdsa::signature::hazmat::PrehashSigner;
+use p256::ecdsa::{Signature, SigningKey};
+use sha2::{Digest, Sha256};
+
+pub const REPORT_SIZE: usize = 64;
+pub const MEAS_RECORD_SIZE: usize = 48;
+
+const SPDM_ERROR_UNSUPPORTED_REQUEST: u8 = 0x07;
+
+const ROOT_KEY: [u8; 32] = [0x5a; 32];
+const DEVICE_KEY: [u8; 32] = [0x3c; 32];
+const RSP_SESSION_ID: u16 = 1;
+
+// DER tags and encoded OIDs of the certificates
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_UTF8_STRING: u8 = 0x0C;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const VERSION_V3: &[u8] = &[0xA0, 0x03, 0x02, 0x01, 0x02];
+const OID_ECDSA_WITH_SHA256: &[u8] = &[0x06, 0x08, 0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x04, 0x03, 0x02];
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x06, 0x07, 0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01];
+const OID_PRIME256V1: &[u8] = &[0x06, 0x08, 0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x07];
+const OID_COMMON_NAME: &[u8] = &[0x06, 0x03, 0x55, 0x04, 0x03];
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = alloc::vec![tag];
+    match content.len() {
+        len @ 0..=0x7f => out.push(len as u8),
+        len @ 0x80..=0xff => out.extend_from_slice(&[0x81, len as u8]),
+        len => {
+            out.push(0x82);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+fn name(common_name: &str) -> Vec<u8> {
+    let attr = [
+        OID_COMMON_NAME,
+        &der(TAG_UTF8_STRING, common_name.as_bytes()),
+    ]
+    .concat();
+    der(TAG_SEQUENCE, &der(TAG_SET, &der(TAG_SEQUENCE, &attr)))
+}
+
+fn public_key_of(key: &[u8; 32]) -> Vec<u8> {
+    let key = SigningKey::from_slice(key).unwrap();
+    key.verifying_key()
+        .to_encoded_point(false)
+        .as_bytes()
+        .to_vec()
+}
+
+fn certificate(serial: u8, issuer: &str, subject: &str, key: &[u8; 32]) -> Vec<u8> {
+    let validity = [
+        der(TAG_UTC_TIME, b"250101000000Z"),
+        der(TAG_UTC_TIME, b"491231235959Z"),
+    ]
+    .concat();
+    let algorithm = [OID_EC_PUBLIC_KEY, OID_PRIME256V1].concat();
+    let spki = [
+        der(TAG_SEQUENCE, &algorithm),
+        der(
+            TAG_BIT_STRING,
+            &[&[0], public_key_of(key).as_slice()].concat(),
+        ),
+    ]
+    .concat();
+    let tbs = [
+        VERSION_V3,
+        &der(TAG_INTEGER, &[serial]),
+        &der(TAG_SEQUENCE, OID_ECDSA_WITH_SHA256),
+        &name(issuer),
+        &der(TAG_SEQUENCE, &validity),
+        &name(subject),
+        &der(TAG_SEQUENCE, &spki),
+    ]
+    .concat();
+    let cert = [
+        der(TAG_SEQUENCE, &tbs),
+        der(TAG_SEQUENCE, OID_ECDSA_WITH_SHA256),
+        der(TAG_BIT_STRING, &[0; 65]),
+    ]
+    .concat();
+    der(TAG_SEQUENCE, &cert)
+}
+
+/// The certificate chain of slot 0 in the SPDM format, with SHA-256.
+/// Its names are long enough for it to be transferred in several
+/// CERTIFICATE responses.
+pub fn cert_chain() -> Vec<u8> {
+    let root_name = "Islet software PDEV root CA ".repeat(16);
+    let device_name = "Islet software PDEV ".repeat(16);
+    let root = certificate(1, &root_name, &root_name, &ROOT_KEY);
+    let leaf = certificate(2, &root_name, &device_name, &DEVICE_KEY);
+
+    let root_hash = Sha256::digest(&root);
+    let len = 4 + root_hash.len() + root.len() + leaf.len();
+    [
+        &(len as u16).to_le_bytes(),
+        &[0, 0],
+        root_hash.as_slice(),
+        &root,
+        &leaf,
+    ]
+    .concat()
+}
+
+/// The public key of the device (SEC1, uncompressed P-256),
+/// which the host sets through PDEV_SET_PUBKEY.
+pub fn public_key() -> Vec<u8> {
+    public_key_of(&DEVICE_KEY)
+}
+
+pub fn interface_report() -> Vec<u8> {
+    (0..REPORT_SIZE).map(|i| (i * 3 + 1) as u8).collect()
+}
+
+pub fn measurement_record() -> Vec<u8> {
+    (0..MEAS_RECORD_SIZE).map(|i| (i * 5 + 2) as u8).collect()
+}
+
+#[derive(Debug, Default)]
+pub struct SoftPdev {
+    tdi_id: u32,
+    nonce: [u8; START_NONCE_SIZE],
+    locked: bool,
+    started: bool,
+    // VCA of the transcript which KEY_EXCHANGE_RSP is signed over
+    vca: Vec<u8>,
+    forge_signature: bool,
+}
+
+impl SoftPdev {
+    pub fn new(tdi_id: u32) -> Self {
+        Self {
+            tdi_id,
+            nonce: [0xA5; START_NONCE_SIZE],
+            ..Default::default()
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.started
+    }
+
+    /// Makes KEY_EXCHANGE_RSP carry a signature which doesn't verify.
+    pub fn forge_signature(&mut self) {
+        self.forge_signature = true;
+    }
+
+    /// Writes the response to `req` into `resp` and returns its length.
+    pub fn respond(&mut self, req: &[u8], resp: &mut [u8]) -> usize {
+        if req.len() < SPDM_HEADER_SIZE {
+            return spdm_error(resp);
+        }
+        let len = self.respond_to(req, resp);
+        match req[1] {
+            code::GET_VERSION => self.vca = [req, &resp[..len]].concat(),
+            code::GET_CAPABILITIES | code::NEGOTIATE_ALGORITHMS => {
+                self.vca.extend_from_slice(req);
+                self.vca.extend_from_slice(&resp[..len]);
+            }
+            _ => {}
+        }
+        len
+    }
+
+    fn respond_to(&mut self, req: &[u8], resp: &mut [u8]) -> usize {
+        match req[1] {
+            code::GET_VERSION => {
+                // VersionNumberEntryCount: 1, VersionNumberEntry: 1.2
+                header(resp, code::VERSION);
+                resp[0] = spdm::SPDM_VERSION_10;
+                resp[4..8].copy_from_slice(&[0, 1, 0x00, 0x12]);
+                8
+            }
+            code::GET_CAPABILITIES => simple(resp, code::CAPABILITIES),
+            code::NEGOTIATE_ALGORITHMS => simple(resp, code::ALGORITHMS),
+            code::GET_DIGESTS => {
+                header(resp, code::DIGESTS);
+                resp[SPDM_HEADER_SIZE..SPDM_HEADER_SIZE + 32].fill(0);
+                SPDM_HEADER_SIZE + 32
+            }
+            code::GET_CERTIFICATE => self.certificate(req, resp),
+            code::KEY_EXCHANGE => self.key_exchange(req, resp),
+            code::FINISH => simple(resp, code::FINISH_RSP),
+            code::KEY_UPDATE => simple(resp, code::KEY_UPDATE_ACK),
+            code::END_SESSION => simple(resp, code::END_SESSION_ACK),
+            code::GET_MEASUREMENTS => {
+                let record = measurement_record();
+                header(resp, code::MEASUREMENTS);
+                let body = &mut resp[SPDM_HEADER_SIZE..];
+                body[0] = 1;
+                body[1..4].copy_from_slice(&(record.len() as u32).to_le_bytes()[..3]);
+                body[4..4 + record.len()].copy_from_slice(&record);
+                SPDM_HEADER_SIZE + 4 + record.len()
+            }
+            code::VENDOR_DEFINED_REQUEST => self.tdisp(&req[SPDM_HEADER_SIZE..], resp),
+            _ => spdm_error(resp),
+        }
+    }
+
+    fn certificate(&self, req: &[u8], resp: &mut [u8]) -> usize {
+        if req.len() < 8 {
+            return spdm_error(resp);
+        }
+        let chain = cert_chain();
+        let offset = u16::from_le_bytes([req[4], req[5]]) as usize;
+        let length = u16::from_le_bytes([req[6], req[7]]) as usize;
+        if offset >= chain.len() {
+            return spdm_error(resp);
+        }
+        let portion = core::cmp::min(length, chain.len() - offset);
+        let remainder = chain.len() - offset - portion;
+
+        header(resp, code::CERTIFICATE);
+        let body = &mut resp[SPDM_HEADER_SIZE..];
+        body[0..2].copy_from_slice(&(portion as u16).to_le_bytes());
+        body[2..4].copy_from_slice(&(remainder as u16).to_le_bytes());
+        body[4..4 + portion].copy_from_slice(&chain[offset..offset + portion]);
+        SPDM_HEADER_SIZE + 4 + portion
+    }
+
+    fn key_exchange(&self, req: &[u8], resp: &mut [u8]) -> usize {
+        header(resp, code::KEY_EXCHANGE_RSP);
+        // RspSessionID, MutAuthRequested, ReqSlotIDParam, RandomData, OpaqueDataLength
+        let body = &mut resp[SPDM_HEADER_SIZE..];
+        body[0..2].copy_from_slice(&RSP_SESSION_ID.to_le_bytes());
+        body[2..4].fill(0);
+        body[4..4 + spdm::RANDOM_SIZE].fill(0x77);
+        body[4 + spdm::RANDOM_SIZE..6 + spdm::RANDOM_SIZE].fill(0);
+        let sig_offset = SPDM_HEADER_SIZE + 6 + spdm::RANDOM_SIZE;
+
+        let th = Sha256::new()
+            .chain_update(&self.vca)
+            .chain_update(Sha256::digest(cert_chain()))
+            .chain_update(req)
+            .chain_update(&resp[..sig_offset])
+            .finalize();
+        let digest = Sha256::new()
+            .chain_update(spdm::signing_prefix(spdm::KEY_EXCHANGE_RSP_CONTEXT))
+            .chain_update(th)
+            .finalize();
+        let key = SigningKey::from_slice(&DEVICE_KEY).unwrap();
+        let sig: Signature = key.sign_prehash(&digest).unwrap();
+        let sig = sig.to_bytes();
+
+        let sig_end = sig_offset + sig.len();
+        resp[sig_offset..sig_end].copy_from_slice(&sig);
+        if self.forge_signature {
+            resp[sig_offset] ^= 1;
+        }
+        // ResponderVerifyData, as no session keys are derived
+        resp[sig_end..sig_end + 32].fill(0);
+        sig_end + 32
+    }
+
+    fn tdisp(&mut self, body: &[u8], resp: &mut [u8]) -> usize {
+        let Ok((message, tdi_id, payload)) = tdisp::parse(body) else {
+            return spdm_error(resp);
+        };
+        if tdi_id != self.tdi_id {
+            return tdisp_error(tdi_id, resp);
+        }
+
+        let (message, payload) = match message {
+            msg::LOCK_INTERFACE_REQUEST if !self.locked => {
+                self.locked = true;
+                (msg::LOCK_INTERFACE_RESPONSE, self.nonce.to_vec())
+            }
+            msg::GET_DEVICE_INTERFACE_REPORT if self.locked => {
+                let report = interface_report();
+                let mut payload = Vec::new();
+                payload.extend_from_slice(&(report.len() as u16).to_le_bytes());
+                payload.extend_from_slice(&0u16.to_le_bytes());
+                payload.extend_from_slice(&report);
+                (msg::DEVICE_INTERFACE_REPORT, payload)
+            }
+            msg::START_INTERFACE_REQUEST if self.locked && payload == self.nonce => {
+                self.started = true;
+                (msg::START_INTERFACE_RESPONSE, Vec::new())
+            }
+            msg::STOP_INTERFACE_REQUEST => {
+                self.locked = false;
+                self.started = false;
+                (msg::STOP_INTERFACE_RESPONSE, Vec::new())
+            }
+            _ => return tdisp_error(tdi_id, resp),
+        };
+        tdisp::build_message(
+            code::VENDOR_DEFINED_RESPONSE,
+            message,
+            tdi_id,
+            &payload,
+            resp,
+        )
+    }
+}
+
+fn header(resp: &mut [u8], code: u8) {
+    resp[..SPDM_HEADER_SIZE].copy_from_slice(&[SPDM_VERSION, code, 0, 0]);
+}
+
+fn simple(resp: &mut [u8], code: u8) -> usize {
+    header(resp, code);
+    SPDM_HEADER_SIZE
+}
+
+fn spdm_error(resp: &mut [u8]) -> usize {
+    resp[..SPDM_HEADER_SIZE].copy_from_slice(&[
+        SPDM_VERSION,
+        code::ERROR,
+        SPDM_ERROR_UNSUPPORTED_REQUEST,
+        0,
+    ]);
+    SPDM_HEADER_SIZE
+}
+
+fn tdisp_error(tdi_id: u32, resp: &mut [u8]) -> usize {
+    tdisp::build_message(
+        code::VENDOR_DEFINED_RESPONSE,
+        msg::TDISP_ERROR,
+        tdi_id,
+        &[],
+        resp,
+    )
+}
@@ -0,0 +1,405 @@
+//! A minimal SPDM requester driven by the host through PDEV/VDEV_COMMUNICATE.
+//!
+//! The RMM never talks to a device directly. Each exchange is split into
+//! a request which the RMM writes into a host buffer and a response which the
+//! host gets back from the device (or from a software responder).
+//! The requester keeps track of where the current operation is, so that it can
+//! be resumed on the next communicate call.
+
+use super::tdisp;
+use crate::rmi::error::Error;
+
+pub const SPDM_VERSION_10: u8 = 0x10;
+pub const SPDM_VERSION: u8 = 0x12;
+pub const SPDM_HEADER_SIZE: usize = 4;
+
+pub mod code {
+    pub const DIGESTS: u8 = 0x01;
+    pub const CERTIFICATE: u8 = 0x02;
+    pub const VERSION: u8 = 0x04;
+    pub const MEASUREMENTS: u8 = 0x60;
+    pub const CAPABILITIES: u8 = 0x61;
+    pub const ALGORITHMS: u8 = 0x63;
+    pub const KEY_EXCHANGE_RSP: u8 = 0x64;
+    pub const FINISH_RSP: u8 = 0x65;
+    pub const KEY_UPDATE_ACK: u8 = 0x69;
+    pub const END_SESSION_ACK: u8 = 0x6C;
+    pub const VENDOR_DEFINED_RESPONSE: u8 = 0x7E;
+    pub const ERROR: u8 = 0x7F;
+
+    pub const GET_DIGESTS: u8 = 0x81;
+    pub const GET_CERTIFICATE: u8 = 0x82;
+    pub const GET_VERSION: u8 = 0x84;
+    pub const GET_MEASUREMENTS: u8 = 0xE0;
+    pub const GET_CAPABILITIES: u8 = 0xE1;
+    pub const NEGOTIATE_ALGORITHMS: u8 = 0xE3;
+    pub const KEY_EXCHANGE: u8 = 0xE4;
+    pub const FINISH: u8 = 0xE5;
+    pub const KEY_UPDATE: u8 = 0xE9;
+    pub const END_SESSION: u8 = 0xEC;
+    pub const VENDOR_DEFINED_REQUEST: u8 = 0xFE;
+}
+
+// The size of a certificate chain portion requested at once
+pub const CERT_PORTION_SIZE: u16 = 0x400;
+// Request all measurement blocks
+pub const MEASUREMENTS_ALL: u8 = 0xFF;
+
+// RandomData of KEY_EXCHANGE and KEY_EXCHANGE_RSP
+pub const RANDOM_SIZE: usize = 32;
+// Requester's session ID of KEY_EXCHANGE
+const REQ_SESSION_ID: u16 = 0xFFFF;
+
+// The prefix of the messages signed in SPDM 1.2 (15 Signature generation):
+// the version string repeated 4 times, then the context padded to 36 bytes
+const SIGNING_VERSION: &[u8; 16] = b"dmtf-spdm-v1.2.*";
+const SIGNING_CONTEXT_SIZE: usize = 36;
+pub const SIGNING_PREFIX_SIZE: usize = SIGNING_VERSION.len() * 4 + SIGNING_CONTEXT_SIZE;
+pub const KEY_EXCHANGE_RSP_CONTEXT: &[u8] = b"responder-key_exchange_rsp signing";
+
+// The transport of a message. Messages after KEY_EXCHANGE go through the secure session.
+pub const PROTOCOL_SPDM: u8 = 0;
+pub const PROTOCOL_SECURE_SPDM: u8 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Step {
+    GetVersion,
+    GetCapabilities,
+    NegotiateAlgorithms,
+    GetDigests,
+    GetCertificate,
+    KeyExchange,
+    Finish,
+    KeyUpdate,
+    EndSession,
+    GetMeasurements,
+    LockInterface,
+    GetInterfaceReport,
+    StartInterface,
+    StopInterface,
+}
+
+impl Step {
+    pub fn protocol(&self) -> u8 {
+        match self {
+            Step::GetVersion
+            | Step::GetCapabilities
+            | Step::NegotiateAlgorithms
+            | Step::GetDigests
+            | Step::GetCertificate
+            | Step::KeyExchange => PROTOCOL_SPDM,
+            _ => PROTOCOL_SECURE_SPDM,
+        }
+    }
+
+    /// The response code expected for the request of this step
+    pub fn response_code(&self) -> u8 {
+        match self {
+            Step::GetVersion => code::VERSION,
+            Step::GetCapabilities => code::CAPABILITIES,
+            Step::NegotiateAlgorithms => code::ALGORITHMS,
+            Step::GetDigests => code::DIGESTS,
+            Step::GetCertificate => code::CERTIFICATE,
+            Step::KeyExchange => code::KEY_EXCHANGE_RSP,
+            Step::Finish => code::FINISH_RSP,
+            Step::KeyUpdate => code::KEY_UPDATE_ACK,
+            Step::EndSession => code::END_SESSION_ACK,
+            Step::GetMeasurements => code::MEASUREMENTS,
+            Step::LockInterface
+            | Step::GetInterfaceReport
+            | Step::StartInterface
+            | Step::StopInterface => code::VENDOR_DEFINED_RESPONSE,
+        }
+    }
+}
+
+/// A sequence of exchanges which moves a device object to its next state
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(u8)]
+pub enum Operation {
+    None = 0,
+    // PDEV
+    Connect,
+    Session,
+    KeyUpdate,
+    EndSession,
+    // VDEV
+    Lock,
+    Start,
+    Stop,
+}
+
+impl Operation {
+    pub fn steps(&self) -> &'static [Step] {
+        match self {
+            Operation::None => &[],
+            Operation::Connect => &[
+                Step::GetVersion,
+                Step::GetCapabilities,
+                Step::NegotiateAlgorithms,
+                Step::GetDigests,
+                Step::GetCertificate,
+            ],
+            Operation::Session => &[Step::KeyExchange, Step::Finish],
+            Operation::KeyUpdate => &[Step::KeyUpdate],
+            Operation::EndSession => &[Step::EndSession],
+            Operation::Lock => &[
+                Step::LockInterface,
+                Step::GetInterfaceReport,
+                Step::GetMeasurements,
+            ],
+            Operation::Start => &[Step::StartInterface],
+            Operation::Stop => &[Step::StopInterface],
+        }
+    }
+}
+
+/// What to do after a response has been consumed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Progress {
+    /// Move on to the next step
+    Next,
+    /// Issue the same step again (e.g., the remainder of a certificate chain)
+    Again,
+}
+
+/// Progress of the operation in flight.
+///
+/// This is kept inside the PDEV/VDEV granule, so it must stay plain data.
+#[derive(Clone, Copy, Debug)]
+pub struct Requester {
+    op: Operation,
+    step: usize,
+    // Offset used by multi-part responses (CERTIFICATE, DEVICE_INTERFACE_REPORT)
+    offset: usize,
+    // A request has been handed over to the host and its response is awaited
+    pending: bool,
+}
+
+impl Default for Requester {
+    fn default() -> Self {
+        Self {
+            op: Operation::None,
+            step: 0,
+            offset: 0,
+            pending: false,
+        }
+    }
+}
+
+impl Requester {
+    pub fn start(&mut self, op: Operation) {
+        *self = Self {
+            op,
+            ..Default::default()
+        };
+    }
+
+    pub fn abort(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn op(&self) -> Operation {
+        self.op
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.op == Operation::None
+    }
+
+    pub fn current(&self) -> Option<Step> {
+        self.op.steps().get(self.step).copied()
+    }
+
+    pub fn is_done(&self) -> bool {
+        !self.is_idle() && self.current().is_none()
+    }
+
+    pub fn pending(&self) -> Option<Step> {
+        if self.pending {
+            self.current()
+        } else {
+            None
+        }
+    }
+
+    pub fn set_pending(&mut self, pending: bool) {
+        self.pending = pending;
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn set_offset(&mut self, offset: usize) {
+        self.offset = offset;
+    }
+
+    pub fn advance(&mut self, progress: Progress) {
+        self.pending = false;
+        if progress == Progress::Next {
+            self.step += 1;
+            self.offset = 0;
+        }
+    }
+}
+
+fn put_header(buf: &mut [u8], code: u8, param1: u8, param2: u8) {
+    let version = if code == code::GET_VERSION {
+        SPDM_VERSION_10
+    } else {
+        SPDM_VERSION
+    };
+    buf[..SPDM_HEADER_SIZE].copy_from_slice(&[version, code, param1, param2]);
+}
+
+/// Writes the request for `step` into `buf` and returns its length.
+pub fn build_request(
+    step: Step,
+    offset: usize,
+    tdi_id: u32,
+    nonce: &[u8],
+    buf: &mut [u8],
+) -> usize {
+    match step {
+        Step::GetVersion => {
+            put_header(buf, code::GET_VERSION, 0, 0);
+            SPDM_HEADER_SIZE
+        }
+        Step::GetCapabilities => {
+            put_header(buf, code::GET_CAPABILITIES, 0, 0);
+            SPDM_HEADER_SIZE
+        }
+        Step::NegotiateAlgorithms => {
+            put_header(buf, code::NEGOTIATE_ALGORITHMS, 0, 0);
+            SPDM_HEADER_SIZE
+        }
+        Step::GetDigests => {
+            put_header(buf, code::GET_DIGESTS, 0, 0);
+            SPDM_HEADER_SIZE
+        }
+        Step::GetCertificate => {
+            // slot 0
+            put_header(buf, code::GET_CERTIFICATE, 0, 0);
+            buf[4..6].copy_from_slice(&(offset as u16).to_le_bytes());
+            buf[6..8].copy_from_slice(&CERT_PORTION_SIZE.to_le_bytes());
+            8
+        }
+        Step::KeyExchange => {
+            // No measurement summary hash, slot 0
+            put_header(buf, code::KEY_EXCHANGE, 0, 0);
+            // ReqSessionID, SessionPolicy, Reserved, RandomData, OpaqueDataLength
+            let random_end = 8 + RANDOM_SIZE;
+            buf[4..6].copy_from_slice(&REQ_SESSION_ID.to_le_bytes());
+            buf[6..8].fill(0);
+            buf[8..random_end].copy_from_slice(&nonce[..RANDOM_SIZE]);
+            buf[random_end..random_end + 2].fill(0);
+            random_end + 2
+        }
+        Step::Finish => {
+            put_header(buf, code::FINISH, 0, 0);
+            SPDM_HEADER_SIZE
+        }
+        Step::KeyUpdate => {
+            put_header(buf, code::KEY_UPDATE, 0, 0);
+            SPDM_HEADER_SIZE
+        }
+        Step::EndSession => {
+            put_header(buf, code::END_SESSION, 0, 0);
+            SPDM_HEADER_SIZE
+        }
+        Step::GetMeasurements => {
+            put_header(buf, code::GET_MEASUREMENTS, 0, MEASUREMENTS_ALL);
+            SPDM_HEADER_SIZE
+        }
+        Step::LockInterface => tdisp::build_request(
+            tdisp::msg::LOCK_INTERFACE_REQUEST,
+            tdi_id,
+            &[0u8; tdisp::LOCK_PARAMS_SIZE],
+            buf,
+        ),
+        Step::GetInterfaceReport => {
+            let mut params = [0u8; 4];
+            params[0..2].copy_from_slice(&(offset as u16).to_le_bytes());
+            params[2..4].copy_from_slice(&(tdisp::MAX_REPORT_PORTION as u16).to_le_bytes());
+            tdisp::build_request(
+                tdisp::msg::GET_DEVICE_INTERFACE_REPORT,
+                tdi_id,
+                &params,
+                buf,
+            )
+        }
+        Step::StartInterface => {
+            tdisp::build_request(tdisp::msg::START_INTERFACE_REQUEST, tdi_id, nonce, buf)
+        }
+        Step::StopInterface => {
+            tdisp::build_request(tdisp::msg::STOP_INTERFACE_REQUEST, tdi_id, &[], buf)
+        }
+    }
+}
+
+/// Checks the SPDM header of the response to `step` and returns its body.
+pub fn response_body(step: Step, resp: &[u8]) -> Result<&[u8], Error> {
+    if resp.len() < SPDM_HEADER_SIZE {
+        return Err(Error::RmiErrorInput);
+    }
+    if resp[1] == code::ERROR {
+        warn!("SPDM error response: {:#x} to {:?}", resp[2], step);
+        return Err(Error::RmiErrorInput);
+    }
+    if resp[1] != step.response_code() {
+        return Err(Error::RmiErrorInput);
+    }
+    Ok(&resp[SPDM_HEADER_SIZE..])
+}
+
+/// Parses a CERTIFICATE response body into (portion, remainder_length).
+pub fn certificate_portion(body: &[u8]) -> Result<(&[u8], usize), Error> {
+    if body.len() < 4 {
+        return Err(Error::RmiErrorInput);
+    }
+    let portion_len = u16::from_le_bytes([body[0], body[1]]) as usize;
+    let remainder_len = u16::from_le_bytes([body[2], body[3]]) as usize;
+    let portion = body.get(4..4 + portion_len).ok_or(Error::RmiErrorInput)?;
+    Ok((portion, remainder_len))
+}
+
+/// Parses a MEASUREMENTS response body and returns the measurement record.
+pub fn measurement_record(body: &[u8]) -> Result<&[u8], Error> {
+    // NumberOfBlocks (1 byte), MeasurementRecordLength (3 bytes)
+    if body.len() < 4 {
+        return Err(Error::RmiErrorInput);
+    }
+    let record_len = u32::from_le_bytes([body[1], body[2], body[3], 0]) as usize;
+    body.get(4..4 + record_len).ok_or(Error::RmiErrorInput)
+}
+
+/// Returns the prefix of a message signed for `context`.
+pub fn signing_prefix(context: &[u8]) -> [u8; SIGNING_PREFIX_SIZE] {
+    let mut prefix = [0u8; SIGNING_PREFIX_SIZE];
+    for chunk in prefix[..SIGNING_VERSION.len() * 4].chunks_mut(SIGNING_VERSION.len()) {
+        chunk.copy_from_slice(SIGNING_VERSION);
+    }
+    prefix[SIGNING_PREFIX_SIZE - context.len()..].copy_from_slice(context);
+    prefix
+}
+
+/// Parses a KEY_EXCHANGE_RSP body and returns the offset of its signature
+/// within the body, along with the signature of `sig_size` bytes.
+///
+/// No ExchangeData nor MeasurementSummaryHash is expected, as neither a
+/// DHE group nor a measurement summary is requested.
+pub fn key_exchange_signature(body: &[u8], sig_size: usize) -> Result<(usize, &[u8]), Error> {
+    // RspSessionID, MutAuthRequested, ReqSlotIDParam, RandomData
+    let opaque_len_offset = 4 + RANDOM_SIZE;
+    let opaque_len = body
+        .get(opaque_len_offset..opaque_len_offset + 2)
+        .map(|len| u16::from_le_bytes([len[0], len[1]]) as usize)
+        .ok_or(Error::RmiErrorInput)?;
+    let sig_offset = opaque_len_offset + 2 + opaque_len;
+    // ResponderVerifyData, if any, follows the signature
+    let sig = body
+        .get(sig_offset..sig_offset + sig_size)
+        .ok_or(Error::RmiErrorInput)?;
+    Ok((sig_offset, sig))
+}
@@ -0,0 +1,117 @@
+//! TDISP messages carried over SPDM VENDOR_DEFINED_REQUEST/RESPONSE.
+
+use super::spdm::{self, code, SPDM_HEADER_SIZE};
+use crate::rmi::error::Error;
+
+pub const TDISP_VERSION: u8 = 0x10;
+
+// StandardID (2), Len (1), VendorID (2), ReqLength/RespLength (2)
+pub const VENDOR_HEADER_SIZE: usize = 7;
+const STANDARD_ID_PCISIG: u16 = 0x0003;
+const VENDOR_ID_PCISIG: u16 = 0x0001;
+
+// TDISPVersion (1), MessageType (1), Reserved (2), InterfaceID (12)
+pub const TDISP_HEADER_SIZE: usize = 16;
+const INTERFACE_ID_OFFSET: usize = 4;
+
+pub const LOCK_PARAMS_SIZE: usize = 20;
+pub const START_NONCE_SIZE: usize = 32;
+pub const MAX_REPORT_PORTION: usize = 0x400;
+
+pub mod msg {
+    pub const LOCK_INTERFACE_RESPONSE: u8 = 0x03;
+    pub const DEVICE_INTERFACE_REPORT: u8 = 0x04;
+    pub const START_INTERFACE_RESPONSE: u8 = 0x06;
+    pub const STOP_INTERFACE_RESPONSE: u8 = 0x07;
+    pub const TDISP_ERROR: u8 = 0x7F;
+
+    pub const LOCK_INTERFACE_REQUEST: u8 = 0x83;
+    pub const GET_DEVICE_INTERFACE_REPORT: u8 = 0x84;
+    pub const START_INTERFACE_REQUEST: u8 = 0x86;
+    pub const STOP_INTERFACE_REQUEST: u8 = 0x87;
+}
+
+const MSG_OFFSET: usize = SPDM_HEADER_SIZE + VENDOR_HEADER_SIZE;
+pub const PAYLOAD_OFFSET: usize = MSG_OFFSET + TDISP_HEADER_SIZE;
+
+/// Writes a TDISP request wrapped in VENDOR_DEFINED_REQUEST into `buf`
+/// and returns its length.
+pub fn build_request(message: u8, tdi_id: u32, params: &[u8], buf: &mut [u8]) -> usize {
+    build_message(code::VENDOR_DEFINED_REQUEST, message, tdi_id, params, buf)
+}
+
+/// Writes a TDISP message wrapped in the SPDM vendor defined message `spdm_code`.
+pub fn build_message(
+    spdm_code: u8,
+    message: u8,
+    tdi_id: u32,
+    payload: &[u8],
+    buf: &mut [u8],
+) -> usize {
+    let len = PAYLOAD_OFFSET + payload.len();
+    let tdisp_len = (TDISP_HEADER_SIZE + payload.len()) as u16;
+
+    buf[..len].fill(0);
+    buf[0] = spdm::SPDM_VERSION;
+    buf[1] = spdm_code;
+    let vendor = &mut buf[SPDM_HEADER_SIZE..MSG_OFFSET];
+    vendor[0..2].copy_from_slice(&STANDARD_ID_PCISIG.to_le_bytes());
+    vendor[2] = 2;
+    vendor[3..5].copy_from_slice(&VENDOR_ID_PCISIG.to_le_bytes());
+    vendor[5..7].copy_from_slice(&tdisp_len.to_le_bytes());
+
+    let tdisp = &mut buf[MSG_OFFSET..PAYLOAD_OFFSET];
+    tdisp[0] = TDISP_VERSION;
+    tdisp[1] = message;
+    tdisp[INTERFACE_ID_OFFSET..INTERFACE_ID_OFFSET + 4].copy_from_slice(&tdi_id.to_le_bytes());
+
+    buf[PAYLOAD_OFFSET..len].copy_from_slice(payload);
+    len
+}
+
+/// Returns (message type, interface id, payload) of a TDISP message
+/// carried in the body of a VENDOR_DEFINED request or response.
+pub fn parse(body: &[u8]) -> Result<(u8, u32, &[u8]), Error> {
+    if body.len() < VENDOR_HEADER_SIZE + TDISP_HEADER_SIZE {
+        return Err(Error::RmiErrorInput);
+    }
+    let standard_id = u16::from_le_bytes([body[0], body[1]]);
+    let vendor_id = u16::from_le_bytes([body[3], body[4]]);
+    let tdisp_len = u16::from_le_bytes([body[5], body[6]]) as usize;
+    if standard_id != STANDARD_ID_PCISIG || vendor_id != VENDOR_ID_PCISIG {
+        return Err(Error::RmiErrorInput);
+    }
+    let tdisp = body
+        .get(VENDOR_HEADER_SIZE..VENDOR_HEADER_SIZE + tdisp_len)
+        .ok_or(Error::RmiErrorInput)?;
+    if tdisp.len() < TDISP_HEADER_SIZE || tdisp[0] != TDISP_VERSION {
+        return Err(Error::RmiErrorInput);
+    }
+    let mut id = [0u8; 4];
+    id.copy_from_slice(&tdisp[INTERFACE_ID_OFFSET..INTERFACE_ID_OFFSET + 4]);
+    Ok((
+        tdisp[1],
+        u32::from_le_bytes(id),
+        &tdisp[TDISP_HEADER_SIZE..],
+    ))
+}
+
+/// Checks that the TDISP response is `expected` for the interface `tdi_id`
+/// and returns its payload.
+pub fn response_payload(body: &[u8], expected: u8, tdi_id: u32) -> Result<&[u8], Error> {
+    let (message, id, payload) = parse(body)?;
+    if message == msg::TDISP_ERROR {
+        warn!("TDISP error response to interface {:#x}", id);
+        return Err(Error::RmiErrorInput);
+    }
+    if message != expected || id != tdi_id {
+        return Err(Error::RmiErrorInput);
+    }
+    Ok(payload)
+}
+
+/// Parses a DEVICE_INTERFACE_REPORT payload into (portion, remainder_length).
+pub fn report_portion(payload: &[u8]) -> Result<(&[u8], usize), Error> {
+    // PortionLength and RemainderLength have the same layout as in CERTIFICATE
+    spdm::certificate_portion(payload)
+}
@@ -0,0 +1,227 @@
+use super::pdev::PDEV_MAX_DIGEST_SIZE;
+use super::spdm::{self, Operation, Progress, Requester, Step};
+use super::tdisp::{self, msg, START_NONCE_SIZE};
+use super::DevObject;
+use crate::measurement::Hasher;
+use crate::rmi::error::Error;
+
+use vmsa::guard::Content;
+
+// The number of VDEVs that can be assigned to a realm
+pub const MAX_VDEVS: usize = 8;
+
+// RmiVdevState
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum State {
+    New = 0,
+    Locked = 1,
+    Started = 2,
+    Stopping = 3,
+    Stopped = 4,
+    Error = 5,
+}
+
+#[derive(Debug)]
+pub struct Vdev {
+    state: State,
+    rd: usize,
+    pdev: usize,
+    vdev_id: u64,
+    tdi_id: u32,
+    hash_algo: u8,
+    cert_digest: [u8; PDEV_MAX_DIGEST_SIZE],
+    meas_digest: [u8; PDEV_MAX_DIGEST_SIZE],
+    report_digest: [u8; PDEV_MAX_DIGEST_SIZE],
+    start_nonce: [u8; START_NONCE_SIZE],
+    num_maps: usize,
+    requester: Requester,
+}
+
+impl Vdev {
+    pub fn init(
+        &mut self,
+        rd: usize,
+        pdev: usize,
+        vdev_id: u64,
+        tdi_id: u32,
+        hash_algo: u8,
+        cert_digest: &[u8],
+    ) {
+        self.state = State::New;
+        self.rd = rd;
+        self.pdev = pdev;
+        self.vdev_id = vdev_id;
+        self.tdi_id = tdi_id;
+        self.hash_algo = hash_algo;
+        self.cert_digest.copy_from_slice(cert_digest);
+        self.meas_digest = [0; PDEV_MAX_DIGEST_SIZE];
+        self.report_digest = [0; PDEV_MAX_DIGEST_SIZE];
+        self.start_nonce = [0; START_NONCE_SIZE];
+        self.num_maps = 0;
+        self.requester = Requester::default();
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    pub fn set_state(&mut self, state: State) {
+        self.state = state;
+    }
+
+    pub fn rd(&self) -> usize {
+        self.rd
+    }
+
+    pub fn pdev(&self) -> usize {
+        self.pdev
+    }
+
+    pub fn vdev_id(&self) -> u64 {
+        self.vdev_id
+    }
+
+    pub fn hash_algo(&self) -> u8 {
+        self.hash_algo
+    }
+
+    pub fn cert_digest(&self) -> &[u8] {
+        &self.cert_digest
+    }
+
+    pub fn meas_digest(&self) -> &[u8] {
+        &self.meas_digest
+    }
+
+    pub fn report_digest(&self) -> &[u8] {
+        &self.report_digest
+    }
+
+    /// Returns true if the device memory of this interface
+    /// can be mapped to (or validated by) the realm.
+    pub fn is_mappable(&self) -> bool {
+        matches!(self.state, State::Locked | State::Started)
+    }
+
+    pub fn num_maps(&self) -> usize {
+        self.num_maps
+    }
+
+    pub fn inc_maps(&mut self) {
+        self.num_maps += 1;
+    }
+
+    pub fn dec_maps(&mut self) {
+        self.num_maps -= 1;
+    }
+
+    pub fn abort(&mut self) {
+        self.requester.abort();
+    }
+
+    fn measure(&self, data: &[u8], out: &mut [u8]) -> Result<(), Error> {
+        Hasher::from_hash_algo(self.hash_algo)?.hash_fields_into(out, |h| h.hash(data))?;
+        Ok(())
+    }
+}
+
+impl DevObject for Vdev {
+    fn requester(&mut self) -> &mut Requester {
+        &mut self.requester
+    }
+
+    fn operation(&self) -> Operation {
+        match self.state {
+            State::New => Operation::Lock,
+            State::Locked => Operation::Start,
+            State::Stopping => Operation::Stop,
+            _ => Operation::None,
+        }
+    }
+
+    fn build_request(&self, step: Step, offset: usize, buf: &mut [u8]) -> usize {
+        spdm::build_request(step, offset, self.tdi_id, &self.start_nonce, buf)
+    }
+
+    fn handle_response(
+        &mut self,
+        step: Step,
+        _offset: usize,
+        resp: &[u8],
+    ) -> Result<Progress, Error> {
+        let body = spdm::response_body(step, resp)?;
+        match step {
+            Step::LockInterface => {
+                let payload =
+                    tdisp::response_payload(body, msg::LOCK_INTERFACE_RESPONSE, self.tdi_id)?;
+                let nonce = payload
+                    .get(..START_NONCE_SIZE)
+                    .ok_or(Error::RmiErrorInput)?;
+                self.start_nonce.copy_from_slice(nonce);
+            }
+            Step::GetInterfaceReport => {
+                let payload =
+                    tdisp::response_payload(body, msg::DEVICE_INTERFACE_REPORT, self.tdi_id)?;
+                // The report is expected to fit in a single response.
+                let (report, remainder) = tdisp::report_portion(payload)?;
+                if remainder != 0 {
+                    return Err(Error::RmiErrorInput);
+                }
+                let mut digest = [0u8; PDEV_MAX_DIGEST_SIZE];
+                self.measure(report, &mut digest)?;
+                self.report_digest = digest;
+            }
+            Step::GetMeasurements => {
+                let record = spdm::measurement_record(body)?;
+                let mut digest = [0u8; PDEV_MAX_DIGEST_SIZE];
+                self.measure(record, &mut digest)?;
+                self.meas_digest = digest;
+            }
+            Step::StartInterface => {
+                tdisp::response_payload(body, msg::START_INTERFACE_RESPONSE, self.tdi_id)?;
+            }
+            Step::StopInterface => {
+                tdisp::response_payload(body, msg::STOP_INTERFACE_RESPONSE, self.tdi_id)?;
+            }
+            _ => return Err(Error::RmiErrorInput),
+        }
+        Ok(Progress::Next)
+    }
+
+    fn on_complete(&mut self, op: Operation) -> Result<(), Error> {
+        self.state = match op {
+            Operation::Lock => State::Locked,
+            Operation::Start => State::Started,
+            Operation::Stop => State::Stopped,
+            _ => return Err(Error::RmiErrorInput),
+        };
+        Ok(())
+    }
+
+    fn on_error(&mut self) {
+        self.state = State::Error;
+    }
+}
+
+impl Content for Vdev {}
+
+impl safe_abstraction::raw_ptr::RawPtr for Vdev {}
+
+impl safe_abstraction::raw_ptr::SafetyChecked for Vdev {}
+
+impl safe_abstraction::raw_ptr::SafetyAssured for Vdev {
+    fn is_initialized(&self) -> bool {
+        // The initialization of this memory is guaranteed
+        // according to the RMM Specification A2.2.4 Granule Wiping.
+        // This instance belongs to a VDEV Granule and has been initialized.
+        true
+    }
+
+    fn verify_ownership(&self) -> bool {
+        // The ownership of this instance is exclusively ensured by the RMM.
+        // It is accessed only within the lock scope of the `get_granule*` macros
+        // through the `content*` functions.
+        true
+    }
+}
@@ -1,7 +1,11 @@
 pub mod mainloop;
 pub mod realmexit;
+#[cfg(any(test, miri, fuzzing, feature = "replay"))]
+pub mod replay;
 pub mod rmihandle;
 pub mod rsihandle;
+#[cfg(not(kani))]
+pub mod trace;
 
 pub use crate::rmi::error::Error;
 pub use crate::rsi;
@@ -9,97 +13,226 @@ pub use mainloop::Mainloop;
 pub use rmihandle::RmiHandle;
 pub use rsihandle::RsiHandle;
 
-extern crate alloc;
-use alloc::vec::Vec;
-
 #[macro_export]
 macro_rules! listen {
     ($eventloop:expr, $code:expr, $handler:expr) => {{
-        $eventloop.add_event_handler($code.into(), alloc::boxed::Box::new($handler))
+        $eventloop.add_event_handler($code.into(), $handler)
     }};
 }
 
 pub type Command = usize;
 
+// X0-X17 of the SMC calling convention v1.2. Fuzz targets pass the realm exit
+// to emulate beyond the registers of REC_ENTER (see test_utils::rmi_call).
+#[cfg(not(fuzzing))]
+pub const MAX_REGS: usize = 18;
+#[cfg(fuzzing)]
+pub const MAX_REGS: usize = 40;
+
+// The registers an RMI command returns in, X0-X4
+const RMI_RET_REGS: usize = 5;
+
+/// Function IDs from `base` to `base + len`
+#[derive(Clone, Copy)]
+pub struct FidRange {
+    base: Command,
+    len: usize,
+}
+
+impl FidRange {
+    pub const fn new(base: Command, len: usize) -> Self {
+        Self { base, len }
+    }
+}
+
+pub const fn fid_count(ranges: &[FidRange]) -> usize {
+    let mut count = 0;
+    let mut i = 0;
+    while i < ranges.len() {
+        count += ranges[i].len;
+        i += 1;
+    }
+    count
+}
+
+/// A table indexed by the offset of function IDs in their ranges,
+/// which looks up commands without allocating or searching a map.
+pub struct FidTable<T: Copy, const N: usize> {
+    ranges: &'static [FidRange],
+    entries: [Option<T>; N],
+}
+
+impl<T: Copy, const N: usize> FidTable<T, N> {
+    pub const fn new(ranges: &'static [FidRange]) -> Self {
+        assert!(fid_count(ranges) == N);
+        Self {
+            ranges,
+            entries: [None; N],
+        }
+    }
+
+    const fn index(&self, fid: Command) -> Option<usize> {
+        let mut offset = 0;
+        let mut i = 0;
+        while i < self.ranges.len() {
+            let range = self.ranges[i];
+            if fid >= range.base && fid - range.base < range.len {
+                return Some(offset + fid - range.base);
+            }
+            offset += range.len;
+            i += 1;
+        }
+        None
+    }
+
+    pub fn get(&self, fid: Command) -> Option<&T> {
+        self.entries[self.index(fid)?].as_ref()
+    }
+
+    /// Panics if `fid` is out of the ranges of the table,
+    /// at compile time for the tables built in const contexts.
+    pub const fn insert(&mut self, fid: Command, value: T) {
+        match self.index(fid) {
+            Some(idx) => self.entries[idx] = Some(value),
+            None => panic!("The function ID is out of the table"),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Context {
     pub cmd: Command,
-    pub arg: Vec<usize>,
-    pub ret: Vec<usize>,
+    arg: [usize; MAX_REGS],
+    arg_len: usize,
+    ret: [usize; MAX_REGS],
+    ret_len: usize,
     pub sve_hint: bool,
     pub x4: u64,
 }
 
 impl Context {
-    pub fn new(cmd: Command) -> Context {
+    pub const fn new(cmd: Command) -> Context {
         Context {
             cmd,
-            arg: Vec::new(),
-            ret: Vec::new(),
+            arg: [0; MAX_REGS],
+            arg_len: 0,
+            ret: [0; MAX_REGS],
+            ret_len: 0,
             sve_hint: false,
             x4: 0,
         }
     }
 
     pub fn init_arg(&mut self, arg: &[usize]) {
-        self.arg.clear();
-        self.arg.extend_from_slice(arg);
+        self.arg_len = arg.len().min(MAX_REGS);
+        self.arg[..self.arg_len].copy_from_slice(&arg[..self.arg_len]);
     }
 
     pub fn init_ret(&mut self, ret: &[usize]) {
-        self.ret.clear();
-        self.ret.extend_from_slice(ret);
+        self.ret_len = ret.len().min(MAX_REGS);
+        self.ret[..self.ret_len].copy_from_slice(&ret[..self.ret_len]);
     }
 
     pub fn resize_ret(&mut self, new_len: usize) {
-        self.ret.clear();
-        self.ret.resize(new_len, 0);
+        self.ret_len = new_len.min(MAX_REGS);
+        self.ret[..self.ret_len].fill(0);
     }
 
     pub fn arg_slice(&self) -> &[usize] {
-        &self.arg[..]
+        &self.arg[..self.arg_len]
     }
 
     pub fn ret_slice(&self) -> &[usize] {
-        &self.ret[..]
+        &self.ret[..self.ret_len]
     }
 
     pub fn cmd(&self) -> Command {
         self.cmd
     }
 
+    pub fn do_rmi<F>(&mut self, mut handler: F)
+    where
+        F: FnMut(&[usize], &mut [usize]) -> Result<(), Error>,
+    {
+        let arg = &self.arg[..self.arg_len];
+        let ret = &mut self.ret[..self.ret_len];
+
+        #[cfg(feature = "stat")]
+        {
+            use crate::rmi;
+
+            // STATS is not held while realms run or the stats are read
+            if !matches!(
+                self.cmd,
+                rmi::REC_ENTER | rmi::ISLET_STATS_READ | rmi::ISLET_STATS_RESET
+            ) {
+                trace!("let's get STATS.lock() with cmd {}", rmi::to_str(self.cmd));
+                crate::stat::STATS.lock().measure(self.cmd, || {
+                    if let Err(code) = handler(arg, ret) {
+                        ret[0] = code.into();
+                    }
+                });
+            } else if let Err(code) = handler(arg, ret) {
+                ret[0] = code.into();
+            }
+        }
+        #[cfg(not(feature = "stat"))]
+        {
+            if let Err(code) = handler(arg, ret) {
+                ret[0] = code.into();
+            }
+        }
+    }
+
+    /// Passes the return values of an RMI command to the next SMC.
+    pub fn ret_to_arg(&mut self) {
+        // SMC calling convention requires x4-x7 to be preserved unless used.
+        // Since the rmmd in EL3 takes care of preserving x5-x7,
+        // we only restore x4.
+        if self.arg_len < RMI_RET_REGS {
+            self.arg[self.arg_len..RMI_RET_REGS].fill(0);
+        }
+        self.arg_len = RMI_RET_REGS;
+        self.arg[..self.ret_len].copy_from_slice(&self.ret[..self.ret_len]);
+        if self.ret_len < RMI_RET_REGS {
+            self.arg[4] = self.x4 as usize;
+        }
+    }
+
     pub fn do_rsi<F>(&mut self, mut handler: F)
     where
         F: FnMut(&[usize], &mut [usize]) -> Result<(), Error>,
     {
-        self.ret[0] = rsi::SUCCESS;
+        let arg = &self.arg[..self.arg_len];
+        let ret = &mut self.ret[..self.ret_len];
+        ret[0] = rsi::SUCCESS;
 
         #[cfg(feature = "stat")]
         {
             trace!("let's get STATS.lock() with cmd {}", rsi::to_str(self.cmd));
             crate::stat::STATS.lock().measure(self.cmd, || {
-                if let Err(code) = handler(&self.arg[..], &mut self.ret[..]) {
+                if let Err(code) = handler(arg, ret) {
                     error!("rsi handler returns error:{:?}", code);
-                    self.ret[0] = code.into();
+                    ret[0] = code.into();
                 }
             });
         }
         #[cfg(not(feature = "stat"))]
         {
-            if let Err(code) = handler(&self.arg[..], &mut self.ret[..]) {
+            if let Err(code) = handler(arg, ret) {
                 error!("rsi handler returns error:{:?}", code);
-                self.ret[0] = code.into();
+                ret[0] = code.into();
             }
         }
 
         trace!(
             "RSI: {0: <20} {1:X?} > {2:X?}",
             rsi::to_str(self.cmd),
-            &self.arg,
-            &self.ret
+            self.arg_slice(),
+            self.ret_slice()
         );
-        self.arg.clear();
-        self.arg.extend_from_slice(&self.ret[..]);
+        self.arg[..self.ret_len].copy_from_slice(&self.ret[..self.ret_len]);
+        self.arg_len = self.ret_len;
     }
 }
 
@@ -108,3 +241,33 @@ impl Default for Context {
         Context::new(0)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rmi;
+
+    #[test]
+    fn fid_table() {
+        let mut table = rmihandle::table::<usize>();
+        table.insert(rmi::VERSION, 1);
+        table.insert(rmi::ISLET_REPLAY_RESET, 2);
+
+        assert_eq!(table.get(rmi::VERSION), Some(&1));
+        assert_eq!(table.get(rmi::ISLET_REPLAY_RESET), Some(&2));
+        assert_eq!(table.get(rmi::GRANULE_DELEGATE), None);
+        assert_eq!(table.get(rmi::BOOT_COMPLETE), None);
+    }
+
+    #[test]
+    fn ret_to_arg() {
+        let mut ctx =
+            rmi::constraint::validate(rmi::REC_ENTER, &[0x8800_0000, 0x8800_1000, 0, 0x44]);
+        assert_eq!(ctx.arg_slice(), &[0x8800_0000, 0x8800_1000]);
+        assert_eq!(ctx.ret_slice(), &[0]);
+
+        ctx.init_ret(&[rmi::SUCCESS]);
+        ctx.ret_to_arg();
+        assert_eq!(ctx.arg_slice(), &[rmi::SUCCESS, 0x8800_1000, 0, 0, 0x44]);
+    }
+}
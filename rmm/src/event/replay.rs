@@ -0,0 +1,429 @@
+use crate::rmi;
+
+use alloc::vec::Vec;
+
+// A replay log is a sequence of records, each of which is a sequence of
+// little-endian u64s starting with its tag.
+//
+//   TAG_DRAM   base, size                       a region of the NS DRAM
+//   TAG_RMI    cmd, nargs, args, nrets, rets,   an RMI command along with
+//              addr, [data; GRANULE_SIZE]       the NS buffer it read
+//   TAG_STATE  addr, state                      the state of a granule at the end
+//   TAG_RSI    cmd, nargs, args, nrets, rets    an RSI command the realm made
+//                                               during the next REC_ENTER
+//
+// addr of TAG_RMI is NO_BUFFER, with no data following, if the command
+// didn't read an NS buffer. args of TAG_RSI are X1 onwards of the REC on the
+// call, and rets only its X0 afterwards, as the other registers may carry
+// secrets of the realm (e.g., ISLET_REALM_SEALING_KEY). Realm exits other
+// than RSI calls, such as data aborts, are not recorded.
+//
+// With the `replay` feature, the RMM logs the commands from ISLET_REPLAY_RESET on,
+// and the host reads the log with ISLET_REPLAY_READ. Tests replay it with
+// `test_utils::replay(include_bytes!(...))`.
+pub const TAG_DRAM: u64 = 1;
+pub const TAG_RMI: u64 = 2;
+pub const TAG_STATE: u64 = 3;
+pub const TAG_RSI: u64 = 4;
+
+pub const NO_BUFFER: u64 = u64::MAX;
+
+/// The NS buffer read by a command (e.g., RmiRealmParams), as the host wrote it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NsBuffer {
+    pub addr: usize,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Record {
+    Dram {
+        base: usize,
+        size: usize,
+    },
+    Rmi {
+        cmd: usize,
+        args: Vec<usize>,
+        ret: Vec<usize>,
+        buffer: Option<NsBuffer>,
+    },
+    State {
+        addr: usize,
+        state: u64,
+    },
+    Rsi {
+        cmd: usize,
+        args: Vec<usize>,
+        ret: Vec<usize>,
+    },
+}
+
+impl Record {
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        let mut push = |word: usize| out.extend_from_slice(&(word as u64).to_le_bytes());
+        match self {
+            Record::Dram { base, size } => {
+                push(TAG_DRAM as usize);
+                push(*base);
+                push(*size);
+            }
+            Record::Rmi {
+                cmd,
+                args,
+                ret,
+                buffer,
+            } => {
+                push(TAG_RMI as usize);
+                push(*cmd);
+                push(args.len());
+                args.iter().for_each(|arg| push(*arg));
+                push(ret.len());
+                ret.iter().for_each(|ret| push(*ret));
+                match buffer {
+                    Some(buffer) => {
+                        push(buffer.addr);
+                        out.extend_from_slice(&buffer.data);
+                    }
+                    None => push(NO_BUFFER as usize),
+                }
+            }
+            Record::State { addr, state } => {
+                push(TAG_STATE as usize);
+                push(*addr);
+                push(*state as usize);
+            }
+            Record::Rsi { cmd, args, ret } => {
+                push(TAG_RSI as usize);
+                push(*cmd);
+                push(args.len());
+                args.iter().for_each(|arg| push(*arg));
+                push(ret.len());
+                ret.iter().for_each(|ret| push(*ret));
+            }
+        }
+    }
+}
+
+#[cfg(any(test, miri, fuzzing))]
+struct Reader<'a>(&'a [u8]);
+
+#[cfg(any(test, miri, fuzzing))]
+impl Reader<'_> {
+    fn bytes(&mut self, len: usize) -> Option<&[u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(bytes)
+    }
+
+    fn word(&mut self) -> Option<usize> {
+        let bytes = self.bytes(8)?;
+        Some(u64::from_le_bytes(bytes.try_into().ok()?) as usize)
+    }
+
+    // The number of words is bounded by the registers of a command, X0-X17
+    fn words(&mut self) -> Option<Vec<usize>> {
+        let len = self.word()?;
+        if len > 18 {
+            return None;
+        }
+        (0..len).map(|_| self.word()).collect()
+    }
+
+    fn record(&mut self) -> Option<Record> {
+        let record = match self.word()? as u64 {
+            TAG_DRAM => Record::Dram {
+                base: self.word()?,
+                size: self.word()?,
+            },
+            TAG_RMI => {
+                let cmd = self.word()?;
+                let args = self.words()?;
+                let ret = self.words()?;
+                let buffer = match self.word()? as u64 {
+                    NO_BUFFER => None,
+                    addr => Some(NsBuffer {
+                        addr: addr as usize,
+                        data: self.bytes(crate::granule::GRANULE_SIZE)?.to_vec(),
+                    }),
+                };
+                Record::Rmi {
+                    cmd,
+                    args,
+                    ret,
+                    buffer,
+                }
+            }
+            TAG_STATE => Record::State {
+                addr: self.word()?,
+                state: self.word()? as u64,
+            },
+            TAG_RSI => Record::Rsi {
+                cmd: self.word()?,
+                args: self.words()?,
+                ret: self.words()?,
+            },
+            _ => return None,
+        };
+        Some(record)
+    }
+}
+
+#[cfg(any(test, miri, fuzzing))]
+pub fn encode(records: &[Record]) -> Vec<u8> {
+    let mut out = Vec::new();
+    records.iter().for_each(|record| record.encode(&mut out));
+    out
+}
+
+#[cfg(any(test, miri, fuzzing))]
+/// Decodes the log copied out by ISLET_REPLAY_READ. None if it is malformed.
+pub fn decode(buf: &[u8]) -> Option<Vec<Record>> {
+    let mut reader = Reader(buf);
+    let mut records = Vec::new();
+    while !reader.0.is_empty() {
+        records.push(reader.record()?);
+    }
+    Some(records)
+}
+
+/// Returns the arguments of the command holding physical addresses of granules,
+/// which are relocated on replay. Indices start from X1.
+pub fn granule_args(cmd: usize) -> &'static [usize] {
+    match cmd {
+        rmi::DATA_CREATE => &[0, 1, 3],
+        rmi::REC_CREATE | rmi::ISLET_REALM_SET_METADATA => &[0, 1, 2],
+        rmi::DATA_CREATE_UNKNOWN
+        | rmi::REALM_CREATE
+        | rmi::REC_ENTER
+        | rmi::RTT_CREATE
+        | rmi::RTT_SET_RIPAS
+        | rmi::RTT_AUX_CREATE
+        | rmi::PSCI_COMPLETE => &[0, 1],
+        rmi::GRANULE_DELEGATE
+        | rmi::GRANULE_UNDELEGATE
+        | rmi::DATA_DESTROY
+        | rmi::REALM_ACTIVATE
+        | rmi::REALM_DESTROY
+        | rmi::REC_DESTROY
+        | rmi::REC_AUX_COUNT
+        | rmi::RTT_DESTROY
+        | rmi::RTT_MAP_UNPROTECTED
+        | rmi::RTT_UNMAP_UNPROTECTED
+        | rmi::RTT_READ_ENTRY
+        | rmi::RTT_INIT_RIPAS
+        | rmi::RTT_FOLD
+        | rmi::RTT_AUX_DESTROY
+        | rmi::RTT_AUX_MAP_PROTECTED
+        | rmi::RTT_AUX_UNMAP_PROTECTED => &[0],
+        _ => &[],
+    }
+}
+
+#[cfg(feature = "replay")]
+pub use capture::*;
+
+#[cfg(feature = "replay")]
+mod capture {
+    use super::{granule_args, NsBuffer, Record};
+    use crate::config::NS_DRAM_REGIONS;
+    use crate::event::Context;
+    use crate::get_granule;
+    use crate::granule::{is_not_in_realm, GRANULE_SIZE};
+    use crate::host::{self, DataPage};
+    use crate::rec::Rec;
+    use crate::rmi;
+    use crate::rsi;
+    use crate::Monitor;
+
+    use alloc::collections::BTreeSet;
+    use alloc::vec::Vec;
+    use spin::mutex::Mutex;
+
+    /// Records beyond this size are dropped, leaving the log without the final states.
+    pub const LOG_MAX_SIZE: usize = 1024 * 1024;
+
+    struct Log {
+        buf: Vec<u8>,
+        // Granules the commands referred to
+        granules: BTreeSet<usize>,
+        truncated: bool,
+    }
+
+    static LOG: Mutex<Log> = Mutex::new(Log {
+        buf: Vec::new(),
+        granules: BTreeSet::new(),
+        truncated: false,
+    });
+
+    // Returns the argument of the command holding the NS buffer it reads
+    fn ns_buffer_arg(cmd: usize) -> Option<usize> {
+        match cmd {
+            rmi::REALM_CREATE | rmi::REC_ENTER => Some(1),
+            rmi::REC_CREATE | rmi::ISLET_REALM_SET_METADATA => Some(2),
+            rmi::DATA_CREATE => Some(3),
+            _ => None,
+        }
+    }
+
+    /// Copies the NS buffer the command is about to read.
+    pub fn ns_buffer(ctx: &Context, rmm: &Monitor) -> Option<NsBuffer> {
+        let addr = *ctx.arg_slice().get(ns_buffer_arg(ctx.cmd)?)?;
+        if !is_not_in_realm(addr) {
+            return None;
+        }
+        rmm.page_table.map(addr, false);
+        let page = host::copy_from::<DataPage>(addr);
+        rmm.page_table.unmap(addr);
+        Some(NsBuffer {
+            addr,
+            data: page?.as_slice().to_vec(),
+        })
+    }
+
+    pub fn record_rmi(ctx: &Context, buffer: Option<NsBuffer>) {
+        // Vendor calls out of the realm lifecycle are not replayed
+        if matches!(
+            ctx.cmd,
+            rmi::ISLET_STATS_READ
+                | rmi::ISLET_STATS_RESET
+                | rmi::ISLET_TRACE_READ
+                | rmi::ISLET_LOG_SET_FILTER
+                | rmi::ISLET_LOG_SET_SINKS
+                | rmi::ISLET_LOG_DRAIN
+                | rmi::ISLET_REPLAY_READ
+                | rmi::ISLET_REPLAY_RESET
+        ) {
+            return;
+        }
+
+        let record = Record::Rmi {
+            cmd: ctx.cmd,
+            args: ctx.arg_slice().to_vec(),
+            ret: ctx.ret_slice().to_vec(),
+            buffer,
+        };
+        let granules = granule_args(ctx.cmd)
+            .iter()
+            .filter_map(|idx| ctx.arg_slice().get(*idx));
+        append(&record, granules);
+    }
+
+    /// Returns the arguments of the RSI command the REC is about to make.
+    pub fn rsi_args(cmd: usize, rec: &Rec<'_>) -> Vec<usize> {
+        let nargs = (rsi::constraint::arg_num(cmd) - 1).min(rec.context.gp_regs.len() - 1);
+        rec.context.gp_regs[1..=nargs]
+            .iter()
+            .map(|reg| *reg as usize)
+            .collect()
+    }
+
+    pub fn record_rsi(cmd: usize, args: Vec<usize>, rec: &Rec<'_>) {
+        let record = Record::Rsi {
+            cmd,
+            args,
+            ret: alloc::vec![rec.context.gp_regs[0] as usize],
+        };
+        append(&record, core::iter::empty());
+    }
+
+    fn append<'a>(record: &Record, granules: impl Iterator<Item = &'a usize>) {
+        let mut log = LOG.lock();
+        let len = log.buf.len();
+        record.encode(&mut log.buf);
+        if log.truncated || log.buf.len() > LOG_MAX_SIZE {
+            if !log.truncated {
+                warn!("The replay log is full");
+            }
+            log.buf.truncate(len);
+            log.truncated = true;
+            return;
+        }
+        for addr in granules {
+            log.granules.insert(addr & !(GRANULE_SIZE - 1));
+        }
+    }
+
+    /// Returns the log from the NS DRAM regions to the current states of
+    /// the granules the commands referred to.
+    pub fn snapshot() -> Vec<u8> {
+        let mut out = Vec::new();
+        for region in NS_DRAM_REGIONS.lock().iter() {
+            Record::Dram {
+                base: region.start,
+                size: region.end - region.start,
+            }
+            .encode(&mut out);
+        }
+
+        let log = LOG.lock();
+        out.extend_from_slice(&log.buf);
+        if log.truncated {
+            return out;
+        }
+        for addr in log.granules.iter() {
+            if let Ok(granule) = get_granule!(*addr) {
+                Record::State {
+                    addr: *addr,
+                    state: granule.state() as u64,
+                }
+                .encode(&mut out);
+            }
+        }
+        out
+    }
+
+    pub fn reset() {
+        let mut log = LOG.lock();
+        log.buf.clear();
+        log.granules.clear();
+        log.truncated = false;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::granule::GRANULE_SIZE;
+    use alloc::vec;
+
+    #[test]
+    fn replay_log_decode() {
+        let records = vec![
+            Record::Dram {
+                base: 0x8800_0000,
+                size: 0x1000_0000,
+            },
+            Record::Rmi {
+                cmd: rmi::REALM_CREATE,
+                args: vec![0x8830_0000, 0x8830_3000],
+                ret: vec![rmi::SUCCESS, 0],
+                buffer: Some(NsBuffer {
+                    addr: 0x8830_3000,
+                    data: vec![0x5a; GRANULE_SIZE],
+                }),
+            },
+            Record::Rmi {
+                cmd: rmi::REALM_ACTIVATE,
+                args: vec![0x8830_0000],
+                ret: vec![rmi::SUCCESS],
+                buffer: None,
+            },
+            Record::Rsi {
+                cmd: crate::rsi::MEASUREMENT_EXTEND,
+                args: vec![1, 64, 0, 0, 0, 0, 0, 0, 0, 0],
+                ret: vec![crate::rsi::SUCCESS],
+            },
+            Record::State {
+                addr: 0x8830_0000,
+                state: 2,
+            },
+        ];
+
+        let log = encode(&records);
+        assert_eq!(decode(&log), Some(records));
+        assert_eq!(decode(&log[..log.len() - 1]), None);
+    }
+}
@@ -1,34 +1,47 @@
-extern crate alloc;
-
+use super::{fid_count, FidRange, FidTable};
 use crate::rmi;
 use crate::rmi::error::Error;
 use crate::Monitor;
 
-use alloc::boxed::Box;
-use alloc::collections::btree_map::BTreeMap;
+pub type Handler = fn(&[usize], &mut [usize], &Monitor) -> Result<(), Error>;
+
+// RMI commands along with REQ_COMPLETE, and the vendor calls of Islet
+const FIDS: &[FidRange] = &[
+    FidRange::new(rmi::VERSION, 0x40),
+    FidRange::new(rmi::ISLET_REALM_SET_METADATA, 0x10),
+];
+pub const FID_CNT: usize = fid_count(FIDS);
 
-pub type Handler = Box<dyn Fn(&[usize], &mut [usize], &Monitor) -> Result<(), Error>>;
+/// Returns an empty table indexed by RMI function IDs
+pub const fn table<T: Copy>() -> FidTable<T, FID_CNT> {
+    FidTable::new(FIDS)
+}
 
 pub struct RmiHandle {
-    pub on_event: BTreeMap<usize, Handler>,
+    pub on_event: FidTable<Handler, FID_CNT>,
 }
 
 impl RmiHandle {
     pub fn new() -> Self {
-        let mut rmi = Self {
-            on_event: BTreeMap::new(),
-        };
+        let mut rmi = Self { on_event: table() };
         rmi.add_event_handlers();
         rmi
     }
 
     #[cfg(not(kani))]
     pub fn add_event_handlers(&mut self) {
+        rmi::dev::set_event_handler(self);
         rmi::features::set_event_handler(self);
         rmi::gpt::set_event_handler(self);
+        rmi::logger::set_event_handler(self);
         rmi::realm::set_event_handler(self);
         rmi::rec::set_event_handler(self);
+        #[cfg(feature = "replay")]
+        rmi::replay::set_event_handler(self);
         rmi::rtt::set_event_handler(self);
+        #[cfg(feature = "stat")]
+        rmi::stat::set_event_handler(self);
+        rmi::trace::set_event_handler(self);
         rmi::version::set_event_handler(self);
     }
 
@@ -1,21 +1,34 @@
-extern crate alloc;
-
+use super::{fid_count, FidRange, FidTable};
 use crate::rec::Rec;
 use crate::rmi::rec::run::Run;
 use crate::rsi;
+use crate::rsi::plane;
 use crate::rsi::psci;
+use crate::rsi::vdev;
 use crate::Monitor;
 // TODO: Change this into rsi::error::Error
 use crate::rmi::error::Error;
 
-use alloc::boxed::Box;
-use alloc::collections::btree_map::BTreeMap;
-
 pub type Handler =
-    Box<dyn Fn(&[usize], &mut [usize], &Monitor, &mut Rec<'_>, &mut Run) -> Result<(), Error>>;
+    fn(&[usize], &mut [usize], &Monitor, &mut Rec<'_>, &mut Run) -> Result<(), Error>;
+
+// RSI commands, PSCI of SMC32 and SMC64, SMCCC_VERSION and the vendor calls of Islet
+const FIDS: &[FidRange] = &[
+    FidRange::new(rsi::ABI_VERSION, 0x20),
+    FidRange::new(rsi::PSCI_VERSION, 0x20),
+    FidRange::new(0xc400_0000, 0x20),
+    FidRange::new(rsi::SMCCC_VERSION, 1),
+    FidRange::new(0xc700_0190, 0x10),
+];
+pub const FID_CNT: usize = fid_count(FIDS);
+
+/// Returns an empty table indexed by RSI function IDs
+pub const fn table<T: Copy>() -> FidTable<T, FID_CNT> {
+    FidTable::new(FIDS)
+}
 
 pub struct RsiHandle {
-    pub on_event: BTreeMap<usize, Handler>,
+    pub on_event: FidTable<Handler, FID_CNT>,
 }
 
 impl RsiHandle {
@@ -24,9 +37,7 @@ impl RsiHandle {
     pub const NOT_SUPPORTED: usize = !0;
 
     pub fn new() -> Self {
-        let mut rsi = Self {
-            on_event: BTreeMap::new(),
-        };
+        let mut rsi = Self { on_event: table() };
         rsi.set_event_handlers();
         rsi
     }
@@ -34,12 +45,11 @@ impl RsiHandle {
     fn set_event_handlers(&mut self) {
         rsi::set_event_handler(self);
         psci::set_event_handler(self);
+        plane::set_event_handler(self);
+        vdev::set_event_handler(self);
     }
 
     pub fn add_event_handler(&mut self, code: usize, handler: Handler) {
         self.on_event.insert(code, handler);
     }
 }
-
-unsafe impl Send for RsiHandle {}
-unsafe impl Sync for RsiHandle {}
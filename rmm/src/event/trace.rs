@@ -0,0 +1,361 @@
+use super::Context;
+use crate::config::NUM_OF_CPU;
+use crate::granule::GranuleState;
+use crate::realm::rd::Rd;
+use crate::rec::Rec;
+use crate::rmi;
+use crate::rsi;
+use crate::{get_granule, get_granule_if};
+
+use alloc::vec::Vec;
+use core::array::from_fn;
+use core::fmt;
+use lazy_static::lazy_static;
+use spin::mutex::Mutex;
+
+/// The number of records kept per CPU. The oldest one is overwritten first.
+pub const TRACE_ENTRIES: usize = 64;
+pub const TRACE_ARGS: usize = 5;
+pub const TRACE_RETS: usize = 2;
+
+const RECORD_WORDS: usize = 6 + TRACE_ARGS + TRACE_RETS;
+/// The size of an encoded record, which is a sequence of little-endian u64s.
+pub const RECORD_SIZE: usize = RECORD_WORDS * core::mem::size_of::<u64>();
+
+pub const KIND_RMI: u64 = 0;
+pub const KIND_RSI: u64 = 1;
+
+/// The id of an RMI which takes no RD or REC, or whose RD or REC is not there
+pub const ID_NONE: u64 = u64::MAX;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Record {
+    pub timestamp: u64,
+    pub cpu: u64,
+    pub kind: u64,
+    pub cmd: u64,
+    pub realm: u64,
+    pub rec: u64,
+    pub args: [u64; TRACE_ARGS],
+    pub ret: [u64; TRACE_RETS],
+}
+
+impl Record {
+    fn new(kind: u64, cmd: usize, realm: u64, rec: u64) -> Self {
+        Self {
+            timestamp: timestamp(),
+            cpu: cpu_id() as u64,
+            kind,
+            cmd: cmd as u64,
+            realm,
+            rec,
+            ..Default::default()
+        }
+    }
+
+    fn rsi(cmd: usize, rec: &Rec<'_>) -> Self {
+        let realm = rec.realmid().map_or(ID_NONE, |id| id as u64);
+        let mut record = Self::new(KIND_RSI, cmd, realm, rec.vcpuid() as u64);
+        record.ret[0] = rec.context.gp_regs[0];
+        record
+    }
+
+    fn words(&self) -> [u64; RECORD_WORDS] {
+        let mut words = [0; RECORD_WORDS];
+        words[..6].copy_from_slice(&[
+            self.timestamp,
+            self.cpu,
+            self.kind,
+            self.cmd,
+            self.realm,
+            self.rec,
+        ]);
+        words[6..6 + TRACE_ARGS].copy_from_slice(&self.args);
+        words[6 + TRACE_ARGS..].copy_from_slice(&self.ret);
+        words
+    }
+
+    pub fn to_bytes(&self) -> [u8; RECORD_SIZE] {
+        let mut bytes = [0; RECORD_SIZE];
+        for (chunk, word) in bytes.chunks_exact_mut(8).zip(self.words()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < RECORD_SIZE {
+            return None;
+        }
+        let mut words = [0u64; RECORD_WORDS];
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(8)) {
+            *word = u64::from_le_bytes(chunk.try_into().ok()?);
+        }
+        let mut record = Self {
+            timestamp: words[0],
+            cpu: words[1],
+            kind: words[2],
+            cmd: words[3],
+            realm: words[4],
+            rec: words[5],
+            ..Default::default()
+        };
+        record.args.copy_from_slice(&words[6..6 + TRACE_ARGS]);
+        record.ret.copy_from_slice(&words[6 + TRACE_ARGS..]);
+        Some(record)
+    }
+}
+
+struct Id(u64);
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            ID_NONE => write!(f, "-"),
+            id => write!(f, "{}", id),
+        }
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (kind, name) = match self.kind {
+            KIND_RMI => ("RMI", rmi::to_str(self.cmd as usize).into()),
+            KIND_RSI => ("RSI", rsi::to_str(self.cmd as usize).into()),
+            _ => ("???", alloc::format!("{:#x}", self.cmd)),
+        };
+        write!(
+            f,
+            "{:>16} cpu{} {} {: <24} realm={} rec={} {:x?} > {:x?}",
+            self.timestamp,
+            self.cpu,
+            kind,
+            name,
+            Id(self.realm),
+            Id(self.rec),
+            self.args,
+            self.ret
+        )
+    }
+}
+
+/// Decodes the records copied out by ISLET_TRACE_READ.
+pub fn decode(buf: &[u8]) -> impl Iterator<Item = Record> + '_ {
+    buf.chunks_exact(RECORD_SIZE).filter_map(Record::from_bytes)
+}
+
+struct Ring {
+    // The number of records pushed so far
+    count: usize,
+    entries: [Record; TRACE_ENTRIES],
+}
+
+impl Ring {
+    fn new() -> Self {
+        Self {
+            count: 0,
+            entries: [Record::default(); TRACE_ENTRIES],
+        }
+    }
+
+    fn push(&mut self, record: Record) {
+        self.entries[self.count % TRACE_ENTRIES] = record;
+        self.count = self.count.wrapping_add(1);
+    }
+
+    // Returns the records from the oldest one
+    fn records(&self) -> Vec<Record> {
+        let len = core::cmp::min(self.count, TRACE_ENTRIES);
+        let start = self.count.wrapping_sub(len);
+        (start..start + len)
+            .map(|i| self.entries[i % TRACE_ENTRIES])
+            .collect()
+    }
+}
+
+lazy_static! {
+    static ref TRACE: [Mutex<Ring>; NUM_OF_CPU] = from_fn(|_| Mutex::new(Ring::new()));
+}
+
+// The indexes of the RD and the REC among the arguments of an RMI
+fn id_args(cmd: usize) -> (Option<usize>, Option<usize>) {
+    match cmd {
+        rmi::REC_CREATE | rmi::RTT_SET_RIPAS => (Some(0), Some(1)),
+        rmi::REC_ENTER | rmi::REC_DESTROY | rmi::PSCI_COMPLETE => (None, Some(0)),
+        rmi::DATA_CREATE
+        | rmi::DATA_CREATE_UNKNOWN
+        | rmi::DATA_DESTROY
+        | rmi::REALM_ACTIVATE
+        | rmi::REALM_CREATE
+        | rmi::REALM_DESTROY
+        | rmi::RTT_CREATE
+        | rmi::RTT_DESTROY
+        | rmi::RTT_MAP_UNPROTECTED
+        | rmi::RTT_UNMAP_UNPROTECTED
+        | rmi::RTT_READ_ENTRY
+        | rmi::RTT_FOLD
+        | rmi::RTT_INIT_RIPAS
+        | rmi::RTT_AUX_CREATE
+        | rmi::RTT_AUX_DESTROY
+        | rmi::RTT_AUX_MAP_PROTECTED
+        | rmi::RTT_AUX_UNMAP_PROTECTED
+        | rmi::VDEV_CREATE
+        | rmi::DEV_MEM_MAP
+        | rmi::DEV_MEM_UNMAP
+        | rmi::ISLET_REALM_SET_METADATA => (Some(0), None),
+        _ => (None, None),
+    }
+}
+
+fn realm_id(rd: usize) -> Option<u64> {
+    let granule = get_granule_if!(rd, GranuleState::RD).ok()?;
+    let rd = granule.content::<Rd>().ok()?;
+    Some(rd.id() as u64)
+}
+
+fn rec_ids(rec: usize) -> Option<(u64, u64)> {
+    let granule = get_granule_if!(rec, GranuleState::Rec).ok()?;
+    let rec = granule.content::<Rec<'_>>().ok()?;
+    let realm = rec.realmid().map_or(ID_NONE, |id| id as u64);
+    Some((realm, rec.vcpuid() as u64))
+}
+
+/// Resolves the realm and REC ids of an RMI from its RD or REC argument.
+/// It is called before the command, while the RD or REC it destroys is still there.
+pub fn ids(ctx: &Context) -> (u64, u64) {
+    let arg = |idx: usize| ctx.arg_slice().get(idx).copied();
+    let (rd, rec) = id_args(ctx.cmd);
+    let realm = rd.and_then(arg).and_then(realm_id).unwrap_or(ID_NONE);
+    match rec.and_then(arg).and_then(rec_ids) {
+        Some((ID_NONE, rec)) => (realm, rec),
+        Some(ids) => ids,
+        None => (realm, ID_NONE),
+    }
+}
+
+/// Records an RMI along with the ids resolved by `ids()` before it.
+pub fn record_rmi(ctx: &Context, ids_before: (u64, u64)) {
+    // The RD or REC a command creates is only there after it
+    let (realm, rec) = match ctx.cmd {
+        rmi::REALM_CREATE | rmi::REC_CREATE => ids(ctx),
+        _ => ids_before,
+    };
+    let mut record = Record::new(KIND_RMI, ctx.cmd, realm, rec);
+    for (dst, src) in record.args.iter_mut().zip(ctx.arg_slice()) {
+        *dst = *src as u64;
+    }
+    for (dst, src) in record.ret.iter_mut().zip(ctx.ret_slice()) {
+        *dst = *src as u64;
+    }
+    TRACE[cpu_id()].lock().push(record);
+}
+
+/// Records an RSI along with its status in X0. The other registers are left out,
+/// as they may carry secrets of the realm (e.g., ISLET_REALM_SEALING_KEY).
+pub fn record_rsi(cmd: usize, rec: &Rec<'_>) {
+    TRACE[cpu_id()].lock().push(Record::rsi(cmd, rec));
+}
+
+/// Returns the encoded records of the given CPU from the oldest one.
+pub fn read(cpu: usize) -> Option<Vec<u8>> {
+    let ring = TRACE.get(cpu)?.lock();
+    Some(ring.records().iter().flat_map(|r| r.to_bytes()).collect())
+}
+
+#[cfg(not(any(test, miri, fuzzing)))]
+fn timestamp() -> u64 {
+    use aarch64_cpu::registers::{Readable, CNTPCT_EL0};
+    CNTPCT_EL0.get()
+}
+
+#[cfg(any(test, miri, fuzzing))]
+fn timestamp() -> u64 {
+    0
+}
+
+#[cfg(not(any(test, miri, fuzzing)))]
+fn cpu_id() -> usize {
+    crate::cpu::get_cpu_id()
+}
+
+#[cfg(any(test, miri, fuzzing))]
+fn cpu_id() -> usize {
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn trace_ring_wraps() {
+        let mut ring = Ring::new();
+        for cmd in 0..TRACE_ENTRIES + 3 {
+            ring.push(Record {
+                cmd: cmd as u64,
+                ..Default::default()
+            });
+        }
+
+        let records = ring.records();
+        assert_eq!(records.len(), TRACE_ENTRIES);
+        assert_eq!(records[0].cmd, 3);
+        assert_eq!(records[TRACE_ENTRIES - 1].cmd, (TRACE_ENTRIES + 2) as u64);
+    }
+
+    #[test]
+    fn trace_record_decode() {
+        let mut record = Record::new(KIND_RMI, rmi::REALM_CREATE, ID_NONE, ID_NONE);
+        record.args[..2].copy_from_slice(&[0x1000, 0x2000]);
+
+        let bytes = record.to_bytes();
+        let decoded: Vec<Record> = decode(&bytes).collect();
+        assert_eq!(decoded, [record]);
+
+        let line = alloc::format!("{}", decoded[0]);
+        assert!(line.contains("RMI REALM_CREATE"));
+        assert!(line.contains("realm=- rec=-"));
+    }
+
+    #[test]
+    fn trace_rsi_hides_registers() {
+        const SECRET: u64 = 0x5ea1;
+        let mut rec = Rec::new();
+        rec.context.gp_regs[..5].fill(SECRET);
+        rec.context.gp_regs[0] = rsi::SUCCESS as u64;
+
+        let record = Record::rsi(rsi::ISLET_REALM_SEALING_KEY, &rec);
+        assert_eq!(record.args, [0; TRACE_ARGS]);
+        assert_eq!(record.ret, [rsi::SUCCESS as u64, 0]);
+    }
+
+    #[test]
+    fn trace_rmi_ids() {
+        use crate::test_utils::*;
+
+        let ctx = |cmd, args: &[usize]| {
+            let mut ctx = Context::new(cmd);
+            ctx.init_arg(args);
+            ctx
+        };
+
+        let rd = realm_create();
+        let realm = realm_id(rd).unwrap();
+        let (rec1, run1) = (granule_addr(IDX_REC1), granule_addr(IDX_REC1_RUN));
+        assert_eq!(ids(&ctx(rmi::REC_CREATE, &[rd, rec1])), (realm, ID_NONE));
+
+        rec_create(rd, IDX_REC1, IDX_REC1_PARAMS, IDX_REC1_AUX);
+        assert_eq!(ids(&ctx(rmi::REC_CREATE, &[rd, rec1])), (realm, 0));
+        assert_eq!(ids(&ctx(rmi::REC_ENTER, &[rec1, run1])), (realm, 0));
+        assert_eq!(ids(&ctx(rmi::RTT_READ_ENTRY, &[rd])), (realm, ID_NONE));
+        // Not an RD
+        assert_eq!(ids(&ctx(rmi::RTT_READ_ENTRY, &[rec1])), (ID_NONE, ID_NONE));
+        assert_eq!(ids(&ctx(rmi::VERSION, &[rd])), (ID_NONE, ID_NONE));
+
+        rec_destroy(IDX_REC1, IDX_REC1_AUX);
+        assert_eq!(ids(&ctx(rmi::REC_ENTER, &[rec1, run1])), (ID_NONE, ID_NONE));
+        realm_destroy(rd);
+
+        miri_teardown();
+    }
+}
@@ -1,7 +1,9 @@
+use crate::debug::{get_bp, get_wp, set_bp, set_wp};
 use crate::exception::trap;
 use crate::rec::Rec;
 
 use aarch64_cpu::registers::*;
+use armv9a::regs::debug::MDSCR_EL1;
 use armv9a::regs::*;
 
 fn check_sysreg_id_access(esr: u64) -> bool {
@@ -19,9 +21,16 @@ fn check_sysreg_icc_access(esr: u64) -> bool {
     direction == 0 && (esr_iss == ISS_ID_ICC_MASK || esr_iss == ISS_ID_ICC_PMR_EL1)
 }
 
+// The debug registers trapped by MDCR_EL2.TDA are encoded with Op0 == 0b10
+fn check_sysreg_debug_access(esr: u64) -> bool {
+    ISS::new(esr).get_masked_value(ISS::Op0) == 0b10
+}
+
 pub fn handle(rec: &mut Rec<'_>, esr: u64) -> u64 {
     if check_sysreg_id_access(esr) {
         handle_sysreg_id(rec, esr);
+    } else if check_sysreg_debug_access(esr) {
+        handle_sysreg_debug(rec, esr);
     } else if check_sysreg_icc_access(esr) {
         return trap::RET_TO_RMM;
     } else {
@@ -63,9 +72,18 @@ fn handle_sysreg_id(rec: &mut Rec<'_>, esr: u64) -> u64 {
         // Present FEAT_SVE only if Rec is set to use SVE.
         ISS_ID_AA64ZFR0_EL1 => (!rec.context.simd.cfg.sve_en as u64).wrapping_neg(),
         ISS_ID_AA64PFR1_EL1 => {
-            (ID_AA64PFR1_SME_EL1::MTE.mask << ID_AA64PFR1_SME_EL1::MTE.shift)
-                + (ID_AA64PFR1_SME_EL1::SME.mask << ID_AA64PFR1_SME_EL1::SME.shift)
+            (if !rec.context.mte.enabled {
+                ID_AA64PFR1_SME_EL1::MTE.mask << ID_AA64PFR1_SME_EL1::MTE.shift
+            } else {
+                0
+            }) + if !rec.context.simd.cfg.sme_en {
+                ID_AA64PFR1_SME_EL1::SME.mask << ID_AA64PFR1_SME_EL1::SME.shift
+            } else {
+                0
+            }
         }
+        // Present FEAT_SME only if Rec is set to use SME.
+        ISS_ID_AA64SMFR0_EL1 => (!rec.context.simd.cfg.sme_en as u64).wrapping_neg(),
         ISS_ID_AA64DFR0_EL1 => {
             (ID_AA64DFR0_EL1::BRBE.mask << ID_AA64DFR0_EL1::BRBE.shift)
                 + (ID_AA64DFR0_EL1::MTPMU.mask << ID_AA64DFR0_EL1::MTPMU.shift)
@@ -86,11 +104,15 @@ fn handle_sysreg_id(rec: &mut Rec<'_>, esr: u64) -> u64 {
         ISS_ID_AA64PFR0_EL1 => ID_AA64PFR0_EL1.get() & mask,
         ISS_ID_AA64PFR1_EL1 => ID_AA64PFR1_EL1.get() & mask,
         ISS_ID_AA64ZFR0_EL1 => ID_AA64ZFR0_EL1.get() & mask,
+        ISS_ID_AA64SMFR0_EL1 => ID_AA64SMFR0_EL1.get() & mask,
         ISS_ID_AA64DFR0_EL1 => {
+            // Armv8 debug architecture with the breakpoints and watchpoints of the realm
+            const DEBUG_VER_V8: u64 = 6;
+            let (num_bps, num_wps) = rec.debug_config().unwrap_or((0, 0));
             let mut dfr0_set = 0u64;
-            dfr0_set &= 6 << ID_AA64DFR0_EL1::DebugVer.shift;
-            dfr0_set &= 1 << ID_AA64DFR0_EL1::BRPs.shift;
-            dfr0_set &= 1 << ID_AA64DFR0_EL1::WRPs.shift;
+            dfr0_set |= DEBUG_VER_V8 << ID_AA64DFR0_EL1::DebugVer.shift;
+            dfr0_set |= (num_bps as u64) << ID_AA64DFR0_EL1::BRPs.shift;
+            dfr0_set |= (num_wps as u64) << ID_AA64DFR0_EL1::WRPs.shift;
             ID_AA64DFR0_EL1.get() & mask | dfr0_set
         }
         ISS_ID_AA64DFR1_EL1 => ID_AA64DFR1_EL1.get() & mask,
@@ -105,3 +127,71 @@ fn handle_sysreg_id(rec: &mut Rec<'_>, esr: u64) -> u64 {
     };
     trap::RET_TO_REC
 }
+
+// Gives the realm the breakpoints and watchpoints of REALM_CREATE only.
+// The others, and the debug registers other than MDSCR_EL1, are RAZ/WI.
+fn handle_sysreg_debug(rec: &mut Rec<'_>, esr: u64) -> u64 {
+    const OP2_DBGBVR: u64 = 4;
+    const OP2_DBGBCR: u64 = 5;
+    const OP2_DBGWVR: u64 = 6;
+    const OP2_DBGWCR: u64 = 7;
+
+    let esr = ISS::new(esr);
+    let rt = esr.get_masked_value(ISS::Rt) as usize;
+    // direction: 0b0 - write, 0b1 - read
+    let read = esr.get_masked_value(ISS::Direction) == 1;
+    let (op1, crn) = (
+        esr.get_masked_value(ISS::Op1),
+        esr.get_masked_value(ISS::CRn),
+    );
+    let (crm, op2) = (
+        esr.get_masked_value(ISS::CRm),
+        esr.get_masked_value(ISS::Op2),
+    );
+    // Encoded as the number minus one, like ID_AA64DFR0_EL1.{BRPs, WRPs}
+    let (num_bps, num_wps) = rec.debug_config().unwrap_or((0, 0));
+    let value = if rt == 31 { 0 } else { rec.context.gp_regs[rt] };
+    let n = crm as usize;
+
+    // The realm's registers are live while it runs, and saved on its exit
+    let access = |reg: &mut u64| {
+        if read {
+            *reg
+        } else {
+            *reg = value;
+            0
+        }
+    };
+    let ret = match (op1, crn, crm, op2) {
+        (0, 0, _, OP2_DBGBVR | OP2_DBGBCR) if n <= num_bps => {
+            let (mut dbgbcr, mut dbgbvr) = get_bp(n);
+            let ret = match op2 {
+                OP2_DBGBVR => access(&mut dbgbvr),
+                _ => access(&mut dbgbcr),
+            };
+            set_bp(n, dbgbcr, dbgbvr);
+            ret
+        }
+        (0, 0, _, OP2_DBGWVR | OP2_DBGWCR) if n <= num_wps => {
+            let (mut dbgwcr, mut dbgwvr) = get_wp(n);
+            let ret = match op2 {
+                OP2_DBGWVR => access(&mut dbgwvr),
+                _ => access(&mut dbgwcr),
+            };
+            set_wp(n, dbgwcr, dbgwvr);
+            ret
+        }
+        // MDSCR_EL1
+        (0, 0, 2, 2) => {
+            let mut mdscr = MDSCR_EL1.get();
+            let ret = access(&mut mdscr);
+            MDSCR_EL1.set(mdscr);
+            ret
+        }
+        _ => 0,
+    };
+    if read && rt != 31 {
+        rec.context.gp_regs[rt] = ret;
+    }
+    trap::RET_TO_REC
+}
@@ -1,6 +1,7 @@
 pub mod lower;
 pub mod trap;
 
+#[cfg(target_arch = "aarch64")]
 core::arch::global_asm!(include_str!("vectors.s"));
 extern "C" {
     pub static mut vectors: u64;
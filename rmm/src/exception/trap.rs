@@ -212,10 +212,14 @@ pub extern "C" fn handle_lower_exception(
                 debug!("Synchronous: SIMD");
                 let abort: bool = match Syndrome::from(esr) {
                     Syndrome::SVE => !rec.context.simd.cfg.sve_en,
-                    // Note: Since only FEAT_SVE is set for Realms and
-                    // we are doing lazy restore, being reported as Syndrome::SME
-                    // is actually comming from the NW's SME setting.
-                    Syndrome::SME => !rec.context.simd.cfg.sve_en,
+                    // Note: Since we are doing lazy restore, being reported as
+                    // Syndrome::SME before the restoration may come from the NW's
+                    // SME setting. After that, SME is trapped only for Realms
+                    // without FEAT_SME.
+                    Syndrome::SME => {
+                        rec.context.simd.is_used
+                            || (!rec.context.simd.cfg.sve_en && !rec.context.simd.cfg.sme_en)
+                    }
                     _ => false,
                 };
                 if abort {
@@ -174,7 +174,7 @@ impl Granule {
         (entry_addr - table_base) / core::mem::size_of::<Entry>()
     }
 
-    #[cfg(not(any(kani, miri, test, fuzzing)))]
+    #[cfg(not(any(kani, miri, test, all(fuzzing, not(feature = "sim")))))]
     fn index_to_addr(&self) -> usize {
         let mut idx = self.index();
         let regions = config::NS_DRAM_REGIONS.lock();
@@ -188,7 +188,7 @@ impl Granule {
         }
         0
     }
-    #[cfg(any(kani, miri, test, fuzzing))]
+    #[cfg(any(kani, miri, test, all(fuzzing, not(feature = "sim"))))]
     // DIFF: calculate addr using GRANULE_REGION
     pub fn index_to_addr(&self) -> usize {
         use crate::granule::{GRANULE_REGION, GRANULE_STATUS_TABLE_SIZE};
@@ -204,7 +204,7 @@ impl Granule {
         return unsafe { GRANULE_REGION.as_ptr() as usize + (idx * GRANULE_SIZE) };
     }
 
-    #[cfg(not(any(kani, miri, test, fuzzing)))]
+    #[cfg(not(any(kani, miri, test, all(fuzzing, not(feature = "sim")))))]
     fn zeroize(&mut self) {
         let addr = self.index_to_addr();
 
@@ -214,8 +214,10 @@ impl Granule {
         unsafe {
             core::ptr::write_bytes(addr as *mut u8, 0x0, GRANULE_SIZE);
         }
+        // Allocation tags are wiped as well, not to leak them across realms
+        crate::mte::zero_tags(addr, GRANULE_SIZE);
     }
-    #[cfg(any(kani, miri, test, fuzzing))]
+    #[cfg(any(kani, miri, test, all(fuzzing, not(feature = "sim"))))]
     // DIFF: assertion is added to reduce the proof burden
     //       `write_bytes()` uses a small count value
     fn zeroize(&mut self) {
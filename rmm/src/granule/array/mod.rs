@@ -24,7 +24,7 @@ pub const GRANULE_MEM_SIZE: usize = GRANULE_SIZE * GRANULE_STATUS_TABLE_SIZE;
 //       so the last region cannot be utilized.
 pub static mut GRANULE_REGION: [u8; GRANULE_MEM_SIZE] = [0; GRANULE_MEM_SIZE];
 
-#[cfg(not(any(kani, miri, test, fuzzing)))]
+#[cfg(not(any(kani, miri, test, all(fuzzing, not(feature = "sim")))))]
 pub fn validate_addr(addr: usize) -> bool {
     if addr % GRANULE_SIZE != 0 {
         // if the address is out of range.
@@ -38,7 +38,7 @@ pub fn validate_addr(addr: usize) -> bool {
     }
     true
 }
-#[cfg(any(kani, miri, test, fuzzing))]
+#[cfg(any(kani, miri, test, all(fuzzing, not(feature = "sim"))))]
 // DIFF: check against GRANULE_REGION
 pub fn validate_addr(addr: usize) -> bool {
     if addr % GRANULE_SIZE != 0 {
@@ -51,7 +51,20 @@ pub fn validate_addr(addr: usize) -> bool {
     addr >= g_start && addr < g_end
 }
 
-#[cfg(not(any(kani, miri, test, fuzzing)))]
+#[cfg(not(any(kani, miri, test, all(fuzzing, not(feature = "sim")))))]
+/// Whether [base, top) overlaps the memory tracked as granules
+pub fn overlaps_granules(base: usize, top: usize) -> bool {
+    config::overlaps_ns_dram(base, top)
+}
+#[cfg(any(kani, miri, test, all(fuzzing, not(feature = "sim"))))]
+// DIFF: check against GRANULE_REGION
+pub fn overlaps_granules(base: usize, top: usize) -> bool {
+    let g_start = unsafe { GRANULE_REGION.as_ptr() as usize };
+    let g_end = g_start + GRANULE_MEM_SIZE;
+    base < g_end && top > g_start
+}
+
+#[cfg(not(any(kani, miri, test, all(fuzzing, not(feature = "sim")))))]
 pub fn granule_addr_to_index(addr: usize) -> usize {
     let regions = config::NS_DRAM_REGIONS.lock();
 
@@ -65,7 +78,7 @@ pub fn granule_addr_to_index(addr: usize) -> usize {
 
     usize::MAX
 }
-#[cfg(any(kani, miri, test, fuzzing))]
+#[cfg(any(kani, miri, test, all(fuzzing, not(feature = "sim"))))]
 // DIFF: calculate index using GRANULE_REGION
 pub fn granule_addr_to_index(addr: usize) -> usize {
     let g_start = unsafe { GRANULE_REGION.as_ptr() as usize };
@@ -95,6 +108,9 @@ impl GranuleState {
     pub const Data: u8 = 5;
     pub const RTT: u8 = 6;
     pub const Metadata: u8 = 7;
+    pub const Pdev: u8 = 8;
+    pub const PdevAux: u8 = 9;
+    pub const Vdev: u8 = 10;
 
     pub fn new(state: u8) -> Self {
         Self { inner: state }
@@ -114,9 +130,12 @@ pub const GRANULE_STATUS_TABLE_SIZE: usize = config::MAX_DRAM_SIZE / GRANULE_SIZ
 #[cfg(kani)]
 pub const GRANULE_STATUS_TABLE_SIZE: usize = 6;
 #[cfg(any(miri, test))]
-pub const GRANULE_STATUS_TABLE_SIZE: usize = 55;
-#[cfg(fuzzing)]
+pub const GRANULE_STATUS_TABLE_SIZE: usize = 64;
+#[cfg(all(fuzzing, not(feature = "sim")))]
 pub const GRANULE_STATUS_TABLE_SIZE: usize = 2048;
+// The simulator registers a host memory region of up to 256MB as NS DRAM
+#[cfg(all(fuzzing, feature = "sim"))]
+pub const GRANULE_STATUS_TABLE_SIZE: usize = 0x1000_0000 / GRANULE_SIZE;
 
 pub struct GranuleStatusTable {
     pub entries: [Entry; GRANULE_STATUS_TABLE_SIZE],
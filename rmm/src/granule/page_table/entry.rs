@@ -24,7 +24,7 @@ pub struct Granule {
     /// physical address which is aligned with GRANULE_SIZE
     addr: usize,
     /// parent that this granule points to
-    /// the only case at this point is "Rd(parent) - Rec(child)"
+    /// the cases at this point are "Rd(parent) - Rec(child)" and "Rd(parent) - Vdev(child)"
     /// Notice: do not put self-reference into this field, which may cause undefined behaviors.
     parent: Option<Inner>,
 }
@@ -78,8 +78,11 @@ impl Granule {
 
     fn set_parent(&mut self, parent: Inner) -> Result<(), Error> {
         // parent-child state validation check
-        // (Parent, Child): (Rd, Rec) --> only one case at this moment.
-        if self.state() != GranuleState::Rec || parent.granule.state() != GranuleState::RD {
+        // (Parent, Child): (Rd, Rec), (Rd, Vdev)
+        let child = self.state();
+        if (child != GranuleState::Rec && child != GranuleState::Vdev)
+            || parent.granule.state() != GranuleState::RD
+        {
             return Err(Error::MmWrongParentChild);
         }
         self.parent = Some(parent);
@@ -104,6 +107,7 @@ impl Granule {
         unsafe {
             core::ptr::write_bytes(buf as *mut usize, 0x0, GRANULE_SIZE / 8);
         }
+        crate::mte::zero_tags(buf, GRANULE_SIZE);
     }
 
     #[cfg(test)]
@@ -240,7 +244,7 @@ impl page_table::Entry for Entry {
         self.0.get_mut()
     }
 
-    fn address(&self, _level: usize) -> Option<PhysAddr> {
+    fn address(&self, _level: isize) -> Option<PhysAddr> {
         Some(PhysAddr::from(self.0.lock().addr()))
     }
 
@@ -268,7 +272,7 @@ impl page_table::Entry for Entry {
         }
     }
 
-    fn as_subtable(&self, index: usize, _level: usize) -> Result<usize, Error> {
+    fn as_subtable(&self, index: usize, _level: isize) -> Result<usize, Error> {
         get_l1_table_addr(index)
     }
 
@@ -21,7 +21,7 @@ pub const GRANULE_MASK: usize = !((1 << GRANULE_SHIFT) - 1);
 /// Each entry (L1table) covers 4mb. This is a configurable number.
 pub enum L0Table {}
 impl Level for L0Table {
-    const THIS_LEVEL: usize = 0;
+    const THIS_LEVEL: isize = 0;
     const TABLE_SIZE: usize = Self::NUM_ENTRIES * core::mem::size_of::<Entry>();
     const TABLE_ALIGN: usize = 64;
     const NUM_ENTRIES: usize = L1Table::NUM_ENTRIES;
@@ -39,7 +39,7 @@ impl HasSubtable for L0Table {
 /// Each entry covers PAGE_SIZE (4kb).
 pub enum L1Table {}
 impl Level for L1Table {
-    const THIS_LEVEL: usize = 1;
+    const THIS_LEVEL: isize = 1;
     const TABLE_SIZE: usize = Self::NUM_ENTRIES * core::mem::size_of::<Entry>();
     const TABLE_ALIGN: usize = 64;
     const NUM_ENTRIES: usize = (L0_TABLE_ENTRY_SIZE_RANGE / L1_TABLE_ENTRY_SIZE_RANGE);
@@ -60,6 +60,9 @@ impl GranuleState {
     pub const Data: u64 = 5;
     pub const RTT: u64 = 6;
     pub const Metadata: u64 = 7;
+    pub const Pdev: u64 = 8;
+    pub const PdevAux: u64 = 9;
+    pub const Vdev: u64 = 10;
 
     pub fn new(state: u64) -> Self {
         Self { inner: state }
@@ -224,6 +227,11 @@ pub fn validate_addr(addr: usize) -> bool {
     true
 }
 
+/// Whether [base, top) overlaps the memory tracked as granules
+pub fn overlaps_granules(base: usize, top: usize) -> bool {
+    config::overlaps_ns_dram(base, top)
+}
+
 // TODO: we can use "constructors" for this kind of initialization. (we can define macros for that)
 pub fn create_granule_status_table() {
     unsafe {
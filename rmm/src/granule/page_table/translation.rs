@@ -54,7 +54,7 @@ impl GranuleStatusTable<'_, '_> {
 pub enum GranuleSize {}
 impl PageSize for GranuleSize {
     const SIZE: usize = GRANULE_SIZE;
-    const MAP_TABLE_LEVEL: usize = 1;
+    const MAP_TABLE_LEVEL: isize = 1;
     const MAP_EXTRA_FLAG: u64 = GranuleState::Undelegated;
 }
 
@@ -5,6 +5,8 @@ use crate::granule::{GranuleState, GRANULE_SIZE};
 #[cfg(not(feature = "gst_page_table"))]
 use crate::{get_granule, get_granule_if};
 
+use crate::monitor::Monitor;
+
 use safe_abstraction::raw_ptr::{assume_safe, SafetyAssured, SafetyChecked};
 use vmsa::guard::Content;
 
@@ -68,6 +70,26 @@ pub fn copy_to_ptr<T: SafetyChecked + SafetyAssured + Copy>(src: &T, dst: usize)
     }
 }
 
+/// Copies `bytes` to the host's buffer of `num_granules` contiguous granules at `buf`,
+/// one granule at a time. The rest of the last granule is zero-filled.
+pub fn copy_out(rmm: &Monitor, buf: usize, num_granules: usize, bytes: &[u8]) -> Option<()> {
+    if bytes.len() > num_granules.checked_mul(GRANULE_SIZE)? {
+        return None;
+    }
+
+    for (i, chunk) in bytes.chunks(GRANULE_SIZE).enumerate() {
+        let addr = buf.checked_add(i * GRANULE_SIZE)?;
+        let mut page = DataPage::default();
+        page.as_mut_slice()[..chunk.len()].copy_from_slice(chunk);
+
+        rmm.page_table.map(addr, false);
+        let ret = copy_to_ptr::<DataPage>(&page, addr);
+        rmm.page_table.unmap(addr);
+        ret?;
+    }
+    Some(())
+}
+
 /// DataPage is used to convey realm data from host to realm.
 #[repr(C)]
 #[derive(Copy, Clone)]
@@ -77,6 +99,10 @@ impl DataPage {
     pub fn as_slice(&self) -> &[u8] {
         self.0.as_slice()
     }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.0.as_mut_slice()
+    }
 }
 
 impl Default for DataPage {
@@ -10,6 +10,8 @@ pub mod asm;
 pub mod config;
 pub(crate) mod cose;
 pub mod cpu;
+pub mod debug;
+pub mod dev;
 pub(crate) mod event;
 pub mod exception;
 pub mod gic;
@@ -18,7 +20,9 @@ pub mod granule;
 #[macro_use]
 pub(crate) mod host;
 pub mod logger;
+pub mod mec;
 pub mod mm;
+pub mod mte;
 #[cfg(not(any(test, kani, miri, fuzzing)))]
 pub mod panic;
 pub mod pmu;
@@ -44,6 +48,9 @@ pub mod monitor;
 mod monitor;
 mod rmm_el3;
 
+#[cfg(not(kani))]
+pub use event::trace;
+
 extern crate alloc;
 
 #[macro_use]
@@ -133,6 +140,8 @@ unsafe fn setup_el2() {
     VBAR_EL2.set(addr_of!(vectors) as u64);
     SCTLR_EL2
         .write(SCTLR_EL2::C::SET + SCTLR_EL2::I::SET + SCTLR_EL2::M::SET + SCTLR_EL2::EOS::SET);
+    mte::setup_el2();
+    mec::setup_el2();
     CPTR_EL2.write(CPTR_EL2::TAM::SET);
     ICC_SRE_EL2.write(
         ICC_SRE_EL2::ENABLE::SET
@@ -164,6 +173,7 @@ unsafe fn setup_el2() {
 /// Failing to meet these requirements can result in system crashes, memory corruption, security
 /// vulnerabilities, or other undefined behavior.
 unsafe fn setup_mmu_cfg(layout: PlatformMemoryLayout) {
+    #[cfg(target_arch = "aarch64")]
     core::arch::asm!("tlbi alle2is", "dsb ish", "isb",);
 
     // /* Set attributes in the right indices of the MAIR. */
@@ -196,6 +206,7 @@ unsafe fn setup_mmu_cfg(layout: PlatformMemoryLayout) {
     MAIR_EL2.write(mair_el2);
     TCR_EL2.write(tcr_el2);
     TTBR0_EL2.set(ttbl_base);
+    #[cfg(target_arch = "aarch64")]
     core::arch::asm!("dsb ish", "isb",);
 }
 
@@ -216,7 +227,7 @@ unsafe fn setup_mmu_cfg(layout: PlatformMemoryLayout) {
 ///
 /// - This function alters the processor's execution level by jumping to EL1;
 ///   the caller must ensure that the system is in a correct state for this transition.
-#[cfg(not(kani))]
+#[cfg(all(not(kani), target_arch = "aarch64"))]
 pub unsafe fn rmm_exit(args: [usize; 4]) -> [usize; 4] {
     let mut ret: [usize; 4] = [0usize; 4];
 
@@ -231,7 +242,8 @@ pub unsafe fn rmm_exit(args: [usize; 4]) -> [usize; 4] {
     ret
 }
 
-#[cfg(kani)]
+// Hosted builds on other architectures (e.g., rmm/sim on x86) have no EL1 to jump to
+#[cfg(any(kani, not(target_arch = "aarch64")))]
 pub unsafe fn rmm_exit(_args: [usize; 4]) -> [usize; 4] {
     let ret: [usize; 4] = [0usize; 4];
     ret
@@ -1,16 +1,33 @@
 use io::Write;
 use log::{Level, LevelFilter, Metadata, Record};
 
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use spin::{Mutex, RwLock};
+
 struct SimpleLogger;
 extern crate alloc;
 
+pub const SINK_CONSOLE: usize = 1 << 0;
+pub const SINK_RING: usize = 1 << 1;
+pub const SINK_ALL: usize = SINK_CONSOLE | SINK_RING;
+
+pub const MAX_FILTERS: usize = 8;
+pub const MAX_TARGET_LEN: usize = 64;
+pub const LOG_RING_SIZE: usize = 16 * 1024;
+
 impl log::Log for SimpleLogger {
     fn enabled(&self, metadata: &Metadata<'_>) -> bool {
-        metadata.level() <= Level::Trace
+        metadata.level() <= FILTERS.read().level_for(metadata.target())
     }
 
     fn log(&self, record: &Record<'_>) {
-        if self.enabled(record.metadata()) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let sinks = SINKS.load(Ordering::Relaxed);
+        if sinks & SINK_CONSOLE != 0 {
             if record.metadata().level() <= Level::Warn {
                 crate::eprintln!(
                     "[{}]{} -- {}",
@@ -27,14 +44,253 @@ impl log::Log for SimpleLogger {
                 );
             }
         }
+        if sinks & SINK_RING != 0 {
+            let _ = fmt::Write::write_fmt(
+                &mut *LOG_RING.lock(),
+                format_args!(
+                    "[{}]{} -- {}\n",
+                    record.level(),
+                    record.target(),
+                    record.args()
+                ),
+            );
+        }
     }
 
     fn flush(&self) {}
 }
 
 static LOGGER: SimpleLogger = SimpleLogger;
+static FILTERS: RwLock<Filters> = RwLock::new(Filters::new(LevelFilter::Trace));
+static SINKS: AtomicUsize = AtomicUsize::new(SINK_ALL);
+static LOG_RING: Mutex<LogRing> = Mutex::new(LogRing::new());
 
 pub fn register_global_logger(maxlevel: LevelFilter) {
     log::set_logger(&LOGGER).unwrap();
+    FILTERS.write().default = maxlevel;
     log::set_max_level(maxlevel);
 }
+
+#[derive(Clone, Copy)]
+struct Filter {
+    target: [u8; MAX_TARGET_LEN],
+    len: usize,
+    level: LevelFilter,
+}
+
+impl Filter {
+    fn target(&self) -> &[u8] {
+        &self.target[..self.len]
+    }
+
+    // Targets are module paths, so "islet_rmm::rmi" covers
+    // "islet_rmm::rmi::realm" but not "islet_rmm::rmi_foo".
+    fn covers(&self, target: &str) -> bool {
+        let target = target.as_bytes();
+        target.starts_with(self.target())
+            && (target.len() == self.len || target[self.len..].starts_with(b"::"))
+    }
+}
+
+struct Filters {
+    default: LevelFilter,
+    list: [Option<Filter>; MAX_FILTERS],
+}
+
+impl Filters {
+    const fn new(default: LevelFilter) -> Self {
+        Self {
+            default,
+            list: [None; MAX_FILTERS],
+        }
+    }
+
+    // The most specific filter takes precedence
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.list
+            .iter()
+            .flatten()
+            .filter(|f| f.covers(target))
+            .max_by_key(|f| f.len)
+            .map_or(self.default, |f| f.level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.list
+            .iter()
+            .flatten()
+            .map(|f| f.level)
+            .fold(self.default, core::cmp::max)
+    }
+
+    fn set(&mut self, target: &str, level: Option<LevelFilter>) -> Result<(), LogError> {
+        let target = target.as_bytes();
+        if target.len() > MAX_TARGET_LEN {
+            return Err(LogError::TargetTooLong);
+        }
+
+        let existing = self
+            .list
+            .iter()
+            .position(|f| f.is_some_and(|f| f.target() == target));
+        let Some(level) = level else {
+            if let Some(idx) = existing {
+                self.list[idx] = None;
+            }
+            return Ok(());
+        };
+
+        let idx = existing
+            .or_else(|| self.list.iter().position(|f| f.is_none()))
+            .ok_or(LogError::NoRoom)?;
+        let mut filter = Filter {
+            target: [0; MAX_TARGET_LEN],
+            len: target.len(),
+            level,
+        };
+        filter.target[..target.len()].copy_from_slice(target);
+        self.list[idx] = Some(filter);
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LogError {
+    TargetTooLong,
+    NoRoom,
+}
+
+/// Sets the log level of the target and the modules below it.
+/// `None` removes the filter of the target, and an empty target
+/// sets the level of the ones without any filter.
+pub fn set_filter(target: &str, level: Option<LevelFilter>) -> Result<(), LogError> {
+    let mut filters = FILTERS.write();
+    if target.is_empty() {
+        filters.default = level.unwrap_or(LevelFilter::Trace);
+    } else {
+        filters.set(target, level)?;
+    }
+    log::set_max_level(filters.max_level());
+    Ok(())
+}
+
+/// Selects where the logs go, with `SINK_CONSOLE` and `SINK_RING`.
+pub fn set_sinks(sinks: usize) {
+    SINKS.store(sinks & SINK_ALL, Ordering::Relaxed);
+}
+
+/// Copies the oldest logs in the ring into `buf` without consuming them.
+/// Returns the number of bytes copied and the ones dropped by overwriting.
+pub fn peek(buf: &mut [u8]) -> (usize, usize) {
+    let ring = LOG_RING.lock();
+    (ring.peek(buf), ring.lost)
+}
+
+/// Consumes `len` bytes of the oldest logs in the ring, which are peeked.
+pub fn consume(len: usize) {
+    LOG_RING.lock().consume(len);
+}
+
+struct LogRing {
+    buf: [u8; LOG_RING_SIZE],
+    // The offset of the oldest byte
+    head: usize,
+    len: usize,
+    // The number of bytes overwritten before being drained
+    lost: usize,
+}
+
+impl LogRing {
+    const fn new() -> Self {
+        Self {
+            buf: [0; LOG_RING_SIZE],
+            head: 0,
+            len: 0,
+            lost: 0,
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            if self.len == LOG_RING_SIZE {
+                self.head = (self.head + 1) % LOG_RING_SIZE;
+                self.len -= 1;
+                self.lost = self.lost.saturating_add(1);
+            }
+            self.buf[(self.head + self.len) % LOG_RING_SIZE] = *byte;
+            self.len += 1;
+        }
+    }
+
+    fn peek(&self, buf: &mut [u8]) -> usize {
+        let len = core::cmp::min(self.len, buf.len());
+        for (i, dst) in buf[..len].iter_mut().enumerate() {
+            *dst = self.buf[(self.head + i) % LOG_RING_SIZE];
+        }
+        len
+    }
+
+    fn consume(&mut self, len: usize) {
+        let len = core::cmp::min(self.len, len);
+        self.head = (self.head + len) % LOG_RING_SIZE;
+        self.len -= len;
+        self.lost = 0;
+    }
+}
+
+impl fmt::Write for LogRing {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn logger_filter_level() {
+        let mut filters = Filters::new(LevelFilter::Info);
+        assert!(filters
+            .set("islet_rmm::rmi", Some(LevelFilter::Trace))
+            .is_ok());
+        assert!(filters
+            .set("islet_rmm::rmi::rtt", Some(LevelFilter::Error))
+            .is_ok());
+
+        assert_eq!(filters.level_for("islet_rmm::rsi"), LevelFilter::Info);
+        assert_eq!(filters.level_for("islet_rmm::rmi_foo"), LevelFilter::Info);
+        assert_eq!(filters.level_for("islet_rmm::rmi"), LevelFilter::Trace);
+        assert_eq!(
+            filters.level_for("islet_rmm::rmi::realm"),
+            LevelFilter::Trace
+        );
+        assert_eq!(filters.level_for("islet_rmm::rmi::rtt"), LevelFilter::Error);
+        assert_eq!(filters.max_level(), LevelFilter::Trace);
+
+        assert!(filters.set("islet_rmm::rmi", None).is_ok());
+        assert_eq!(
+            filters.level_for("islet_rmm::rmi::realm"),
+            LevelFilter::Info
+        );
+        assert_eq!(filters.max_level(), LevelFilter::Info);
+    }
+
+    #[test]
+    fn logger_ring_overwrite() {
+        let mut ring = LogRing::new();
+        ring.push(&[b'a'; LOG_RING_SIZE]);
+        ring.push(b"bc");
+        assert_eq!(ring.lost, 2);
+
+        let mut buf = [0u8; LOG_RING_SIZE];
+        assert_eq!(ring.peek(&mut buf), LOG_RING_SIZE);
+        assert_eq!(&buf[LOG_RING_SIZE - 2..], b"bc");
+
+        ring.consume(LOG_RING_SIZE - 1);
+        assert_eq!(ring.peek(&mut buf), 1);
+        assert_eq!(buf[0], b'c');
+        assert_eq!(ring.lost, 0);
+    }
+}
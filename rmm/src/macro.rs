@@ -1,19 +1,3 @@
-// TODO: Expands to cover args, ret
-#[macro_export]
-macro_rules! define_interface {
-    (command {$($variant:ident = $val:expr),*,}) => {
-        $(pub const $variant: usize = $val;)*
-        pub fn to_str(code: usize) ->  alloc::string::String {
-            use alloc::string::ToString;
-            use alloc::format;
-            match code {
-                $($variant => stringify!($variant).to_string()),*,
-                _ =>  format!("Undefined {}", code)
-            }
-        }
-    };
-}
-
 #[macro_export]
 macro_rules! print {
     ($($arg:tt)*) => {
@@ -0,0 +1,125 @@
+use crate::rmi::{MEC_POLICY_PRIVATE, MEC_POLICY_SHARED};
+
+use lazy_static::lazy_static;
+
+/// MECID shared by the RMM and the Realms which don't own a private one
+pub const MECID_SHARED: usize = 0;
+
+#[derive(Default, Debug)]
+// MEC configuration structure
+pub struct MecConfig {
+    // FEAT_MEC implemented flag
+    pub present: bool,
+
+    // The largest MECID supported by the PE
+    pub max_mecid: usize,
+}
+
+lazy_static! {
+    // Global MEC configuration
+    static ref MEC_CONFIG: MecConfig = {
+        trace!("Reading mec features");
+        read_mec_config()
+    };
+}
+
+#[cfg(not(any(test, miri, fuzzing)))]
+fn read_mec_config() -> MecConfig {
+    use aarch64_cpu::registers::Readable;
+    use armv9a::regs::{ID_AA64MMFR3_EL1, MECIDR_EL2};
+
+    if ID_AA64MMFR3_EL1.read(ID_AA64MMFR3_EL1::MEC) == 0 {
+        return MecConfig::default();
+    }
+
+    let width = MECIDR_EL2.read(MECIDR_EL2::MECIDWidthm1) + 1;
+    let max_mecid = (1 << width) - 1;
+    trace!("MEC is set, max_mecid={:?}", max_mecid);
+
+    MecConfig {
+        present: true,
+        max_mecid,
+    }
+}
+
+#[cfg(any(test, miri, fuzzing))]
+fn read_mec_config() -> MecConfig {
+    MecConfig {
+        present: true,
+        max_mecid: u16::MAX as usize,
+    }
+}
+
+pub fn validate(policy: u8) -> bool {
+    match policy {
+        MEC_POLICY_SHARED => true,
+        MEC_POLICY_PRIVATE => MEC_CONFIG.present,
+        _ => false,
+    }
+}
+
+/// Lets the RMM access the granules mapped with AMEC (see `PageTable::map_realm`)
+/// under the MECID in MECID_A0_EL2, while its own memory stays under MECID_SHARED.
+///
+/// # Safety
+///
+/// It must be called at EL2 before any granule gets mapped with AMEC.
+pub unsafe fn setup_el2() {
+    #[cfg(not(any(test, miri, fuzzing, kani)))]
+    {
+        use aarch64_cpu::registers::ReadWriteable;
+        use armv9a::regs::SCTLR2_EL2;
+
+        if mec_present() {
+            SCTLR2_EL2.modify(SCTLR2_EL2::EMEC::SET);
+        }
+    }
+}
+
+/// Makes the following accesses of the RMM to realm granules use `mecid`.
+///
+/// MECID_A0_EL2 is per PE, so it's set for each realm operation on the PE.
+pub fn switch_to(mecid: usize) {
+    if !mec_present() {
+        return;
+    }
+    #[cfg(not(any(test, miri, fuzzing, kani)))]
+    {
+        use aarch64_cpu::registers::Writeable;
+        use armv9a::regs::MECID_A0_EL2;
+
+        MECID_A0_EL2.set(mecid as u64);
+        crate::asm::isb();
+    }
+    #[cfg(any(test, miri, fuzzing, kani))]
+    let _ = mecid;
+}
+
+/// Cleans and invalidates the granule at `addr` to the Point of Encryption,
+/// so that no cache line of it is left under the MECID it's been wiped with.
+pub fn clean_granule(addr: usize) {
+    if !mec_present() {
+        return;
+    }
+    #[cfg(not(any(test, miri, fuzzing, kani)))]
+    {
+        use crate::config::PAGE_SIZE;
+        const CACHE_LINE_SIZE: usize = 64;
+
+        for line in (addr..addr + PAGE_SIZE).step_by(CACHE_LINE_SIZE) {
+            // DC CIPAE
+            unsafe { core::arch::asm!("sys #4, c7, c14, #0, {}", in(reg) line) };
+        }
+        unsafe { core::arch::asm!("dsb ish") };
+    }
+    #[cfg(any(test, miri, fuzzing, kani))]
+    let _ = addr;
+}
+
+pub fn mec_present() -> bool {
+    MEC_CONFIG.present
+}
+
+pub fn max_mecid() -> usize {
+    MEC_CONFIG.max_mecid
+}
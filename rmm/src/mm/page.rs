@@ -9,7 +9,7 @@ use vmsa::page::PageSize;
 pub enum BasePageSize {}
 impl PageSize for BasePageSize {
     const SIZE: usize = PAGE_SIZE;
-    const MAP_TABLE_LEVEL: usize = 3;
+    const MAP_TABLE_LEVEL: isize = 3;
     const MAP_EXTRA_FLAG: u64 = bits_in_reg(PTDesc::TYPE, attr::page_type::TABLE_OR_PAGE)
         | bits_in_reg(PTDesc::SH, attr::shareable::INNER)
         | bits_in_reg(PTDesc::VALID, 1)
@@ -17,5 +17,7 @@ pub mod page_type {
 pub mod mair_idx {
     pub const RMM_MEM: u64 = 0b0;
     pub const DEVICE_MEM: u64 = 0b1;
+    // Normal Tagged memory, only when FEAT_MTE2 is present
+    pub const TAGGED_MEM: u64 = 0b11;
     pub const RW_DATA: u64 = 0b0;
 }
@@ -10,6 +10,7 @@ use armv9a::{define_bitfield, define_bits, define_mask};
 
 define_bits!(
     PTDesc,
+    AMEC[63 - 63], // alternate MECID (FEAT_MEC)
     Reserved[58 - 55],
     UXN[54 - 54],
     PXN[53 - 53],
@@ -50,7 +51,7 @@ impl page_table::Entry for Entry {
         self.0.get_mut()
     }
 
-    fn address(&self, level: usize) -> Option<PhysAddr> {
+    fn address(&self, level: isize) -> Option<PhysAddr> {
         match self.is_valid() {
             true => match self.0.get_masked_value(PTDesc::TYPE) {
                 attr::page_type::TABLE_OR_PAGE => {
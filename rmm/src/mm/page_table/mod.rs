@@ -13,7 +13,7 @@ use vmsa::page_table::{HasSubtable, Level};
 /// The Level 0 Table
 pub enum L0Table {}
 impl Level for L0Table {
-    const THIS_LEVEL: usize = 0;
+    const THIS_LEVEL: isize = 0;
     const TABLE_SIZE: usize = PAGE_SIZE;
     const TABLE_ALIGN: usize = PAGE_SIZE;
     const NUM_ENTRIES: usize = (Self::TABLE_SIZE / core::mem::size_of::<Entry>());
@@ -25,7 +25,7 @@ impl HasSubtable for L0Table {
 /// The Level 1 Table
 pub enum L1Table {}
 impl Level for L1Table {
-    const THIS_LEVEL: usize = 1;
+    const THIS_LEVEL: isize = 1;
     const TABLE_SIZE: usize = PAGE_SIZE;
     const TABLE_ALIGN: usize = PAGE_SIZE;
     const NUM_ENTRIES: usize = (Self::TABLE_SIZE / core::mem::size_of::<Entry>());
@@ -38,7 +38,7 @@ impl HasSubtable for L1Table {
 /// The Level 2 Table
 pub enum L2Table {}
 impl Level for L2Table {
-    const THIS_LEVEL: usize = 2;
+    const THIS_LEVEL: isize = 2;
     const TABLE_SIZE: usize = PAGE_SIZE;
     const TABLE_ALIGN: usize = PAGE_SIZE;
     const NUM_ENTRIES: usize = (Self::TABLE_SIZE / core::mem::size_of::<Entry>());
@@ -51,7 +51,7 @@ impl HasSubtable for L2Table {
 /// The Level 3 Table (Doesn't have Subtable!)
 pub enum L3Table {}
 impl Level for L3Table {
-    const THIS_LEVEL: usize = 3;
+    const THIS_LEVEL: isize = 3;
     const TABLE_SIZE: usize = PAGE_SIZE;
     const TABLE_ALIGN: usize = PAGE_SIZE;
     const NUM_ENTRIES: usize = (Self::TABLE_SIZE / core::mem::size_of::<Entry>());
@@ -1,6 +1,7 @@
 use super::page_table::entry::Entry;
 use super::page_table::{attr, L1Table};
 use crate::config::{PlatformMemoryLayout, PAGE_SIZE, RMM_STACK_GUARD_SIZE, RMM_STACK_SIZE};
+use crate::mec::MECID_SHARED;
 use crate::mm::page::BasePageSize;
 use crate::mm::page_table::entry::PTDesc;
 
@@ -27,7 +28,24 @@ impl PageTable {
     }
 
     pub fn map(&self, addr: usize, secure: bool) -> bool {
-        self.page_table.lock().set_pages_for_rmi(addr, secure)
+        self.page_table
+            .lock()
+            .set_pages_for_rmi(addr, secure, false)
+    }
+
+    /// Maps a granule of the realm owning `mecid`, which is accessed under
+    /// the MECID set by `mec::switch_to()` unless it's `MECID_SHARED`.
+    pub fn map_realm(&self, addr: usize, mecid: usize) -> bool {
+        let mapped = self
+            .page_table
+            .lock()
+            .set_pages_for_rmi(addr, true, mecid != MECID_SHARED);
+        // The granule may have been mapped without AMEC, which must not be used anymore
+        #[cfg(not(any(miri, test, fuzzing, kani)))]
+        unsafe {
+            core::arch::asm!("tlbi vae2is, {}", "dsb ish", "isb", in(reg) addr >> 12);
+        }
+        mapped
     }
 
     pub fn unmap(&self, addr: usize) -> bool {
@@ -148,18 +166,25 @@ impl Inner<'_> {
         self.root_pgtbl.unset_page(page);
     }
 
-    fn set_pages_for_rmi(&mut self, addr: usize, secure: bool) -> bool {
+    fn set_pages_for_rmi(&mut self, addr: usize, secure: bool, amec: bool) -> bool {
         if addr == 0 {
             warn!("map address is empty");
             return false;
         }
 
         let rw_flags = bits_in_reg(PTDesc::AP, attr::permission::RW);
-        let memattr_flags = bits_in_reg(PTDesc::INDX, attr::mair_idx::RMM_MEM);
+        // Granules of the realm world carry allocation tags to be wiped
+        let memattr = if secure && crate::mte::mte_present() {
+            attr::mair_idx::TAGGED_MEM
+        } else {
+            attr::mair_idx::RMM_MEM
+        };
+        let memattr_flags = bits_in_reg(PTDesc::INDX, memattr);
         let sh_flags = bits_in_reg(PTDesc::SH, attr::shareable::INNER);
         let secure_flags = bits_in_reg(PTDesc::NS, !secure as u64);
         let xn_flags = bits_in_reg(PTDesc::UXN, 1) | bits_in_reg(PTDesc::PXN, 1);
         let valid_flags = bits_in_reg(PTDesc::VALID, 1);
+        let amec_flags = bits_in_reg(PTDesc::AMEC, amec as u64);
 
         let va = VirtAddr::from(addr);
         let phys = PhysAddr::from(addr);
@@ -168,7 +193,13 @@ impl Inner<'_> {
             va,
             phys,
             PAGE_SIZE,
-            rw_flags | memattr_flags | secure_flags | sh_flags | xn_flags | valid_flags,
+            rw_flags
+                | memattr_flags
+                | secure_flags
+                | sh_flags
+                | xn_flags
+                | valid_flags
+                | amec_flags,
         );
 
         true
@@ -75,53 +75,38 @@ impl Monitor {
 
         self.handle_rmi(&mut ctx);
         ctx = self.mainloop.dispatch(ctx, symbolic);
-        let ret_len = ctx.ret.len();
-        result[..ret_len].copy_from_slice(&ctx.ret[..]);
+        let ret = ctx.ret_slice();
+        result[..ret.len()].copy_from_slice(ret);
         result
     }
 
     pub fn handle_rmi(&mut self, ctx: &mut Context) {
-        if let Some(handler) = self.rmi.on_event.get(&ctx.cmd) {
-            #[cfg(feature = "stat")]
-            {
-                if ctx.cmd != rmi::REC_ENTER {
-                    trace!("let's get STATS.lock() with cmd {}", rmi::to_str(ctx.cmd));
-                    crate::stat::STATS.lock().measure(ctx.cmd, || {
-                        if let Err(code) = handler(&ctx.arg[..], &mut ctx.ret[..], self) {
-                            ctx.ret[0] = code.into();
-                        }
-                    });
-                } else if let Err(code) = handler(&ctx.arg[..], &mut ctx.ret[..], self) {
-                    ctx.ret[0] = code.into();
-                }
-            }
-            #[cfg(not(feature = "stat"))]
-            {
-                if let Err(code) = handler(&ctx.arg[..], &mut ctx.ret[..], self) {
-                    ctx.ret[0] = code.into();
-                }
-            }
+        if let Some(handler) = self.rmi.on_event.get(ctx.cmd) {
+            #[cfg(feature = "replay")]
+            let buffer = crate::event::replay::ns_buffer(ctx, self);
+            #[cfg(not(kani))]
+            let ids = crate::event::trace::ids(ctx);
+
+            ctx.do_rmi(|arg, ret| handler(arg, ret, self));
+
+            #[cfg(not(kani))]
+            crate::event::trace::record_rmi(ctx, ids);
+            #[cfg(feature = "replay")]
+            crate::event::replay::record_rmi(ctx, buffer);
 
             trace!(
                 "RMI: {0: <20} {1:X?} > {2:X?}",
                 rmi::to_str(ctx.cmd),
-                &ctx.arg,
-                &ctx.ret
+                ctx.arg_slice(),
+                ctx.ret_slice()
             );
 
-            // SMC calling convention requires x4-x7 to be preserved unless used.
-            // Since the rmmd in EL3 takes care of preserving x5-x7,
-            // we only restore x4.
-            ctx.arg.resize(5, 0);
-            ctx.arg[..ctx.ret.len()].copy_from_slice(&ctx.ret[..]);
-            if ctx.ret.len() < 5 {
-                ctx.arg[4] = ctx.x4 as usize;
-            }
+            ctx.ret_to_arg();
 
             #[cfg(kani)]
             // the below is a proof helper
             {
-                let ret_len = ctx.ret.len();
+                let ret_len = ctx.ret_slice().len();
                 #[cfg(any(
                     feature = "mc_rmi_granule_delegate",
                     feature = "mc_rmi_granule_undelegate",
@@ -145,9 +130,26 @@ impl Monitor {
 
     pub fn handle_rsi(&self, ctx: &mut Context, rec: &mut Rec<'_>, run: &mut Run) -> usize {
         #[cfg(not(kani))]
-        match self.rsi.on_event.get(&ctx.cmd) {
+        match self.rsi.on_event.get(ctx.cmd) {
             Some(handler) => {
+                #[cfg(feature = "stat")]
+                let (vmid, start) = (rec.realmid(), crate::stat::cycles());
+
+                #[cfg(feature = "replay")]
+                let args = crate::event::replay::rsi_args(ctx.cmd, rec);
+
                 ctx.do_rsi(|arg, ret| handler(arg, ret, self, rec, run));
+                crate::event::trace::record_rsi(ctx.cmd, rec);
+                #[cfg(feature = "replay")]
+                crate::event::replay::record_rsi(ctx.cmd, args, rec);
+
+                #[cfg(feature = "stat")]
+                if let Ok(vmid) = vmid {
+                    let cycles = crate::stat::cycles().saturating_sub(start);
+                    crate::stat::REALM_STATS
+                        .lock()
+                        .rsi_called(vmid, ctx.cmd, cycles);
+                }
             }
             None => {
                 ctx.init_ret(&[RsiHandle::NOT_SUPPORTED]);
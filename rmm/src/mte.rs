@@ -0,0 +1,99 @@
+use crate::mm::page_table::attr::mair_idx;
+
+use lazy_static::lazy_static;
+
+#[derive(Default, Debug)]
+// MTE configuration structure
+pub struct MteConfig {
+    // FEAT_MTE2 implemented flag (allocation tags in memory)
+    pub present: bool,
+}
+
+lazy_static! {
+    // Global MTE configuration
+    static ref MTE_CONFIG: MteConfig = {
+        trace!("Reading mte features");
+        read_mte_config()
+    };
+}
+
+#[cfg(not(any(test, miri, fuzzing)))]
+fn read_mte_config() -> MteConfig {
+    use aarch64_cpu::registers::Readable;
+    use armv9a::regs::ID_AA64PFR1_SME_EL1;
+
+    // ID_AA64PFR1_EL1.MTE is 0b0010 or higher for FEAT_MTE2
+    let mte = ID_AA64PFR1_SME_EL1.read(ID_AA64PFR1_SME_EL1::MTE);
+    trace!("MTE={:?}", mte);
+
+    MteConfig {
+        present: mte >= 0b0010,
+    }
+}
+
+#[cfg(any(test, miri, fuzzing))]
+fn read_mte_config() -> MteConfig {
+    MteConfig { present: true }
+}
+
+pub fn validate(mte_en: bool) -> bool {
+    !mte_en || MTE_CONFIG.present
+}
+
+pub fn mte_present() -> bool {
+    MTE_CONFIG.present
+}
+
+/// Lets the RMM access the allocation tags of the granules mapped with
+/// `mair_idx::TAGGED_MEM`, so that they can be wiped along with the data.
+///
+/// # Safety
+///
+/// It must be called at EL2 after MAIR_EL2 and SCTLR_EL2 are initialized,
+/// before any granule gets mapped with `mair_idx::TAGGED_MEM`.
+pub unsafe fn setup_el2() {
+    use aarch64_cpu::registers::*;
+
+    // Inner/Outer Write-Back Non-transient, Read/Write-Allocate, Tagged
+    const MAIR_ATTR_TAGGED: u64 = 0xf0;
+    // SCTLR_EL2.ATA: allocation tag access at EL2
+    const SCTLR_EL2_ATA: u64 = 1 << 43;
+
+    if !mte_present() {
+        return;
+    }
+    MAIR_EL2.set(MAIR_EL2.get() | (MAIR_ATTR_TAGGED << (mair_idx::TAGGED_MEM * 8)));
+    SCTLR_EL2.set(SCTLR_EL2.get() | SCTLR_EL2_ATA);
+}
+
+/// Zeroes the allocation tags of the given memory region.
+/// The region should be mapped as Normal Tagged memory.
+#[cfg(not(any(test, miri, fuzzing, kani)))]
+pub fn zero_tags(addr: usize, size: usize) {
+    // Each tag covers 16 bytes and ST2G stores two of them
+    const TAG_STORE_SIZE: usize = 32;
+
+    if !mte_present() || size == 0 {
+        return;
+    }
+    debug_assert!(addr % TAG_STORE_SIZE == 0 && size % TAG_STORE_SIZE == 0);
+
+    // Safety: tags are stored from the identity mapped address,
+    //         whose logical tag (bits[59:56]) is zero.
+    unsafe {
+        core::arch::asm!(
+            ".arch_extension memtag",
+            "2:",
+            "st2g {addr}, [{addr}], #{step}",
+            "subs {size}, {size}, #{step}",
+            "b.ne 2b",
+            "dsb ish",
+            addr = inout(reg) addr => _,
+            size = inout(reg) size => _,
+            step = const TAG_STORE_SIZE,
+        );
+    }
+}
+
+#[cfg(any(test, miri, fuzzing, kani))]
+pub fn zero_tags(_addr: usize, _size: usize) {}
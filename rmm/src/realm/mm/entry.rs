@@ -4,11 +4,20 @@ use crate::realm::mm::stage2_tte::S2TTE;
 use crate::realm::mm::table_level::L3Table;
 use vmsa::address::PhysAddr;
 use vmsa::error::Error;
+use vmsa::guard::{EntryGuard, InPlaceLock};
 use vmsa::page_table::{self, Level};
 use vmsa::RawGPA;
 
-#[derive(Clone, Copy)]
-pub struct Entry(S2TTE);
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// An entry of the RTT, which CPUs access concurrently through `&self`.
+/// A table descriptor holds the lock of the table it points to (S2TTE::LOCK),
+/// and the entries of a table are modified only under its lock.
+pub struct Entry(UnsafeCell<S2TTE>);
+
+// Safety: shared entries are only accessed atomically (see atomic())
+unsafe impl Sync for Entry {}
 
 impl From<usize> for S2TTE {
     fn from(val: usize) -> Self {
@@ -16,38 +25,80 @@ impl From<usize> for S2TTE {
     }
 }
 
+impl Entry {
+    fn atomic(&self) -> &AtomicU64 {
+        // Safety: S2TTE is a repr(C) u64, and entries are aligned to 8 bytes
+        unsafe { AtomicU64::from_ptr(self.0.get() as *mut u64) }
+    }
+
+    fn load(&self) -> S2TTE {
+        S2TTE::new(self.atomic().load(Ordering::Acquire) & !S2TTE::LOCK)
+    }
+
+    fn sync(&self) {
+        #[cfg(not(any(miri, test, fuzzing)))]
+        unsafe {
+            core::arch::asm!(
+                "dsb ishst",
+                "dc civac, {}",
+                "dsb ish",
+                "isb",
+                in(reg) self.0.get() as usize,
+            );
+        }
+    }
+
+    /// Sets the entry to `val`, holding the lock of the table it belongs to.
+    pub fn set_pte(&self, val: u64) {
+        self.atomic().store(val, Ordering::Release);
+        self.sync();
+    }
+}
+
+impl InPlaceLock<S2TTE> for Entry {
+    fn unlock(&self, value: &S2TTE) {
+        let val = value.get() & !S2TTE::LOCK;
+        let old = self.atomic().swap(val, Ordering::Release);
+        // The lock bit is ignored by the walk, so only the other changes are published
+        if old & !S2TTE::LOCK != val {
+            self.sync();
+        }
+    }
+}
+
 impl page_table::Entry for Entry {
     type Inner = S2TTE;
 
     fn new() -> Self {
-        Self(S2TTE::new(0))
+        Self(UnsafeCell::new(S2TTE::new(0)))
     }
 
     fn is_valid(&self) -> bool {
-        self.0.get_masked_value(S2TTE::VALID) != 0
+        self.load().get_masked_value(S2TTE::VALID) != 0
     }
 
     fn clear(&mut self) {
-        self.0 = S2TTE::new(0);
+        *self.0.get_mut() = S2TTE::new(0);
     }
 
     fn pte(&self) -> u64 {
-        self.0.get()
+        self.load().get()
     }
 
     fn mut_pte(&mut self) -> &mut Self::Inner {
         self.0.get_mut()
     }
 
-    fn address(&self, level: usize) -> Option<PhysAddr> {
+    fn address(&self, level: isize) -> Option<PhysAddr> {
+        let s2tte = self.load();
         match self.is_valid() {
-            true => match self.0.get_masked_value(S2TTE::TYPE) {
+            true => match s2tte.get_masked_value(S2TTE::TYPE) {
                 page_type::TABLE_OR_PAGE => {
-                    Some(PhysAddr::from(self.0.get_masked(S2TTE::ADDR_TBL_OR_PAGE)))
+                    Some(PhysAddr::from(s2tte.get_masked(S2TTE::ADDR_TBL_OR_PAGE)))
                 }
                 page_type::BLOCK => match level {
-                    1 => Some(PhysAddr::from(self.0.get_masked(S2TTE::ADDR_BLK_L1))),
-                    2 => Some(PhysAddr::from(self.0.get_masked(S2TTE::ADDR_BLK_L2))),
+                    1 => Some(PhysAddr::from(s2tte.get_masked(S2TTE::ADDR_BLK_L1))),
+                    2 => Some(PhysAddr::from(s2tte.get_masked(S2TTE::ADDR_BLK_L2))),
                     _ => None,
                 },
                 _ => None,
@@ -57,18 +108,8 @@ impl page_table::Entry for Entry {
     }
 
     fn set(&mut self, addr: PhysAddr, flags: u64) -> Result<(), Error> {
-        self.0.set(addr.as_u64() | flags);
-
-        #[cfg(not(any(miri, test, fuzzing)))]
-        unsafe {
-            core::arch::asm!(
-                "dsb ishst",
-                "dc civac, {}",
-                "dsb ish",
-                "isb",
-                in(reg) &self.0 as *const _ as usize,
-            );
-        }
+        self.0.get_mut().set(addr.as_u64() | flags);
+        self.sync();
         Ok(())
     }
 
@@ -81,9 +122,48 @@ impl page_table::Entry for Entry {
         self.set(addr, flags.get())
     }
 
+    // Locks the table which this table descriptor points to, spinning on S2TTE::LOCK.
+    // The caller holds the lock of the table this entry belongs to, so the descriptor
+    // doesn't change underneath.
+    fn lock(&self) -> Result<Option<EntryGuard<'_, Self::Inner>>, Error> {
+        if !self.points_to_table_or_page() {
+            return Err(Error::MmStateError);
+        }
+        let atomic = self.atomic();
+        let mut val = atomic.load(Ordering::Relaxed);
+        loop {
+            if val & S2TTE::LOCK != 0 {
+                core::hint::spin_loop();
+                val = atomic.load(Ordering::Relaxed);
+                continue;
+            }
+            match atomic.compare_exchange_weak(
+                val,
+                val | S2TTE::LOCK,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(cur) => val = cur,
+            }
+        }
+        let s2tte = S2TTE::new(val);
+        let addr = s2tte.get_masked(S2TTE::ADDR_TBL_OR_PAGE) as usize;
+        Ok(Some(EntryGuard::in_place(s2tte, self, addr, 0)))
+    }
+
     fn index<L: Level>(addr: usize) -> usize {
         match L::THIS_LEVEL {
-            0 => RawGPA::from(addr).get_masked_value(RawGPA::L0Index) as usize,
+            -1 => RawGPA::from(addr).get_masked_value(RawGPA::LM1Index) as usize,
+            0 => {
+                if L::TABLE_SIZE > PAGE_SIZE {
+                    let lm1 = RawGPA::from(addr).get_masked_value(RawGPA::LM1Index) as usize;
+                    let l0 = RawGPA::from(addr).get_masked_value(RawGPA::L0Index) as usize;
+                    lm1 * L3Table::NUM_ENTRIES + l0
+                } else {
+                    RawGPA::from(addr).get_masked_value(RawGPA::L0Index) as usize
+                }
+            }
             1 => {
                 if L::TABLE_SIZE > PAGE_SIZE {
                     // We know that refering one direct parent table is enough
@@ -112,7 +192,7 @@ impl page_table::Entry for Entry {
 
     fn points_to_table_or_page(&self) -> bool {
         match self.is_valid() {
-            true => match self.0.get_masked_value(S2TTE::TYPE) {
+            true => match self.load().get_masked_value(S2TTE::TYPE) {
                 page_type::TABLE_OR_PAGE => true,
                 page_type::BLOCK => false,
                 _ => false,
@@ -10,28 +10,18 @@ pub mod table_level;
 use crate::rmi::error::Error;
 use core::ffi::c_void;
 use core::fmt::Debug;
-use core::slice::Iter;
 
 use address::{GuestPhysAddr, PhysAddr};
-use stage2_translation::Tlbi;
+use stage2_translation::LockedTable;
 
 pub trait IPATranslation: Debug + Send + Sync {
     fn get_base_address(&self) -> *const c_void;
-    // TODO: remove mut
-    fn ipa_to_pa(&mut self, guest: GuestPhysAddr, level: usize) -> Option<PhysAddr>;
-    fn ipa_to_pte(&mut self, guest: GuestPhysAddr, level: usize) -> Option<(u64, usize)>;
-    fn ipa_to_pte_set(
-        &mut self,
-        guest: GuestPhysAddr,
-        level: usize,
-        val: u64,
-        invalidate: Tlbi,
-    ) -> Result<(), Error>;
-    fn clean(&mut self, vmid: usize);
-    fn space_size(&self, level: usize) -> usize;
-    fn entries(
-        &self,
-        guest: GuestPhysAddr,
-        level: usize,
-    ) -> Result<(Iter<'_, entry::Entry>, usize), Error>;
+    fn ipa_to_pa(&self, guest: GuestPhysAddr, level: isize) -> Option<PhysAddr>;
+    fn ipa_to_pte(&self, guest: GuestPhysAddr, level: isize) -> Option<(u64, isize)>;
+    /// Walks to the table at `level` holding `guest`, or the last table on the way,
+    /// and returns it locked. Tables on the way are locked hand-over-hand,
+    /// so walks of different CPUs only wait for each other on a shared table.
+    fn walk(&self, guest: GuestPhysAddr, level: isize) -> Result<LockedTable<'_>, Error>;
+    fn clean(&self, vmid: usize);
+    fn space_size(&self, level: isize) -> usize;
 }
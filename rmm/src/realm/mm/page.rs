@@ -12,7 +12,7 @@ use armv9a::bits_in_reg;
 pub enum BasePageSize {}
 impl PageSize for BasePageSize {
     const SIZE: usize = PAGE_SIZE;
-    const MAP_TABLE_LEVEL: usize = L3Table::THIS_LEVEL;
+    const MAP_TABLE_LEVEL: isize = L3Table::THIS_LEVEL;
     const MAP_EXTRA_FLAG: u64 = bits_in_reg(S2TTE::TYPE, page_type::TABLE_OR_PAGE);
 }
 
@@ -21,7 +21,7 @@ impl PageSize for BasePageSize {
 pub enum LargePageSize {}
 impl PageSize for LargePageSize {
     const SIZE: usize = LARGE_PAGE_SIZE;
-    const MAP_TABLE_LEVEL: usize = L2Table::THIS_LEVEL;
+    const MAP_TABLE_LEVEL: isize = L2Table::THIS_LEVEL;
     const MAP_EXTRA_FLAG: u64 = bits_in_reg(S2TTE::TYPE, page_type::BLOCK);
 }
 
@@ -30,6 +30,6 @@ impl PageSize for LargePageSize {
 pub enum HugePageSize {}
 impl PageSize for HugePageSize {
     const SIZE: usize = HUGE_PAGE_SIZE;
-    const MAP_TABLE_LEVEL: usize = L1Table::THIS_LEVEL;
+    const MAP_TABLE_LEVEL: isize = L1Table::THIS_LEVEL;
     const MAP_EXTRA_FLAG: u64 = bits_in_reg(S2TTE::TYPE, page_type::BLOCK);
 }
@@ -1,16 +1,16 @@
 use crate::granule::GRANULE_SHIFT;
 use crate::granule::{set_granule, GranuleState};
-use crate::measurement::HashContext;
 use crate::realm::mm::address::GuestPhysAddr;
 use crate::realm::mm::attribute::desc_type;
 use crate::realm::mm::entry;
-use crate::realm::mm::stage2_translation::{RttAllocator, Tlbi};
-use crate::realm::mm::stage2_tte::{hipas, mapping_size, ripas, S2TTE};
+use crate::realm::mm::stage2_translation::{LockedTable, RttAllocator, Tlbi};
+use crate::realm::mm::stage2_tte::{attr_bits, hipas, mapping_size, oa_bits, ripas, S2TTE};
 use crate::realm::mm::stage2_tte::{
-    level_mask, INVALID_UNPROTECTED, TABLE_TTE, TTE_ATTR_MASK, VALID_NS_TTE, VALID_TTE,
+    level_mask, INVALID_UNPROTECTED, TABLE_TTE, TTE_ATTR_MASK, VALID_DEV_TTE, VALID_NS_TTE,
+    VALID_TTE,
 };
 use crate::realm::mm::table_level;
-use crate::realm::rd::Rd;
+use crate::realm::rd::{Rd, Rtts};
 use crate::rmi::error::Error;
 use crate::rmi::rtt_entry_state;
 use crate::{get_granule, get_granule_if};
@@ -18,14 +18,16 @@ use armv9a::bits_in_reg;
 use vmsa::address::PhysAddr;
 use vmsa::page_table::{Entry, Level, PageTable};
 
-pub const RTT_MIN_BLOCK_LEVEL: usize = table_level::L1Table::THIS_LEVEL;
-pub const RTT_PAGE_LEVEL: usize = table_level::L3Table::THIS_LEVEL;
+pub const RTT_MIN_LEVEL: isize = table_level::L0Table::THIS_LEVEL;
+pub const RTT_MIN_LEVEL_LPA2: isize = RTT_MIN_LEVEL - 1;
+pub const RTT_MIN_BLOCK_LEVEL: isize = table_level::L1Table::THIS_LEVEL;
+pub const RTT_PAGE_LEVEL: isize = table_level::L3Table::THIS_LEVEL;
 pub const RTT_STRIDE: usize = GRANULE_SHIFT - 3;
 
 const CHANGE_DESTROYED: u64 = 0x1;
 
-fn level_space_size(rd: &Rd, level: usize) -> usize {
-    rd.s2_table().lock().space_size(level)
+fn level_space_size(rd: &Rtts<'_>, level: isize) -> usize {
+    rd.s2_table().space_size(level)
 }
 
 fn create_pgtbl_at(
@@ -33,9 +35,11 @@ fn create_pgtbl_at(
     flags: u64,
     mut pa: usize,
     map_size: usize,
+    lpa2: bool,
 ) -> Result<(), Error> {
     let alloc = RttAllocator { base: rtt_addr };
-    let mut new_s2tte = pa as u64 | flags;
+    let flags = attr_bits(flags, lpa2);
+    let mut new_s2tte = oa_bits(pa, lpa2) | flags;
 
     let ret = PageTable::<
         GuestPhysAddr,
@@ -46,7 +50,7 @@ fn create_pgtbl_at(
         for e in entries.iter_mut() {
             let _ = (*e).set(PhysAddr::from(pa), new_s2tte);
             pa += map_size;
-            new_s2tte = pa as u64 | flags;
+            new_s2tte = oa_bits(pa, lpa2) | flags;
         }
     });
 
@@ -56,10 +60,15 @@ fn create_pgtbl_at(
     Ok(())
 }
 
-pub fn create(rd: &Rd, rtt_addr: usize, ipa: usize, level: usize) -> Result<(), Error> {
+pub fn create(rd: &Rtts<'_>, rtt_addr: usize, ipa: usize, level: isize) -> Result<(), Error> {
     let mut invalidate = Tlbi::NONE;
+    let lpa2 = rd.lpa2();
 
-    let (parent_s2tte, last_level) = S2TTE::get_s2tte(rd, ipa, level - 1, Error::RmiErrorInput)?;
+    let parent = rd
+        .s2_table()
+        .walk(GuestPhysAddr::from(ipa), level - 1)
+        .or(Err(Error::RmiErrorInput))?;
+    let (parent_s2tte, last_level) = (S2TTE::in_table(rd, &parent, ipa), parent.level());
 
     if last_level != level - 1 || parent_s2tte.is_table(last_level) {
         return Err(Error::RmiErrorRtt(last_level));
@@ -74,7 +83,7 @@ pub fn create(rd: &Rd, rtt_addr: usize, ipa: usize, level: usize) -> Result<(),
     }
 
     if parent_s2tte.is_unassigned() || parent_s2tte.is_unassigned_ns() {
-        create_pgtbl_at(rtt_addr, flags, 0, 0)?;
+        create_pgtbl_at(rtt_addr, flags, 0, 0, lpa2)?;
     } else {
         // for assigned_ram or assinged_ns, set desc_type
         if !parent_s2tte.is_assigned_invalid() {
@@ -85,44 +94,46 @@ pub fn create(rd: &Rd, rtt_addr: usize, ipa: usize, level: usize) -> Result<(),
             }
             invalidate = Tlbi::LEAF(rd.id());
         }
-        let pa: usize = parent_s2tte.addr_as_block(level - 1).into(); //XXX: check this again
+        let pa: usize = parent_s2tte.addr_as_block(level - 1, lpa2).into(); //XXX: check this again
         flags |= parent_s2tte.get_masked(TTE_ATTR_MASK);
-        create_pgtbl_at(rtt_addr, flags, pa, map_size)?;
+        create_pgtbl_at(rtt_addr, flags, pa, map_size, lpa2)?;
     }
 
-    let parent_s2tte = rtt_addr as u64 | TABLE_TTE;
-    rd.s2_table().lock().ipa_to_pte_set(
-        GuestPhysAddr::from(ipa),
-        level - 1,
-        parent_s2tte,
-        invalidate,
-    )?;
+    let parent_s2tte = oa_bits(rtt_addr, lpa2) | attr_bits(TABLE_TTE, lpa2);
+    parent.set_s2tte(ipa, parent_s2tte, invalidate);
 
     Ok(())
 }
 
 pub fn destroy<F: FnMut(usize)>(
-    rd: &Rd,
+    rd: &Rtts<'_>,
     ipa: usize,
-    level: usize,
+    level: isize,
     mut f: F,
 ) -> Result<(usize, usize), Error> {
     let invalidate;
-    let (parent_s2tte, last_level) = S2TTE::get_s2tte(rd, ipa, level - 1, Error::RmiErrorRtt(0))?;
+    let parent = rd
+        .s2_table()
+        .walk(GuestPhysAddr::from(ipa), level - 1)
+        .or(Err(Error::RmiErrorRtt(0)))?;
+    let (parent_s2tte, last_level) = (S2TTE::in_table(rd, &parent, ipa), parent.level());
 
     if (last_level != level - 1) || !parent_s2tte.is_table(last_level) {
-        let top_ipa = skip_non_live_entries(rd, ipa, last_level)?;
+        let top_ipa = skip_non_live_entries(rd, &parent, ipa);
         f(top_ipa);
         return Err(Error::RmiErrorRtt(last_level));
     }
 
-    let rtt_addr = parent_s2tte.addr_as_block(RTT_PAGE_LEVEL).into();
+    let rtt_addr = parent_s2tte.addr_as_block(RTT_PAGE_LEVEL, rd.lpa2()).into();
 
     let mut g_rtt = get_granule_if!(rtt_addr, GranuleState::RTT)?;
 
+    // Waits for the walks which have gone down to the RTT
+    let rtt = parent.subtable(ipa)?;
+
     // TODO: granule needs to contain its refcount info.
     //       Unless its ref count is 0, RTT DESTROY should fail
-    if is_live_rtt(rd, ipa, level).unwrap_or(false) {
+    if is_live_rtt(&rtt) {
         f(ipa);
         return Err(Error::RmiErrorRtt(level));
     }
@@ -139,23 +150,23 @@ pub fn destroy<F: FnMut(usize)>(
             | INVALID_UNPROTECTED
     };
 
-    rd.s2_table().lock().ipa_to_pte_set(
-        GuestPhysAddr::from(ipa),
-        level - 1,
-        parent_s2tte,
-        invalidate,
-    )?;
+    rtt.unlink(ipa, parent_s2tte, invalidate);
 
     set_granule(&mut g_rtt, GranuleState::Delegated)?;
 
-    let top_ipa = skip_non_live_entries(rd, ipa, last_level)?;
+    let top_ipa = skip_non_live_entries(rd, &parent, ipa);
     Ok((rtt_addr, top_ipa))
 }
 
 pub fn init_ripas(rd: &mut Rd, base: usize, top: usize) -> Result<usize, Error> {
+    let rtts = rd.rtts();
     // TODO: get s2tte without the level input
     let level = RTT_PAGE_LEVEL;
-    let (s2tte, last_level) = S2TTE::get_s2tte(rd, base, level, Error::RmiErrorRtt(0))?;
+    let table = rtts
+        .s2_table()
+        .walk(GuestPhysAddr::from(base), level)
+        .or(Err(Error::RmiErrorRtt(0)))?;
+    let (s2tte, last_level) = (S2TTE::in_table(&rtts, &table, base), table.level());
 
     let map_size = mapping_size(last_level);
 
@@ -175,33 +186,33 @@ pub fn init_ripas(rd: &mut Rd, base: usize, top: usize) -> Result<usize, Error>
         return Err(Error::RmiErrorRtt(last_level));
     }
 
-    let space_size = level_space_size(rd, last_level);
+    let space_size = level_space_size(&rtts, last_level);
     let top_addr = (addr & !(space_size - 1)) + space_size;
     while addr < top_addr {
         let next = addr + map_size;
         if next > top {
             break;
         }
-        let (s2tte, last_level) = S2TTE::get_s2tte(rd, addr, level, Error::RmiErrorRtt(0))?;
-        if s2tte.is_table(last_level) || s2tte.get_masked_value(S2TTE::HIPAS) == hipas::ASSIGNED {
+        let s2tte = S2TTE::in_table(&rtts, &table, addr);
+        if s2tte.is_table(last_level) || s2tte.get_masked_value(S2TTE::HIPAS) != hipas::UNASSIGNED {
             break;
         }
         let new_s2tte =
             bits_in_reg(S2TTE::HIPAS, hipas::UNASSIGNED) | bits_in_reg(S2TTE::RIPAS, ripas::RAM);
 
-        rd.s2_table().lock().ipa_to_pte_set(
-            GuestPhysAddr::from(addr),
-            last_level,
-            new_s2tte,
-            Tlbi::NONE,
-        )?;
-
-        #[cfg(not(kani))]
-        // `rsi` is currently not reachable in model checking harnesses
-        HashContext::new(rd)?.measure_ripas_granule(addr as u64, next as u64)?;
+        table.set_s2tte(addr, new_s2tte, Tlbi::NONE);
 
         addr += map_size;
     }
+    // The RIM is extended in the same order, after the table is released
+    drop(table);
+
+    #[cfg(not(kani))]
+    // `rsi` is currently not reachable in model checking harnesses
+    for granule in (base..addr).step_by(map_size) {
+        crate::measurement::rim_context(rd)?
+            .measure_ripas_granule(granule as u64, (granule + map_size) as u64)?;
+    }
 
     if addr > base {
         Ok(addr)
@@ -211,7 +222,7 @@ pub fn init_ripas(rd: &mut Rd, base: usize, top: usize) -> Result<usize, Error>
 }
 
 // return (out_top, ripas)
-pub fn get_ripas(rd: &Rd, start: usize, end: usize) -> Result<(usize, u64), Error> {
+pub fn get_ripas(rd: &Rtts<'_>, start: usize, end: usize) -> Result<(usize, u64), Error> {
     let level = RTT_PAGE_LEVEL;
     let mut addr = start;
     let mut common_ripas = 0; // initialized in the below if condition (addr == start)
@@ -236,10 +247,11 @@ pub fn get_ripas(rd: &Rd, start: usize, end: usize) -> Result<(usize, u64), Erro
     Ok((addr, common_ripas))
 }
 
-pub fn read_entry(rd: &Rd, ipa: usize, level: usize) -> Result<[usize; 4], Error> {
+pub fn read_entry(rd: &Rtts<'_>, ipa: usize, level: isize) -> Result<[usize; 4], Error> {
     let (s2tte, last_level) = S2TTE::get_s2tte(rd, ipa, level, Error::RmiErrorRtt(0))?;
+    let lpa2 = rd.lpa2();
 
-    let r1 = last_level;
+    let r1 = last_level as usize;
     let (mut r2, mut r3, mut r4) = (0, 0, 0);
 
     if s2tte.is_unassigned() {
@@ -247,7 +259,11 @@ pub fn read_entry(rd: &Rd, ipa: usize, level: usize) -> Result<[usize; 4], Error
         r4 = s2tte.get_masked_value(S2TTE::RIPAS) as usize;
     } else if s2tte.is_assigned() {
         r2 = rtt_entry_state::RMI_ASSIGNED;
-        r3 = s2tte.addr_as_block(last_level).into(); //XXX: check this again
+        r3 = s2tte.addr_as_block(last_level, lpa2).into(); //XXX: check this again
+        r4 = s2tte.get_masked_value(S2TTE::RIPAS) as usize;
+    } else if s2tte.is_assigned_dev() {
+        r2 = rtt_entry_state::RMI_ASSIGNED_DEV;
+        r3 = s2tte.addr_as_block(last_level, lpa2).into();
         r4 = s2tte.get_masked_value(S2TTE::RIPAS) as usize;
     } else if s2tte.is_table(last_level) {
         r2 = rtt_entry_state::RMI_TABLE;
@@ -257,7 +273,10 @@ pub fn read_entry(rd: &Rd, ipa: usize, level: usize) -> Result<[usize; 4], Error
     } else if s2tte.is_assigned_ns(last_level) {
         r2 = rtt_entry_state::RMI_ASSIGNED;
         let addr_mask: u64 = level_mask(last_level).ok_or(Error::RmiErrorRtt(0))?;
-        let mask = addr_mask | S2TTE::MEMATTR | S2TTE::S2AP;
+        let mut mask = addr_mask | S2TTE::MEMATTR | S2TTE::S2AP;
+        if lpa2 {
+            mask |= S2TTE::OA_HIGH;
+        }
         r3 = s2tte.get_masked(mask);
     } else {
         error!("Unexpected S2TTE value retrieved!");
@@ -265,13 +284,22 @@ pub fn read_entry(rd: &Rd, ipa: usize, level: usize) -> Result<[usize; 4], Error
     Ok([r1, r2, r3 as usize, r4])
 }
 
-pub fn map_unprotected(rd: &Rd, ipa: usize, level: usize, host_s2tte: usize) -> Result<(), Error> {
+pub fn map_unprotected(
+    rd: &Rtts<'_>,
+    ipa: usize,
+    level: isize,
+    host_s2tte: usize,
+) -> Result<(), Error> {
     if rd.addr_in_par(ipa) {
         return Err(Error::RmiErrorInput);
     }
 
     // TODO: should return actual last level, not level 0
-    let (s2tte, last_level) = S2TTE::get_s2tte(rd, ipa, level, Error::RmiErrorRtt(0))?;
+    let table = rd
+        .s2_table()
+        .walk(GuestPhysAddr::from(ipa), level)
+        .or(Err(Error::RmiErrorRtt(0)))?;
+    let (s2tte, last_level) = (S2TTE::in_table(rd, &table, ipa), table.level());
 
     if level != last_level {
         return Err(Error::RmiErrorRtt(last_level));
@@ -286,36 +314,38 @@ pub fn map_unprotected(rd: &Rd, ipa: usize, level: usize, host_s2tte: usize) ->
         | host_s2tte.get_masked(S2TTE::MEMATTR)
         | host_s2tte.get_masked(S2TTE::S2AP)
         | bits_in_reg(S2TTE::HIPAS, hipas::ASSIGNED);
+    if rd.lpa2() {
+        new_s2tte |= host_s2tte.get_masked(S2TTE::OA_HIGH);
+    }
     if level == RTT_PAGE_LEVEL {
         new_s2tte |= VALID_NS_TTE | bits_in_reg(S2TTE::DESC_TYPE, desc_type::L3_PAGE);
     } else {
         new_s2tte |= VALID_NS_TTE | bits_in_reg(S2TTE::DESC_TYPE, desc_type::L012_BLOCK);
     }
 
-    rd.s2_table().lock().ipa_to_pte_set(
-        GuestPhysAddr::from(ipa),
-        level,
-        new_s2tte,
-        Tlbi::LEAF(rd.id()),
-    )?;
+    table.set_s2tte(ipa, new_s2tte, Tlbi::LEAF(rd.id()));
 
     Ok(())
 }
 
 pub fn unmap_unprotected<F: FnMut(usize)>(
-    rd: &Rd,
+    rd: &Rtts<'_>,
     ipa: usize,
-    level: usize,
+    level: isize,
     mut f: F,
 ) -> Result<usize, Error> {
     if rd.addr_in_par(ipa) {
         return Err(Error::RmiErrorInput);
     }
 
-    let (s2tte, last_level) = S2TTE::get_s2tte(rd, ipa, level, Error::RmiErrorRtt(0))?;
+    let table = rd
+        .s2_table()
+        .walk(GuestPhysAddr::from(ipa), level)
+        .or(Err(Error::RmiErrorRtt(0)))?;
+    let (s2tte, last_level) = (S2TTE::in_table(rd, &table, ipa), table.level());
 
     if level != last_level || !s2tte.is_assigned_ns(last_level) {
-        let top_ipa = skip_non_live_entries(rd, ipa, last_level)?;
+        let top_ipa = skip_non_live_entries(rd, &table, ipa);
         f(top_ipa);
         return Err(Error::RmiErrorRtt(last_level));
     }
@@ -324,21 +354,27 @@ pub fn unmap_unprotected<F: FnMut(usize)>(
         | bits_in_reg(S2TTE::HIPAS, hipas::UNASSIGNED)
         | INVALID_UNPROTECTED;
 
-    rd.s2_table().lock().ipa_to_pte_set(
-        GuestPhysAddr::from(ipa),
-        level,
-        new_s2tte,
-        Tlbi::LEAF(rd.id()),
-    )?;
+    table.set_s2tte(ipa, new_s2tte, Tlbi::LEAF(rd.id()));
 
-    let top_ipa = skip_non_live_entries(rd, ipa, level)?;
+    let top_ipa = skip_non_live_entries(rd, &table, ipa);
     Ok(top_ipa)
 }
 
-pub fn set_ripas(rd: &Rd, base: usize, top: usize, ripas: u8, flags: u64) -> Result<usize, Error> {
+pub fn set_ripas(
+    rd: &Rtts<'_>,
+    base: usize,
+    top: usize,
+    ripas: u8,
+    flags: u64,
+) -> Result<usize, Error> {
     // TODO: get it from s2table with the start address
     let level = RTT_PAGE_LEVEL;
-    let (_s2tte, level) = S2TTE::get_s2tte(rd, base, RTT_PAGE_LEVEL, Error::RmiErrorRtt(level))?;
+    let lpa2 = rd.lpa2();
+    let table = rd
+        .s2_table()
+        .walk(GuestPhysAddr::from(base), level)
+        .or(Err(Error::RmiErrorRtt(level)))?;
+    let level = table.level();
 
     let map_size = mapping_size(level);
 
@@ -357,14 +393,18 @@ pub fn set_ripas(rd: &Rd, base: usize, top: usize, ripas: u8, flags: u64) -> Res
 
     while addr < table_top && addr < top {
         let mut invalidate = Tlbi::NONE;
-        let (s2tte, last_level) = S2TTE::get_s2tte(rd, addr, level, Error::RmiErrorRtt(level))?;
+        let (s2tte, last_level) = (S2TTE::in_table(rd, &table, addr), level);
         let mut new_s2tte = 0;
         let mut add_pa = false;
 
         if s2tte.is_table(last_level) || s2tte.is_destroyed() && flags & CHANGE_DESTROYED == 0 {
             break;
         }
-        let pa: usize = s2tte.addr_as_block(last_level).into(); //XXX: check this again
+        // RIPAS of device memory is changed only through VDEV_VALIDATE_MAPPING
+        if s2tte.is_assigned_dev() {
+            break;
+        }
+        let pa: usize = s2tte.addr_as_block(last_level, lpa2).into(); //XXX: check this again
         new_s2tte |= s2tte.get_masked(S2TTE::HIPAS);
         new_s2tte |= bits_in_reg(S2TTE::RIPAS, ripas as u64);
         // If requested riaps  == current ripas, skip it.
@@ -372,6 +412,10 @@ pub fn set_ripas(rd: &Rd, base: usize, top: usize, ripas: u8, flags: u64) -> Res
             addr += map_size;
             continue;
         }
+        // Like data_destroy, the granule must be unmapped from the auxiliary RTTs first
+        if last_level == RTT_PAGE_LEVEL && is_aux_mapped(rd, addr)? {
+            break;
+        }
         if ripas as u64 == ripas::EMPTY {
             if s2tte.is_assigned_ram(last_level) {
                 add_pa = true;
@@ -382,7 +426,7 @@ pub fn set_ripas(rd: &Rd, base: usize, top: usize, ripas: u8, flags: u64) -> Res
             new_s2tte |= bits_in_reg(S2TTE::DESC_TYPE, desc_type::LX_INVALID);
         } else if ripas as u64 == ripas::RAM {
             if s2tte.is_assigned_invalid() {
-                new_s2tte |= VALID_TTE;
+                new_s2tte |= attr_bits(VALID_TTE, lpa2);
                 if last_level == RTT_PAGE_LEVEL {
                     new_s2tte |= bits_in_reg(S2TTE::DESC_TYPE, desc_type::L3_PAGE);
                 } else {
@@ -394,14 +438,9 @@ pub fn set_ripas(rd: &Rd, base: usize, top: usize, ripas: u8, flags: u64) -> Res
             unreachable!();
         }
         if add_pa {
-            new_s2tte |= pa as u64;
+            new_s2tte |= oa_bits(pa, lpa2);
         }
-        rd.s2_table().lock().ipa_to_pte_set(
-            GuestPhysAddr::from(addr),
-            last_level,
-            new_s2tte,
-            invalidate,
-        )?;
+        table.set_s2tte(addr, new_s2tte, invalidate);
 
         addr += map_size;
     }
@@ -412,9 +451,18 @@ pub fn set_ripas(rd: &Rd, base: usize, top: usize, ripas: u8, flags: u64) -> Res
     }
 }
 
-pub fn data_create(rd: &Rd, ipa: usize, target_pa: usize, unknown: bool) -> Result<(), Error> {
+pub fn data_create(
+    rd: &Rtts<'_>,
+    ipa: usize,
+    target_pa: usize,
+    unknown: bool,
+) -> Result<(), Error> {
     let level = RTT_PAGE_LEVEL;
-    let (s2tte, last_level) = S2TTE::get_s2tte(rd, ipa, level, Error::RmiErrorRtt(0))?;
+    let table = rd
+        .s2_table()
+        .walk(GuestPhysAddr::from(ipa), level)
+        .or(Err(Error::RmiErrorRtt(0)))?;
+    let (s2tte, last_level) = (S2TTE::in_table(rd, &table, ipa), table.level());
 
     if level != last_level {
         return Err(Error::RmiErrorRtt(last_level));
@@ -424,7 +472,7 @@ pub fn data_create(rd: &Rd, ipa: usize, target_pa: usize, unknown: bool) -> Resu
         return Err(Error::RmiErrorRtt(RTT_PAGE_LEVEL));
     }
 
-    let mut new_s2tte = target_pa as u64;
+    let mut new_s2tte = oa_bits(target_pa, rd.lpa2());
     if s2tte.is_ripas() {
         panic!("invalid ripas");
     }
@@ -438,32 +486,40 @@ pub fn data_create(rd: &Rd, ipa: usize, target_pa: usize, unknown: bool) -> Resu
         // New RIPAS: RAM
         new_s2tte |= bits_in_reg(S2TTE::RIPAS, ripas::RAM);
         new_s2tte |= bits_in_reg(S2TTE::DESC_TYPE, desc_type::L3_PAGE);
-        new_s2tte |= VALID_TTE;
+        new_s2tte |= attr_bits(VALID_TTE, rd.lpa2());
     }
 
-    rd.s2_table()
-        .lock()
-        .ipa_to_pte_set(GuestPhysAddr::from(ipa), level, new_s2tte, Tlbi::NONE)?;
+    table.set_s2tte(ipa, new_s2tte, Tlbi::NONE);
 
     Ok(())
 }
 
 pub fn data_destroy<F: FnMut(usize)>(
-    rd: &Rd,
+    rd: &Rtts<'_>,
     ipa: usize,
     mut f: F,
 ) -> Result<(usize, usize), Error> {
     let mut invalidate = Tlbi::NONE;
     let level = RTT_PAGE_LEVEL;
-    let (s2tte, last_level) = S2TTE::get_s2tte(rd, ipa, level, Error::RmiErrorRtt(level))?;
+    let table = rd
+        .s2_table()
+        .walk(GuestPhysAddr::from(ipa), level)
+        .or(Err(Error::RmiErrorRtt(level)))?;
+    let (s2tte, last_level) = (S2TTE::in_table(rd, &table, ipa), table.level());
 
     if last_level < level || !s2tte.is_assigned() {
-        let top_ipa = skip_non_live_entries(rd, ipa, last_level)?;
+        let top_ipa = skip_non_live_entries(rd, &table, ipa);
         f(top_ipa);
         return Err(Error::RmiErrorRtt(last_level));
     }
 
-    let pa = s2tte.addr_as_block(last_level).into(); //XXX: check this again
+    // The granule must be unmapped from the auxiliary RTTs first.
+    // Auxiliary RTTs are always locked after the primary one.
+    if is_aux_mapped(rd, ipa)? {
+        return Err(Error::RmiErrorRtt(level));
+    }
+
+    let pa = s2tte.addr_as_block(last_level, rd.lpa2()).into(); //XXX: check this again
 
     let mut new_s2tte = bits_in_reg(S2TTE::HIPAS, hipas::UNASSIGNED)
         | bits_in_reg(S2TTE::DESC_TYPE, desc_type::LX_INVALID);
@@ -475,52 +531,123 @@ pub fn data_destroy<F: FnMut(usize)>(
     if s2tte.is_assigned_ram(RTT_PAGE_LEVEL) {
         invalidate = Tlbi::LEAF(rd.id());
     }
-    rd.s2_table()
-        .lock()
-        .ipa_to_pte_set(GuestPhysAddr::from(ipa), level, new_s2tte, invalidate)?;
+    table.set_s2tte(ipa, new_s2tte, invalidate);
 
-    let top_ipa = skip_non_live_entries(rd, ipa, level)?;
+    let top_ipa = skip_non_live_entries(rd, &table, ipa);
 
     Ok((pa, top_ipa))
 }
 
-fn is_live_rtt(rd: &Rd, base: usize, level: usize) -> Result<bool, Error> {
-    let binding = rd.s2_table();
-    let binding = binding.lock();
-    let (entries_iter, last_level) = binding.entries(GuestPhysAddr::from(base), level)?;
+pub fn dev_mem_map(rd: &Rtts<'_>, ipa: usize, pa: usize) -> Result<(), Error> {
+    let level = RTT_PAGE_LEVEL;
+    let table = rd
+        .s2_table()
+        .walk(GuestPhysAddr::from(ipa), level)
+        .or(Err(Error::RmiErrorRtt(0)))?;
+    let (s2tte, last_level) = (S2TTE::in_table(rd, &table, ipa), table.level());
+
     if level != last_level {
-        error!(
-            "level doesn't match! level:{:?} last_level:{:?}",
-            level, last_level
-        );
+        return Err(Error::RmiErrorRtt(last_level));
     }
 
-    for entry in entries_iter {
-        let s2tte = S2TTE::new(entry.pte());
-        if s2tte.is_live(level) {
-            return Ok(true);
+    if !s2tte.is_unassigned_empty() {
+        return Err(Error::RmiErrorRtt(level));
+    }
+
+    // The mapping becomes accessible once the realm validates it.
+    let new_s2tte = oa_bits(pa, rd.lpa2())
+        | bits_in_reg(S2TTE::HIPAS, hipas::ASSIGNED_DEV)
+        | bits_in_reg(S2TTE::RIPAS, ripas::EMPTY)
+        | bits_in_reg(S2TTE::DESC_TYPE, desc_type::LX_INVALID);
+
+    table.set_s2tte(ipa, new_s2tte, Tlbi::NONE);
+
+    Ok(())
+}
+
+pub fn dev_mem_unmap(rd: &Rtts<'_>, ipa: usize) -> Result<usize, Error> {
+    let mut invalidate = Tlbi::NONE;
+    let level = RTT_PAGE_LEVEL;
+    let table = rd
+        .s2_table()
+        .walk(GuestPhysAddr::from(ipa), level)
+        .or(Err(Error::RmiErrorRtt(0)))?;
+    let (s2tte, last_level) = (S2TTE::in_table(rd, &table, ipa), table.level());
+
+    if level != last_level || !s2tte.is_assigned_dev() {
+        return Err(Error::RmiErrorRtt(last_level));
+    }
+
+    let pa = s2tte.addr_as_block(last_level, rd.lpa2()).into();
+
+    let mut new_s2tte = bits_in_reg(S2TTE::HIPAS, hipas::UNASSIGNED)
+        | bits_in_reg(S2TTE::DESC_TYPE, desc_type::LX_INVALID);
+    if s2tte.is_assigned_dev_empty() {
+        new_s2tte |= bits_in_reg(S2TTE::RIPAS, ripas::EMPTY);
+    } else {
+        new_s2tte |= bits_in_reg(S2TTE::RIPAS, ripas::DESTROYED);
+        invalidate = Tlbi::LEAF(rd.id());
+    }
+    table.set_s2tte(ipa, new_s2tte, invalidate);
+
+    Ok(pa)
+}
+
+// Makes the device memory mappings of [base, top) accessible to the realm
+// if they are backed by the PA range starting from `pa_base`.
+// Returns the IPA where the validation stopped.
+pub fn validate_dev_mapping(
+    rd: &Rtts<'_>,
+    base: usize,
+    top: usize,
+    pa_base: usize,
+) -> Result<usize, Error> {
+    let level = RTT_PAGE_LEVEL;
+    let map_size = mapping_size(level);
+    let mut addr = base;
+
+    while addr < top {
+        let table = rd
+            .s2_table()
+            .walk(GuestPhysAddr::from(addr), level)
+            .or(Err(Error::RmiErrorRtt(0)))?;
+        let (s2tte, last_level) = (S2TTE::in_table(rd, &table, addr), table.level());
+        if last_level != level || !s2tte.is_assigned_dev() {
+            break;
+        }
+        let pa: usize = s2tte.addr_as_block(last_level, rd.lpa2()).into();
+        if pa != pa_base + (addr - base) {
+            break;
         }
+
+        let new_s2tte = oa_bits(pa, rd.lpa2())
+            | VALID_DEV_TTE
+            | bits_in_reg(S2TTE::HIPAS, hipas::ASSIGNED_DEV)
+            | bits_in_reg(S2TTE::RIPAS, ripas::DEV)
+            | bits_in_reg(S2TTE::DESC_TYPE, desc_type::L3_PAGE);
+        table.set_s2tte(addr, new_s2tte, Tlbi::NONE);
+
+        addr += map_size;
     }
-    Err(Error::RmiErrorRtt(level))
+    Ok(addr)
 }
 
-fn skip_non_live_entries(rd: &Rd, base: usize, level: usize) -> Result<usize, Error> {
+fn is_live_rtt(rtt: &LockedTable<'_>) -> bool {
+    rtt.entries()
+        .any(|entry| S2TTE::new(entry.pte()).is_live(rtt.level()))
+}
+
+// Returns the IPA of the first live entry of the locked table from `base`,
+// or the end of the table if there is none
+fn skip_non_live_entries(rd: &Rtts<'_>, table: &LockedTable<'_>, base: usize) -> usize {
+    let level = table.level();
     let map_size = mapping_size(level);
 
     let mut addr = base & !(map_size - 1);
     let space_size = level_space_size(rd, level);
     let mut entry0_ipa = addr & !(space_size - 1);
 
-    let binding = rd.s2_table();
-    let binding = binding.lock();
-    let (entries_iter, last_level) = binding.entries(GuestPhysAddr::from(base), level)?;
-    if level != last_level {
-        warn!(
-            "level doesn't match! level:{:?} last_level:{:?}",
-            level, last_level
-        );
-    }
-    for entry in entries_iter {
+    for entry in table.entries() {
         // skip entries less than the base ipa
         if entry0_ipa < base {
             entry0_ipa += map_size;
@@ -528,20 +655,26 @@ fn skip_non_live_entries(rd: &Rd, base: usize, level: usize) -> Result<usize, Er
         }
         let s2tte = S2TTE::new(entry.pte());
         if s2tte.is_live(level) {
-            return Ok(addr);
+            return addr;
         }
         addr += map_size;
     }
-    Ok(addr)
+    addr
 }
 
-pub fn fold(rd: &Rd, ipa: usize, level: usize) -> Result<usize, Error> {
+pub fn fold(rd: &Rtts<'_>, ipa: usize, level: isize) -> Result<usize, Error> {
     let is_protected_ipa = rd.addr_in_par(ipa);
-    let (fold_s2tte, _last_level) = S2TTE::get_s2tte(rd, ipa, level, Error::RmiErrorRtt(level))?;
-    let (parent_s2tte, parent_level) = S2TTE::get_s2tte(rd, ipa, level - 1, Error::RmiErrorInput)?;
+    let lpa2 = rd.lpa2();
+    let parent = rd
+        .s2_table()
+        .walk(GuestPhysAddr::from(ipa), level - 1)
+        .or(Err(Error::RmiErrorInput))?;
+    let (parent_s2tte, parent_level) = (S2TTE::in_table(rd, &parent, ipa), parent.level());
     if parent_level < (level - 1) || !parent_s2tte.is_table(level - 1) {
         return Err(Error::RmiErrorRtt(parent_level));
     }
+    let rtt = parent.subtable(ipa)?;
+    let fold_s2tte = S2TTE::in_table(rd, &rtt, ipa);
     // TODO: spec doesn't reject the fold with its state in TABLE.
     if fold_s2tte.is_table(level) {
         warn!("Trying to fold which points another RTT");
@@ -553,21 +686,18 @@ pub fn fold(rd: &Rd, ipa: usize, level: usize) -> Result<usize, Error> {
 
     // TODO: ref count check
 
-    let binding = rd.s2_table();
-    let mut binding = binding.lock();
-    let (mut entries_iter, _) = binding.entries(GuestPhysAddr::from(ipa), level)?;
-    if !S2TTE::is_homogeneous(&mut entries_iter, level) {
+    if !S2TTE::is_homogeneous(&mut rtt.entries(), level, lpa2) {
         return Err(Error::RmiErrorRtt(level));
     }
-    let mut pa: u64 = 0;
+    let mut pa: usize = 0;
     let mut attr = fold_s2tte.get_masked(S2TTE::NS);
     let hipas = fold_s2tte.get_masked(S2TTE::HIPAS);
     let mut ripas = 0;
     let mut desc_type = 0;
 
     if fold_s2tte.get_masked_value(S2TTE::HIPAS) == hipas::ASSIGNED {
-        pa = fold_s2tte.addr_as_block(level).into();
-        attr |= fold_s2tte.get_masked(TTE_ATTR_MASK);
+        pa = fold_s2tte.addr_as_block(level, lpa2).into();
+        attr |= attr_bits(fold_s2tte.get_masked(TTE_ATTR_MASK), lpa2);
     }
     if is_protected_ipa {
         ripas = fold_s2tte.get_masked(S2TTE::RIPAS);
@@ -576,14 +706,124 @@ pub fn fold(rd: &Rd, ipa: usize, level: usize) -> Result<usize, Error> {
         desc_type = desc_type::L012_BLOCK;
     }
 
-    let parent_s2tte = pa | attr | hipas | ripas | desc_type;
-    binding.ipa_to_pte_set(
-        GuestPhysAddr::from(ipa),
-        level - 1,
-        parent_s2tte,
-        Tlbi::BREAKDOWN(rd.id()),
-    )?;
+    let parent_s2tte = oa_bits(pa, lpa2) | attr | hipas | ripas | desc_type;
+    rtt.unlink(ipa, parent_s2tte, Tlbi::BREAKDOWN(rd.id()));
     //Change state of child table (pa)
     set_granule(&mut g_rtt, GranuleState::Delegated)?;
     Ok(rtt_addr as usize)
 }
+
+// Auxiliary RTTs only hold tables and the mirrored leaf entries of the primary RTT.
+// So, their entries are either zero (unassigned), a table, or a copy of a primary leaf.
+fn aux_s2tte(
+    rd: &Rtts<'_>,
+    plane: usize,
+    ipa: usize,
+    level: isize,
+) -> Result<(S2TTE, isize), Error> {
+    let (s2tte, last_level) = rd
+        .plane_s2_table(plane)?
+        .ipa_to_pte(GuestPhysAddr::from(ipa), level)
+        .ok_or(Error::RmiErrorRtt(0))?;
+    Ok((S2TTE::from(s2tte as usize), last_level))
+}
+
+pub fn aux_create(
+    rd: &Rtts<'_>,
+    plane: usize,
+    rtt_addr: usize,
+    ipa: usize,
+    level: isize,
+) -> Result<(), Error> {
+    let parent = rd
+        .plane_s2_table(plane)?
+        .walk(GuestPhysAddr::from(ipa), level - 1)
+        .or(Err(Error::RmiErrorRtt(0)))?;
+    let (parent_s2tte, last_level) = (parent.s2tte(ipa), parent.level());
+
+    if last_level != level - 1 || parent_s2tte.get() != 0 {
+        return Err(Error::RmiErrorRtt(last_level));
+    }
+
+    create_pgtbl_at(rtt_addr, 0, 0, 0, rd.lpa2())?;
+
+    let parent_s2tte = oa_bits(rtt_addr, rd.lpa2()) | attr_bits(TABLE_TTE, rd.lpa2());
+    parent.set_s2tte(ipa, parent_s2tte, Tlbi::NONE);
+    Ok(())
+}
+
+pub fn aux_destroy(rd: &Rtts<'_>, plane: usize, ipa: usize, level: isize) -> Result<usize, Error> {
+    let parent = rd
+        .plane_s2_table(plane)?
+        .walk(GuestPhysAddr::from(ipa), level - 1)
+        .or(Err(Error::RmiErrorRtt(0)))?;
+    let (parent_s2tte, last_level) = (parent.s2tte(ipa), parent.level());
+
+    if last_level != level - 1 || !parent_s2tte.is_table(last_level) {
+        return Err(Error::RmiErrorRtt(last_level));
+    }
+
+    let rtt_addr = parent_s2tte.addr_as_block(RTT_PAGE_LEVEL, rd.lpa2()).into();
+    let mut g_rtt = get_granule_if!(rtt_addr, GranuleState::RTT)?;
+
+    let rtt = parent.subtable(ipa)?;
+    if rtt.entries().any(|entry| entry.pte() != 0) {
+        return Err(Error::RmiErrorRtt(level));
+    }
+
+    rtt.unlink(ipa, 0, Tlbi::BREAKDOWN(rd.plane_vmid(plane)?));
+
+    set_granule(&mut g_rtt, GranuleState::Delegated)?;
+    Ok(rtt_addr)
+}
+
+pub fn aux_map_protected(rd: &Rtts<'_>, plane: usize, ipa: usize) -> Result<(), Error> {
+    let level = RTT_PAGE_LEVEL;
+    // The primary entry stays locked until it is mirrored
+    let table = rd
+        .s2_table()
+        .walk(GuestPhysAddr::from(ipa), level)
+        .or(Err(Error::RmiErrorRtt(0)))?;
+    let (s2tte, last_level) = (S2TTE::in_table(rd, &table, ipa), table.level());
+    if last_level != level || !s2tte.is_assigned_ram(level) {
+        return Err(Error::RmiErrorRtt(last_level));
+    }
+
+    let aux_table = rd
+        .plane_s2_table(plane)?
+        .walk(GuestPhysAddr::from(ipa), level)
+        .or(Err(Error::RmiErrorRtt(0)))?;
+    let (aux_s2tte, aux_last_level) = (aux_table.s2tte(ipa), aux_table.level());
+    if aux_last_level != level || aux_s2tte.get() != 0 {
+        return Err(Error::RmiErrorRtt(aux_last_level));
+    }
+
+    aux_table.set_s2tte(ipa, s2tte.get(), Tlbi::NONE);
+    Ok(())
+}
+
+pub fn aux_unmap_protected(rd: &Rtts<'_>, plane: usize, ipa: usize) -> Result<(), Error> {
+    let level = RTT_PAGE_LEVEL;
+    let table = rd
+        .plane_s2_table(plane)?
+        .walk(GuestPhysAddr::from(ipa), level)
+        .or(Err(Error::RmiErrorRtt(0)))?;
+    let (aux_s2tte, aux_last_level) = (table.s2tte(ipa), table.level());
+    if aux_last_level != level || !aux_s2tte.is_assigned() || aux_s2tte.get() == 0 {
+        return Err(Error::RmiErrorRtt(aux_last_level));
+    }
+
+    table.set_s2tte(ipa, 0, Tlbi::LEAF(rd.plane_vmid(plane)?));
+    Ok(())
+}
+
+// Returns true if the protected `ipa` is mirrored in any auxiliary RTT.
+fn is_aux_mapped(rd: &Rtts<'_>, ipa: usize) -> Result<bool, Error> {
+    for plane in 1..=rd.num_aux_planes() {
+        let (aux_s2tte, aux_last_level) = aux_s2tte(rd, plane, ipa, RTT_PAGE_LEVEL)?;
+        if aux_last_level == RTT_PAGE_LEVEL && aux_s2tte.get() != 0 {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
@@ -2,19 +2,21 @@ use core::arch::asm;
 use core::ffi::c_void;
 use core::fmt;
 use core::slice::Iter;
+use core::sync::atomic::{AtomicBool, Ordering};
 
 use crate::granule::GRANULE_SHIFT;
 use crate::realm::mm::address::GuestPhysAddr;
 use crate::realm::mm::entry;
 use crate::realm::mm::page::BasePageSize;
 use crate::realm::mm::page::{HugePageSize, LargePageSize};
-use crate::realm::mm::stage2_tte::mapping_size;
+use crate::realm::mm::stage2_tte::{mapping_size, S2TTE};
 use crate::realm::mm::table_level::RootTable;
-use crate::realm::mm::table_level::{L0Table, L1Table, L2Table};
+use crate::realm::mm::table_level::{L0Table, L1Table, L2Table, L3Table};
 use crate::realm::mm::IPATranslation;
 use crate::rmi::error::Error;
 use alloc::alloc::Layout;
 use vmsa::address::PhysAddr;
+use vmsa::guard::EntryGuard;
 use vmsa::page::{Page, PageIter, PageSize};
 use vmsa::page_table::Entry;
 use vmsa::page_table::{Level, MemAlloc, PageTable, PageTableMethods};
@@ -49,11 +51,13 @@ const ENTR2: usize = ENTR1 * 2;
 const ENTR4: usize = ENTR1 * 4;
 const ENTR8: usize = ENTR1 * 8;
 const ENTR16: usize = ENTR1 * 16;
+const LM1_NUM_ENTRIES: usize = 16;
 
-type RootTBL<'a, const L: usize, const N: usize, const E: usize> =
+type RootTBL<'a, const L: isize, const N: usize, const E: usize> =
     &'a mut PageTable<GuestPhysAddr, RootTable<{ L }, { N }>, entry::Entry, { E }>;
 
 pub enum Root<'a> {
+    LM1N1(RootTBL<'a, -1, 1, ENTR1>),
     L0N1(RootTBL<'a, 0, 1, ENTR1>),
     L0N16(RootTBL<'a, 0, 16, ENTR16>),
     L1N1(RootTBL<'a, 1, 1, ENTR1>),
@@ -84,22 +88,116 @@ macro_rules! init_table {
 pub struct Stage2Translation<'a> {
     // We will set the translation granule with 4KB.
     root_pgtbl: Root<'a>,
-    root_level: usize,
+    // A table descriptor pointing to the root table, which holds its lock
+    root: entry::Entry,
+    root_level: isize,
     root_pages: usize,
-    dirty: bool,
+    dirty: AtomicBool,
+}
+
+/// A table of the RTT, locked by the entry pointing to it until dropped.
+/// Its entries are modified only through this, by a CPU at a time.
+pub struct LockedTable<'a> {
+    entries: &'a [entry::Entry],
+    level: isize,
+    dirty: &'a AtomicBool,
+    lock: EntryGuard<'a, S2TTE>,
+}
+
+impl<'a> LockedTable<'a> {
+    pub fn level(&self) -> isize {
+        self.level
+    }
+
+    fn index(&self, ipa: usize) -> usize {
+        (ipa / mapping_size(self.level)) % self.entries.len()
+    }
+
+    /// Returns the entry holding `ipa`.
+    pub fn s2tte(&self, ipa: usize) -> S2TTE {
+        S2TTE::new(self.entries[self.index(ipa)].pte())
+    }
+
+    pub fn entries(&self) -> Iter<'a, entry::Entry> {
+        self.entries.iter()
+    }
+
+    /// Sets the entry holding `ipa`, which doesn't point to a table.
+    pub fn set_s2tte(&self, ipa: usize, val: u64, invalidate: Tlbi) {
+        self.entries[self.index(ipa)].set_pte(val);
+        invalidate_ipa(self.level, ipa, invalidate, self.dirty);
+    }
+
+    /// Locks the table which the entry holding `ipa` points to.
+    /// This table may be unlocked afterwards.
+    pub fn subtable(&self, ipa: usize) -> Result<LockedTable<'a>, Error> {
+        if self.level >= L3Table::THIS_LEVEL {
+            return Err(Error::RmiErrorRtt(self.level));
+        }
+        let entries: &'a [entry::Entry] = self.entries;
+        let lock = entries[self.index(ipa)]
+            .lock()?
+            .ok_or(Error::RmiErrorRtt(self.level))?;
+        let addr = lock.get_masked(S2TTE::ADDR_TBL_OR_PAGE) as usize;
+        // Safety: the table stays linked while it is locked
+        let entries = unsafe {
+            core::slice::from_raw_parts(addr as *const entry::Entry, L3Table::NUM_ENTRIES)
+        };
+        Ok(LockedTable {
+            entries,
+            level: self.level + 1,
+            dirty: self.dirty,
+            lock,
+        })
+    }
+
+    /// Replaces the entry pointing to this table with `val`, unlinking the table.
+    /// The caller holds the lock of the parent table.
+    pub fn unlink(mut self, ipa: usize, val: u64, invalidate: Tlbi) {
+        let (level, dirty) = (self.level - 1, self.dirty);
+        *self.lock = S2TTE::new(val);
+        // The new entry is written back on unlock
+        core::mem::drop(self);
+        invalidate_ipa(level, ipa, invalidate, dirty);
+    }
+}
+
+#[allow(unused_variables)]
+fn invalidate_ipa(level: isize, ipa: usize, invalidate: Tlbi, dirty: &AtomicBool) {
+    match invalidate {
+        Tlbi::LEAF(vmid) => {
+            #[cfg(not(any(miri, test, fuzzing)))]
+            Stage2Translation::tlbi_by_vmid_ipa(level, GuestPhysAddr::from(ipa), vmid);
+            dirty.store(true, Ordering::Relaxed);
+        }
+        Tlbi::BREAKDOWN(vmid) => {
+            #[cfg(not(any(miri, test, fuzzing)))]
+            Stage2Translation::tlbi_by_vmid_ipa_range(level, GuestPhysAddr::from(ipa), vmid);
+            dirty.store(true, Ordering::Relaxed);
+        }
+        _ => {}
+    }
 }
 
 impl Stage2Translation<'_> {
-    pub fn new(rtt_base: usize, root_level: usize, root_pages: usize) -> Self {
+    /// Fails if the root tables can't be concatenated as many as `root_pages`.
+    pub fn new(rtt_base: usize, root_level: isize, root_pages: usize) -> Result<Self, Error> {
         // Concatenated translation tables
         // For stage 2 address translations, for the initial lookup,
         // up to 16 translation tables can be concatenated.
         let root_pgtbl = match root_level {
+            // level -1 (FEAT_LPA2) doesn't allow concatenation
+            -1 => unsafe {
+                match root_pages {
+                    1 => Root::LM1N1(init_table!(-1, 1, rtt_base)),
+                    _ => return Err(Error::RmiErrorInput),
+                }
+            },
             0 => unsafe {
                 match root_pages {
                     1 => Root::L0N1(init_table!(0, 1, rtt_base)),
                     16 => Root::L0N16(init_table!(0, 16, rtt_base)),
-                    _ => todo!(),
+                    _ => return Err(Error::RmiErrorInput),
                 }
             },
             1 => unsafe {
@@ -107,7 +205,7 @@ impl Stage2Translation<'_> {
                     1 => Root::L1N1(init_table!(1, 1, rtt_base)),
                     2 => Root::L1N2(init_table!(1, 2, rtt_base)),
                     8 => Root::L1N8(init_table!(1, 8, rtt_base)),
-                    _ => todo!(),
+                    _ => return Err(Error::RmiErrorInput),
                 }
             },
             2 => unsafe {
@@ -115,20 +213,23 @@ impl Stage2Translation<'_> {
                     4 => Root::L2N4(init_table!(2, 4, rtt_base)),
                     8 => Root::L2N8(init_table!(2, 8, rtt_base)),
                     16 => Root::L2N16(init_table!(2, 16, rtt_base)),
-                    _ => todo!(),
+                    _ => return Err(Error::RmiErrorInput),
                 }
             },
-            _ => todo!(),
+            _ => return Err(Error::RmiErrorInput),
         };
-        Self {
+        let mut root = entry::Entry::new();
+        let _ = root.point_to_subtable(0, PhysAddr::from(rtt_base));
+        Ok(Self {
             root_pgtbl,
+            root,
             root_level,
             root_pages,
-            dirty: false,
-        }
+            dirty: AtomicBool::new(false),
+        })
     }
 
-    fn tlbi_iter<S: PageSize>(level: usize, guest_iter: PageIter<S, GuestPhysAddr>, vmid: usize) {
+    fn tlbi_iter<S: PageSize>(level: isize, guest_iter: PageIter<S, GuestPhysAddr>, vmid: usize) {
         let vmid_saved = VTTBR_EL2.read(VTTBR_EL2::VMID);
         VTTBR_EL2.write(VTTBR_EL2::VMID.val(vmid as u64));
 
@@ -138,8 +239,9 @@ impl Stage2Translation<'_> {
         for guest in guest_iter {
             let mut ipa: u64 = guest.address().as_u64() >> GRANULE_SHIFT;
             unsafe {
-                ipa = bits_in_reg(TLBI_OP::TTL, 0b0100 | level as u64)
-                    | bits_in_reg(TLBI_OP::IPA, ipa);
+                // no level hint for level -1
+                let ttl = if level < 0 { 0 } else { 0b0100 | level as u64 };
+                ipa = bits_in_reg(TLBI_OP::TTL, ttl) | bits_in_reg(TLBI_OP::IPA, ipa);
                 asm!(
                     "tlbi IPAS2E1IS, {}",
                     in(reg) ipa,
@@ -156,7 +258,7 @@ impl Stage2Translation<'_> {
     // According to DDI0608A E1.2.1.11 Cache and TLB operations
     // 'TLBI IPAS2E1, Xt; DSB; TLBI VMALLE1'
     // or TLBI ALL or TLBI VMALLS1S2
-    fn tlbi_by_vmid_ipa(level: usize, guest: GuestPhysAddr, vmid: usize) {
+    fn tlbi_by_vmid_ipa(level: isize, guest: GuestPhysAddr, vmid: usize) {
         let guest_iter =
             Page::<BasePageSize, GuestPhysAddr>::range_with_size(guest, BasePageSize::SIZE);
         Self::tlbi_iter(level, guest_iter, vmid);
@@ -166,7 +268,7 @@ impl Stage2Translation<'_> {
 
     #[allow(unused)]
     // Saved in case tlb-rmi arch extension is not provided
-    fn tlbi_by_vmid_ipa_range_v2(level: usize, guest: GuestPhysAddr, vmid: usize) {
+    fn tlbi_by_vmid_ipa_range_v2(level: isize, guest: GuestPhysAddr, vmid: usize) {
         match level {
             L2Table::THIS_LEVEL => {
                 let guest_iter = Page::<BasePageSize, GuestPhysAddr>::range_with_size(
@@ -197,7 +299,12 @@ impl Stage2Translation<'_> {
         Self::tlbi_vmalle1is(vmid);
     }
 
-    fn tlbi_by_vmid_ipa_range(level: usize, guest: GuestPhysAddr, vmid: usize) {
+    fn tlbi_by_vmid_ipa_range(level: isize, guest: GuestPhysAddr, vmid: usize) {
+        // A range under a level -1 entry doesn't fit in a single range operation
+        if level < 0 {
+            Self::tlbi_vmalle1is(vmid);
+            return;
+        }
         let vmid_saved = VTTBR_EL2.read(VTTBR_EL2::VMID);
         VTTBR_EL2.write(VTTBR_EL2::VMID.val(vmid as u64));
         unsafe {
@@ -228,11 +335,12 @@ impl Stage2Translation<'_> {
     }
 
     fn tlbi_vmalle1is(vmid: usize) {
+        let vmid_saved = VTTBR_EL2.read(VTTBR_EL2::VMID);
+        VTTBR_EL2.write(VTTBR_EL2::VMID.val(vmid as u64));
+        // According to DDI0608A E1.2.1.11 Cache and TLB operations
+        // second half part
+        #[cfg(target_arch = "aarch64")]
         unsafe {
-            let vmid_saved = VTTBR_EL2.read(VTTBR_EL2::VMID);
-            VTTBR_EL2.write(VTTBR_EL2::VMID.val(vmid as u64));
-            // According to DDI0608A E1.2.1.11 Cache and TLB operations
-            // second half part
             asm! {
                 "
                     dsb ishst
@@ -241,8 +349,8 @@ impl Stage2Translation<'_> {
                     isb
                     "
             }
-            VTTBR_EL2.write(VTTBR_EL2::VMID.val(vmid_saved));
         }
+        VTTBR_EL2.write(VTTBR_EL2::VMID.val(vmid_saved));
     }
 }
 
@@ -264,41 +372,11 @@ impl MemAlloc for RttAllocator {
     }
 }
 
-#[macro_export]
-// ipa_to_pa closure
-macro_rules! to_pa {
-    ($root:expr, $guest:expr, $level:expr, $pa:expr) => {
-        $root.entry($guest, $level, false, |entry| {
-            $pa = entry.address(0);
-            Ok(None)
-        })
-    };
-}
-
-// ipa_to_pte closure
-macro_rules! to_pte {
-    ($root:expr, $guest:expr, $level:expr, $pte:expr) => {
-        $root.entry($guest, $level, true, |entry| {
-            $pte = entry.pte();
-            Ok(None)
-        })
-    };
-}
-
-// ipa_to_pte_set clousre
-macro_rules! set_pte {
-    ($root:expr, $guest:expr, $level:expr, $val:expr) => {
-        $root.entry($guest, $level, true, |entry| {
-            let _ = entry.set(PhysAddr::from(0 as u64), $val); //FIXME: get pa
-            Ok(None)
-        })
-    };
-}
-
 impl IPATranslation for Stage2Translation<'_> {
     fn get_base_address(&self) -> *const c_void {
         match &self.root_pgtbl {
             Root::L2N8(c) => *c as *const _ as *const c_void, // most likely first, for linux-realm
+            Root::LM1N1(a) => *a as *const _ as *const c_void,
             Root::L0N1(a) => *a as *const _ as *const c_void,
             Root::L0N16(a) => *a as *const _ as *const c_void,
             Root::L1N1(b) => *b as *const _ as *const c_void,
@@ -320,24 +398,12 @@ impl IPATranslation for Stage2Translation<'_> {
     ///      physical address
     ///   else,
     ///      None
-    fn ipa_to_pa(&mut self, guest: GuestPhysAddr, level: usize) -> Option<PhysAddr> {
-        let guest = Page::<BasePageSize, GuestPhysAddr>::including_address(guest);
-        let mut pa = None;
-
-        let res = match &mut self.root_pgtbl {
-            Root::L2N8(root) => to_pa!(root, guest, level, pa), // most likely first, for linux-realm
-            Root::L0N1(root) => to_pa!(root, guest, level, pa),
-            Root::L0N16(root) => to_pa!(root, guest, level, pa),
-            Root::L1N1(root) => to_pa!(root, guest, level, pa),
-            Root::L1N2(root) => to_pa!(root, guest, level, pa),
-            Root::L1N8(root) => to_pa!(root, guest, level, pa),
-            Root::L2N4(root) => to_pa!(root, guest, level, pa),
-            Root::L2N16(root) => to_pa!(root, guest, level, pa),
-        };
-        if res.is_ok() {
-            pa
-        } else {
-            None
+    fn ipa_to_pa(&self, guest: GuestPhysAddr, level: isize) -> Option<PhysAddr> {
+        let table = self.walk(guest, level).ok()?;
+        let entry = table.entries.get(table.index(guest.as_usize()))?;
+        match entry.is_valid() {
+            true => entry.address(0),
+            false => None,
         }
     }
 
@@ -349,109 +415,54 @@ impl IPATranslation for Stage2Translation<'_> {
     ///
     /// (output)
     ///   if exists,
-    ///      A tuple of (pte value (u64), lastly reached page table level (usize))
+    ///      A tuple of (pte value (u64), lastly reached page table level (isize))
     ///   else,
     ///      None
-    fn ipa_to_pte(&mut self, guest: GuestPhysAddr, level: usize) -> Option<(u64, usize)> {
-        let guest = Page::<BasePageSize, GuestPhysAddr>::including_address(guest);
-        let mut pte = 0;
-        let res = match &mut self.root_pgtbl {
-            Root::L2N8(root) => to_pte!(root, guest, level, pte), // most likely first, for linux-realm
-            Root::L0N1(root) => to_pte!(root, guest, level, pte),
-            Root::L0N16(root) => to_pte!(root, guest, level, pte),
-            Root::L1N1(root) => to_pte!(root, guest, level, pte),
-            Root::L1N2(root) => to_pte!(root, guest, level, pte),
-            Root::L1N8(root) => to_pte!(root, guest, level, pte),
-            Root::L2N4(root) => to_pte!(root, guest, level, pte),
-            Root::L2N16(root) => to_pte!(root, guest, level, pte),
-        };
-        if let Ok(x) = res {
-            Some((pte, x.1))
-        } else {
-            None
-        }
+    fn ipa_to_pte(&self, guest: GuestPhysAddr, level: isize) -> Option<(u64, isize)> {
+        let table = self.walk(guest, level).ok()?;
+        Some((table.s2tte(guest.as_usize()).get(), table.level()))
     }
 
-    fn ipa_to_pte_set(
-        &mut self,
-        guest: GuestPhysAddr,
-        level: usize,
-        val: u64,
-        invalidate: Tlbi,
-    ) -> Result<(), Error> {
-        let map_addr = guest;
+    fn walk(&self, guest: GuestPhysAddr, level: isize) -> Result<LockedTable<'_>, Error> {
         let guest = Page::<BasePageSize, GuestPhysAddr>::including_address(guest);
-        let res = match &mut self.root_pgtbl {
-            Root::L2N8(root) => set_pte!(root, guest, level, val),
-            Root::L0N1(root) => set_pte!(root, guest, level, val),
-            Root::L0N16(root) => set_pte!(root, guest, level, val),
-            Root::L1N1(root) => set_pte!(root, guest, level, val),
-            Root::L1N2(root) => set_pte!(root, guest, level, val),
-            Root::L1N8(root) => set_pte!(root, guest, level, val),
-            Root::L2N4(root) => set_pte!(root, guest, level, val),
-            Root::L2N16(root) => set_pte!(root, guest, level, val),
-        };
-        if let Ok(_x) = res {
-            match invalidate {
-                Tlbi::LEAF(vmid) => {
-                    #[cfg(not(any(miri, test, fuzzing)))]
-                    Self::tlbi_by_vmid_ipa(level, map_addr, vmid);
-                    self.dirty = true;
-                }
-                Tlbi::BREAKDOWN(vmid) => {
-                    #[cfg(not(any(miri, test, fuzzing)))]
-                    Self::tlbi_by_vmid_ipa_range(level, map_addr, vmid);
-                    self.dirty = true;
-                }
-                _ => {}
-            }
-
-            Ok(())
-        } else {
-            Err(Error::RmiErrorInput)
+        let lock = self.root.lock()?.ok_or(Error::RmiErrorInput)?;
+        let (entries, lock, level) = match &self.root_pgtbl {
+            Root::L2N8(root) => root.table_entries_locked(guest, level, lock), // most likely first, for linux-realm
+            Root::LM1N1(root) => root.table_entries_locked(guest, level, lock),
+            Root::L0N1(root) => root.table_entries_locked(guest, level, lock),
+            Root::L0N16(root) => root.table_entries_locked(guest, level, lock),
+            Root::L1N1(root) => root.table_entries_locked(guest, level, lock),
+            Root::L1N2(root) => root.table_entries_locked(guest, level, lock),
+            Root::L1N8(root) => root.table_entries_locked(guest, level, lock),
+            Root::L2N4(root) => root.table_entries_locked(guest, level, lock),
+            Root::L2N16(root) => root.table_entries_locked(guest, level, lock),
         }
+        .or(Err(Error::RmiErrorInput))?;
+        Ok(LockedTable {
+            entries,
+            level,
+            dirty: &self.dirty,
+            lock,
+        })
     }
 
-    fn clean(&mut self, vmid: usize) {
-        if self.dirty {
+    fn clean(&self, vmid: usize) {
+        if self.dirty.swap(false, Ordering::Relaxed) {
             Self::tlbi_vmalle1is(vmid);
-            self.dirty = false;
         }
     }
 
-    fn space_size(&self, level: usize) -> usize {
+    fn space_size(&self, level: isize) -> usize {
         let count = if level == self.root_level {
             self.root_pages
         } else {
             1
         };
-        if level == 0 {
-            mapping_size(0) * L0Table::NUM_ENTRIES * count
-        } else {
-            mapping_size(level - 1) * count
-        }
-    }
-
-    fn entries(
-        &self,
-        guest: GuestPhysAddr,
-        level: usize,
-    ) -> Result<(Iter<'_, entry::Entry>, usize), Error> {
-        let guest = Page::<BasePageSize, GuestPhysAddr>::including_address(guest);
-        let res = match &self.root_pgtbl {
-            Root::L2N8(root) => root.table_entries(guest, level),
-            Root::L0N1(root) => root.table_entries(guest, level),
-            Root::L0N16(root) => root.table_entries(guest, level),
-            Root::L1N1(root) => root.table_entries(guest, level),
-            Root::L1N2(root) => root.table_entries(guest, level),
-            Root::L1N8(root) => root.table_entries(guest, level),
-            Root::L2N4(root) => root.table_entries(guest, level),
-            Root::L2N16(root) => root.table_entries(guest, level),
-        };
-        if let Ok(ref _x) = res {
-            Ok(res?)
-        } else {
-            Err(Error::RmiErrorInput)
+        match level {
+            // A level -1 table resolves IPA[51:48] only
+            -1 => mapping_size(-1) * LM1_NUM_ENTRIES,
+            0 => mapping_size(0) * L0Table::NUM_ENTRIES * count,
+            _ => mapping_size(level - 1) * count,
         }
     }
 }
@@ -461,3 +472,93 @@ impl fmt::Debug for Stage2Translation<'_> {
         f.debug_struct(stringify!(Self)).finish()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::granule::GRANULE_SIZE;
+    use crate::realm::mm::stage2_tte::ripas;
+    use crate::test_utils::*;
+
+    const IPA: usize = 0x4000_0000;
+
+    // A level 0 root table with an empty level 1 table linked at IPA
+    fn table_linked() -> (Stage2Translation<'static>, usize, u64) {
+        let (l0, l1) = (alloc_granule(IDX_RTT_LEVEL0), alloc_granule(IDX_RTT_LEVEL1));
+        unsafe { core::ptr::write_bytes(l1 as *mut u8, 0, GRANULE_SIZE) };
+        let s2 = Stage2Translation::new(l0, 0, 1).unwrap();
+
+        let mut desc = entry::Entry::new();
+        let _ = desc.point_to_subtable(0, PhysAddr::from(l1));
+        s2.walk(GuestPhysAddr::from(IPA), 0)
+            .unwrap()
+            .set_s2tte(IPA, desc.pte(), Tlbi::NONE);
+        (s2, l0, desc.pte())
+    }
+
+    // The raw value of the root entry holding IPA, the lock bit included
+    fn root_entry(l0: usize) -> u64 {
+        unsafe { core::ptr::read_volatile(l0 as *const u64) }
+    }
+
+    #[test]
+    fn unsupported_root_tables() {
+        // Neither level 1 with 4 concatenated tables nor level 3 as the root
+        assert!(Stage2Translation::new(0, 1, 4).is_err());
+        assert!(Stage2Translation::new(0, 3, 1).is_err());
+    }
+
+    #[test]
+    fn lock_and_unlock_table() {
+        let (s2, l0, desc) = table_linked();
+        let root = s2.walk(GuestPhysAddr::from(IPA), 0).unwrap();
+
+        let table = root.subtable(IPA).unwrap();
+        assert_eq!(table.level(), 1);
+        // The lock is held in the table descriptor, which reads without it
+        assert_eq!(root_entry(l0), desc | S2TTE::LOCK);
+        assert_eq!(root.s2tte(IPA).get(), desc);
+
+        core::mem::drop(table);
+        assert_eq!(root_entry(l0), desc);
+
+        // Only a table descriptor holds a lock
+        assert!(root.subtable(IPA + mapping_size(0)).is_err());
+    }
+
+    #[test]
+    fn walk_hand_over_hand() {
+        let (s2, l0, desc) = table_linked();
+
+        // The walk releases the lock of a table once the lock of its subtable is taken
+        let table = s2.walk(GuestPhysAddr::from(IPA), 3).unwrap();
+        assert_eq!(table.level(), 1);
+        assert_eq!(root_entry(l0), desc | S2TTE::LOCK);
+
+        // So the root is free while the level 1 table is still locked
+        let root = s2.walk(GuestPhysAddr::from(IPA), 0).unwrap();
+        assert_eq!(root.level(), 0);
+        core::mem::drop(root);
+
+        core::mem::drop(table);
+        assert_eq!(root_entry(l0), desc);
+    }
+
+    #[test]
+    fn unlink_table() {
+        let (s2, l0, _) = table_linked();
+        let root = s2.walk(GuestPhysAddr::from(IPA), 0).unwrap();
+
+        let unassigned_ram = bits_in_reg(S2TTE::RIPAS, ripas::RAM);
+        let table = root.subtable(IPA).unwrap();
+        table.unlink(IPA, unassigned_ram, Tlbi::NONE);
+        // The entry is replaced and unlocked
+        assert_eq!(root_entry(l0), unassigned_ram);
+        assert!(root.subtable(IPA).is_err());
+        core::mem::drop(root);
+
+        // The walk no longer reaches the unlinked table
+        let table = s2.walk(GuestPhysAddr::from(IPA), 3).unwrap();
+        assert_eq!(table.level(), 0);
+    }
+}
@@ -7,8 +7,9 @@ use vmsa::page_table::Entry;
 use crate::realm::mm::address::GuestPhysAddr;
 use crate::realm::mm::attribute::{desc_type, memattr, permission, shareable};
 use crate::realm::mm::entry;
-use crate::realm::mm::rtt::{RTT_MIN_BLOCK_LEVEL, RTT_PAGE_LEVEL};
-use crate::realm::rd::Rd;
+use crate::realm::mm::rtt::{RTT_MIN_BLOCK_LEVEL, RTT_PAGE_LEVEL, RTT_STRIDE};
+use crate::realm::mm::stage2_translation::LockedTable;
+use crate::realm::rd::Rtts;
 use crate::rmi::error::Error;
 
 pub const INVALID_UNPROTECTED: u64 = 0x0;
@@ -20,15 +21,27 @@ pub const VALID_TTE: u64 = bits_in_reg(S2TTE::MEMATTR, memattr::NORMAL_FWB)
 pub const VALID_NS_TTE: u64 =
     bits_in_reg(S2TTE::NS, 1) | bits_in_reg(S2TTE::XN, 1) | bits_in_reg(S2TTE::AF, 1);
 
+pub const VALID_DEV_TTE: u64 = bits_in_reg(S2TTE::MEMATTR, memattr::DEVICE_NGNRE)
+    | bits_in_reg(S2TTE::S2AP, permission::RW)
+    | bits_in_reg(S2TTE::XN, 1)
+    | bits_in_reg(S2TTE::AF, 1);
+
 pub const TABLE_TTE: u64 = bits_in_reg(S2TTE::DESC_TYPE, desc_type::L012_TABLE)
     | bits_in_reg(S2TTE::MEMATTR, memattr::NORMAL_FWB)
     | bits_in_reg(S2TTE::SH, shareable::INNER);
 
 pub const TTE_ATTR_MASK: u64 = S2TTE::MEMATTR | S2TTE::S2AP | S2TTE::SH | S2TTE::AF;
 
+// With FEAT_LPA2, OA[51:50] is held in bits[9:8] of a descriptor
+const OA_LOW_MASK: u64 = (1 << 50) - 1;
+const OA_HIGH_SHIFT: u64 = 50;
+// Without FEAT_LPA2, bits[49:48] of the output address are RES0
+const OA_MASK_48: u64 = (1 << 48) - 1;
+
 pub mod hipas {
-    pub const UNASSIGNED: u64 = 0b0;
-    pub const ASSIGNED: u64 = 0b1;
+    pub const UNASSIGNED: u64 = 0b00;
+    pub const ASSIGNED: u64 = 0b01;
+    pub const ASSIGNED_DEV: u64 = 0b10;
 }
 
 pub mod ripas {
@@ -38,17 +51,18 @@ pub mod ripas {
     pub const DEV: u64 = 0b11;
 }
 
-pub fn mapping_size(level: usize) -> usize {
+pub fn mapping_size(level: isize) -> usize {
     match level {
         3 => 1 << S2TTE::ADDR_BLK_L3.trailing_zeros(), // 4096
         2 => 1 << S2TTE::ADDR_BLK_L2.trailing_zeros(),
         1 => 1 << S2TTE::ADDR_BLK_L1.trailing_zeros(),
         0 => 1 << S2TTE::ADDR_BLK_L0.trailing_zeros(),
+        -1 => 1 << (S2TTE::ADDR_BLK_L0.trailing_zeros() + RTT_STRIDE as u32),
         _ => unreachable!(),
     }
 }
 
-pub fn level_mask(level: usize) -> Option<u64> {
+pub fn level_mask(level: isize) -> Option<u64> {
     match level {
         3 => Some(S2TTE::ADDR_BLK_L3),
         2 => Some(S2TTE::ADDR_BLK_L2),
@@ -58,25 +72,47 @@ pub fn level_mask(level: usize) -> Option<u64> {
     }
 }
 
-fn is_block_level(level: usize) -> bool {
+fn is_block_level(level: isize) -> bool {
     level >= RTT_MIN_BLOCK_LEVEL && level < RTT_PAGE_LEVEL
 }
 
+/// Returns the output address fields of a descriptor pointing to `pa`.
+pub fn oa_bits(pa: usize, lpa2: bool) -> u64 {
+    let pa = pa as u64;
+    if lpa2 {
+        (pa & OA_LOW_MASK) | bits_in_reg(S2TTE::OA_HIGH, pa >> OA_HIGH_SHIFT)
+    } else {
+        pa
+    }
+}
+
+/// Returns `attr` without the fields that don't exist in the descriptor format.
+/// With FEAT_LPA2, shareability comes from VTCR_EL2.SH0 instead of SH.
+pub fn attr_bits(attr: u64, lpa2: bool) -> u64 {
+    if lpa2 {
+        attr & !S2TTE::SH
+    } else {
+        attr
+    }
+}
+
 define_bits!(
     S2TTE,
-    HIPAS[58 - 58], // Host IPA State (HIPAS)
+    HIPAS[59 - 58], // Host IPA State (HIPAS)
     RIPAS[57 - 56], // Realm IPA State (RIPAS)
     NS[55 - 55], // DDI0615A: For a Block or Page descriptor fetched for stage 2 in the Realm Security state, bit 55 is the NS field. if set, it means output address is in NS PAS.
     XN[54 - 54],
     CONT[52 - 52],
+    LOCK[51 - 51], // software use; the lock of the table a table descriptor points to
     // https://armv8-ref.codingbelief.com/en/chapter_d4/d43_1_vmsav8-64_translation_table_descriptor_formats.html
-    ADDR_BLK_L0[47 - 39],      // block descriptor; level 0 w/o concatenation
-    ADDR_BLK_L1[47 - 30],      // block descriptor; level 1
-    ADDR_BLK_L2[47 - 21],      // block descriptor; level 2
-    ADDR_BLK_L3[47 - 12],      // page descriptor; level 3
-    ADDR_TBL_OR_PAGE[47 - 12], // table descriptor(level 0-2) || page descriptor(level3)
+    ADDR_BLK_L0[49 - 39],      // block descriptor; level 0 w/o concatenation
+    ADDR_BLK_L1[49 - 30],      // block descriptor; level 1
+    ADDR_BLK_L2[49 - 21],      // block descriptor; level 2
+    ADDR_BLK_L3[49 - 12],      // page descriptor; level 3
+    ADDR_TBL_OR_PAGE[49 - 12], // table descriptor(level 0-2) || page descriptor(level3)
     AF[10 - 10],
     SH[9 - 8],      // pte_shareable
+    OA_HIGH[9 - 8], // OA[51:50] with FEAT_LPA2, in place of SH
     S2AP[7 - 6],    // pte_access_perm
     MEMATTR[5 - 2], // pte_mem_attr
     DESC_TYPE[1 - 0],
@@ -86,25 +122,29 @@ define_bits!(
 
 impl S2TTE {
     pub fn get_s2tte(
-        rd: &Rd,
+        rd: &Rtts<'_>,
         ipa: usize,
-        level: usize,
+        level: isize,
         error_code: Error,
-    ) -> Result<(S2TTE, usize), Error> {
-        let (mut s2tte, last_level) = rd
+    ) -> Result<(S2TTE, isize), Error> {
+        let table = rd
             .s2_table()
-            .lock()
-            .ipa_to_pte(GuestPhysAddr::from(ipa), level)
-            .ok_or(error_code)?;
+            .walk(GuestPhysAddr::from(ipa), level)
+            .or(Err(error_code))?;
+        Ok((S2TTE::in_table(rd, &table, ipa), table.level()))
+    }
 
-        if ipa < rd.ipa_size() && !rd.addr_in_par(ipa) && s2tte == 0 {
-            s2tte |= S2TTE::NS;
+    /// Returns the entry holding `ipa` in the locked table,
+    /// with an unassigned entry of the unprotected space marked as NS.
+    pub fn in_table(rd: &Rtts<'_>, table: &LockedTable<'_>, ipa: usize) -> S2TTE {
+        let mut s2tte = table.s2tte(ipa);
+        if ipa < rd.ipa_size() && !rd.addr_in_par(ipa) && s2tte.get() == 0 {
+            s2tte.set_bits(S2TTE::NS);
         }
-
-        Ok((S2TTE::from(s2tte as usize), last_level))
+        s2tte
     }
 
-    pub fn is_host_ns_valid(&self, level: usize) -> bool {
+    pub fn is_host_ns_valid(&self, level: isize, lpa2: bool) -> bool {
         let tmp = S2TTE::new(!0);
         let addr_mask = match level {
             1 => tmp.get_masked(S2TTE::ADDR_BLK_L1),
@@ -112,10 +152,13 @@ impl S2TTE {
             3 => tmp.get_masked(S2TTE::ADDR_BLK_L3),
             _ => return false,
         };
-        let mask = addr_mask
-            | tmp.get_masked(S2TTE::MEMATTR)
-            | tmp.get_masked(S2TTE::S2AP)
-            | tmp.get_masked(S2TTE::SH);
+        // bits[9:8] are either OA[51:50] or SH
+        let mask = if lpa2 {
+            addr_mask | tmp.get_masked(S2TTE::OA_HIGH)
+        } else {
+            (addr_mask & OA_MASK_48) | tmp.get_masked(S2TTE::SH)
+        } | tmp.get_masked(S2TTE::MEMATTR)
+            | tmp.get_masked(S2TTE::S2AP);
 
         if (self.get() & !mask) != 0 {
             return false;
@@ -125,7 +168,7 @@ impl S2TTE {
             return false;
         }
 
-        if self.get_masked_value(S2TTE::SH) == shareable::RESERVED {
+        if !lpa2 && self.get_masked_value(S2TTE::SH) == shareable::RESERVED {
             return false;
         }
 
@@ -181,7 +224,7 @@ impl S2TTE {
         self.is_assigned_invalid() && self.get_masked_value(S2TTE::RIPAS) == ripas::DESTROYED
     }
 
-    pub fn is_assigned_ram(&self, level: usize) -> bool {
+    pub fn is_assigned_ram(&self, level: isize) -> bool {
         if self.get_masked_value(S2TTE::NS) != 0
             || self.get_masked_value(S2TTE::RIPAS) != ripas::RAM
         {
@@ -196,7 +239,7 @@ impl S2TTE {
         false
     }
 
-    pub fn is_assigned_ns(&self, level: usize) -> bool {
+    pub fn is_assigned_ns(&self, level: isize) -> bool {
         if self.get_masked_value(S2TTE::NS) == 0
             || self.get_masked_value(S2TTE::HIPAS) != hipas::ASSIGNED
         {
@@ -211,13 +254,26 @@ impl S2TTE {
         false
     }
 
-    pub fn has_ripas(&self, level: usize) -> bool {
+    // Device memory assigned to the realm through a VDEV
+    pub fn is_assigned_dev(&self) -> bool {
+        self.get_masked_value(S2TTE::NS) == 0
+            && self.get_masked_value(S2TTE::HIPAS) == hipas::ASSIGNED_DEV
+    }
+
+    // Device memory mapping which is not yet validated by the realm
+    pub fn is_assigned_dev_empty(&self) -> bool {
+        self.is_assigned_dev()
+            && self.get_masked_value(S2TTE::DESC_TYPE) == desc_type::LX_INVALID
+            && self.get_masked_value(S2TTE::RIPAS) == ripas::EMPTY
+    }
+
+    pub fn has_ripas(&self, level: isize) -> bool {
         self.get_masked_value(S2TTE::NS) == 0 && !self.is_table(level)
     }
 
     // level should be the value returned in page table walking
     // (== the last level that has been reached)
-    pub fn is_table(&self, level: usize) -> bool {
+    pub fn is_table(&self, level: isize) -> bool {
         (level < RTT_PAGE_LEVEL)
             && self.get_masked_value(S2TTE::DESC_TYPE) == desc_type::L012_TABLE
             && self.get_masked_value(S2TTE::NS | S2TTE::HIPAS | S2TTE::RIPAS) == 0x0
@@ -228,12 +284,17 @@ impl S2TTE {
             && (self.get_ripas() != ripas::RAM)
     }
 
-    pub fn addr_as_block(&self, level: usize) -> PhysAddr {
-        match level {
-            1 => PhysAddr::from(self.get_masked(S2TTE::ADDR_BLK_L1)),
-            2 => PhysAddr::from(self.get_masked(S2TTE::ADDR_BLK_L2)),
-            3 => PhysAddr::from(self.get_masked(S2TTE::ADDR_BLK_L3)),
+    pub fn addr_as_block(&self, level: isize, lpa2: bool) -> PhysAddr {
+        let addr = match level {
+            1 => self.get_masked(S2TTE::ADDR_BLK_L1),
+            2 => self.get_masked(S2TTE::ADDR_BLK_L2),
+            3 => self.get_masked(S2TTE::ADDR_BLK_L3),
             _ => unreachable!(),
+        };
+        if lpa2 {
+            PhysAddr::from(addr | (self.get_masked_value(S2TTE::OA_HIGH) << OA_HIGH_SHIFT))
+        } else {
+            PhysAddr::from(addr & OA_MASK_48)
         }
     }
 
@@ -241,14 +302,14 @@ impl S2TTE {
         self.get_masked_value(S2TTE::RIPAS)
     }
 
-    pub fn is_live(&self, _level: usize) -> bool {
-        // live tte: ASSIGNED, ASSIGNED_NS, TABLE
+    pub fn is_live(&self, _level: isize) -> bool {
+        // live tte: ASSIGNED, ASSIGNED_DEV, ASSIGNED_NS, TABLE
         self.get_masked_value(S2TTE::DESC_TYPE) != desc_type::LX_INVALID
-            || self.get_masked_value(S2TTE::HIPAS) == hipas::ASSIGNED
+            || self.get_masked_value(S2TTE::HIPAS) != hipas::UNASSIGNED
     }
 
     // TODO: remvoe mut
-    pub fn is_homogeneous(entries: &mut Iter<'_, entry::Entry>, level: usize) -> bool {
+    pub fn is_homogeneous(entries: &mut Iter<'_, entry::Entry>, level: isize, lpa2: bool) -> bool {
         let mut hipas = 0;
         let mut ripas = 0;
         let mut desc_type = 0;
@@ -259,6 +320,10 @@ impl S2TTE {
         let map_size = mapping_size(level) as u64;
         for entry in entries {
             let s2tte = S2TTE::new(entry.pte());
+            // device memory mappings are never folded
+            if s2tte.is_assigned_dev() {
+                return false;
+            }
             if first {
                 desc_type = s2tte.get_masked_value(S2TTE::DESC_TYPE);
                 hipas = s2tte.get_masked_value(S2TTE::HIPAS);
@@ -270,10 +335,10 @@ impl S2TTE {
                     if level != 2 && level != 3 {
                         return false;
                     }
-                    pa = s2tte.addr_as_block(level).into(); //XXX: check this again
+                    pa = s2tte.addr_as_block(level, lpa2).into(); //XXX: check this again
                     attr = entry.pte() & !level_mask(level).unwrap_or(0);
                     // output of first entry is algned to parent's mapping size
-                    if (pa & !level_mask(level - 1).unwrap_or(0)) != 0 {
+                    if pa & (mapping_size(level - 1) as u64 - 1) != 0 {
                         return false;
                     }
                 }
@@ -285,7 +350,7 @@ impl S2TTE {
             } else if s2tte.is_assigned_ns(level) {
                 // addr is contiguous
                 pa += map_size;
-                if pa != s2tte.addr_as_block(level).into() {
+                if pa != s2tte.addr_as_block(level, lpa2).into() {
                     return false;
                 }
                 // attributes are identical
@@ -295,7 +360,7 @@ impl S2TTE {
             } else if s2tte.is_assigned() {
                 // addr is contiguous
                 pa += map_size;
-                if pa != s2tte.addr_as_block(level).into() {
+                if pa != s2tte.addr_as_block(level, lpa2).into() {
                     return false;
                 }
                 // ripas is identical
@@ -6,14 +6,19 @@ use vmsa::page_table::{HasSubtable, Level, PageTable};
 use crate::config::PAGE_SIZE;
 
 /// Root Table
-pub enum RootTable<const L: usize, const N: usize> {}
-impl<const L: usize, const N: usize> Level for RootTable<L, N> {
-    const THIS_LEVEL: usize = L;
+pub enum RootTable<const L: isize, const N: usize> {}
+impl<const L: isize, const N: usize> Level for RootTable<L, N> {
+    const THIS_LEVEL: isize = L;
     const TABLE_SIZE: usize = (PAGE_SIZE * N);
     const TABLE_ALIGN: usize = PAGE_SIZE;
     const NUM_ENTRIES: usize = (Self::TABLE_SIZE / core::mem::size_of::<Entry>());
 }
 
+// Level -1 only exists as the root with FEAT_LPA2
+impl<const N: usize> HasSubtable for RootTable<-1, N> {
+    type NextLevel = L0Table;
+}
+
 impl<const N: usize> HasSubtable for RootTable<0, N> {
     type NextLevel = L1Table;
 }
@@ -29,7 +34,7 @@ impl<const N: usize> HasSubtable for RootTable<2, N> {
 /// The Level 0 Table
 pub enum L0Table {}
 impl Level for L0Table {
-    const THIS_LEVEL: usize = 0;
+    const THIS_LEVEL: isize = 0;
     const TABLE_SIZE: usize = PAGE_SIZE;
     const TABLE_ALIGN: usize = PAGE_SIZE;
     const NUM_ENTRIES: usize = (Self::TABLE_SIZE / core::mem::size_of::<Entry>());
@@ -41,7 +46,7 @@ impl HasSubtable for L0Table {
 /// The Level 1 Table
 pub enum L1Table {}
 impl Level for L1Table {
-    const THIS_LEVEL: usize = 1;
+    const THIS_LEVEL: isize = 1;
     const TABLE_SIZE: usize = PAGE_SIZE;
     const TABLE_ALIGN: usize = PAGE_SIZE;
     const NUM_ENTRIES: usize = (Self::TABLE_SIZE / core::mem::size_of::<Entry>());
@@ -53,7 +58,7 @@ impl HasSubtable for L1Table {
 /// The Level 2 Table
 pub enum L2Table {}
 impl Level for L2Table {
-    const THIS_LEVEL: usize = 2;
+    const THIS_LEVEL: isize = 2;
     const TABLE_SIZE: usize = PAGE_SIZE;
     const TABLE_ALIGN: usize = PAGE_SIZE;
     const NUM_ENTRIES: usize = (Self::TABLE_SIZE / core::mem::size_of::<Entry>());
@@ -65,7 +70,7 @@ impl HasSubtable for L2Table {
 /// The Level 3 Table (Doesn't have Subtable!)
 pub enum L3Table {}
 impl Level for L3Table {
-    const THIS_LEVEL: usize = 3;
+    const THIS_LEVEL: isize = 3;
     const TABLE_SIZE: usize = PAGE_SIZE;
     const TABLE_ALIGN: usize = PAGE_SIZE;
     const NUM_ENTRIES: usize = (Self::TABLE_SIZE / core::mem::size_of::<Entry>());
@@ -1,25 +1,18 @@
 use vmsa::guard::Content;
 
+use crate::dev::vdev::MAX_VDEVS;
+use crate::granule::{GranuleState, GRANULE_SIZE};
 use crate::measurement::{Measurement, MEASUREMENTS_SLOT_NR};
+use crate::mec::MECID_SHARED;
+use crate::realm::mm::stage2_translation::Stage2Translation;
 use crate::realm::mm::IPATranslation;
+use crate::rmi::error::Error;
+use crate::rmi::MAX_AUX_PLANES;
 use crate::simd::SimdConfig;
-use alloc::boxed::Box;
-use alloc::collections::btree_map::BTreeMap;
-use alloc::sync::Arc;
-use spin::mutex::Mutex;
-
-pub const RPV_SIZE: usize = 64;
-
-lazy_static! {
-    static ref RTT_TABLES: Mutex<BTreeMap<usize, Arc<Mutex<Box<dyn IPATranslation>>>>> = {
-        let m = BTreeMap::new();
-        Mutex::new(m)
-    };
-}
+use crate::{get_granule, get_granule_if};
+use core::mem::MaybeUninit;
 
-pub fn insert_rtt(id: usize, table: Arc<Mutex<Box<dyn IPATranslation>>>) {
-    RTT_TABLES.lock().insert(id, table);
-}
+pub use crate::rmi::realm::params::RPV_SIZE;
 
 #[derive(Debug)]
 pub struct Rd {
@@ -30,6 +23,9 @@ pub struct Rd {
     ipa_bits: usize,
     rec_index: usize,
     s2_starting_level: isize,
+    lpa2: bool,
+    mecid: usize,
+    mte_en: bool,
     hash_algo: u8,
     rpv: [u8; RPV_SIZE],
     num_recs: usize,
@@ -39,8 +35,25 @@ pub struct Rd {
     simd_cfg: SimdConfig,
     pmu_en: bool,
     pmu_num_ctrs: usize,
+    num_bps: usize,
+    num_wps: usize,
+    num_aux_planes: usize,
+    aux_vmid: [u16; MAX_AUX_PLANES],
+    aux_rtt_base: [usize; MAX_AUX_PLANES],
+    da_en: bool,
+    // VDEVs assigned to the realm (addresses and their vdev_id)
+    vdevs: [usize; MAX_VDEVS],
+    vdev_ids: [u64; MAX_VDEVS],
+    // Stage 2 translations of the planes, set up by init() and set_aux_planes()
+    // and ended by destroy_s2_tables()
+    s2_tables: [MaybeUninit<Stage2Translation<'static>>; 1 + MAX_AUX_PLANES],
 }
 
+// Rd is placed in the RD granule, which the fields above should fit in
+const _: () = {
+    crate::const_assert!(core::mem::size_of::<Rd>() <= GRANULE_SIZE);
+};
+
 impl Rd {
     #[allow(clippy::too_many_arguments)]
     pub fn init(
@@ -55,7 +68,8 @@ impl Rd {
         sve_vl: u64,
         pmu_en: bool,
         pmu_num_ctrs: usize,
-    ) {
+    ) -> Result<(), Error> {
+        let s2_table = Stage2Translation::new(rtt_base, s2_starting_level, rtt_num_start)?;
         self.vmid = vmid;
         self.state = State::New;
         self.rtt_base = rtt_base;
@@ -63,11 +77,16 @@ impl Rd {
         self.ipa_bits = ipa_bits;
         self.rec_index = 0;
         self.s2_starting_level = s2_starting_level;
+        self.lpa2 = false;
+        self.mecid = MECID_SHARED;
+        self.mte_en = false;
         self.measurements = [Measurement::empty(); MEASUREMENTS_SLOT_NR];
         self.vcpu_index = 0;
         self.rpv.copy_from_slice(rpv.as_slice());
         self.metadata = None;
         self.simd_cfg.sve_en = sve_en;
+        self.simd_cfg.sme_en = false;
+        self.simd_cfg.sme_svq = 0;
         self.pmu_en = pmu_en;
         if sve_en {
             self.simd_cfg.sve_vq = sve_vl;
@@ -76,6 +95,46 @@ impl Rd {
             self.pmu_num_ctrs = pmu_num_ctrs;
         }
         self.num_recs = 0;
+        self.num_bps = 0;
+        self.num_wps = 0;
+        self.num_aux_planes = 0;
+        self.aux_vmid = [0; MAX_AUX_PLANES];
+        self.aux_rtt_base = [0; MAX_AUX_PLANES];
+        self.da_en = false;
+        self.vdevs = [0; MAX_VDEVS];
+        self.vdev_ids = [0; MAX_VDEVS];
+        self.s2_tables[0].write(s2_table);
+        Ok(())
+    }
+
+    /// Sets the auxiliary planes along with their stage 2 translations.
+    /// Must be called after init().
+    pub fn set_aux_planes(
+        &mut self,
+        aux_vmid: &[u16],
+        aux_rtt_base: &[usize],
+    ) -> Result<(), Error> {
+        let num_aux_planes = core::cmp::min(aux_vmid.len(), MAX_AUX_PLANES);
+        for (i, rtt_base) in aux_rtt_base[..num_aux_planes].iter().enumerate() {
+            self.s2_tables[i + 1].write(Stage2Translation::new(
+                *rtt_base,
+                self.s2_starting_level,
+                self.rtt_num_start,
+            )?);
+        }
+        self.num_aux_planes = num_aux_planes;
+        self.aux_vmid[..num_aux_planes].copy_from_slice(&aux_vmid[..num_aux_planes]);
+        self.aux_rtt_base[..num_aux_planes].copy_from_slice(&aux_rtt_base[..num_aux_planes]);
+        Ok(())
+    }
+
+    /// Ends the stage 2 translations of the planes on REALM_DESTROY.
+    /// The caller returns their RTTs to the host.
+    pub fn destroy_s2_tables(&mut self) {
+        for table in self.s2_tables[..=self.num_aux_planes].iter_mut() {
+            // Safety: set up on REALM_CREATE, and not used from here on
+            unsafe { table.assume_init_drop() };
+        }
     }
 
     pub fn id(&self) -> usize {
@@ -86,8 +145,80 @@ impl Rd {
         self.num_recs
     }
 
-    pub fn s2_table(&self) -> Arc<Mutex<Box<dyn IPATranslation>>> {
-        Arc::clone(RTT_TABLES.lock().get_mut(&self.id()).unwrap())
+    /// Returns the RTTs of the realm along with the fields the RTT operations read.
+    pub fn rtts(&self) -> Rtts<'_> {
+        Rtts {
+            vmid: self.vmid,
+            ipa_bits: self.ipa_bits,
+            s2_starting_level: self.s2_starting_level,
+            lpa2: self.lpa2,
+            num_aux_planes: self.num_aux_planes,
+            aux_vmid: self.aux_vmid,
+            s2_tables: &self.s2_tables,
+        }
+    }
+
+    pub fn s2_table(&self) -> &dyn IPATranslation {
+        self.rtts().s2_table()
+    }
+
+    /// Returns the stage 2 table of the given plane.
+    /// Plane 0 is the primary plane, auxiliary planes start from 1.
+    pub fn plane_s2_table(&self, plane: usize) -> Result<&dyn IPATranslation, Error> {
+        self.rtts().plane_s2_table(plane)
+    }
+
+    pub fn plane_vmid(&self, plane: usize) -> Result<usize, Error> {
+        self.rtts().plane_vmid(plane)
+    }
+
+    pub fn num_aux_planes(&self) -> usize {
+        self.num_aux_planes
+    }
+
+    pub fn aux_rtt_base(&self, plane: usize) -> Result<usize, Error> {
+        match plane {
+            p if (1..=self.num_aux_planes).contains(&p) => Ok(self.aux_rtt_base[p - 1]),
+            _ => Err(Error::RmiErrorInput),
+        }
+    }
+
+    pub fn da_en(&self) -> bool {
+        self.da_en
+    }
+
+    pub fn set_da_en(&mut self, da_en: bool) {
+        self.da_en = da_en;
+    }
+
+    /// Returns the address of the VDEV whose vdev_id is `vdev_id`.
+    pub fn find_vdev(&self, vdev_id: u64) -> Option<usize> {
+        self.vdevs
+            .iter()
+            .zip(self.vdev_ids.iter())
+            .find(|(addr, id)| **addr != 0 && **id == vdev_id)
+            .map(|(addr, _)| *addr)
+    }
+
+    pub fn add_vdev(&mut self, vdev: usize, vdev_id: u64) -> Result<(), Error> {
+        if self.find_vdev(vdev_id).is_some() {
+            return Err(Error::RmiErrorInput);
+        }
+        let idx = self
+            .vdevs
+            .iter()
+            .position(|addr| *addr == 0)
+            .ok_or(Error::RmiErrorRealm(0))?;
+        self.vdevs[idx] = vdev;
+        self.vdev_ids[idx] = vdev_id;
+        Ok(())
+    }
+
+    pub fn remove_vdev(&mut self, vdev: usize) {
+        if let Some(idx) = self.vdevs.iter().position(|addr| *addr == vdev) {
+            self.vdevs[idx] = 0;
+            self.vdev_ids[idx] = 0;
+        }
     }
 
     pub fn state(&self) -> State {
@@ -126,6 +257,43 @@ impl Rd {
         self.s2_starting_level
     }
 
+    // Stage 2 descriptors of the realm use the FEAT_LPA2 format
+    pub fn lpa2(&self) -> bool {
+        self.lpa2
+    }
+
+    pub fn set_lpa2(&mut self, lpa2: bool) {
+        self.lpa2 = lpa2;
+    }
+
+    // The number of breakpoints and watchpoints of the realm, minus one
+    pub fn debug_config(&self) -> (usize, usize) {
+        (self.num_bps, self.num_wps)
+    }
+
+    pub fn set_debug_config(&mut self, num_bps: usize, num_wps: usize) {
+        self.num_bps = num_bps;
+        self.num_wps = num_wps;
+    }
+
+    // Memory encryption context used by the stage 2 translations of the realm
+    pub fn mecid(&self) -> usize {
+        self.mecid
+    }
+
+    pub fn set_mecid(&mut self, mecid: usize) {
+        self.mecid = mecid;
+    }
+
+    // Allocation tag access of the realm (FEAT_MTE2)
+    pub fn mte_en(&self) -> bool {
+        self.mte_en
+    }
+
+    pub fn set_mte_en(&mut self, mte_en: bool) {
+        self.mte_en = mte_en;
+    }
+
     pub fn inc_recs(&mut self) {
         self.num_recs += 1;
         self.rec_index += 1;
@@ -170,6 +338,14 @@ impl Rd {
     pub fn simd_config(&self) -> &SimdConfig {
         &self.simd_cfg
     }
+
+    // SME is enabled on top of SVE, which is configured by init()
+    pub fn set_sme_config(&mut self, sme_en: bool, sme_svq: u64) {
+        self.simd_cfg.sme_en = sme_en;
+        if sme_en {
+            self.simd_cfg.sme_svq = sme_svq;
+        }
+    }
 }
 
 impl Content for Rd {}
@@ -192,7 +368,8 @@ impl safe_abstraction::raw_ptr::SafetyAssured for Rd {
         //
         // 1. A lock on the given address is obtained using the `get_granule*` macros.
         // 2. The instance is converted from a raw pointer through the `content*` functions.
-        // 3. The instance is accessed only within the lock scope.
+        // 3. The instance is accessed only within the lock scope,
+        //    except the stage 2 tables which `RdRef` keeps referencing (see Rtts).
         //
         // Ownership verification is guaranteed because these criteria are satisfied
         // in all cases where this object is accessed.
@@ -200,6 +377,124 @@ impl safe_abstraction::raw_ptr::SafetyAssured for Rd {
     }
 }
 
+/// The RTTs of a realm, along with the fields of its Rd which the RTT operations read.
+/// The fields don't change after REALM_CREATE, so a copy taken under the RD granule
+/// lock stays valid without the lock. The stage 2 tables are referenced instead,
+/// as the RTT locks are in them.
+#[derive(Clone, Copy)]
+pub struct Rtts<'a> {
+    vmid: u16,
+    ipa_bits: usize,
+    s2_starting_level: isize,
+    lpa2: bool,
+    num_aux_planes: usize,
+    aux_vmid: [u16; MAX_AUX_PLANES],
+    s2_tables: &'a [MaybeUninit<Stage2Translation<'static>>; 1 + MAX_AUX_PLANES],
+}
+
+impl<'a> Rtts<'a> {
+    pub fn id(&self) -> usize {
+        self.vmid as usize
+    }
+
+    pub fn s2_table(&self) -> &'a dyn IPATranslation {
+        // Safety: set up by init() on REALM_CREATE, before the RD is reachable
+        unsafe { self.s2_tables[0].assume_init_ref() }
+    }
+
+    pub fn plane_s2_table(&self, plane: usize) -> Result<&'a dyn IPATranslation, Error> {
+        if plane > self.num_aux_planes {
+            return Err(Error::RmiErrorInput);
+        }
+        // Safety: set up by init() and set_aux_planes() on REALM_CREATE
+        Ok(unsafe { self.s2_tables[plane].assume_init_ref() })
+    }
+
+    pub fn plane_vmid(&self, plane: usize) -> Result<usize, Error> {
+        match plane {
+            0 => Ok(self.id()),
+            p if p <= self.num_aux_planes => Ok(self.aux_vmid[p - 1] as usize),
+            _ => Err(Error::RmiErrorInput),
+        }
+    }
+
+    pub fn num_aux_planes(&self) -> usize {
+        self.num_aux_planes
+    }
+
+    pub fn s2_starting_level(&self) -> isize {
+        self.s2_starting_level
+    }
+
+    pub fn lpa2(&self) -> bool {
+        self.lpa2
+    }
+
+    pub fn ipa_size(&self) -> usize {
+        1 << self.ipa_bits
+    }
+
+    pub fn par_size(&self) -> usize {
+        self.ipa_size() / 2
+    }
+
+    pub fn addr_in_par(&self, ipa: usize) -> bool {
+        ipa < self.par_size()
+    }
+}
+
+/// A reference to the RD of a realm, which lets the RTT operations go on without
+/// the RD granule lock, so that the operations on the RTTs of a realm run concurrently
+/// under the locks of the tables they reach.
+/// The RD counts it as a child, so REALM_DESTROY fails until it's dropped.
+pub struct RdRef {
+    #[cfg(not(feature = "gst_page_table"))]
+    addr: usize,
+    rtts: Rtts<'static>,
+    mecid: usize,
+    #[cfg(feature = "gst_page_table")]
+    _granule: crate::granule::entry::Inner,
+}
+
+impl RdRef {
+    pub fn new(addr: usize) -> Result<Self, Error> {
+        #[allow(unused_mut)]
+        let mut rd_granule = get_granule_if!(addr, GranuleState::RD)?;
+        let rd = rd_granule.content::<Rd>()?;
+        // Safety: the RD stays until the reference is dropped, and only the stage 2
+        //         tables in it are referenced after the lock is released.
+        let rd: &'static Rd = unsafe { &*(&*rd as *const Rd) };
+
+        #[cfg(not(feature = "gst_page_table"))]
+        rd_granule.inc_count();
+        Ok(Self {
+            #[cfg(not(feature = "gst_page_table"))]
+            addr,
+            rtts: rd.rtts(),
+            mecid: rd.mecid(),
+            #[cfg(feature = "gst_page_table")]
+            _granule: rd_granule.clone(),
+        })
+    }
+
+    pub fn rtts(&self) -> Rtts<'_> {
+        self.rtts
+    }
+
+    pub fn mecid(&self) -> usize {
+        self.mecid
+    }
+}
+
+impl Drop for RdRef {
+    fn drop(&mut self) {
+        #[cfg(not(feature = "gst_page_table"))]
+        if let Ok(mut rd_granule) = get_granule!(self.addr) {
+            rd_granule.dec_count();
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum State {
     Null,
@@ -1,4 +1,6 @@
+use crate::mec::{max_mecid, MECID_SHARED};
 use crate::rmi::error::Error;
+use crate::rmm_el3;
 use alloc::collections::BTreeSet;
 use spinning_top::Spinlock;
 
@@ -11,3 +13,68 @@ pub fn remove(id: usize) -> Result<(), Error> {
         .then_some(())
         .ok_or(Error::RmiErrorInput)
 }
+
+pub struct MecidSet {
+    in_use: BTreeSet<usize>,
+    // MECIDs released by destroyed realms, still holding their old keys
+    to_scrub: BTreeSet<usize>,
+}
+
+pub static MECID_SET: Spinlock<MecidSet> = Spinlock::new(MecidSet {
+    in_use: BTreeSet::new(),
+    to_scrub: BTreeSet::new(),
+});
+
+pub fn alloc_mecid() -> Result<usize, Error> {
+    let mut mecid_set = MECID_SET.lock();
+    let mecid = (MECID_SHARED + 1..=max_mecid())
+        .find(|id| !mecid_set.in_use.contains(id))
+        .ok_or(Error::RmiErrorInput)?;
+
+    // A released MECID can only be reused after its key gets refreshed
+    if mecid_set.to_scrub.contains(&mecid) {
+        if !rmm_el3::refresh_mec_key(mecid) {
+            return Err(Error::RmiErrorInput);
+        }
+        mecid_set.to_scrub.remove(&mecid);
+    }
+    mecid_set.in_use.insert(mecid);
+
+    Ok(mecid)
+}
+
+pub fn release_mecid(mecid: usize) -> Result<(), Error> {
+    if mecid == MECID_SHARED {
+        return Ok(());
+    }
+
+    let mut mecid_set = MECID_SET.lock();
+    if !mecid_set.in_use.remove(&mecid) {
+        return Err(Error::RmiErrorInput);
+    }
+    mecid_set.to_scrub.insert(mecid);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mecid_reuse_after_scrub() {
+        let mecid = alloc_mecid().unwrap();
+        assert_ne!(mecid, MECID_SHARED);
+        assert!(MECID_SET.lock().in_use.contains(&mecid));
+
+        release_mecid(mecid).unwrap();
+        assert!(MECID_SET.lock().to_scrub.contains(&mecid));
+        assert!(release_mecid(mecid).is_err());
+
+        let reused = alloc_mecid().unwrap();
+        assert_eq!(reused, mecid);
+        assert!(!MECID_SET.lock().to_scrub.contains(&mecid));
+
+        release_mecid(reused).unwrap();
+    }
+}
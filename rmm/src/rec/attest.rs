@@ -0,0 +1,113 @@
+use super::{Rec, RecAuxIndex};
+use crate::granule::{GranuleState, GRANULE_SIZE};
+use crate::host::DataPage;
+use crate::rmi::error::Error;
+use crate::{get_granule, get_granule_if};
+
+/// The number of auxiliary granules, from `RecAuxIndex::ATTEST`,
+/// where the attestation token is kept while it is being streamed.
+pub const ATTEST_TOKEN_AUX_NUM: usize = 4;
+pub const ATTEST_TOKEN_MAX_SIZE: usize = ATTEST_TOKEN_AUX_NUM * GRANULE_SIZE;
+
+const _: () = assert!(
+    RecAuxIndex::ATTEST as usize + ATTEST_TOKEN_AUX_NUM <= crate::rmi::MAX_REC_AUX_GRANULES
+);
+
+fn token_aux(rec: &Rec<'_>, index: usize) -> usize {
+    rec.aux(RecAuxIndex::ATTEST as usize + index) as usize
+}
+
+/// Keeps the token signed at ATTEST_TOKEN_INIT, replacing the previous one.
+pub fn store_token(rec: &mut Rec<'_>, token: &[u8]) -> Result<(), Error> {
+    wipe_token(rec)?;
+    if token.len() > ATTEST_TOKEN_MAX_SIZE {
+        error!("Attestation token is too big: {}", token.len());
+        return Err(Error::RmiErrorRec);
+    }
+
+    for (i, chunk) in token.chunks(GRANULE_SIZE).enumerate() {
+        let mut aux_granule = get_granule_if!(token_aux(rec, i), GranuleState::RecAux)?;
+        let mut page = aux_granule.content_mut::<DataPage>()?;
+        page.as_mut_slice()[..chunk.len()].copy_from_slice(chunk);
+    }
+    rec.set_attest_token_len(token.len());
+    rec.set_attest_offset(0);
+    Ok(())
+}
+
+/// Copies the token from the current offset into `buf`, advancing the offset.
+/// Returns the number of bytes copied and the ones left.
+pub fn read_token(rec: &mut Rec<'_>, buf: &mut [u8]) -> Result<(usize, usize), Error> {
+    let (offset, len) = (rec.attest_token_offset(), rec.attest_token_len());
+    let end = core::cmp::min(len, offset + buf.len());
+
+    let mut pos = offset;
+    while pos < end {
+        let (index, start) = (pos / GRANULE_SIZE, pos % GRANULE_SIZE);
+        let size = core::cmp::min(GRANULE_SIZE - start, end - pos);
+
+        let aux_granule = get_granule_if!(token_aux(rec, index), GranuleState::RecAux)?;
+        let page = aux_granule.content::<DataPage>()?;
+        buf[pos - offset..pos - offset + size]
+            .copy_from_slice(&page.as_slice()[start..start + size]);
+        pos += size;
+    }
+
+    rec.set_attest_offset(end);
+    Ok((end - offset, len - end))
+}
+
+/// Zeroes the token so that it doesn't outlive the attestation.
+pub fn wipe_token(rec: &mut Rec<'_>) -> Result<(), Error> {
+    let len = rec.attest_token_len();
+    for i in 0..len.div_ceil(GRANULE_SIZE) {
+        let mut aux_granule = get_granule_if!(token_aux(rec, i), GranuleState::RecAux)?;
+        let mut page = aux_granule.content_mut::<DataPage>()?;
+        page.as_mut_slice().fill(0);
+    }
+    rec.set_attest_token_len(0);
+    rec.set_attest_offset(0);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::*;
+
+    use alloc::vec;
+
+    #[test]
+    fn rec_attest_token_stream() {
+        let rd = mock::host::realm_setup();
+        let rec = unsafe { &mut *(granule_addr(IDX_REC1) as *mut Rec<'_>) };
+
+        let token: alloc::vec::Vec<u8> = (0..GRANULE_SIZE + 100).map(|i| i as u8).collect();
+        assert!(store_token(rec, &token).is_ok());
+
+        // Parts of the token cross the boundary of the auxiliary granules
+        let mut streamed = vec![];
+        let mut buf = [0u8; 1000];
+        loop {
+            let (copied, left) = read_token(rec, &mut buf).unwrap();
+            streamed.extend_from_slice(&buf[..copied]);
+            if left == 0 {
+                break;
+            }
+        }
+        assert_eq!(streamed, token);
+
+        assert!(wipe_token(rec).is_ok());
+        let aux = rec.aux(RecAuxIndex::ATTEST as usize) as usize;
+        let page = unsafe { &*(aux as *const DataPage) };
+        assert!(page.as_slice().iter().all(|b| *b == 0));
+        assert_eq!(read_token(rec, &mut buf).unwrap(), (0, 0));
+
+        let too_big = vec![0u8; ATTEST_TOKEN_MAX_SIZE + 1];
+        assert!(store_token(rec, &too_big).is_err());
+
+        mock::host::realm_teardown(rd);
+
+        miri_teardown();
+    }
+}
@@ -1,4 +1,6 @@
+use super::debug::DebugRegister;
 use super::gic;
+use super::mte::MteRegister;
 use super::pauth;
 use super::pauth::PauthRegister;
 use super::pmu;
@@ -24,6 +26,8 @@ pub struct Context {
     pub simd: SimdContext,
     pub pauth: PauthRegister,
     pub pmu: PmuRegister,
+    pub debug: DebugRegister,
+    pub mte: MteRegister,
 }
 
 pub struct RegOffset;
@@ -92,6 +96,12 @@ impl Context {
         gic::restore_state(rec);
         pauth::restore_state(rec);
         pmu::restore_state(rec);
+        #[cfg(not(any(test, miri, fuzzing)))]
+        super::debug::restore_state(rec);
+        #[cfg(not(any(test, miri, fuzzing)))]
+        super::mec::restore_state(rec);
+        #[cfg(not(any(test, miri, fuzzing)))]
+        super::mte::restore_state(rec);
         #[cfg(not(fuzzing))]
         timer::restore_state(rec);
         #[cfg(not(any(test, miri, fuzzing)))]
@@ -108,6 +118,10 @@ impl Context {
         gic::save_state(rec);
         pauth::save_state(rec);
         pmu::save_state(rec);
+        #[cfg(not(any(test, miri, fuzzing)))]
+        super::debug::save_state(rec);
+        #[cfg(not(any(test, miri, fuzzing)))]
+        super::mte::save_state(rec);
         #[cfg(not(fuzzing))]
         timer::save_state(rec);
         #[cfg(not(any(test, miri, fuzzing)))]
@@ -134,7 +148,7 @@ pub struct GICRegister {
 }
 
 #[repr(C)]
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, Copy)]
 // System registers without explicit exception level are for EL1
 // unless it is unique to EL2 (e.g., vmpidr_el2, hpfar_el2)
 pub struct SystemRegister {
@@ -0,0 +1,84 @@
+use super::Rec;
+use crate::debug::*;
+
+use core::array::from_fn;
+use lazy_static::lazy_static;
+use spin::mutex::Mutex;
+
+use aarch64_cpu::registers::*;
+use armv9a::regs::debug::*;
+use armv9a::MDCR_EL2;
+
+use crate::config::NUM_OF_CPU;
+use crate::cpu::get_cpu_id;
+
+#[repr(C)]
+#[derive(Default, Debug)]
+pub struct DebugRegister {
+    pub mdscr_el1: u64,
+    pub dbgbcr_el1: [u64; MAX_BPS],
+    pub dbgbvr_el1: [u64; MAX_BPS],
+    pub dbgwcr_el1: [u64; MAX_WPS],
+    pub dbgwvr_el1: [u64; MAX_WPS],
+}
+
+lazy_static! {
+    static ref NS_DEBUG: [Mutex<DebugRegister>; NUM_OF_CPU] =
+        from_fn(|_| Mutex::new(DebugRegister::default()));
+}
+
+pub fn init_debug(rec: &mut Rec<'_>) {
+    // Accesses of the realm to the debug registers are trapped, so that
+    // it only uses the breakpoints and watchpoints given on REALM_CREATE.
+    // See exception::lower::synchronous::sys_reg.
+    rec.context.mdcr_el2 |= MDCR_EL2::TDA::SET.value;
+}
+
+// All the implemented registers are switched, not only the ones of the realm,
+// so that the breakpoints and watchpoints of the host don't hit in the realm.
+fn restore_debug(debug: &DebugRegister) {
+    MDSCR_EL1.set(debug.mdscr_el1);
+    set_bp_regs(
+        max_num_bps() as usize + 1,
+        &debug.dbgbcr_el1,
+        &debug.dbgbvr_el1,
+    );
+    set_wp_regs(
+        max_num_wps() as usize + 1,
+        &debug.dbgwcr_el1,
+        &debug.dbgwvr_el1,
+    );
+}
+
+fn save_debug(debug: &mut DebugRegister) {
+    debug.mdscr_el1 = MDSCR_EL1.get();
+    get_bp_regs(
+        max_num_bps() as usize + 1,
+        &mut debug.dbgbcr_el1,
+        &mut debug.dbgbvr_el1,
+    );
+    get_wp_regs(
+        max_num_wps() as usize + 1,
+        &mut debug.dbgwcr_el1,
+        &mut debug.dbgwvr_el1,
+    );
+}
+
+pub fn restore_state(rec: &Rec<'_>) {
+    MDCR_EL2.set(rec.context.mdcr_el2);
+    restore_debug(&rec.context.debug);
+}
+
+pub fn save_state(rec: &mut Rec<'_>) {
+    save_debug(&mut rec.context.debug);
+}
+
+pub fn save_host_state(_rec: &Rec<'_>) {
+    let mut ns_debug = NS_DEBUG[get_cpu_id()].lock();
+    save_debug(&mut ns_debug);
+}
+
+pub fn restore_host_state(_rec: &Rec<'_>) {
+    let ns_debug = NS_DEBUG[get_cpu_id()].lock();
+    restore_debug(&ns_debug);
+}
@@ -0,0 +1,16 @@
+use super::Rec;
+use crate::mec;
+
+use aarch64_cpu::registers::Writeable;
+use armv9a::regs::VMECID_P_EL2;
+
+// The RMM handles the exits of the REC, e.g., RSI calls writing realm memory,
+// under the same MECID as the realm.
+pub fn restore_state(rec: &Rec<'_>) {
+    if !mec::mec_present() {
+        return;
+    }
+    let mecid = rec.mecid().expect("REC should belong to a realm");
+    VMECID_P_EL2.set(mecid as u64);
+    mec::switch_to(mecid);
+}
@@ -0,0 +1,312 @@
+//! Decoder for the A64 load/store instructions that a Realm may use to access
+//! emulated MMIO when ESR_EL2 carries no valid instruction syndrome (ISV == 0).
+//!
+//! Only general purpose register transfers are handled: single register
+//! (immediate, unscaled, pre/post-indexed and register offset) and pair forms.
+//! SIMD&FP, exclusive, atomic and tagged accesses are rejected.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Offset {
+    /// Byte offset, already scaled
+    Imm(i64),
+    /// Offset register extended by `option` and shifted left by `shift`
+    Reg { rm: usize, option: u32, shift: u32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Writeback {
+    None,
+    PreIndex,
+    PostIndex,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MmioAccess {
+    pub write: bool,
+    /// Access size of a single register in bytes
+    pub size: usize,
+    pub sign_extend: bool,
+    /// The transfer register is 64-bit wide (Xt)
+    pub sf: bool,
+    pub rt: usize,
+    /// Second transfer register of the pair forms
+    pub rt2: Option<usize>,
+    pub rn: usize,
+    pub offset: Offset,
+    pub writeback: Writeback,
+}
+
+const XZR_OR_SP: usize = 31;
+
+// UXTW, LSL (UXTX), SXTW, SXTX
+const EXTEND_UXTW: u32 = 0b010;
+const EXTEND_UXTX: u32 = 0b011;
+const EXTEND_SXTW: u32 = 0b110;
+const EXTEND_SXTX: u32 = 0b111;
+
+fn bits(inst: u32, hi: u32, lo: u32) -> u32 {
+    (inst >> lo) & ((1 << (hi - lo + 1)) - 1)
+}
+
+fn sign_extend_imm(imm: u32, width: u32) -> i64 {
+    let shift = 64 - width;
+    ((imm as i64) << shift) >> shift
+}
+
+/// Sign extends the lowest `size` bytes of `val` to 64 bits
+pub fn sign_extend(val: u64, size: usize) -> u64 {
+    let shift = 64 - (size as u32 * 8);
+    (((val << shift) as i64) >> shift) as u64
+}
+
+impl MmioAccess {
+    pub fn is_pair(&self) -> bool {
+        self.rt2.is_some()
+    }
+
+    /// Mask of the bytes transferred for a single register
+    pub fn size_mask(&self) -> u64 {
+        match self.size {
+            8 => u64::MAX,
+            size => (1 << (size * 8)) - 1,
+        }
+    }
+
+    /// Returns the address of the first element accessed,
+    /// given the values of the base and offset registers.
+    pub fn address(&self, base: u64, xm: u64) -> u64 {
+        if self.writeback == Writeback::PostIndex {
+            return base;
+        }
+        base.wrapping_add(self.offset_value(xm))
+    }
+
+    /// Returns the value of the base register after the access, if it's updated
+    pub fn writeback_value(&self, base: u64) -> Option<u64> {
+        match self.writeback {
+            Writeback::None => None,
+            _ => Some(base.wrapping_add(self.offset_value(0))),
+        }
+    }
+
+    fn offset_value(&self, xm: u64) -> u64 {
+        match self.offset {
+            Offset::Imm(imm) => imm as u64,
+            Offset::Reg { option, shift, .. } => {
+                let extended = match option {
+                    EXTEND_UXTW => xm & u32::MAX as u64,
+                    EXTEND_SXTW => sign_extend(xm, 4),
+                    _ => xm,
+                };
+                extended << shift
+            }
+        }
+    }
+}
+
+/// Decodes a load/store instruction. Returns `None` if it can't be emulated.
+pub fn decode(inst: u32) -> Option<MmioAccess> {
+    // SIMD&FP registers are not supported
+    if bits(inst, 26, 26) != 0 {
+        return None;
+    }
+
+    let access = match bits(inst, 29, 27) {
+        0b111 => decode_single(inst)?,
+        0b101 => decode_pair(inst)?,
+        _ => return None,
+    };
+
+    // The base register can't be a transfer register when it's written back
+    let overlaps = access.rt == access.rn || access.rt2 == Some(access.rn);
+    if access.writeback != Writeback::None && access.rn != XZR_OR_SP && overlaps {
+        return None;
+    }
+
+    Some(access)
+}
+
+fn decode_single(inst: u32) -> Option<MmioAccess> {
+    let size_log2 = bits(inst, 31, 30);
+    let opc = bits(inst, 23, 22);
+
+    let (offset, writeback) = match bits(inst, 25, 24) {
+        // Unsigned immediate
+        0b01 => (
+            Offset::Imm((bits(inst, 21, 10) as i64) << size_log2),
+            Writeback::None,
+        ),
+        0b00 if bits(inst, 21, 21) == 0 => {
+            let imm9 = Offset::Imm(sign_extend_imm(bits(inst, 20, 12), 9));
+            match bits(inst, 11, 10) {
+                0b01 => (imm9, Writeback::PostIndex),
+                0b11 => (imm9, Writeback::PreIndex),
+                // Unscaled and unprivileged
+                _ => (imm9, Writeback::None),
+            }
+        }
+        0b00 if bits(inst, 11, 10) == 0b10 => {
+            let option = bits(inst, 15, 13);
+            if !matches!(
+                option,
+                EXTEND_UXTW | EXTEND_UXTX | EXTEND_SXTW | EXTEND_SXTX
+            ) {
+                return None;
+            }
+            let shift = if bits(inst, 12, 12) != 0 {
+                size_log2
+            } else {
+                0
+            };
+            (
+                Offset::Reg {
+                    rm: bits(inst, 20, 16) as usize,
+                    option,
+                    shift,
+                },
+                Writeback::None,
+            )
+        }
+        // Atomic memory operations and pointer authenticated loads
+        _ => return None,
+    };
+
+    let (write, sign_extend, sf) = match (opc, size_log2) {
+        (0b00, _) => (true, false, size_log2 == 3),
+        (0b01, _) => (false, false, size_log2 == 3),
+        // Prefetch
+        (0b10, 3) => return None,
+        (0b10, _) => (false, true, true),
+        (0b11, 0 | 1) => (false, true, false),
+        _ => return None,
+    };
+
+    Some(MmioAccess {
+        write,
+        size: 1 << size_log2,
+        sign_extend,
+        sf,
+        rt: bits(inst, 4, 0) as usize,
+        rt2: None,
+        rn: bits(inst, 9, 5) as usize,
+        offset,
+        writeback,
+    })
+}
+
+fn decode_pair(inst: u32) -> Option<MmioAccess> {
+    let opc = bits(inst, 31, 30);
+    let load = bits(inst, 22, 22) != 0;
+
+    let writeback = match bits(inst, 25, 23) {
+        // No-allocate and signed offset
+        0b000 | 0b010 => Writeback::None,
+        0b001 => Writeback::PostIndex,
+        0b011 => Writeback::PreIndex,
+        _ => return None,
+    };
+    let no_allocate = bits(inst, 25, 23) == 0b000;
+
+    let (size_log2, sign_extend) = match opc {
+        0b00 => (2, false),
+        // LDPSW (STGP isn't supported)
+        0b01 if load && !no_allocate => (2, true),
+        0b10 => (3, false),
+        _ => return None,
+    };
+
+    let rt = bits(inst, 4, 0) as usize;
+    let rt2 = bits(inst, 14, 10) as usize;
+    if load && rt == rt2 {
+        return None;
+    }
+
+    Some(MmioAccess {
+        write: !load,
+        size: 1 << size_log2,
+        sign_extend,
+        sf: size_log2 == 3 || sign_extend,
+        rt,
+        rt2: Some(rt2),
+        rn: bits(inst, 9, 5) as usize,
+        offset: Offset::Imm(sign_extend_imm(bits(inst, 21, 15), 7) << size_log2),
+        writeback,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_single_register() {
+        // ldr x1, [x2, #8]
+        let access = decode(0xf9400441).unwrap();
+        assert!(!access.write);
+        assert_eq!((access.size, access.rt, access.rn), (8, 1, 2));
+        assert_eq!(access.offset, Offset::Imm(8));
+        assert_eq!(access.writeback, Writeback::None);
+        assert_eq!(access.address(0x1000, 0), 0x1008);
+
+        // strb w3, [x4], #-1
+        let access = decode(0x381ff483).unwrap();
+        assert!(access.write);
+        assert_eq!((access.size, access.rt, access.rn), (1, 3, 4));
+        assert_eq!(access.writeback, Writeback::PostIndex);
+        assert_eq!(access.address(0x1000, 0), 0x1000);
+        assert_eq!(access.writeback_value(0x1000), Some(0xfff));
+
+        // ldrsh x5, [x6, #-2]!
+        let access = decode(0x789fecc5).unwrap();
+        assert!(access.sign_extend && access.sf);
+        assert_eq!(access.size, 2);
+        assert_eq!(access.writeback, Writeback::PreIndex);
+        assert_eq!(access.address(0x1000, 0), 0xffe);
+
+        // ldr w7, [x8, w9, sxtw #2]
+        let access = decode(0xb869d907).unwrap();
+        assert_eq!((access.size, access.rt, access.rn), (4, 7, 8));
+        assert_eq!(access.address(0x1000, 0xffff_ffff), 0xffc);
+    }
+
+    #[test]
+    fn decode_pair_register() {
+        // ldp x0, x1, [x2, #16]!
+        let access = decode(0xa9c10440).unwrap();
+        assert!(!access.write);
+        assert_eq!((access.size, access.rt, access.rt2), (8, 0, Some(1)));
+        assert_eq!(access.writeback, Writeback::PreIndex);
+        assert_eq!(access.address(0x1000, 0), 0x1010);
+        assert_eq!(access.writeback_value(0x1000), Some(0x1010));
+
+        // stp w3, w4, [x5], #-8
+        let access = decode(0x28bf10a3).unwrap();
+        assert!(access.write);
+        assert_eq!((access.size, access.rt, access.rt2), (4, 3, Some(4)));
+        assert_eq!(access.writeback_value(0x1000), Some(0xff8));
+
+        // ldpsw x6, x7, [x8]
+        let access = decode(0x69401d06).unwrap();
+        assert!(access.sign_extend && access.sf);
+        assert_eq!(access.size, 4);
+    }
+
+    #[test]
+    fn decode_unsupported() {
+        // ldr q0, [x1]
+        assert!(decode(0x3dc00020).is_none());
+        // ldxr x0, [x1]
+        assert!(decode(0xc85f7c20).is_none());
+        // prfm pldl1keep, [x0]
+        assert!(decode(0xf9800000).is_none());
+        // ldr x0, [x0], #8
+        assert!(decode(0xf8408400).is_none());
+    }
+
+    #[test]
+    fn sign_extension() {
+        assert_eq!(sign_extend(0x80, 1), 0xffff_ffff_ffff_ff80);
+        assert_eq!(sign_extend(0x7fff, 2), 0x7fff);
+        assert_eq!(sign_extend(0x8000_0000, 4), 0xffff_ffff_8000_0000);
+    }
+}
@@ -0,0 +1,301 @@
+mod decoder;
+
+use self::decoder::{sign_extend, MmioAccess, Offset};
+use crate::granule::GRANULE_MASK;
+use crate::realm::mm::address::GuestPhysAddr;
+use crate::realm::mm::rtt::RTT_PAGE_LEVEL;
+use crate::realm::rd::Rd;
+use crate::rec::context::get_reg;
+use crate::rec::{Rec, RmmRecEmulatableAbort::EmulatableAbort};
+use crate::rmi;
+use crate::rmi::error::Error;
+use crate::rmi::rec::run::{EntryFlag, Run};
+use armv9a::regs::*;
+
+use safe_abstraction::raw_ptr::assume_safe;
+
+const XZR: usize = 31;
+
+/// MMIO access decoded from the faulting instruction, which is emulated
+/// by the host one register at a time.
+#[derive(Clone, Copy, Debug)]
+pub struct PendingMmio {
+    access: MmioAccess,
+    /// Syndrome of the Data Abort taken by the Realm
+    esr_el2: u64,
+    /// Address of the first element accessed
+    addr: u64,
+    /// Index of the register being emulated by the host (1 for Rt2 of a pair)
+    step: usize,
+    /// Value returned by the host for the first register of a pair
+    data: u64,
+}
+
+impl PendingMmio {
+    fn rt(&self) -> usize {
+        match self.step {
+            0 => self.access.rt,
+            _ => self.access.rt2.unwrap_or(self.access.rt),
+        }
+    }
+
+    /// Returns the syndrome reported to the host for the current step,
+    /// as if the instruction was a single register access with ISV set.
+    pub fn esr(&self) -> u64 {
+        let access = &self.access;
+        let mut esr = EsrEl2::new(self.esr_el2);
+        esr.set_masked_value(EsrEl2::ISV, 1)
+            .set_masked_value(EsrEl2::SAS, access.size.trailing_zeros() as u64)
+            .set_masked_value(EsrEl2::SSE, access.sign_extend as u64)
+            .set_masked_value(EsrEl2::SRT, self.rt() as u64)
+            .set_masked_value(EsrEl2::SF, access.sf as u64)
+            .set_masked_value(EsrEl2::WNR, access.write as u64);
+        esr.get()
+    }
+
+    /// Returns the value the host should write for the current step
+    pub fn write_val(&self, rec: &Rec<'_>) -> Result<u64, Error> {
+        match self.rt() {
+            XZR => Ok(0),
+            rt => Ok(get_reg(rec, rt)? as u64 & self.access.size_mask()),
+        }
+    }
+
+    /// Returns the address of the element accessed by the current step
+    pub fn far(&self) -> u64 {
+        self.addr + (self.step * self.access.size) as u64
+    }
+}
+
+/// Decodes the instruction which caused a Data Abort without a valid
+/// instruction syndrome. Returns `None` if it can't be emulated by the host.
+pub fn decode_abort(rd: &Rd, rec: &Rec<'_>, esr_el2: u64) -> Option<PendingMmio> {
+    let inst = fetch_instruction(rd, rec.context.elr_el2)?;
+    let access = decoder::decode(inst)?;
+
+    let base = match access.rn {
+        XZR => current_sp(rec),
+        rn => rec.context.gp_regs[rn],
+    };
+    let xm = match access.offset {
+        Offset::Reg { rm: XZR, .. } => 0,
+        Offset::Reg { rm, .. } => rec.context.gp_regs[rm],
+        Offset::Imm(_) => 0,
+    };
+    let addr = access.address(base, xm);
+
+    // Both registers of a pair are reported with the same fault address
+    let len = access.size as u64 * if access.is_pair() { 2 } else { 1 };
+    let last = addr.checked_add(len - 1)?;
+    if addr & GRANULE_MASK as u64 != last & GRANULE_MASK as u64 {
+        return None;
+    }
+
+    Some(PendingMmio {
+        access,
+        esr_el2,
+        addr,
+        step: 0,
+        data: 0,
+    })
+}
+
+/// Completes the emulation of an MMIO access by the host.
+/// Returns true if the access needs another exit to the host
+/// before the Realm can be resumed.
+pub fn emulate_mmio(rec: &mut Rec<'_>, run: &mut Run) -> Result<bool, Error> {
+    let flags = run.entry_flags();
+    let pending = rec.take_pending_mmio();
+
+    // Host has not completed emulation for an Emulatable Abort.
+    // if INJECT_SEA is set then the value of EMUL_MMIO is ignored.
+    if flags.get_masked(EntryFlag::INJECT_SEA | EntryFlag::EMUL_MMIO) == 0 {
+        return Ok(false);
+    }
+
+    let esr_el2 = rec.context.sys_regs.esr_el2;
+    let esr = EsrEl2::new(esr_el2);
+    let ec = esr.get_masked_value(EsrEl2::EC);
+
+    if ec != ESR_EL2_EC_DATA_ABORT || rec.emulatable_abort() != EmulatableAbort {
+        return Err(Error::RmiErrorRec);
+    }
+
+    if let Some(pending) = pending {
+        if flags.get_masked(EntryFlag::INJECT_SEA) != 0 {
+            return Ok(false);
+        }
+        return complete_decoded(rec, run, pending);
+    }
+
+    let wnr = esr.get_masked_value(EsrEl2::WNR);
+    let rt = esr.get_masked_value(EsrEl2::SRT) as usize;
+
+    // MMIO read case
+    if wnr == 0 && rt != XZR {
+        let mask = esr.get_access_size_mask();
+        let mut val = run.entry_gpr(0)? & mask;
+        if esr.get_masked_value(EsrEl2::SSE) != 0 {
+            let size = 1 << esr.get_masked_value(EsrEl2::SAS);
+            val = sign_extend(val, size);
+        }
+        if esr.get_masked_value(EsrEl2::SF) == 0 {
+            val &= u32::MAX as u64;
+        }
+        rec.context.gp_regs[rt] = val;
+    }
+    rec.context.elr_el2 += 4;
+    Ok(false)
+}
+
+fn complete_decoded(
+    rec: &mut Rec<'_>,
+    run: &mut Run,
+    mut pending: PendingMmio,
+) -> Result<bool, Error> {
+    let access = pending.access;
+    let val = read_val(&access, run.entry_gpr(0)?);
+
+    // Report the second register of a pair without running the Realm
+    if access.is_pair() && pending.step == 0 {
+        pending.data = val;
+        pending.step = 1;
+        if access.write {
+            run.set_gpr(0, pending.write_val(rec)?)?;
+        }
+        run.set_exit_reason(rmi::EXIT_SYNC);
+        run.set_esr(pending.esr() & EMULATABLE_ABORT_MASK);
+        run.set_far(pending.far() & !(GRANULE_MASK as u64));
+        run.set_hpfar(rec.context.sys_regs.hpfar);
+        rec.set_pending_mmio(Some(pending));
+        return Ok(true);
+    }
+
+    if !access.write {
+        match access.rt2 {
+            Some(rt2) => {
+                set_gpr(rec, access.rt, pending.data);
+                set_gpr(rec, rt2, val);
+            }
+            None => set_gpr(rec, access.rt, val),
+        }
+    }
+
+    let base = match access.rn {
+        XZR => current_sp(rec),
+        rn => rec.context.gp_regs[rn],
+    };
+    if let Some(base) = access.writeback_value(base) {
+        match access.rn {
+            XZR => set_current_sp(rec, base),
+            rn => rec.context.gp_regs[rn] = base,
+        }
+    }
+    rec.context.elr_el2 += 4;
+    Ok(false)
+}
+
+fn read_val(access: &MmioAccess, val: u64) -> u64 {
+    let mut val = val & access.size_mask();
+    if access.sign_extend {
+        val = sign_extend(val, access.size);
+    }
+    if !access.sf {
+        val &= u32::MAX as u64;
+    }
+    val
+}
+
+fn set_gpr(rec: &mut Rec<'_>, rt: usize, val: u64) {
+    if rt != XZR {
+        rec.context.gp_regs[rt] = val;
+    }
+}
+
+fn uses_sp_el0(rec: &Rec<'_>) -> bool {
+    // SPSR_EL2.M[0] selects SP_ELx over SP_EL0
+    rec.context.spsr_el2 & 0b1 == 0
+}
+
+fn current_sp(rec: &Rec<'_>) -> u64 {
+    match uses_sp_el0(rec) {
+        true => rec.context.sys_regs.sp_el0,
+        false => rec.context.sys_regs.sp,
+    }
+}
+
+fn set_current_sp(rec: &mut Rec<'_>, val: u64) {
+    match uses_sp_el0(rec) {
+        true => rec.context.sys_regs.sp_el0 = val,
+        false => rec.context.sys_regs.sp = val,
+    }
+}
+
+/// Reads the instruction at `va` from Realm memory.
+/// Only instructions residing in protected IPA space are trusted.
+fn fetch_instruction(rd: &Rd, va: u64) -> Option<u32> {
+    let ipa = va_to_ipa(va)? as usize;
+    if !rd.addr_in_par(ipa) {
+        return None;
+    }
+    let page: usize = rd
+        .s2_table()
+        .ipa_to_pa(GuestPhysAddr::from(ipa), RTT_PAGE_LEVEL)?
+        .into();
+    assume_safe::<Instruction>(page | (ipa & !GRANULE_MASK))
+        .map(|inst| inst.0)
+        .ok()
+}
+
+/// Translates `va` with the Realm's stage 1 tables,
+/// which are still live on this PE after the Realm exit.
+#[cfg(not(any(miri, test, fuzzing)))]
+fn va_to_ipa(va: u64) -> Option<u64> {
+    // PAR_EL1.PA, including PA[51:48] for 52-bit addresses
+    const PAR_PA_MASK: u64 = 0x000f_ffff_ffff_f000;
+    const PAR_F: u64 = 1;
+
+    let par: u64;
+    unsafe {
+        core::arch::asm!(
+            "at s1e1r, {va}",
+            "isb",
+            "mrs {par}, par_el1",
+            va = in(reg) va,
+            par = out(reg) par,
+        );
+    }
+    if par & PAR_F != 0 {
+        return None;
+    }
+    Some((par & PAR_PA_MASK) | (va & !(GRANULE_MASK as u64)))
+}
+
+#[cfg(any(miri, test, fuzzing))]
+fn va_to_ipa(va: u64) -> Option<u64> {
+    // Realms are not running stage 1 translation in these environments
+    Some(va)
+}
+
+#[repr(C)]
+struct Instruction(u32);
+
+impl safe_abstraction::raw_ptr::RawPtr for Instruction {}
+
+impl safe_abstraction::raw_ptr::SafetyChecked for Instruction {}
+
+impl safe_abstraction::raw_ptr::SafetyAssured for Instruction {
+    fn is_initialized(&self) -> bool {
+        // The instruction belongs to a Data Granule of the Realm,
+        // which has been initialized according to the RMM Specification
+        // A2.2.4 Granule Wiping.
+        true
+    }
+
+    fn verify_ownership(&self) -> bool {
+        // The RMM only reads a single aligned word from the Realm's memory.
+        // Other RECs of the Realm may modify it concurrently,
+        // which can only affect the emulation of the Realm's own access.
+        true
+    }
+}
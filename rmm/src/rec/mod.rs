@@ -1,7 +1,12 @@
+pub mod attest;
 pub mod context;
+pub mod debug;
 pub mod gic;
+pub mod mec;
 pub mod mmio;
+pub mod mte;
 pub mod pauth;
+pub mod plane;
 pub mod pmu;
 pub mod sea;
 pub mod simd;
@@ -9,6 +14,7 @@ pub mod timer;
 
 use crate::realm::rd::Rd;
 use crate::rec::context::Context;
+use crate::rec::mmio::PendingMmio;
 use crate::rmi::error::Error;
 use crate::rmi::rec::params::NR_AUX;
 use crate::rmm_exit;
@@ -24,6 +30,9 @@ const MAX_RECS_ORDER_VALUE: u64 = 4;
 pub enum RecAuxIndex {
     SIMD = 0,
     PMU = 1,
+    PLANE = 2,
+    SME = 3,
+    ATTEST = 4,
     Undefined,
 }
 
@@ -62,8 +71,11 @@ pub struct Rec<'a> {
     // TODO: Create consts for both numbers
     attest_challenge: [u8; MAX_CHALLENGE_SIZE],
     attest_token_offset: usize,
+    attest_token_len: usize,
     aux: [u64; NR_AUX], // Addresses of auxiliary Granules
     emulatable_abort: RmmRecEmulatableAbort,
+    /// MMIO access decoded from the instruction, which is being emulated by the host
+    pending_mmio: Option<PendingMmio>,
     /// PA of RD of Realm which owns this REC
     ///
     /// Safety:
@@ -77,6 +89,10 @@ pub struct Rec<'a> {
     ripas: Ripas,
     vtcr: u64,
     host_call_pending: bool,
+    /// Index of the plane currently running on this REC (0 is the primary plane)
+    active_plane: usize,
+    /// IPA of the PlaneRun object given by the primary plane on PLANE_ENTER
+    plane_run: usize,
 }
 
 impl Rec<'_> {
@@ -86,8 +102,10 @@ impl Rec<'_> {
             attest_state: RmmRecAttestState::NoAttestInProgress,
             attest_challenge: [0; MAX_CHALLENGE_SIZE],
             attest_token_offset: 0,
+            attest_token_len: 0,
             aux: [0; NR_AUX],
             emulatable_abort: RmmRecEmulatableAbort::NotEmulatableAbort,
+            pending_mmio: None,
             owner: OnceCell::new(),
             vcpuid: 0,
             runnable: false,
@@ -102,6 +120,8 @@ impl Rec<'_> {
             },
             vtcr: 0,
             host_call_pending: false,
+            active_plane: 0,
+            plane_run: 0,
         }
     }
 
@@ -135,6 +155,8 @@ impl Rec<'_> {
         self.aux.copy_from_slice(&aux);
         pauth::init_pauth(self);
         pmu::init_pmu(self);
+        debug::init_debug(self);
+        mte::init_mte(self);
         timer::init_timer(self);
         gic::init_gic(self);
         simd::init_simd(self)?;
@@ -154,14 +176,30 @@ impl Rec<'_> {
         self.attest_token_offset
     }
 
+    pub fn attest_token_len(&self) -> usize {
+        self.attest_token_len
+    }
+
     pub fn aux(&self, index: usize) -> u64 {
         self.aux[index]
     }
 
+    pub fn active_plane(&self) -> usize {
+        self.active_plane
+    }
+
+    pub fn plane_run(&self) -> usize {
+        self.plane_run
+    }
+
     pub fn emulatable_abort(&self) -> RmmRecEmulatableAbort {
         self.emulatable_abort
     }
 
+    pub fn take_pending_mmio(&mut self) -> Option<PendingMmio> {
+        self.pending_mmio.take()
+    }
+
     pub fn runnable(&self) -> bool {
         self.runnable
     }
@@ -208,10 +246,26 @@ impl Rec<'_> {
         self.attest_token_offset = offset;
     }
 
+    pub fn set_attest_token_len(&mut self, len: usize) {
+        self.attest_token_len = len;
+    }
+
     pub fn set_emulatable_abort(&mut self, val: RmmRecEmulatableAbort) {
         self.emulatable_abort = val;
     }
 
+    pub fn set_pending_mmio(&mut self, val: Option<PendingMmio>) {
+        self.pending_mmio = val;
+    }
+
+    pub fn set_active_plane(&mut self, plane: usize) {
+        self.active_plane = plane;
+    }
+
+    pub fn set_plane_run(&mut self, ipa: usize) {
+        self.plane_run = ipa;
+    }
+
     pub fn set_host_call_pending(&mut self, val: bool) {
         self.host_call_pending = val;
     }
@@ -287,6 +341,21 @@ impl Rec<'_> {
         Ok(owner.ipa_bits())
     }
 
+    pub fn debug_config(&self) -> Result<(usize, usize), Error> {
+        let owner = self.get_owner()?;
+        Ok(owner.debug_config())
+    }
+
+    pub fn mecid(&self) -> Result<usize, Error> {
+        let owner = self.get_owner()?;
+        Ok(owner.mecid())
+    }
+
+    pub fn mte_en(&self) -> Result<bool, Error> {
+        let owner = self.get_owner()?;
+        Ok(owner.mte_en())
+    }
+
     pub fn pmu_config(&self) -> Result<(bool, usize), Error> {
         let owner = self.get_owner()?;
         Ok(owner.pmu_config())
@@ -398,6 +467,8 @@ pub fn max_recs_order() -> usize {
 //       Save the host state only if necessary.
 pub fn save_host_state(rec: &Rec<'_>) {
     pmu::save_host_state(rec);
+    #[cfg(not(any(miri, test, fuzzing)))]
+    debug::save_host_state(rec);
     // TODO: Apply 'ns_state_save' feature to the save_host_state func.
     // For that, we need to move the code here from the corresponding patches
     // in the nw-linux.
@@ -406,5 +477,7 @@ pub fn save_host_state(rec: &Rec<'_>) {
 
 pub fn restore_host_state(rec: &Rec<'_>) {
     pmu::restore_host_state(rec);
+    #[cfg(not(any(miri, test, fuzzing)))]
+    debug::restore_host_state(rec);
     timer::restore_host_state(rec);
 }
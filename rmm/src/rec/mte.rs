@@ -0,0 +1,54 @@
+use super::Rec;
+
+use aarch64_cpu::registers::*;
+use armv9a::regs::mte::*;
+
+// HCR_EL2.ATA: allocation tag access at EL1 and EL0
+const HCR_EL2_ATA: u64 = 1 << 56;
+
+#[repr(C)]
+#[derive(Default, Debug)]
+pub struct MteRegister {
+    pub enabled: bool,
+    pub tfsr_el1: u64,
+    pub tfsre0_el1: u64,
+    pub gcr_el1: u64,
+    pub rgsr_el1: u64,
+}
+
+pub fn init_mte(rec: &mut Rec<'_>) {
+    rec.context.mte.enabled = rec.mte_en().expect("REC should belong to a realm");
+}
+
+// Realms without MTE keep HCR_EL2.ATA cleared,
+// which traps their accesses to the MTE registers.
+pub fn restore_state(rec: &Rec<'_>) {
+    let mte = &rec.context.mte;
+    if !mte.enabled {
+        HCR_EL2.set(HCR_EL2.get() & !HCR_EL2_ATA);
+        return;
+    }
+
+    TFSR_EL1.set(mte.tfsr_el1);
+    TFSRE0_EL1.set(mte.tfsre0_el1);
+    GCR_EL1.set(mte.gcr_el1);
+    RGSR_EL1.set(mte.rgsr_el1);
+    HCR_EL2.set(HCR_EL2.get() | HCR_EL2_ATA);
+}
+
+pub fn save_state(rec: &mut Rec<'_>) {
+    let mte = &mut rec.context.mte;
+    if !mte.enabled {
+        return;
+    }
+
+    // Make asynchronous tag check faults of the realm visible in TFSR_EL1
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        core::arch::asm!("dsb nsh", "isb");
+    }
+    mte.tfsr_el1 = TFSR_EL1.get();
+    mte.tfsre0_el1 = TFSRE0_EL1.get();
+    mte.gcr_el1 = GCR_EL1.get();
+    mte.rgsr_el1 = RGSR_EL1.get();
+}
@@ -3,7 +3,7 @@ use super::Rec;
 use aarch64_cpu::registers::*;
 
 #[repr(C)]
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, Copy)]
 pub struct PauthRegister {
     pub apiakeylo_el1: u64,
     pub apiakeyhi_el1: u64,
@@ -30,7 +30,10 @@ pub fn restore_state(rec: &Rec<'_>) {
 /// # Safety
 ///
 /// Use pauth only for (re)storing Rec's context
-#[target_feature(enable = "pacg", enable = "paca")]
+#[cfg_attr(
+    target_arch = "aarch64",
+    target_feature(enable = "pacg", enable = "paca")
+)]
 unsafe fn _restore_state(rec: &Rec<'_>) {
     let pauth = &rec.context.pauth;
 
@@ -55,7 +58,10 @@ pub fn save_state(rec: &mut Rec<'_>) {
 /// # Safety
 ///
 /// Use pauth only for (re)storing Rec's context
-#[target_feature(enable = "pacg", enable = "paca")]
+#[cfg_attr(
+    target_arch = "aarch64",
+    target_feature(enable = "pacg", enable = "paca")
+)]
 unsafe fn _save_state(rec: &mut Rec<'_>) {
     let pauth = &mut rec.context.pauth;
 
@@ -0,0 +1,180 @@
+use super::context::{RegOffset, SystemRegister};
+use super::pauth::PauthRegister;
+use super::{Rec, RecAuxIndex};
+use crate::event::realmexit::{ExitSyncType, RecExitReason};
+use crate::granule::GranuleState;
+use crate::rmi::error::Error;
+use crate::rmi::MAX_AUX_PLANES;
+use crate::{get_granule, get_granule_if};
+
+use aarch64_cpu::registers::*;
+
+/// Number of planes including the primary plane (plane 0)
+pub const NUM_PLANES: usize = MAX_AUX_PLANES + 1;
+
+/// Per-plane execution context
+///
+/// Only the state owned by a plane is kept here. The state that is shared
+/// by all planes of a REC (e.g., gic, timer, pmu, simd) stays in `Context`.
+#[repr(C)]
+#[derive(Default, Debug, Clone, Copy)]
+pub struct PlaneContext {
+    pub gp_regs: [u64; 31],
+    pub elr_el2: u64,
+    pub spsr_el2: u64,
+    pub sys_regs: SystemRegister,
+    pub pauth: PauthRegister,
+}
+
+impl PlaneContext {
+    fn new(vttbr: u64, vmpidr: u64) -> Self {
+        let mut ctx = Self {
+            spsr_el2: (SPSR_EL2::D.mask << SPSR_EL2::D.shift)
+                | (SPSR_EL2::A.mask << SPSR_EL2::A.shift)
+                | (SPSR_EL2::I.mask << SPSR_EL2::I.shift)
+                | (SPSR_EL2::F.mask << SPSR_EL2::F.shift)
+                | (SPSR_EL2::M.mask & u64::from(SPSR_EL2::M::EL1h)) << SPSR_EL2::M.shift,
+            ..Default::default()
+        };
+        ctx.sys_regs.vttbr = vttbr;
+        ctx.sys_regs.vmpidr = vmpidr;
+        ctx
+    }
+
+    pub fn reg(&self, register: usize) -> Result<u64, Error> {
+        match register {
+            0..=30 => Ok(self.gp_regs[register]),
+            RegOffset::PC => Ok(self.elr_el2),
+            RegOffset::PSTATE => Ok(self.spsr_el2),
+            RegOffset::SCTLR => Ok(self.sys_regs.sctlr),
+            _ => Err(Error::RmiErrorInput),
+        }
+    }
+
+    pub fn set_reg(&mut self, register: usize, value: u64) -> Result<(), Error> {
+        match register {
+            0..=30 => self.gp_regs[register] = value,
+            RegOffset::PC => self.elr_el2 = value,
+            RegOffset::PSTATE => self.spsr_el2 = value,
+            RegOffset::SCTLR => self.sys_regs.sctlr = value,
+            _ => return Err(Error::RmiErrorInput),
+        }
+        Ok(())
+    }
+}
+
+/// Saved contexts of the planes that are not running on the REC
+///
+/// This lives in the auxiliary granule at `RecAuxIndex::PLANE`.
+/// The slot of the active plane is stale; its live state is in `rec.context`.
+#[repr(C)]
+#[derive(Default, Debug)]
+pub struct PlaneContexts {
+    pub planes: [PlaneContext; NUM_PLANES],
+}
+
+impl vmsa::guard::Content for PlaneContexts {}
+impl safe_abstraction::raw_ptr::RawPtr for PlaneContexts {}
+impl safe_abstraction::raw_ptr::SafetyChecked for PlaneContexts {}
+impl safe_abstraction::raw_ptr::SafetyAssured for PlaneContexts {
+    fn is_initialized(&self) -> bool {
+        // It is wiped out with zero's on granule delegation.
+        // Then, PlaneContexts gets initialized on REC_CREATE.
+        true
+    }
+
+    fn verify_ownership(&self) -> bool {
+        true
+    }
+}
+
+// Plane context initialization function
+//
+// `aux_vttbr` holds the VTTBR_EL2 value of each auxiliary plane, which
+// points to the auxiliary RTT of the plane.
+pub fn init_planes(rec: &mut Rec<'_>, aux_vttbr: &[u64]) -> Result<(), Error> {
+    let plane_aux = rec.aux(RecAuxIndex::PLANE as usize) as usize;
+    let mut plane_granule = get_granule_if!(plane_aux, GranuleState::RecAux)?;
+    let mut contexts = plane_granule.new_uninit_with::<PlaneContexts>(PlaneContexts::default())?;
+
+    let vmpidr = rec.context.sys_regs.vmpidr;
+    for (ctx, vttbr) in contexts.planes[1..].iter_mut().zip(aux_vttbr.iter()) {
+        *ctx = PlaneContext::new(*vttbr, vmpidr);
+    }
+    rec.set_active_plane(0);
+    Ok(())
+}
+
+/// Returns true if the realm exit of an auxiliary plane has to be
+/// reported to the primary plane instead of the host.
+///
+/// Synchronous exceptions except for WFx are handled by the primary plane.
+/// Asynchronous exits (IRQ, SError) and WFx go to the host as usual
+/// and the auxiliary plane is resumed on the next REC_ENTER.
+pub fn exits_to_primary(exit_reason: usize) -> bool {
+    matches!(
+        RecExitReason::from(exit_reason),
+        RecExitReason::Sync(sync) if sync != ExitSyncType::WFx
+    )
+}
+
+/// Swaps the live context in `rec.context` with the saved one of `plane`.
+pub fn switch_to(rec: &mut Rec<'_>, plane: usize) -> Result<(), Error> {
+    if plane >= NUM_PLANES {
+        return Err(Error::RmiErrorInput);
+    }
+    let active = rec.active_plane();
+    if active == plane {
+        return Ok(());
+    }
+
+    let plane_aux = rec.aux(RecAuxIndex::PLANE as usize) as usize;
+    let mut plane_granule = get_granule_if!(plane_aux, GranuleState::RecAux)?;
+    let mut contexts = plane_granule.content_mut::<PlaneContexts>()?;
+
+    let saved = &mut contexts.planes[active];
+    saved.gp_regs = rec.context.gp_regs;
+    saved.elr_el2 = rec.context.elr_el2;
+    saved.spsr_el2 = rec.context.spsr_el2;
+    saved.sys_regs = rec.context.sys_regs;
+    saved.pauth = rec.context.pauth;
+
+    let next = &contexts.planes[plane];
+    rec.context.gp_regs = next.gp_regs;
+    rec.context.elr_el2 = next.elr_el2;
+    rec.context.spsr_el2 = next.spsr_el2;
+    rec.context.sys_regs = next.sys_regs;
+    rec.context.pauth = next.pauth;
+
+    rec.set_active_plane(plane);
+    Ok(())
+}
+
+/// Reads a register of the (inactive) auxiliary `plane`.
+pub fn get_plane_reg(rec: &Rec<'_>, plane: usize, register: usize) -> Result<u64, Error> {
+    let plane_aux = rec.aux(RecAuxIndex::PLANE as usize) as usize;
+    let plane_granule = get_granule_if!(plane_aux, GranuleState::RecAux)?;
+    let contexts = plane_granule.content::<PlaneContexts>()?;
+    contexts
+        .planes
+        .get(plane)
+        .ok_or(Error::RmiErrorInput)?
+        .reg(register)
+}
+
+/// Writes a register of the (inactive) auxiliary `plane`.
+pub fn set_plane_reg(
+    rec: &mut Rec<'_>,
+    plane: usize,
+    register: usize,
+    value: u64,
+) -> Result<(), Error> {
+    let plane_aux = rec.aux(RecAuxIndex::PLANE as usize) as usize;
+    let mut plane_granule = get_granule_if!(plane_aux, GranuleState::RecAux)?;
+    let mut contexts = plane_granule.content_mut::<PlaneContexts>()?;
+    contexts
+        .planes
+        .get_mut(plane)
+        .ok_or(Error::RmiErrorInput)?
+        .set_reg(register, value)
+}
@@ -1,6 +1,7 @@
 use aarch64_cpu::registers::{Readable, Writeable};
-use armv9a::regs::{CPTR_EL2, SMCR_EL2, SVCR, ZCR_EL1, ZCR_EL2};
+use armv9a::regs::{CPTR_EL2, SMCR_EL1, SMCR_EL2, SVCR, TPIDR2_EL0, ZCR_EL1, ZCR_EL2};
 use armv9a::InMemoryRegister;
+#[cfg(target_arch = "aarch64")]
 use core::arch::asm;
 use core::array::from_fn;
 use lazy_static::lazy_static;
@@ -13,7 +14,7 @@ use crate::granule::GranuleState;
 use crate::realm::rd::Rd;
 use crate::rec::RecAuxIndex;
 use crate::rmi::error::Error;
-use crate::simd::{sme_en, SimdConfig, MAX_VQ};
+use crate::simd::{sme2_en, sme_en, sme_fa64, SimdConfig, MAX_SVQ, MAX_VQ};
 use crate::{get_granule, get_granule_if};
 
 // SIMD context structure
@@ -34,6 +35,7 @@ pub struct SimdRegister {
     // SIMD data registers
     pub fpu: FpuRegs,
     pub sve: SveRegs,
+    pub sme: SmeRegs,
 }
 
 impl SimdRegister {
@@ -88,6 +90,59 @@ pub struct SveRegs {
     pub zcr_el12: u64,
 }
 
+// SME registers except ZA, which lives in its own Aux granule
+const NUM_ZT0_QUADS: usize = 4;
+
+#[derive(Default, Debug)]
+pub struct SmeRegs {
+    // ZT0 register of FEAT_SME2 (512 bits)
+    pub zt0: [u128; NUM_ZT0_QUADS],
+    pub smcr_el2: u64,
+    pub smcr_el12: u64,
+    pub tpidr2_el0: u64,
+}
+
+// ZA array: SVL_B horizontal slices of SVL_B bytes
+const MAX_SVL_B: usize = MAX_SVQ as usize * 16;
+
+#[derive(Debug)]
+pub struct ZaRegs {
+    pub za: [[u128; MAX_SVQ as usize]; MAX_SVL_B],
+}
+
+impl ZaRegs {
+    pub const fn new() -> Self {
+        Self {
+            za: [[0; MAX_SVQ as usize]; MAX_SVL_B],
+        }
+    }
+}
+
+impl Default for ZaRegs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl vmsa::guard::Content for ZaRegs {}
+impl safe_abstraction::raw_ptr::RawPtr for ZaRegs {}
+impl safe_abstraction::raw_ptr::SafetyChecked for ZaRegs {}
+impl safe_abstraction::raw_ptr::SafetyAssured for ZaRegs {
+    fn is_initialized(&self) -> bool {
+        // It is wiped out with zero's on granule delegation.
+        // Then, ZaRegs gets initialized on Rec::init() call.
+        true
+    }
+
+    fn verify_ownership(&self) -> bool {
+        true
+    }
+}
+
+lazy_static! {
+    static ref NS_ZA: [Mutex<ZaRegs>; NUM_OF_CPU] = from_fn(|_| Mutex::new(ZaRegs::new()));
+}
+
 // TODO: Save according to the hint in FID with SMCCCv1.3 or v1.4
 
 // SIMD context initialization function
@@ -98,12 +153,16 @@ pub fn init_simd(rec: &mut Rec<'_>) -> Result<(), Error> {
 
     let mut zcr_el2: u64 = 0;
     let mut svcr: u64 = 0;
+    let mut smcr_el2: u64 = 0;
 
     rec.context.simd.is_used = false;
     rec.context.simd.is_saved = false;
     rec.context.simd.cfg.sve_en = simd_cfg.sve_en;
     rec.context.simd.cfg.sve_vq = simd_cfg.sve_vq;
     rec.context.simd.cfg.sme_en = simd_cfg.sme_en;
+    rec.context.simd.cfg.sme_svq = simd_cfg.sme_svq;
+    rec.context.simd.cfg.sme2_en = simd_cfg.sme_en && sme2_en();
+    rec.context.simd.cfg.sme_fa64 = simd_cfg.sme_en && sme_fa64();
 
     // Initialize SVE related fields and config registers
     if simd_cfg.sve_en {
@@ -111,6 +170,13 @@ pub fn init_simd(rec: &mut Rec<'_>) -> Result<(), Error> {
     }
     if simd_cfg.sme_en {
         svcr = 0;
+        smcr_el2 = SMCR_EL2::LEN.val(simd_cfg.sme_svq).value;
+        if rec.context.simd.cfg.sme_fa64 {
+            smcr_el2 |= SMCR_EL2::FA64::SET.value;
+        }
+        if rec.context.simd.cfg.sme2_en {
+            smcr_el2 |= SMCR_EL2::EZT0::SET.value;
+        }
     }
 
     let simd_aux = rec.aux(RecAuxIndex::SIMD as usize) as usize;
@@ -124,6 +190,12 @@ pub fn init_simd(rec: &mut Rec<'_>) -> Result<(), Error> {
         (CPTR_EL2::TAM::SET + CPTR_EL2::TSM::SET + CPTR_EL2::TFP::SET + CPTR_EL2::TZ::SET).value;
     simd_regs.sve.zcr_el2 = zcr_el2;
     simd_regs.svcr = svcr;
+    simd_regs.sme.smcr_el2 = smcr_el2;
+
+    let sme_aux = rec.aux(RecAuxIndex::SME as usize) as usize;
+    let mut sme_granule = get_granule_if!(sme_aux, GranuleState::RecAux)?;
+    debug!("RecAux granule for sme at 0x{:x}", sme_aux);
+    let _za_regs = sme_granule.new_uninit_with::<ZaRegs>(ZaRegs::new())?;
     Ok(())
 }
 
@@ -136,6 +208,7 @@ pub fn init_simd(rec: &mut Rec<'_>) -> Result<(), Error> {
 /// # Safety
 ///
 /// Use neon only for (re)storing Rec's simd context
+#[cfg(target_arch = "aarch64")]
 #[target_feature(enable = "neon")]
 unsafe fn save_fpu(fpu: &mut FpuRegs) {
     let addr_q: u64 = fpu.q.as_ptr() as u64;
@@ -166,6 +239,7 @@ unsafe fn save_fpu(fpu: &mut FpuRegs) {
 /// # Safety
 ///
 /// Use neon only for (re)storing Rec's simd context
+#[cfg(target_arch = "aarch64")]
 #[target_feature(enable = "neon")]
 unsafe fn save_fpu_crsr(fpu: &mut FpuRegs) {
     let fpsr: u64;
@@ -185,6 +259,7 @@ unsafe fn save_fpu_crsr(fpu: &mut FpuRegs) {
 /// # Safety
 ///
 /// Use neon only for (re)storing Rec's simd context
+#[cfg(target_arch = "aarch64")]
 #[target_feature(enable = "neon")]
 pub unsafe fn restore_fpu(fpu: &FpuRegs) {
     let addr_q: u64 = fpu.q.as_ptr() as u64;
@@ -215,6 +290,7 @@ pub unsafe fn restore_fpu(fpu: &FpuRegs) {
 /// # Safety
 ///
 /// Use neon only for (re)storing Rec's simd context
+#[cfg(target_arch = "aarch64")]
 #[target_feature(enable = "neon")]
 pub unsafe fn restore_fpu_crsr(fpu: &FpuRegs) {
     unsafe {
@@ -230,6 +306,7 @@ pub unsafe fn restore_fpu_crsr(fpu: &FpuRegs) {
 /// # Safety
 ///
 /// Use neon only for (re)storing Rec's simd context
+#[cfg(target_arch = "aarch64")]
 #[target_feature(enable = "sve")]
 unsafe fn save_sve(sve: &mut SveRegs, save_ffr: bool) {
     let addr_z: u64 = sve.z.as_ptr() as u64;
@@ -305,6 +382,7 @@ unsafe fn save_sve(sve: &mut SveRegs, save_ffr: bool) {
 /// # Safety
 ///
 /// Use neon only for (re)storing Rec's simd context
+#[cfg(target_arch = "aarch64")]
 #[inline(never)]
 #[target_feature(enable = "sve")]
 pub unsafe fn restore_sve(sve: &SveRegs, restore_ffr: bool) {
@@ -379,17 +457,156 @@ pub unsafe fn restore_sve(sve: &SveRegs, restore_ffr: bool) {
     }
 }
 
-fn preserve_ffr(svcr: u64) -> bool {
+/// # Safety
+///
+/// Use sme only for (re)storing Rec's simd context.
+/// PSTATE.ZA must be set, and ZA is stored with the current SVL.
+#[cfg(target_arch = "aarch64")]
+unsafe fn save_za(za: &mut ZaRegs) {
+    let addr_za: u64 = za.za.as_ptr() as u64;
+    unsafe {
+        asm!(
+            ".arch_extension sme",
+            "rdsvl {svl_b}, #1",
+            "mov w12, #0",
+            "2:",
+            "str za[w12, 0], [{addr_za}]",
+            "add {addr_za}, {addr_za}, {svl_b}",
+            "add w12, w12, #1",
+            "cmp w12, {svl_b:w}",
+            "b.lo 2b",
+            addr_za = inout(reg) addr_za => _,
+            svl_b = out(reg) _,
+            out("x12") _,
+        );
+    }
+}
+
+/// # Safety
+///
+/// Use sme only for (re)storing Rec's simd context.
+/// PSTATE.ZA must be set, and ZA is loaded with the current SVL.
+#[cfg(target_arch = "aarch64")]
+unsafe fn restore_za(za: &ZaRegs) {
+    let addr_za: u64 = za.za.as_ptr() as u64;
+    unsafe {
+        asm!(
+            ".arch_extension sme",
+            "rdsvl {svl_b}, #1",
+            "mov w12, #0",
+            "2:",
+            "ldr za[w12, 0], [{addr_za}]",
+            "add {addr_za}, {addr_za}, {svl_b}",
+            "add w12, w12, #1",
+            "cmp w12, {svl_b:w}",
+            "b.lo 2b",
+            addr_za = inout(reg) addr_za => _,
+            svl_b = out(reg) _,
+            out("x12") _,
+        );
+    }
+}
+
+/// # Safety
+///
+/// Use sme2 only for (re)storing Rec's simd context.
+/// PSTATE.ZA must be set.
+#[cfg(target_arch = "aarch64")]
+unsafe fn save_zt0(sme: &mut SmeRegs) {
+    let addr_zt0: u64 = sme.zt0.as_ptr() as u64;
+    unsafe {
+        asm!(
+            ".arch_extension sme2",
+            "str zt0, [{addr_zt0}]",
+            addr_zt0 = in(reg) addr_zt0,
+        );
+    }
+}
+
+/// # Safety
+///
+/// Use sme2 only for (re)storing Rec's simd context.
+/// PSTATE.ZA must be set.
+#[cfg(target_arch = "aarch64")]
+unsafe fn restore_zt0(sme: &SmeRegs) {
+    let addr_zt0: u64 = sme.zt0.as_ptr() as u64;
+    unsafe {
+        asm!(
+            ".arch_extension sme2",
+            "ldr zt0, [{addr_zt0}]",
+            addr_zt0 = in(reg) addr_zt0,
+        );
+    }
+}
+
+// Hosted builds on other architectures (e.g., rmm/sim on x86) never run realms,
+// so the SIMD context of RECs is not switched there.
+#[cfg(not(target_arch = "aarch64"))]
+mod host {
+    use super::{FpuRegs, SmeRegs, SveRegs, ZaRegs};
+
+    pub unsafe fn save_fpu(_fpu: &mut FpuRegs) {
+        unimplemented!()
+    }
+    pub unsafe fn save_fpu_crsr(_fpu: &mut FpuRegs) {
+        unimplemented!()
+    }
+    pub unsafe fn restore_fpu(_fpu: &FpuRegs) {
+        unimplemented!()
+    }
+    pub unsafe fn restore_fpu_crsr(_fpu: &FpuRegs) {
+        unimplemented!()
+    }
+    pub unsafe fn save_sve(_sve: &mut SveRegs, _save_ffr: bool) {
+        unimplemented!()
+    }
+    pub unsafe fn restore_sve(_sve: &SveRegs, _restore_ffr: bool) {
+        unimplemented!()
+    }
+    pub unsafe fn save_za(_za: &mut ZaRegs) {
+        unimplemented!()
+    }
+    pub unsafe fn restore_za(_za: &ZaRegs) {
+        unimplemented!()
+    }
+    pub unsafe fn save_zt0(_sme: &mut SmeRegs) {
+        unimplemented!()
+    }
+    pub unsafe fn restore_zt0(_sme: &SmeRegs) {
+        unimplemented!()
+    }
+}
+#[cfg(not(target_arch = "aarch64"))]
+use host::*;
+
+// FFR is accessible in streaming mode only with FEAT_SME_FA64
+fn preserve_ffr(svcr: u64, smcr_el2: u64) -> bool {
     let svcr: InMemoryRegister<u64, SVCR::Register> = InMemoryRegister::new(svcr);
+    let smcr_el2: InMemoryRegister<u64, SMCR_EL2::Register> = InMemoryRegister::new(smcr_el2);
     let mut rtn = true;
 
     let is_streaming = sme_en() && svcr.read(SVCR::SM) != 0;
     if is_streaming {
-        rtn = SMCR_EL2.read(SMCR_EL2::FA64) != 0;
+        rtn = smcr_el2.read(SMCR_EL2::FA64) != 0;
     }
     rtn
 }
 
+fn za_enabled(svcr: u64) -> bool {
+    let svcr: InMemoryRegister<u64, SVCR::Register> = InMemoryRegister::new(svcr);
+    sme_en() && svcr.read(SVCR::ZA) != 0
+}
+
+// SMCR_EL2 while (re)storing NS's context.
+// The rec's len is used to prevent cross-world leakage,
+// and ZT0 is kept accessible on FEAT_SME2.
+fn ns_smcr_el2(rec_sme: &SmeRegs) -> u64 {
+    match sme2_en() {
+        true => rec_sme.smcr_el2 | SMCR_EL2::EZT0::SET.value,
+        false => rec_sme.smcr_el2,
+    }
+}
+
 // This function is called when a SIMD access in Realm
 // is made for the first time since REC_ENTER.
 // See exception/trap.rs.
@@ -414,20 +631,42 @@ pub fn restore_state_lazy(rec: &Rec<'_>) {
         // To prevent cross-world leakage, set to rec's len
         ZCR_EL2.set(rec_simd.sve.zcr_el2);
         ZCR_EL1.set(rec_simd.sve.zcr_el12);
+        if sme_en() {
+            ns_simd.sme.smcr_el2 = SMCR_EL2.get();
+            ns_simd.sme.smcr_el12 = SMCR_EL1.get();
+            ns_simd.sme.tpidr2_el0 = TPIDR2_EL0.get();
+            SMCR_EL2.set(ns_smcr_el2(&rec_simd.sme));
+            SMCR_EL1.set(rec_simd.sme.smcr_el12);
+            TPIDR2_EL0.set(rec_simd.sme.tpidr2_el0);
+        }
         #[cfg(not(any(test, miri, fuzzing)))]
         unsafe {
             // ns_simd.svcr is save at restore_state() on REC_ENTER
-            let save_ffr = preserve_ffr(ns_simd.svcr);
+            let save_ffr = preserve_ffr(ns_simd.svcr, ns_smcr_el2(&rec_simd.sme));
             save_sve(&mut ns_simd.sve, save_ffr);
             save_fpu_crsr(&mut ns_simd.fpu);
             if sme_en() {
+                // ZA storage is zeroed out when SVCR.ZA changes
+                if za_enabled(ns_simd.svcr) {
+                    save_za(&mut NS_ZA[get_cpu_id()].lock());
+                    if sme2_en() {
+                        save_zt0(&mut ns_simd.sme);
+                    }
+                }
                 SVCR.set(rec_simd.svcr);
+                SMCR_EL2.set(rec_simd.sme.smcr_el2);
             }
 
             if rec_simd_ctxt.is_saved {
-                let restore_ffr = true; // Sinde Realm is not supported with SME, it's always true.
+                let restore_ffr = preserve_ffr(rec_simd.svcr, rec_simd.sme.smcr_el2);
                 restore_sve(&rec_simd.sve, restore_ffr);
                 restore_fpu_crsr(&rec_simd.fpu);
+                if za_enabled(rec_simd.svcr) {
+                    restore_za_lazy(rec);
+                    if rec_simd_ctxt.cfg.sme2_en {
+                        restore_zt0(&rec_simd.sme);
+                    }
+                }
             }
         }
     } else {
@@ -438,6 +677,27 @@ pub fn restore_state_lazy(rec: &Rec<'_>) {
             }
         }
     }
+
+    // Keep trapping SME instructions for Realms without SME
+    if !rec_simd_ctxt.cfg.sme_en {
+        CPTR_EL2.write(CPTR_EL2::TAM::SET + CPTR_EL2::TSM::SET);
+    }
+}
+
+#[cfg(not(any(test, miri, fuzzing)))]
+unsafe fn restore_za_lazy(rec: &Rec<'_>) {
+    let sme_aux = rec.aux(RecAuxIndex::SME as usize) as usize;
+    let sme_granule = match get_granule_if!(sme_aux, GranuleState::RecAux) {
+        Ok(guard) => guard,
+        Err(_e) => {
+            error!("Unable to get RecAux granule at 0x{:x}", sme_aux);
+            return;
+        }
+    };
+    let rec_za = sme_granule.content::<ZaRegs>().unwrap();
+    unsafe {
+        restore_za(&rec_za);
+    }
 }
 
 pub fn restore_state(rec: &Rec<'_>) {
@@ -471,6 +731,7 @@ pub fn restore_state(rec: &Rec<'_>) {
 
 pub fn save_state(rec: &mut Rec<'_>) {
     let simd_aux = rec.aux(RecAuxIndex::SIMD as usize) as usize;
+    let sme_aux = rec.aux(RecAuxIndex::SME as usize) as usize;
     let rec_simd_ctxt = &mut rec.context.simd;
     let mut simd_granule = match get_granule_if!(simd_aux, GranuleState::RecAux) {
         Ok(guard) => guard,
@@ -493,27 +754,56 @@ pub fn save_state(rec: &mut Rec<'_>) {
     }
     // Disable simd traps during the context mgmt.
     CPTR_EL2.write(CPTR_EL2::TAM::SET);
-    // Since FEAT_SME is not for Realms, no need to store SVCR which doesn't change.
 
     if rec_simd_ctxt.cfg.sve_en {
         rec_simd.sve.zcr_el2 = ZCR_EL2.get();
         rec_simd.sve.zcr_el12 = ZCR_EL1.get();
+        if sme_en() {
+            rec_simd.svcr = SVCR.get();
+            rec_simd.sme.smcr_el12 = SMCR_EL1.get();
+            rec_simd.sme.tpidr2_el0 = TPIDR2_EL0.get();
+        }
+        let ns_smcr_el2 = ns_smcr_el2(&rec_simd.sme);
         unsafe {
-            let save_ffr = true; // Since FEAT_SME is not for Realms, it's always true.
+            let save_ffr = preserve_ffr(rec_simd.svcr, rec_simd.sme.smcr_el2);
             save_sve(&mut rec_simd.sve, save_ffr);
             save_fpu_crsr(&mut rec_simd.fpu);
-            // Set SVCR before loading context.
-            // Otherwise, when SVCR:SM is 0, all simd registers are set to zero.
             if sme_en() {
+                if za_enabled(rec_simd.svcr) {
+                    match get_granule_if!(sme_aux, GranuleState::RecAux) {
+                        Ok(mut sme_granule) => {
+                            let mut rec_za = sme_granule.content_mut::<ZaRegs>().unwrap();
+                            save_za(&mut rec_za);
+                        }
+                        Err(_e) => error!("Unable to get RecAux granule at 0x{:x}", sme_aux),
+                    }
+                    if rec_simd_ctxt.cfg.sme2_en {
+                        save_zt0(&mut rec_simd.sme);
+                    }
+                }
+                // Set SVCR before loading context.
+                // Otherwise, when SVCR:SM is 0, all simd registers are set to zero.
+                SMCR_EL2.set(ns_smcr_el2);
                 SVCR.set(ns_simd.svcr);
+                if za_enabled(ns_simd.svcr) {
+                    restore_za(&NS_ZA[get_cpu_id()].lock());
+                    if sme2_en() {
+                        restore_zt0(&ns_simd.sme);
+                    }
+                }
             }
             // To prevent cross-world leakage, restore ns's context of rec's len.
-            let restore_ffr = preserve_ffr(ns_simd.svcr);
+            let restore_ffr = preserve_ffr(ns_simd.svcr, ns_smcr_el2);
             restore_sve(&ns_simd.sve, restore_ffr);
             restore_fpu_crsr(&ns_simd.fpu);
         }
         ZCR_EL2.set(ns_simd.sve.zcr_el2);
         ZCR_EL1.set(ns_simd.sve.zcr_el12);
+        if sme_en() {
+            SMCR_EL2.set(ns_simd.sme.smcr_el2);
+            SMCR_EL1.set(ns_simd.sme.smcr_el12);
+            TPIDR2_EL0.set(ns_simd.sme.tpidr2_el0);
+        }
     } else {
         // For SIMD and FPU
         unsafe {
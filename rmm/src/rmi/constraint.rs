@@ -1,8 +1,8 @@
 use crate::config::SMCCC_1_3_SVE_HINT;
-use crate::event::{Command, Context};
+use crate::event::rmihandle::{self, FID_CNT};
+use crate::event::{Command, Context, FidTable};
 use crate::rmi;
 
-#[allow(dead_code)]
 #[derive(Default, Copy, Clone)]
 pub struct Constraint {
     pub cmd: Command,
@@ -12,7 +12,7 @@ pub struct Constraint {
 }
 
 impl Constraint {
-    pub fn new(cmd: Command, arg_num: usize, ret_num: usize) -> Constraint {
+    pub const fn new(cmd: Command, arg_num: usize, ret_num: usize) -> Constraint {
         Constraint {
             cmd,
             arg_num,
@@ -21,46 +21,89 @@ impl Constraint {
     }
 }
 
-fn pick(cmd: Command) -> Option<Constraint> {
-    let constraint = match cmd {
-        rmi::VERSION => Constraint::new(rmi::VERSION, 2, 3),
-        rmi::GRANULE_DELEGATE => Constraint::new(rmi::GRANULE_DELEGATE, 2, 1),
-        rmi::GRANULE_UNDELEGATE => Constraint::new(rmi::GRANULE_UNDELEGATE, 2, 1),
-        rmi::DATA_CREATE => Constraint::new(rmi::DATA_CREATE, 6, 1),
-        rmi::DATA_CREATE_UNKNOWN => Constraint::new(rmi::DATA_CREATE_UNKNOWN, 4, 1),
-        rmi::DATA_DESTROY => Constraint::new(rmi::DATA_DESTROY, 3, 3),
-        rmi::REALM_ACTIVATE => Constraint::new(rmi::REALM_ACTIVATE, 2, 1),
+// Looked up by the offset of function IDs, without building constraints per command
+static CONSTRAINTS: FidTable<Constraint, FID_CNT> = build(
+    rmihandle::table(),
+    &[
+        Constraint::new(rmi::VERSION, 2, 3),
+        Constraint::new(rmi::GRANULE_DELEGATE, 2, 1),
+        Constraint::new(rmi::GRANULE_UNDELEGATE, 2, 1),
+        Constraint::new(rmi::DATA_CREATE, 6, 1),
+        Constraint::new(rmi::DATA_CREATE_UNKNOWN, 4, 1),
+        Constraint::new(rmi::DATA_DESTROY, 3, 3),
+        Constraint::new(rmi::REALM_ACTIVATE, 2, 1),
         // NOTE: REALM_CREATE has 3 of arg_num and 1 of ret_num according to the specification,
         //       but we're using one more return value for our own purpose.
-        rmi::REALM_CREATE => Constraint::new(rmi::REALM_CREATE, 3, 2),
-        rmi::REALM_DESTROY => Constraint::new(rmi::REALM_DESTROY, 2, 1),
+        Constraint::new(rmi::REALM_CREATE, 3, 2),
+        Constraint::new(rmi::REALM_DESTROY, 2, 1),
         // NOTE: REC_CREATE has 4 of arg_num and 1 of ret_num according to the specification,
         //       but we're using one more return value for our own purpose.
-        rmi::REC_CREATE => Constraint::new(rmi::REC_CREATE, 4, 2),
-        rmi::REC_DESTROY => Constraint::new(rmi::REC_DESTROY, 2, 1),
-        rmi::REC_ENTER => Constraint::new(rmi::REC_ENTER, 3, 1),
-        rmi::RTT_MAP_UNPROTECTED => Constraint::new(rmi::RTT_MAP_UNPROTECTED, 5, 1),
-        rmi::RTT_UNMAP_UNPROTECTED => Constraint::new(rmi::RTT_UNMAP_UNPROTECTED, 4, 2),
-        rmi::RTT_READ_ENTRY => Constraint::new(rmi::RTT_READ_ENTRY, 4, 5),
-        rmi::FEATURES => Constraint::new(rmi::FEATURES, 2, 2),
-        rmi::REC_AUX_COUNT => Constraint::new(rmi::REC_AUX_COUNT, 2, 2),
-        rmi::RTT_CREATE => Constraint::new(rmi::RTT_CREATE, 5, 1),
-        rmi::RTT_DESTROY => Constraint::new(rmi::RTT_DESTROY, 4, 3),
-        rmi::RTT_INIT_RIPAS => Constraint::new(rmi::RTT_INIT_RIPAS, 4, 2),
-        rmi::RTT_SET_RIPAS => Constraint::new(rmi::RTT_SET_RIPAS, 5, 2),
-        rmi::RTT_FOLD => Constraint::new(rmi::RTT_FOLD, 4, 2),
+        Constraint::new(rmi::REC_CREATE, 4, 2),
+        Constraint::new(rmi::REC_DESTROY, 2, 1),
+        Constraint::new(rmi::REC_ENTER, 3, 1),
+        Constraint::new(rmi::RTT_MAP_UNPROTECTED, 5, 1),
+        Constraint::new(rmi::RTT_UNMAP_UNPROTECTED, 4, 2),
+        Constraint::new(rmi::RTT_READ_ENTRY, 4, 5),
+        Constraint::new(rmi::FEATURES, 2, 2),
+        Constraint::new(rmi::REC_AUX_COUNT, 2, 2),
+        Constraint::new(rmi::RTT_CREATE, 5, 1),
+        Constraint::new(rmi::RTT_DESTROY, 4, 3),
+        Constraint::new(rmi::RTT_INIT_RIPAS, 4, 2),
+        Constraint::new(rmi::RTT_SET_RIPAS, 5, 2),
+        Constraint::new(rmi::RTT_FOLD, 4, 2),
+        Constraint::new(rmi::RTT_AUX_CREATE, 6, 1),
+        Constraint::new(rmi::RTT_AUX_DESTROY, 5, 2),
+        Constraint::new(rmi::RTT_AUX_MAP_PROTECTED, 4, 1),
+        Constraint::new(rmi::RTT_AUX_UNMAP_PROTECTED, 4, 1),
+        Constraint::new(rmi::PDEV_AUX_COUNT, 2, 2),
+        Constraint::new(rmi::DEV_MEM_MAP, 5, 1),
+        Constraint::new(rmi::DEV_MEM_UNMAP, 4, 2),
+        Constraint::new(rmi::PDEV_ABORT, 2, 1),
+        Constraint::new(rmi::PDEV_COMMUNICATE, 3, 1),
+        Constraint::new(rmi::PDEV_CREATE, 3, 1),
+        Constraint::new(rmi::PDEV_DESTROY, 2, 1),
+        Constraint::new(rmi::PDEV_GET_STATE, 2, 2),
+        Constraint::new(rmi::PDEV_IDE_RESET, 2, 1),
+        Constraint::new(rmi::PDEV_NOTIFY, 3, 1),
+        Constraint::new(rmi::PDEV_SET_PUBKEY, 3, 1),
+        Constraint::new(rmi::PDEV_STOP, 2, 1),
+        Constraint::new(rmi::VDEV_ABORT, 2, 1),
+        Constraint::new(rmi::VDEV_COMMUNICATE, 3, 1),
+        Constraint::new(rmi::VDEV_CREATE, 5, 1),
+        Constraint::new(rmi::VDEV_DESTROY, 2, 1),
+        Constraint::new(rmi::VDEV_GET_STATE, 2, 2),
+        Constraint::new(rmi::VDEV_STOP, 2, 1),
         // XXX: REQ_COMPLETE do not exist in the spec
-        rmi::REQ_COMPLETE => Constraint::new(rmi::REQ_COMPLETE, 4, 2),
-        rmi::PSCI_COMPLETE => Constraint::new(rmi::PSCI_COMPLETE, 4, 1),
-        rmi::ISLET_REALM_SET_METADATA => Constraint::new(rmi::ISLET_REALM_SET_METADATA, 4, 1),
-        _ => return None,
-    };
-    Some(constraint)
+        Constraint::new(rmi::REQ_COMPLETE, 4, 2),
+        Constraint::new(rmi::PSCI_COMPLETE, 4, 1),
+        Constraint::new(rmi::ISLET_REALM_SET_METADATA, 4, 1),
+        Constraint::new(rmi::ISLET_STATS_READ, 3, 2),
+        Constraint::new(rmi::ISLET_STATS_RESET, 1, 1),
+        Constraint::new(rmi::ISLET_TRACE_READ, 4, 2),
+        Constraint::new(rmi::ISLET_LOG_SET_FILTER, 4, 1),
+        Constraint::new(rmi::ISLET_LOG_SET_SINKS, 2, 1),
+        Constraint::new(rmi::ISLET_LOG_DRAIN, 3, 3),
+        Constraint::new(rmi::ISLET_REPLAY_READ, 4, 2),
+        Constraint::new(rmi::ISLET_REPLAY_RESET, 1, 1),
+    ],
+);
+
+/// Fills the table with the constraints
+pub const fn build<const N: usize>(
+    mut table: FidTable<Constraint, N>,
+    list: &[Constraint],
+) -> FidTable<Constraint, N> {
+    let mut i = 0;
+    while i < list.len() {
+        table.insert(list[i].cmd, list[i]);
+        i += 1;
+    }
+    table
 }
 
 pub fn validate(cmd: Command, arg: &[usize]) -> Context {
     let fid = cmd & !SMCCC_1_3_SVE_HINT;
-    if let Some(c) = pick(fid) {
+    if let Some(c) = CONSTRAINTS.get(fid) {
         let mut ctx = Context::new(fid);
         // SMC calling convention requires x4-x7 to be preserved unless used.
         // Since the rmmd in EL3 takes care of preserving x5-x7,
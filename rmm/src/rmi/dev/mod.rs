@@ -0,0 +1,699 @@
+pub mod params;
+
+use self::params::{DevCommData, DevCommExit, PdevParams, PubkeyParams, VdevParams};
+use crate::dev::pdev::{self, Pdev, PDEV_NUM_AUX};
+use crate::dev::vdev::{self, Vdev};
+use crate::dev::{self as device, DevObject, COMM_EXIT_FLAG_SEND, COMM_STATUS_RESPONSE};
+use crate::event::RmiHandle;
+#[cfg(feature = "gst_page_table")]
+use crate::granule::set_granule_with_parent;
+use crate::granule::{set_granule, GranuleState, GRANULE_SIZE};
+use crate::host;
+use crate::host::DataPage;
+use crate::listen;
+use crate::realm::mm::rtt::{self, RTT_PAGE_LEVEL};
+use crate::realm::mm::stage2_tte::S2TTE;
+use crate::realm::rd::Rd;
+use crate::rmi;
+use crate::rmi::error::Error;
+use crate::rmi::features;
+use crate::rmi::rtt::validate_ipa;
+use crate::Monitor;
+use crate::{get_granule, get_granule_if};
+
+// Relays one message of the operation in flight between the device object
+// and the host, through the DevCommData object at `data_ptr`.
+fn communicate<T: DevObject>(obj: &mut T, data_ptr: usize, rmm: &Monitor) -> Result<(), Error> {
+    rmm.page_table.map(data_ptr, false);
+    let data = host::copy_from::<DevCommData>(data_ptr).ok_or(Error::RmiErrorInput);
+    rmm.page_table.unmap(data_ptr);
+    let mut data = data?;
+    data.verify_compliance()?;
+    trace!("{:?}", data);
+
+    let enter = data.enter;
+    let mut resp = DataPage::default();
+    if enter.status == COMM_STATUS_RESPONSE {
+        let resp_addr = enter.resp_addr as usize;
+        rmm.page_table.map(resp_addr, false);
+        let ret = host::copy_to_obj::<DataPage>(resp_addr, &mut resp);
+        rmm.page_table.unmap(resp_addr);
+        ret.ok_or(Error::RmiErrorInput)?;
+    }
+
+    let mut req = DataPage::default();
+    let exchange = device::communicate(
+        obj,
+        enter.status,
+        &resp.as_slice()[..enter.resp_len as usize],
+        req.as_mut_slice(),
+    )?;
+
+    data.exit = DevCommExit::default();
+    if let Some(exchange) = exchange {
+        let req_addr = enter.req_addr as usize;
+        rmm.page_table.map(req_addr, false);
+        let ret = host::copy_to_ptr::<DataPage>(&req, req_addr);
+        rmm.page_table.unmap(req_addr);
+        ret.ok_or(Error::RmiErrorInput)?;
+
+        data.exit.flags = COMM_EXIT_FLAG_SEND;
+        data.exit.protocol = exchange.protocol;
+        data.exit.req_len = exchange.len as u64;
+    }
+
+    rmm.page_table.map(data_ptr, false);
+    let ret = host::copy_to_ptr::<DevCommData>(&data, data_ptr);
+    rmm.page_table.unmap(data_ptr);
+    ret.ok_or(Error::RmiErrorInput)
+}
+
+// Gives the aux granules of a PDEV which failed to be created back to the host.
+fn release_aux(aux: &[usize], rmm: &Monitor) {
+    for addr in aux {
+        if let Ok(mut aux_granule) = get_granule_if!(*addr, GranuleState::PdevAux) {
+            let _ = set_granule(&mut aux_granule, GranuleState::Delegated);
+        }
+        rmm.page_table.unmap(*addr);
+    }
+}
+
+pub fn set_event_handler(rmi: &mut RmiHandle) {
+    listen!(rmi, rmi::PDEV_AUX_COUNT, |_arg, ret, _| {
+        ret[1] = PDEV_NUM_AUX;
+        Ok(())
+    });
+
+    listen!(rmi, rmi::PDEV_CREATE, |arg, _ret, rmm| {
+        let pdev = arg[0];
+        let params_ptr = arg[1];
+
+        if pdev == params_ptr || !features::da_supported() {
+            return Err(Error::RmiErrorInput);
+        }
+
+        rmm.page_table.map(params_ptr, false);
+        let params = host::copy_from::<PdevParams>(params_ptr).ok_or(Error::RmiErrorInput);
+        rmm.page_table.unmap(params_ptr);
+        let params = params?;
+        trace!("{:?}", params);
+        params.verify_compliance(pdev, params_ptr)?;
+
+        let mut pdev_granule = get_granule_if!(pdev, GranuleState::Delegated)?;
+        rmm.page_table.map(pdev, true);
+
+        let aux: [usize; PDEV_NUM_AUX] = core::array::from_fn(|i| params.aux[i] as usize);
+        let ncoh_num = params.ncoh_num as usize;
+        let ncoh_base = params.ncoh_base.map(|base| base as usize);
+        let ncoh_top = params.ncoh_top.map(|top| top as usize);
+
+        let mut pdev_obj = pdev_granule.content_mut::<Pdev>().inspect_err(|_| {
+            rmm.page_table.unmap(pdev);
+        })?;
+        pdev_obj.init(
+            params.pdev_id,
+            params.segment_id,
+            params.ecam_addr,
+            params.root_id,
+            params.hash_algo,
+            &aux,
+            &ncoh_base[..ncoh_num],
+            &ncoh_top[..ncoh_num],
+        );
+
+        // The aux granules are the last to change their states. If one of them
+        // has been taken away since verify_compliance(), give back the others.
+        for (i, addr) in aux.iter().enumerate() {
+            rmm.page_table.map(*addr, true);
+            let transit = || -> Result<(), Error> {
+                let mut aux_granule = get_granule_if!(*addr, GranuleState::Delegated)?;
+                set_granule(&mut aux_granule, GranuleState::PdevAux)
+            };
+            if let Err(e) = transit() {
+                rmm.page_table.unmap(*addr);
+                release_aux(&aux[..i], rmm);
+                rmm.page_table.unmap(pdev);
+                return Err(e);
+            }
+        }
+        set_granule(&mut pdev_granule, GranuleState::Pdev)
+    });
+
+    listen!(rmi, rmi::PDEV_DESTROY, |arg, _ret, rmm| {
+        let pdev = arg[0];
+        let mut pdev_granule = get_granule_if!(pdev, GranuleState::Pdev)?;
+        let pdev_obj = pdev_granule.content::<Pdev>()?;
+        if pdev_obj.state() != pdev::State::Stopped || pdev_obj.num_vdevs() != 0 {
+            return Err(Error::RmiErrorInput);
+        }
+
+        for addr in pdev_obj.aux() {
+            let mut aux_granule = get_granule_if!(*addr, GranuleState::PdevAux)?;
+            set_granule(&mut aux_granule, GranuleState::Delegated)?;
+            rmm.page_table.unmap(*addr);
+        }
+
+        set_granule(&mut pdev_granule, GranuleState::Delegated)?;
+        rmm.page_table.unmap(pdev);
+        Ok(())
+    });
+
+    listen!(rmi, rmi::PDEV_COMMUNICATE, |arg, _ret, rmm| {
+        let mut pdev_granule = get_granule_if!(arg[0], GranuleState::Pdev)?;
+        let mut pdev = pdev_granule.content_mut::<Pdev>()?;
+        communicate(&mut *pdev, arg[1], rmm)
+    });
+
+    listen!(rmi, rmi::PDEV_ABORT, |arg, _ret, _| {
+        let mut pdev_granule = get_granule_if!(arg[0], GranuleState::Pdev)?;
+        let mut pdev = pdev_granule.content_mut::<Pdev>()?;
+        pdev.abort();
+        Ok(())
+    });
+
+    listen!(rmi, rmi::PDEV_GET_STATE, |arg, ret, _| {
+        let pdev_granule = get_granule_if!(arg[0], GranuleState::Pdev)?;
+        let pdev = pdev_granule.content::<Pdev>()?;
+        ret[1] = pdev.state() as usize;
+        Ok(())
+    });
+
+    listen!(rmi, rmi::PDEV_SET_PUBKEY, |arg, _ret, rmm| {
+        let params_ptr = arg[1];
+        let mut pdev_granule = get_granule_if!(arg[0], GranuleState::Pdev)?;
+        let mut pdev = pdev_granule.content_mut::<Pdev>()?;
+        if pdev.state() != pdev::State::NeedsKey {
+            return Err(Error::RmiErrorInput);
+        }
+
+        rmm.page_table.map(params_ptr, false);
+        let params = host::copy_from::<PubkeyParams>(params_ptr).ok_or(Error::RmiErrorInput);
+        rmm.page_table.unmap(params_ptr);
+        let params = params?;
+
+        let key = params
+            .key
+            .get(..params.key_len as usize)
+            .ok_or(Error::RmiErrorInput)?;
+        pdev.set_pubkey(params.algo, key)?;
+        pdev.set_state(pdev::State::HasKey);
+        Ok(())
+    });
+
+    listen!(rmi, rmi::PDEV_IDE_RESET, |arg, _ret, _| {
+        let mut pdev_granule = get_granule_if!(arg[0], GranuleState::Pdev)?;
+        let mut pdev = pdev_granule.content_mut::<Pdev>()?;
+        if pdev.state() != pdev::State::Ready {
+            return Err(Error::RmiErrorInput);
+        }
+        pdev.set_state(pdev::State::IdeResetting);
+        Ok(())
+    });
+
+    listen!(rmi, rmi::PDEV_NOTIFY, |arg, _ret, _| {
+        let mut pdev_granule = get_granule_if!(arg[0], GranuleState::Pdev)?;
+        let mut pdev = pdev_granule.content_mut::<Pdev>()?;
+        match pdev.state() {
+            pdev::State::Stopping | pdev::State::Stopped => Err(Error::RmiErrorInput),
+            _ => {
+                warn!(
+                    "PDEV {:#X} notified of an event {:#X}",
+                    pdev.pdev_id(),
+                    arg[1]
+                );
+                pdev.abort();
+                pdev.set_state(pdev::State::Error);
+                Ok(())
+            }
+        }
+    });
+
+    listen!(rmi, rmi::PDEV_STOP, |arg, _ret, _| {
+        let mut pdev_granule = get_granule_if!(arg[0], GranuleState::Pdev)?;
+        let mut pdev = pdev_granule.content_mut::<Pdev>()?;
+        if pdev.num_vdevs() != 0 {
+            return Err(Error::RmiErrorInUse);
+        }
+        let next = match pdev.state() {
+            // The secure session has to be closed first
+            pdev::State::Ready | pdev::State::IdeResetting => pdev::State::Stopping,
+            pdev::State::Stopping | pdev::State::Stopped => return Err(Error::RmiErrorInput),
+            _ => pdev::State::Stopped,
+        };
+        pdev.abort();
+        pdev.set_state(next);
+        Ok(())
+    });
+
+    listen!(rmi, rmi::VDEV_CREATE, |arg, _ret, rmm| {
+        let rd = arg[0];
+        let pdev = arg[1];
+        let vdev = arg[2];
+        let params_ptr = arg[3];
+
+        if vdev == rd || vdev == pdev || vdev == params_ptr {
+            return Err(Error::RmiErrorInput);
+        }
+
+        rmm.page_table.map(params_ptr, false);
+        let params = host::copy_from::<VdevParams>(params_ptr).ok_or(Error::RmiErrorInput);
+        rmm.page_table.unmap(params_ptr);
+        let params = params?;
+        trace!("{:?}", params);
+        let tdi_id = u32::try_from(params.tdi_id).or(Err(Error::RmiErrorInput))?;
+
+        let mut rd_granule = get_granule_if!(rd, GranuleState::RD)?;
+        let mut rd_obj = rd_granule.content_mut::<Rd>()?;
+        if !rd_obj.da_en() {
+            return Err(Error::RmiErrorRealm(0));
+        }
+
+        let mut pdev_granule = get_granule_if!(pdev, GranuleState::Pdev)?;
+        let mut pdev_obj = pdev_granule.content_mut::<Pdev>()?;
+        if pdev_obj.state() != pdev::State::Ready {
+            return Err(Error::RmiErrorInput);
+        }
+
+        let mut vdev_granule = get_granule_if!(vdev, GranuleState::Delegated)?;
+        rd_obj.add_vdev(vdev, params.vdev_id)?;
+        rmm.page_table.map(vdev, true);
+        let mut vdev_obj = vdev_granule.content_mut::<Vdev>()?;
+        vdev_obj.init(
+            rd,
+            pdev,
+            params.vdev_id,
+            tdi_id,
+            pdev_obj.hash_algo(),
+            pdev_obj.cert_digest(),
+        );
+        pdev_obj.inc_vdevs();
+
+        #[cfg(not(feature = "gst_page_table"))]
+        rd_granule.inc_count();
+
+        #[cfg(feature = "gst_page_table")]
+        return set_granule_with_parent(rd_granule.clone(), &mut vdev_granule, GranuleState::Vdev);
+        #[cfg(not(feature = "gst_page_table"))]
+        return set_granule(&mut vdev_granule, GranuleState::Vdev);
+    });
+
+    listen!(rmi, rmi::VDEV_DESTROY, |arg, _ret, rmm| {
+        let vdev = arg[0];
+        let mut vdev_granule = get_granule_if!(vdev, GranuleState::Vdev)?;
+        let vdev_obj = vdev_granule.content::<Vdev>()?;
+        if vdev_obj.state() != vdev::State::Stopped || vdev_obj.num_maps() != 0 {
+            return Err(Error::RmiErrorInput);
+        }
+
+        let mut pdev_granule = get_granule_if!(vdev_obj.pdev(), GranuleState::Pdev)?;
+        let mut pdev_obj = pdev_granule.content_mut::<Pdev>()?;
+        pdev_obj.dec_vdevs();
+
+        let mut rd_granule = get_granule_if!(vdev_obj.rd(), GranuleState::RD)?;
+        #[cfg(not(feature = "gst_page_table"))]
+        rd_granule.dec_count();
+        let mut rd_obj = rd_granule.content_mut::<Rd>()?;
+        rd_obj.remove_vdev(vdev);
+
+        set_granule(&mut vdev_granule, GranuleState::Delegated)?;
+        rmm.page_table.unmap(vdev);
+        Ok(())
+    });
+
+    listen!(rmi, rmi::VDEV_COMMUNICATE, |arg, _ret, rmm| {
+        let mut vdev_granule = get_granule_if!(arg[0], GranuleState::Vdev)?;
+        let mut vdev = vdev_granule.content_mut::<Vdev>()?;
+        communicate(&mut *vdev, arg[1], rmm)
+    });
+
+    listen!(rmi, rmi::VDEV_ABORT, |arg, _ret, _| {
+        let mut vdev_granule = get_granule_if!(arg[0], GranuleState::Vdev)?;
+        let mut vdev = vdev_granule.content_mut::<Vdev>()?;
+        vdev.abort();
+        Ok(())
+    });
+
+    listen!(rmi, rmi::VDEV_GET_STATE, |arg, ret, _| {
+        let vdev_granule = get_granule_if!(arg[0], GranuleState::Vdev)?;
+        let vdev = vdev_granule.content::<Vdev>()?;
+        ret[1] = vdev.state() as usize;
+        Ok(())
+    });
+
+    listen!(rmi, rmi::VDEV_STOP, |arg, _ret, _| {
+        let mut vdev_granule = get_granule_if!(arg[0], GranuleState::Vdev)?;
+        let mut vdev = vdev_granule.content_mut::<Vdev>()?;
+        let next = match vdev.state() {
+            // The interface has to be stopped by the device first
+            vdev::State::Locked | vdev::State::Started => vdev::State::Stopping,
+            vdev::State::Stopping | vdev::State::Stopped => return Err(Error::RmiErrorInput),
+            _ => vdev::State::Stopped,
+        };
+        vdev.abort();
+        vdev.set_state(next);
+        Ok(())
+    });
+
+    listen!(rmi, rmi::DEV_MEM_MAP, |arg, _ret, _| {
+        let ipa = arg[2];
+        let pa = arg[3];
+
+        let rd_granule = get_granule_if!(arg[0], GranuleState::RD)?;
+        let rd = rd_granule.content::<Rd>()?;
+        validate_ipa(&rd.rtts(), ipa)?;
+
+        let mut vdev_granule = get_granule_if!(arg[1], GranuleState::Vdev)?;
+        let mut vdev = vdev_granule.content_mut::<Vdev>()?;
+        if vdev.rd() != arg[0] || !vdev.is_mappable() {
+            return Err(Error::RmiErrorInput);
+        }
+
+        let pdev_granule = get_granule_if!(vdev.pdev(), GranuleState::Pdev)?;
+        let pdev = pdev_granule.content::<Pdev>()?;
+        let top = pa.checked_add(GRANULE_SIZE).ok_or(Error::RmiErrorInput)?;
+        if pa % GRANULE_SIZE != 0 || !pdev.ncoh_contains(pa, top) {
+            return Err(Error::RmiErrorInput);
+        }
+
+        device::mem::delegate(pa)?;
+        rtt::dev_mem_map(&rd.rtts(), ipa, pa).inspect_err(|_| {
+            let _ = device::mem::undelegate(pa);
+        })?;
+        vdev.inc_maps();
+        Ok(())
+    });
+
+    listen!(rmi, rmi::DEV_MEM_UNMAP, |arg, ret, _| {
+        let ipa = arg[2];
+
+        let rd_granule = get_granule_if!(arg[0], GranuleState::RD)?;
+        let rd = rd_granule.content::<Rd>()?;
+        validate_ipa(&rd.rtts(), ipa)?;
+
+        let mut vdev_granule = get_granule_if!(arg[1], GranuleState::Vdev)?;
+        let mut vdev = vdev_granule.content_mut::<Vdev>()?;
+        if vdev.rd() != arg[0] || vdev.num_maps() == 0 {
+            return Err(Error::RmiErrorInput);
+        }
+
+        let (s2tte, _) = S2TTE::get_s2tte(&rd.rtts(), ipa, RTT_PAGE_LEVEL, Error::RmiErrorRtt(0))?;
+        let pa: usize = s2tte.addr_as_block(RTT_PAGE_LEVEL, rd.lpa2()).into();
+        let pdev_granule = get_granule_if!(vdev.pdev(), GranuleState::Pdev)?;
+        let pdev = pdev_granule.content::<Pdev>()?;
+        let top = pa.checked_add(GRANULE_SIZE).ok_or(Error::RmiErrorInput)?;
+        if !pdev.ncoh_contains(pa, top) {
+            return Err(Error::RmiErrorInput);
+        }
+
+        ret[1] = rtt::dev_mem_unmap(&rd.rtts(), ipa)?;
+        device::mem::undelegate(pa)?;
+        vdev.dec_maps();
+        Ok(())
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dev::mem;
+    use crate::dev::pdev::{self, Pdev, PUBKEY_ALGO_ECDSA_P256, PUBKEY_ALGO_RSASSA_3072};
+    use crate::dev::soft::{self, SoftPdev};
+    use crate::dev::vdev::{self, Vdev};
+    use crate::granule::GRANULE_SIZE;
+    use crate::realm::mm::rtt;
+    use crate::realm::rd::Rd;
+    use crate::rmi::dev::params::{PdevParams, PubkeyParams, VdevParams};
+    use crate::rmi::realm::Params as RealmParams;
+    use crate::rmi::*;
+    use crate::test_utils::{mock, *};
+
+    use sha2::{Digest, Sha256};
+
+    const TDI_ID: u32 = 0x10;
+    const VDEV_ID: u64 = 1;
+    const DEV_IPA: usize = 0x10000;
+
+    fn pdev_state(pdev: usize) -> usize {
+        let ret = rmi::<PDEV_GET_STATE>(&[pdev]);
+        assert_eq!(ret[0], SUCCESS);
+        ret[1]
+    }
+
+    fn vdev_state(vdev: usize) -> usize {
+        let ret = rmi::<VDEV_GET_STATE>(&[vdev]);
+        assert_eq!(ret[0], SUCCESS);
+        ret[1]
+    }
+
+    fn set_pubkey(pdev: usize, params_ptr: usize, algo: u8) {
+        let key = soft::public_key();
+        unsafe {
+            let params = &mut *(params_ptr as *mut PubkeyParams);
+            *params = PubkeyParams::default();
+            params.key[..key.len()].copy_from_slice(&key);
+            params.key_len = key.len() as u64;
+            params.algo = algo;
+        }
+    }
+
+    fn pdev_create(pdev: usize, aux: usize, params_ptr: usize) {
+        for mocking_addr in &[pdev, aux] {
+            let ret = rmi::<GRANULE_DELEGATE>(&[*mocking_addr]);
+            assert_eq!(ret[0], SUCCESS);
+        }
+        unsafe {
+            let params = &mut *(params_ptr as *mut PdevParams);
+            *params = PdevParams::default();
+            params.pdev_id = 0x100;
+            params.hash_algo = HASH_ALGO_SHA256;
+            params.num_aux = 1;
+            params.aux[0] = aux as u64;
+            params.ncoh_num = 1;
+            params.ncoh_base[0] = DEV_MMIO_BASE as u64;
+            params.ncoh_top[0] = (DEV_MMIO_BASE + DEV_MMIO_SIZE) as u64;
+        }
+        let ret = rmi::<PDEV_CREATE>(&[pdev, params_ptr]);
+        assert_eq!(ret[0], SUCCESS);
+    }
+
+    #[test]
+    fn rmi_pdev_forged_key_exchange() {
+        let (pdev, aux, params_ptr) = (
+            alloc_granule(IDX_PDEV),
+            alloc_granule(IDX_PDEV_AUX),
+            alloc_granule(IDX_DEV_PARAMS),
+        );
+        let mut dev = SoftPdev::new(TDI_ID);
+        pdev_create(pdev, aux, params_ptr);
+
+        mock::host::dev_communicate::<PDEV_COMMUNICATE>(pdev, &mut dev);
+        assert_eq!(pdev_state(pdev), pdev::State::NeedsKey as usize);
+        set_pubkey(pdev, params_ptr, PUBKEY_ALGO_ECDSA_P256);
+        let ret = rmi::<PDEV_SET_PUBKEY>(&[pdev, params_ptr]);
+        assert_eq!(ret[0], SUCCESS);
+
+        // KEY_EXCHANGE_RSP isn't signed by the device
+        dev.forge_signature();
+        mock::host::dev_communicate::<PDEV_COMMUNICATE>(pdev, &mut dev);
+        assert_eq!(pdev_state(pdev), pdev::State::Error as usize);
+
+        let ret = rmi::<PDEV_STOP>(&[pdev]);
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(pdev_state(pdev), pdev::State::Stopped as usize);
+        let ret = rmi::<PDEV_DESTROY>(&[pdev]);
+        assert_eq!(ret[0], SUCCESS);
+        for mocking_addr in &[pdev, aux] {
+            let ret = rmi::<GRANULE_UNDELEGATE>(&[*mocking_addr]);
+            assert_eq!(ret[0], SUCCESS);
+        }
+
+        miri_teardown();
+    }
+
+    #[test]
+    fn rmi_dev_assignment_flow() {
+        let (pdev, aux, params_ptr) = (
+            alloc_granule(IDX_PDEV),
+            alloc_granule(IDX_PDEV_AUX),
+            alloc_granule(IDX_DEV_PARAMS),
+        );
+        let mut dev = SoftPdev::new(TDI_ID);
+
+        let ret = rmi::<PDEV_AUX_COUNT>(&[]);
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(ret[1], 1);
+
+        for mocking_addr in &[pdev, aux] {
+            let ret = rmi::<GRANULE_DELEGATE>(&[*mocking_addr]);
+            assert_eq!(ret[0], SUCCESS);
+        }
+
+        unsafe {
+            let params = &mut *(params_ptr as *mut PdevParams);
+            *params = PdevParams::default();
+            params.pdev_id = 0x100;
+            params.hash_algo = HASH_ALGO_SHA256;
+            params.num_aux = 1;
+            params.aux[0] = aux as u64;
+            params.ncoh_num = 1;
+            // A range starting and ending out of DRAM, which spans the granules
+            params.ncoh_base[0] = GRANULE_SIZE as u64;
+            params.ncoh_top[0] = !(GRANULE_SIZE as u64 - 1);
+        }
+        let ret = rmi::<PDEV_CREATE>(&[pdev, params_ptr]);
+        assert_eq!(ret[0], ERROR_INPUT);
+
+        unsafe {
+            let params = &mut *(params_ptr as *mut PdevParams);
+            params.ncoh_base[0] = DEV_MMIO_BASE as u64;
+            params.ncoh_top[0] = (DEV_MMIO_BASE + DEV_MMIO_SIZE) as u64;
+        }
+        let ret = rmi::<PDEV_CREATE>(&[pdev, params_ptr]);
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(pdev_state(pdev), pdev::State::New as usize);
+
+        // Retrieve the certificate chain
+        mock::host::dev_communicate::<PDEV_COMMUNICATE>(pdev, &mut dev);
+        assert_eq!(pdev_state(pdev), pdev::State::NeedsKey as usize);
+        unsafe {
+            let pdev_obj = &*(pdev as *const Pdev);
+            let digest = Sha256::digest(soft::cert_chain());
+            assert_eq!(&pdev_obj.cert_digest()[..digest.len()], &digest[..]);
+        }
+
+        // Only the key of the device certificate is taken
+        unsafe {
+            let params = &mut *(params_ptr as *mut PubkeyParams);
+            *params = PubkeyParams::default();
+            params.key[0] = 0x04;
+            params.key_len = 65;
+            params.algo = PUBKEY_ALGO_ECDSA_P256;
+        }
+        let ret = rmi::<PDEV_SET_PUBKEY>(&[pdev, params_ptr]);
+        assert_eq!(ret[0], ERROR_INPUT);
+
+        set_pubkey(pdev, params_ptr, PUBKEY_ALGO_RSASSA_3072);
+        let ret = rmi::<PDEV_SET_PUBKEY>(&[pdev, params_ptr]);
+        assert_eq!(ret[0], ERROR_INPUT);
+
+        set_pubkey(pdev, params_ptr, PUBKEY_ALGO_ECDSA_P256);
+        let ret = rmi::<PDEV_SET_PUBKEY>(&[pdev, params_ptr]);
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(pdev_state(pdev), pdev::State::HasKey as usize);
+
+        // Establish the secure session
+        mock::host::dev_communicate::<PDEV_COMMUNICATE>(pdev, &mut dev);
+        assert_eq!(pdev_state(pdev), pdev::State::Ready as usize);
+
+        let realm_params = alloc_granule(IDX_REALM_PARAMS) as *mut RealmParams;
+        unsafe { (*realm_params).flags = 1 << 3 }; // Da
+        let rd = realm_create();
+        unsafe { (*realm_params).flags = 0 };
+
+        let vdev = alloc_granule(IDX_VDEV);
+        let ret = rmi::<GRANULE_DELEGATE>(&[vdev]);
+        assert_eq!(ret[0], SUCCESS);
+
+        unsafe {
+            let params = &mut *(params_ptr as *mut VdevParams);
+            *params = VdevParams::default();
+            params.vdev_id = VDEV_ID;
+            params.tdi_id = TDI_ID as u64;
+        }
+        let ret = rmi::<VDEV_CREATE>(&[rd, pdev, vdev, params_ptr]);
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(vdev_state(vdev), vdev::State::New as usize);
+
+        // Lock the interface and retrieve its report and measurements
+        mock::host::dev_communicate::<VDEV_COMMUNICATE>(vdev, &mut dev);
+        assert_eq!(vdev_state(vdev), vdev::State::Locked as usize);
+        assert!(dev.is_locked());
+        unsafe {
+            let vdev_obj = &*(vdev as *const Vdev);
+            let digest = Sha256::digest(soft::measurement_record());
+            assert_eq!(&vdev_obj.meas_digest()[..digest.len()], &digest[..]);
+            let digest = Sha256::digest(soft::interface_report());
+            assert_eq!(&vdev_obj.report_digest()[..digest.len()], &digest[..]);
+        }
+
+        mock::host::map(rd, DEV_IPA);
+        let ret = rmi::<DEV_MEM_MAP>(&[rd, vdev, DEV_IPA, DEV_MMIO_BASE]);
+        assert_eq!(ret[0], SUCCESS);
+        assert!(mem::is_mapped(DEV_MMIO_BASE));
+
+        // A device granule is mapped only once
+        let ret = rmi::<DEV_MEM_MAP>(&[rd, vdev, DEV_IPA + GRANULE_SIZE, DEV_MMIO_BASE]);
+        assert_eq!(ret[0], ERROR_INPUT);
+        let top_granule = !(GRANULE_SIZE - 1);
+        let ret = rmi::<DEV_MEM_MAP>(&[rd, vdev, DEV_IPA + GRANULE_SIZE, top_granule]);
+        assert_eq!(ret[0], ERROR_INPUT);
+
+        let ret = rmi::<RTT_READ_ENTRY>(&[rd, DEV_IPA, MAP_LEVEL]);
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(ret[2], rtt_entry_state::RMI_ASSIGNED_DEV);
+        assert_eq!(ret[3], DEV_MMIO_BASE);
+        assert_eq!(ret[4], 0); // RIPAS EMPTY
+
+        // The realm accepts the mapping (RSI_VDEV_VALIDATE_MAPPING)
+        unsafe {
+            let rd_obj = &*(rd as *const Rd);
+            let next = rtt::validate_dev_mapping(
+                &rd_obj.rtts(),
+                DEV_IPA,
+                DEV_IPA + GRANULE_SIZE,
+                DEV_MMIO_BASE,
+            )
+            .unwrap();
+            assert_eq!(next, DEV_IPA + GRANULE_SIZE);
+        }
+        let ret = rmi::<RTT_READ_ENTRY>(&[rd, DEV_IPA, MAP_LEVEL]);
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(ret[4], 3); // RIPAS DEV
+
+        mock::host::dev_communicate::<VDEV_COMMUNICATE>(vdev, &mut dev);
+        assert_eq!(vdev_state(vdev), vdev::State::Started as usize);
+        assert!(dev.is_started());
+
+        // The PDEV can't be stopped while it has VDEVs
+        let ret = rmi::<PDEV_STOP>(&[pdev]);
+        assert_eq!(ret[0], ERROR_IN_USE);
+
+        let ret = rmi::<VDEV_STOP>(&[vdev]);
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(vdev_state(vdev), vdev::State::Stopping as usize);
+        mock::host::dev_communicate::<VDEV_COMMUNICATE>(vdev, &mut dev);
+        assert_eq!(vdev_state(vdev), vdev::State::Stopped as usize);
+        assert!(!dev.is_locked());
+
+        // The VDEV can't be destroyed while its memory is mapped
+        let ret = rmi::<VDEV_DESTROY>(&[vdev]);
+        assert_eq!(ret[0], ERROR_INPUT);
+
+        let ret = rmi::<DEV_MEM_UNMAP>(&[rd, vdev, DEV_IPA]);
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(ret[1], DEV_MMIO_BASE);
+        assert!(!mem::is_mapped(DEV_MMIO_BASE));
+
+        let ret = rmi::<VDEV_DESTROY>(&[vdev]);
+        assert_eq!(ret[0], SUCCESS);
+        let ret = rmi::<GRANULE_UNDELEGATE>(&[vdev]);
+        assert_eq!(ret[0], SUCCESS);
+
+        mock::host::unmap(rd, DEV_IPA, false);
+        realm_destroy(rd);
+
+        let ret = rmi::<PDEV_STOP>(&[pdev]);
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(pdev_state(pdev), pdev::State::Stopping as usize);
+        mock::host::dev_communicate::<PDEV_COMMUNICATE>(pdev, &mut dev);
+        assert_eq!(pdev_state(pdev), pdev::State::Stopped as usize);
+
+        let ret = rmi::<PDEV_DESTROY>(&[pdev]);
+        assert_eq!(ret[0], SUCCESS);
+        for mocking_addr in &[pdev, aux] {
+            let ret = rmi::<GRANULE_UNDELEGATE>(&[*mocking_addr]);
+            assert_eq!(ret[0], SUCCESS);
+        }
+
+        miri_teardown();
+    }
+}
@@ -0,0 +1,219 @@
+use crate::const_assert_eq;
+use crate::dev::pdev::{PDEV_MAX_NCOH_RANGES, PDEV_NUM_AUX};
+use crate::granule::{overlaps_granules, GranuleState, GRANULE_SIZE};
+use crate::rmi::error::Error;
+use crate::rmi::{HASH_ALGO_SHA256, HASH_ALGO_SHA512};
+use crate::{get_granule, get_granule_if};
+
+use autopadding::*;
+
+pub const PDEV_MAX_AUX: usize = 32;
+pub const PUBKEY_PARAMS_KEY_SIZE: usize = 1024;
+
+pad_struct_and_impl_default!(
+pub struct PdevParams {
+    0x0    pub flags: u64,
+    0x8    pub pdev_id: u64,
+    0x10   pub segment_id: u64,
+    0x18   pub ecam_addr: u64,
+    0x20   pub root_id: u64,
+    0x28   pub hash_algo: u8,
+    0x100  pub num_aux: u64,
+    0x108  pub aux: [u64; PDEV_MAX_AUX],
+    0x300  pub ncoh_num: u64,
+    0x308  pub ncoh_base: [u64; PDEV_MAX_NCOH_RANGES],
+    0x400  pub ncoh_top: [u64; PDEV_MAX_NCOH_RANGES],
+    0x1000 => @END,
+}
+);
+const_assert_eq!(core::mem::size_of::<PdevParams>(), GRANULE_SIZE);
+
+impl PdevParams {
+    pub fn verify_compliance(&self, pdev: usize, params_ptr: usize) -> Result<(), Error> {
+        if self.num_aux as usize != PDEV_NUM_AUX {
+            return Err(Error::RmiErrorInput);
+        }
+        match self.hash_algo {
+            HASH_ALGO_SHA256 | HASH_ALGO_SHA512 => {}
+            _ => return Err(Error::RmiErrorInput),
+        }
+
+        let mut aux = self.aux;
+        let aux = &mut aux[..PDEV_NUM_AUX];
+        aux.sort();
+        for idx in 0..aux.len() {
+            let addr = aux[idx] as usize;
+            if addr == pdev || addr == params_ptr {
+                return Err(Error::RmiErrorInput);
+            }
+            if idx != 0 && aux[idx - 1] == aux[idx] {
+                return Err(Error::RmiErrorInput);
+            }
+            let _aux_granule = get_granule_if!(addr, GranuleState::Delegated)?;
+        }
+
+        // Non-coherent address ranges are device memory (MMIO),
+        // which is never tracked as a granule.
+        let ncoh_num = self.ncoh_num as usize;
+        if ncoh_num == 0 || ncoh_num > PDEV_MAX_NCOH_RANGES {
+            return Err(Error::RmiErrorInput);
+        }
+        for (base, top) in self.ncoh_base[..ncoh_num]
+            .iter()
+            .zip(self.ncoh_top[..ncoh_num].iter())
+        {
+            let (base, top) = (*base as usize, *top as usize);
+            if base >= top || base % GRANULE_SIZE != 0 || top % GRANULE_SIZE != 0 {
+                return Err(Error::RmiErrorInput);
+            }
+            // Ranges spanning DRAM could map delegated or RMM memory into realms
+            if overlaps_granules(base, top) {
+                return Err(Error::RmiErrorInput);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl core::fmt::Debug for PdevParams {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PdevParams")
+            .field("flags", &format_args!("{:#X}", &self.flags))
+            .field("pdev_id", &format_args!("{:#X}", &self.pdev_id))
+            .field("segment_id", &self.segment_id)
+            .field("ecam_addr", &format_args!("{:#X}", &self.ecam_addr))
+            .field("root_id", &self.root_id)
+            .field("hash_algo", &self.hash_algo)
+            .field("num_aux", &self.num_aux)
+            .field("ncoh_num", &self.ncoh_num)
+            .field("ncoh_base", &format_args!("{:#X?}", &self.ncoh_base))
+            .field("ncoh_top", &format_args!("{:#X?}", &self.ncoh_top))
+            .finish()
+    }
+}
+
+pad_struct_and_impl_default!(
+pub struct PubkeyParams {
+    0x0    pub key: [u8; PUBKEY_PARAMS_KEY_SIZE],
+    0x400  pub key_len: u64,
+    0x408  pub algo: u8,
+    0x1000 => @END,
+}
+);
+const_assert_eq!(core::mem::size_of::<PubkeyParams>(), GRANULE_SIZE);
+
+pad_struct_and_impl_default!(
+pub struct VdevParams {
+    0x0    pub flags: u64,
+    0x8    pub vdev_id: u64,
+    0x10   pub tdi_id: u64,
+    0x1000 => @END,
+}
+);
+const_assert_eq!(core::mem::size_of::<VdevParams>(), GRANULE_SIZE);
+
+impl core::fmt::Debug for VdevParams {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("VdevParams")
+            .field("flags", &format_args!("{:#X}", &self.flags))
+            .field("vdev_id", &format_args!("{:#X}", &self.vdev_id))
+            .field("tdi_id", &format_args!("{:#X}", &self.tdi_id))
+            .finish()
+    }
+}
+
+pad_struct_and_impl_default!(
+pub struct DevCommEnter {
+    0x0    pub status: u64,
+    0x8    pub req_addr: u64,
+    0x10   pub resp_addr: u64,
+    0x18   pub resp_len: u64,
+    0x800  => @END,
+}
+);
+
+pad_struct_and_impl_default!(
+pub struct DevCommExit {
+    0x0    pub flags: u64,
+    0x8    pub protocol: u8,
+    0x10   pub req_len: u64,
+    0x800  => @END,
+}
+);
+
+/// The object shared between the host and the RMM
+/// to relay messages of *_COMMUNICATE to a device.
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+pub struct DevCommData {
+    pub enter: DevCommEnter,
+    pub exit: DevCommExit,
+}
+const_assert_eq!(core::mem::size_of::<DevCommData>(), GRANULE_SIZE);
+
+impl DevCommData {
+    pub fn verify_compliance(&self) -> Result<(), Error> {
+        let enter = &self.enter;
+        let (req, resp) = (enter.req_addr as usize, enter.resp_addr as usize);
+        if req % GRANULE_SIZE != 0 || resp % GRANULE_SIZE != 0 || req == resp {
+            return Err(Error::RmiErrorInput);
+        }
+        if enter.resp_len as usize > GRANULE_SIZE {
+            return Err(Error::RmiErrorInput);
+        }
+        Ok(())
+    }
+}
+
+impl core::fmt::Debug for DevCommData {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DevCommData")
+            .field("enter.status", &self.enter.status)
+            .field(
+                "enter.req_addr",
+                &format_args!("{:#X}", &self.enter.req_addr),
+            )
+            .field(
+                "enter.resp_addr",
+                &format_args!("{:#X}", &self.enter.resp_addr),
+            )
+            .field("enter.resp_len", &self.enter.resp_len)
+            .field("exit.flags", &format_args!("{:#X}", &self.exit.flags))
+            .field("exit.protocol", &self.exit.protocol)
+            .field("exit.req_len", &self.exit.req_len)
+            .finish()
+    }
+}
+
+macro_rules! impl_host_struct {
+    ($($t:ty),*) => {
+        $(
+            impl safe_abstraction::raw_ptr::RawPtr for $t {}
+
+            impl safe_abstraction::raw_ptr::SafetyChecked for $t {}
+
+            impl safe_abstraction::raw_ptr::SafetyAssured for $t {
+                fn is_initialized(&self) -> bool {
+                    // Given the fact that this memory is initialized by the Host,
+                    // it's not possible to unequivocally guarantee
+                    // that the values have been initialized from the perspective of the RMM.
+                    // However, any values, whether correctly initialized or not, are
+                    // verified by the RMM before use.
+                    // Consequently, this function returns `true`.
+                    true
+                }
+
+                fn verify_ownership(&self) -> bool {
+                    // This memory has permissions from the Host's perspective,
+                    // which inherently implies that exclusive ownership cannot be guaranteed by the RMM alone.
+                    // However, the RMM works on a copy of the object
+                    // and verifies it before use.
+                    // Consequently, this function returns `true`.
+                    true
+                }
+            }
+        )*
+    };
+}
+
+impl_host_struct!(PdevParams, PubkeyParams, VdevParams, DevCommData);
@@ -1,3 +1,4 @@
+use crate::rmi::RmiStatus;
 use crate::{measurement::MeasurementError, rsi};
 
 use safe_abstraction::raw_ptr;
@@ -9,7 +10,7 @@ pub enum Error {
     RmiErrorInput,
     RmiErrorRealm(usize),
     RmiErrorRec,
-    RmiErrorRtt(usize),
+    RmiErrorRtt(isize),
     RmiErrorInUse,
     RmiErrorCount,
     //// The below are our-defined errors not in TF-RMM
@@ -26,17 +27,34 @@ pub enum InternalError {
 impl From<Error> for usize {
     fn from(err: Error) -> Self {
         match err {
-            Error::RmiErrorInput => 1,
-            Error::RmiErrorRealm(index) => 2 | (index << 8),
-            Error::RmiErrorRec => 3,
-            Error::RmiErrorRtt(level) => 4 | (level << 8),
-            Error::RmiErrorInUse => 5,
-            Error::RmiErrorCount => 6,
+            Error::RmiErrorInput => RmiStatus::ErrorInput as usize,
+            Error::RmiErrorRealm(index) => RmiStatus::ErrorRealm as usize | (index << 8),
+            Error::RmiErrorRec => RmiStatus::ErrorRec as usize,
+            // The index is 8-bit wide, where level -1 is encoded as 0xFF
+            Error::RmiErrorRtt(level) => {
+                RmiStatus::ErrorRtt as usize | ((level as u8 as usize) << 8)
+            }
+            Error::RmiErrorInUse => RmiStatus::ErrorInUse as usize,
+            Error::RmiErrorCount => RmiStatus::ErrorCount as usize,
             Error::RmiErrorOthers(_) => 7,
         }
     }
 }
 
+// The status returned by the accessors of the shared structures
+impl From<RmiStatus> for Error {
+    fn from(status: RmiStatus) -> Self {
+        match status {
+            RmiStatus::ErrorRealm => Error::RmiErrorRealm(0),
+            RmiStatus::ErrorRec => Error::RmiErrorRec,
+            RmiStatus::ErrorRtt => Error::RmiErrorRtt(0),
+            RmiStatus::ErrorInUse => Error::RmiErrorInUse,
+            RmiStatus::ErrorCount => Error::RmiErrorCount,
+            RmiStatus::Success | RmiStatus::ErrorInput => Error::RmiErrorInput,
+        }
+    }
+}
+
 impl From<vmsa::error::Error> for Error {
     fn from(_e: vmsa::error::Error) -> Self {
         //error!("MmError occured: {}", <Error as Into<usize>>::into(e));
@@ -1,16 +1,22 @@
+use crate::debug;
 use crate::event::RmiHandle;
 use crate::gic;
 use crate::listen;
+use crate::mte;
 use crate::pmu;
 use crate::rec;
 use crate::rmi;
 use crate::simd;
 use armv9a::{define_bitfield, define_bits, define_mask};
 
-extern crate alloc;
-
 define_bits!(
     FeatureReg0,
+    HASH_SHA_384[55 - 55], // Islet specific
+    MTE_EN[54 - 54],
+    SME_VL[53 - 50],
+    SME_EN[49 - 49],
+    MAX_NUM_AUX_PLANES[48 - 45],
+    DA_EN[42 - 42],
     MAX_RECS_ORDER[41 - 38],
     GICV3_NUM_LRS[37 - 34],
     HASH_SHA_512[33 - 33],
@@ -26,15 +32,61 @@ define_bits!(
 );
 
 const S2SZ_VALUE: u64 = 48;
-pub const LPA2_VALUE: u64 = 0;
+const S2SZ_VALUE_LPA2: u64 = 52;
 const HASH_SHA_256_VALUE: u64 = SUPPORTED;
 const HASH_SHA_512_VALUE: u64 = SUPPORTED;
+const HASH_SHA_384_VALUE: u64 = SUPPORTED;
 
 pub const NOT_SUPPORTED: u64 = 0;
 pub const SUPPORTED: u64 = 1;
 
 const FEATURE_REGISTER_0_INDEX: usize = 0;
 
+/// Returns true if the stage 2 translation supports
+/// 52-bit input and output addresses with the 4KB granule (FEAT_LPA2).
+pub fn lpa2_supported() -> bool {
+    #[cfg(not(any(miri, test, fuzzing)))]
+    {
+        use aarch64_cpu::registers::*;
+
+        // ID_AA64MMFR0_EL1.TGran4_2[43:40] and TGran4[31:28]
+        const TGRAN4_2_SHIFT: u64 = 40;
+        const TGRAN4_SHIFT: u64 = 28;
+        const TGRAN4_2_AS_STAGE1: u64 = 0b0000;
+        const TGRAN4_2_52_BIT: u64 = 0b0011;
+        const TGRAN4_52_BIT: u64 = 0b0001;
+
+        let mmfr0 = ID_AA64MMFR0_EL1.get();
+        let tgran4_2 = (mmfr0 >> TGRAN4_2_SHIFT) & 0xf;
+        let tgran4 = (mmfr0 >> TGRAN4_SHIFT) & 0xf;
+        trace!("TGran4_2: {:#b} TGran4: {:#b}", tgran4_2, tgran4);
+        tgran4_2 == TGRAN4_2_52_BIT || (tgran4_2 == TGRAN4_2_AS_STAGE1 && tgran4 == TGRAN4_52_BIT)
+    }
+    #[cfg(any(miri, test, fuzzing))]
+    true
+}
+
+/// Returns true if devices can be authenticated, which needs
+/// random numbers (FEAT_RNG) for the freshness of SPDM KEY_EXCHANGE.
+pub fn da_supported() -> bool {
+    #[cfg(not(any(miri, test, fuzzing)))]
+    {
+        use aarch64_cpu::registers::*;
+
+        ID_AA64ISAR0_EL1.read(ID_AA64ISAR0_EL1::RNDR) != 0
+    }
+    #[cfg(any(miri, test, fuzzing))]
+    true
+}
+
+fn max_s2sz() -> u64 {
+    if lpa2_supported() {
+        S2SZ_VALUE_LPA2
+    } else {
+        S2SZ_VALUE
+    }
+}
+
 pub fn set_event_handler(rmi: &mut RmiHandle) {
     listen!(rmi, rmi::FEATURES, |arg, ret, _| {
         if arg[0] != FEATURE_REGISTER_0_INDEX {
@@ -44,11 +96,38 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
 
         let mut feat_reg0 = FeatureReg0::new(0);
         feat_reg0
-            .set_masked_value(FeatureReg0::S2SZ, S2SZ_VALUE)
-            .set_masked_value(FeatureReg0::LPA2, LPA2_VALUE)
+            .set_masked_value(FeatureReg0::S2SZ, max_s2sz())
+            .set_masked_value(
+                FeatureReg0::LPA2,
+                if lpa2_supported() {
+                    SUPPORTED
+                } else {
+                    NOT_SUPPORTED
+                },
+            )
             .set_masked_value(FeatureReg0::HASH_SHA_256, HASH_SHA_256_VALUE)
             .set_masked_value(FeatureReg0::HASH_SHA_512, HASH_SHA_512_VALUE)
-            .set_masked_value(FeatureReg0::MAX_RECS_ORDER, rec::max_recs_order() as u64);
+            .set_masked_value(FeatureReg0::HASH_SHA_384, HASH_SHA_384_VALUE)
+            .set_masked_value(FeatureReg0::MAX_RECS_ORDER, rec::max_recs_order() as u64)
+            .set_masked_value(FeatureReg0::MAX_NUM_AUX_PLANES, rmi::MAX_AUX_PLANES as u64)
+            .set_masked_value(
+                FeatureReg0::DA_EN,
+                if da_supported() {
+                    SUPPORTED
+                } else {
+                    NOT_SUPPORTED
+                },
+            )
+            .set_masked_value(FeatureReg0::NUM_BPS, debug::max_num_bps())
+            .set_masked_value(FeatureReg0::NUM_WPS, debug::max_num_wps())
+            .set_masked_value(
+                FeatureReg0::MTE_EN,
+                if mte::mte_present() {
+                    SUPPORTED
+                } else {
+                    NOT_SUPPORTED
+                },
+            );
 
         #[cfg(not(any(miri, test, fuzzing)))]
         feat_reg0
@@ -61,6 +140,15 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
                 },
             )
             .set_masked_value(FeatureReg0::SVE_VL, simd::max_sve_vl())
+            .set_masked_value(
+                FeatureReg0::SME_EN,
+                if simd::sve_en() && simd::sme_en() {
+                    SUPPORTED
+                } else {
+                    NOT_SUPPORTED
+                },
+            )
+            .set_masked_value(FeatureReg0::SME_VL, simd::max_sme_svl())
             .set_masked_value(
                 FeatureReg0::PMU_EN,
                 if pmu::pmu_present() {
@@ -75,6 +163,8 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
         feat_reg0
             .set_masked_value(FeatureReg0::SVE_EN, NOT_SUPPORTED)
             .set_masked_value(FeatureReg0::SVE_VL, 0)
+            .set_masked_value(FeatureReg0::SME_EN, NOT_SUPPORTED)
+            .set_masked_value(FeatureReg0::SME_VL, 0)
             .set_masked_value(FeatureReg0::GICV3_NUM_LRS, 0);
 
         ret[1] = feat_reg0.get() as usize;
@@ -84,9 +174,15 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
 }
 
 //TODO: locate validate() in armv9a to check against AA64MMFR_EL1 register
-pub fn validate(s2sz: usize) -> bool {
+pub fn validate(s2sz: usize, lpa2: bool) -> bool {
     const MIN_IPA_SIZE: usize = 32;
-    if !(MIN_IPA_SIZE..=S2SZ_VALUE as usize).contains(&s2sz) {
+    if lpa2 && !lpa2_supported() {
+        return false;
+    }
+
+    // IPA wider than 48 bits is only addressable with FEAT_LPA2
+    let max_ipa_size = if lpa2 { S2SZ_VALUE_LPA2 } else { S2SZ_VALUE };
+    if !(MIN_IPA_SIZE..=max_ipa_size as usize).contains(&s2sz) {
         return false;
     }
 
@@ -95,7 +191,7 @@ pub fn validate(s2sz: usize) -> bool {
 
 #[cfg(test)]
 mod test {
-    use crate::rmi::{FEATURES, SUCCESS};
+    use crate::rmi::{FEATURES, MAX_AUX_PLANES, SUCCESS};
     use crate::test_utils::*;
 
     // Source: https://github.com/ARM-software/cca-rmm-acs
@@ -105,7 +201,18 @@ mod test {
         let ret = rmi::<FEATURES>(&[0]);
 
         assert_eq!(ret[0], SUCCESS);
-        assert_eq!(extract_bits(ret[1], 42, 63), 0);
+        assert_eq!(extract_bits(ret[1], 56, 63), 0);
+        assert_eq!(extract_bits(ret[1], 55, 55), 1);
+        assert_eq!(extract_bits(ret[1], 54, 54), 1);
+        assert_eq!(extract_bits(ret[1], 49, 53), 0);
+        assert_eq!(extract_bits(ret[1], 45, 48), MAX_AUX_PLANES);
+        assert_eq!(extract_bits(ret[1], 43, 44), 0);
+        assert_eq!(extract_bits(ret[1], 42, 42), 1);
+        assert_eq!(extract_bits(ret[1], 32, 33), 0b11);
+        assert_eq!(extract_bits(ret[1], 20, 25), 3);
+        assert_eq!(extract_bits(ret[1], 14, 19), 5);
+        assert_eq!(extract_bits(ret[1], 8, 8), 1);
+        assert_eq!(extract_bits(ret[1], 0, 7), 52);
 
         let ret = rmi::<FEATURES>(&[1]);
         assert_eq!(ret[0], SUCCESS);
@@ -12,8 +12,6 @@ use crate::{get_granule, get_granule_if, set_state_and_get_granule};
 #[cfg(feature = "gst_page_table")]
 use vmsa::error::Error as MmError;
 
-extern crate alloc;
-
 // defined in trusted-firmware-a/include/services/rmmd_svc.h
 pub const MARK_REALM: usize = 0xc400_01b0;
 pub const MARK_NONSECURE: usize = 0xc400_01b1;
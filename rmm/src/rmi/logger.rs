@@ -0,0 +1,147 @@
+use super::error::Error;
+use crate::event::RmiHandle;
+use crate::granule::GRANULE_SIZE;
+use crate::host::{self, DataPage};
+use crate::listen;
+use crate::logger::{self, LOG_RING_SIZE, MAX_TARGET_LEN};
+use crate::rmi;
+
+use alloc::vec;
+use log::LevelFilter;
+
+// Removes the filter of the target, instead of setting its level
+const LOG_LEVEL_REMOVE: usize = 6;
+
+// The maximum number of contiguous NS granules for draining the logs
+const LOG_MAX_GRANULES: usize = LOG_RING_SIZE / GRANULE_SIZE;
+
+fn level_filter(level: usize) -> Result<Option<LevelFilter>, Error> {
+    let filter = match level {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        5 => LevelFilter::Trace,
+        LOG_LEVEL_REMOVE => return Ok(None),
+        _ => return Err(Error::RmiErrorInput),
+    };
+    Ok(Some(filter))
+}
+
+pub fn set_event_handler(rmi: &mut RmiHandle) {
+    // ISLET_LOG_SET_FILTER is a vendor specific RMI for changing the log level at runtime
+    // Input registers
+    // x0: function id (0xC7000154)
+    // x1: level - 0 (off), 1 (error), 2 (warn), 3 (info), 4 (debug), 5 (trace)
+    //             or 6 to remove the filter of the target
+    // x2: target_ptr - a physicall address of the host provided (NS) granule
+    //                  holding the target, such as "islet_rmm::rmi".
+    //                  Zero sets the level of the targets without any filter.
+    // x3: target_len - the length of the target in bytes
+    listen!(rmi, rmi::ISLET_LOG_SET_FILTER, |arg, _ret, rmm| {
+        let level = level_filter(arg[0])?;
+        let target_ptr = arg[1];
+        let target_len = arg[2];
+
+        if target_ptr == 0 {
+            return logger::set_filter("", level).map_err(|_| Error::RmiErrorInput);
+        }
+        if target_ptr % GRANULE_SIZE != 0 || target_len == 0 || target_len > MAX_TARGET_LEN {
+            return Err(Error::RmiErrorInput);
+        }
+
+        rmm.page_table.map(target_ptr, false);
+        let page = host::copy_from::<DataPage>(target_ptr);
+        rmm.page_table.unmap(target_ptr);
+        let page = page.ok_or(Error::RmiErrorInput)?;
+
+        let target = core::str::from_utf8(&page.as_slice()[..target_len])
+            .map_err(|_| Error::RmiErrorInput)?;
+        logger::set_filter(target, level).map_err(|e| {
+            warn!("Failed to set the log filter of {}: {:?}", target, e);
+            Error::RmiErrorInput
+        })
+    });
+
+    // ISLET_LOG_SET_SINKS is a vendor specific RMI for choosing where the logs go
+    // Input registers
+    // x0: function id (0xC7000155)
+    // x1: sinks - bit[0] for the console and bit[1] for the in-memory ring
+    listen!(rmi, rmi::ISLET_LOG_SET_SINKS, |arg, _ret, _rmm| {
+        let sinks = arg[0];
+        if sinks & !logger::SINK_ALL != 0 {
+            return Err(Error::RmiErrorInput);
+        }
+        logger::set_sinks(sinks);
+        Ok(())
+    });
+
+    // ISLET_LOG_DRAIN is a vendor specific RMI for moving the logs in the ring to NS memory
+    // Input registers
+    // x0: function id (0xC7000156)
+    // x1: buf - a physicall address of the host provided (NS) buffer, granule aligned
+    // x2: num_granules - the number of contiguous granules of the buffer
+    // Output registers
+    // x0: status
+    // x1: len - the number of bytes drained, from the oldest one.
+    //           The rest remains in the ring for the next call.
+    // x2: lost - the number of bytes overwritten since the last call
+    listen!(rmi, rmi::ISLET_LOG_DRAIN, |arg, ret, rmm| {
+        let buf = arg[0];
+        let num_granules = arg[1];
+
+        if buf % GRANULE_SIZE != 0 || num_granules == 0 || num_granules > LOG_MAX_GRANULES {
+            return Err(Error::RmiErrorInput);
+        }
+
+        let mut logs = vec![0u8; num_granules * GRANULE_SIZE];
+        let (len, lost) = logger::peek(&mut logs);
+
+        host::copy_out(rmm, buf, num_granules, &logs[..len]).ok_or(Error::RmiErrorInput)?;
+
+        logger::consume(len);
+        ret[1] = len;
+        ret[2] = lost;
+        Ok(())
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rmi::{ERROR_INPUT, ISLET_LOG_DRAIN, ISLET_LOG_SET_FILTER, SUCCESS};
+    use crate::test_utils::*;
+
+    #[test]
+    fn rmi_log_set_filter() {
+        let target = alloc_granule(IDX_DATA1);
+        let name = b"islet_rmm::rmi::log_test";
+        unsafe {
+            core::ptr::copy_nonoverlapping(name.as_ptr(), target as *mut u8, name.len());
+        }
+
+        let ret = rmi::<ISLET_LOG_SET_FILTER>(&[7, target, name.len()]);
+        assert_eq!(ret[0], ERROR_INPUT);
+
+        let ret = rmi::<ISLET_LOG_SET_FILTER>(&[1, target + 1, name.len()]);
+        assert_eq!(ret[0], ERROR_INPUT);
+
+        let ret = rmi::<ISLET_LOG_SET_FILTER>(&[1, target, name.len()]);
+        assert_eq!(ret[0], SUCCESS);
+
+        let ret = rmi::<ISLET_LOG_SET_FILTER>(&[6, target, name.len()]);
+        assert_eq!(ret[0], SUCCESS);
+    }
+
+    #[test]
+    fn rmi_log_drain() {
+        let buf = alloc_granule(IDX_DATA2);
+        let ret = rmi::<ISLET_LOG_DRAIN>(&[buf, 0]);
+        assert_eq!(ret[0], ERROR_INPUT);
+
+        // Nothing is logged as the logger is not registered in tests
+        let ret = rmi::<ISLET_LOG_DRAIN>(&[buf, 1]);
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(ret[1], 0);
+    }
+}
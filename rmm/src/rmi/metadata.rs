@@ -1,191 +1,348 @@
-use core::ffi::CStr;
+use alloc::vec::Vec;
+use coset::{iana, CborSerializable, CoseKey, CoseSign1, Label, TaggedCborSerializable};
 use p384::{
     ecdsa::{signature::Verifier, Signature, VerifyingKey},
     elliptic_curve::generic_array::GenericArray,
     EncodedPoint,
 };
+use sha2::{Digest, Sha384};
+use spin::mutex::Mutex;
 
-use super::{HASH_ALGO_SHA256, HASH_ALGO_SHA512};
+use super::{HASH_ALGO_SHA256, HASH_ALGO_SHA384, HASH_ALGO_SHA512};
 use crate::granule::GRANULE_SIZE;
 use crate::measurement::{Measurement, MEASUREMENTS_SLOT_MAX_SIZE};
 use crate::rmi::error::Error;
 
-const FMT_VERSION: usize = 1;
-pub const REALM_ID_SIZE: usize = 128;
-pub const P384_PUBLIC_KEY_SIZE: usize = 96;
-const P384_SIGNATURE_SIZE: usize = P384_PUBLIC_KEY_SIZE;
+pub use rmm_spec::rmi::metadata::*;
 
-#[allow(dead_code)]
-const P385_SIGNATURE_POINT_SIZE: usize = P384_SIGNATURE_SIZE / 2;
 #[allow(dead_code)]
 const SHA_384_HASH_SIZE: usize = 48;
 
-const METADATA_HASH_SHA_256: usize = 0x01;
-const METADATA_HASH_SHA_512: usize = 0x02;
+/// The maximum number of revoked signer keys
+pub const MAX_REVOKED_KEYS: usize = 16;
+/// Signer keys are revoked by the SHA-384 hash of their raw (x || y) public keys
+pub const KEY_HASH_SIZE: usize = SHA_384_HASH_SIZE;
 
-const REALM_METADATA_HEADER_SIZE: usize = 0x150;
-#[allow(dead_code)]
-const REALM_METADATA_SIGNED_SIZE: usize = 0x1B0;
-const REALM_METADATA_UNUSED_SIZE: usize = 0xE50;
-
-#[derive(Debug, Clone, Copy)]
-#[repr(C)]
-pub struct IsletRealmMetadata {
-    fmt_version: usize,
-    realm_id: [u8; REALM_ID_SIZE],
-    rim: [u8; MEASUREMENTS_SLOT_MAX_SIZE],
-    hash_algo: usize,
-    svn: usize,
-    version_major: usize,
-    version_minor: usize,
-    version_patch: usize,
-    public_key: [u8; P384_PUBLIC_KEY_SIZE],
-    signature: [u8; P384_SIGNATURE_SIZE],
-    _unused: [u8; REALM_METADATA_UNUSED_SIZE],
+const _: () = assert!(core::mem::size_of::<IsletRealmMetadata>() == GRANULE_SIZE);
+const _: () = assert!(RIM_MAX_SIZE == MEASUREMENTS_SLOT_MAX_SIZE);
+
+struct Trust {
+    vendor_key: Option<[u8; P384_PUBLIC_KEY_SIZE]>,
+    revoked: Vec<[u8; KEY_HASH_SIZE]>,
+    require_v2: bool,
 }
 
-const _: () = assert!(core::mem::size_of::<IsletRealmMetadata>() == GRANULE_SIZE);
-const _: () = assert!(core::mem::size_of::<IsletRealmMetadata>() >= REALM_METADATA_SIGNED_SIZE);
-
-const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, fmt_version) == 0x00);
-const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, realm_id) == 0x08);
-const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, rim) == 0x88);
-const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, hash_algo) == 0xc8);
-const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, svn) == 0xd0);
-const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, version_major) == 0xd8);
-const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, version_minor) == 0xe0);
-const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, version_patch) == 0xe8);
-const _: () = assert!(core::mem::offset_of!(IsletRealmMetadata, public_key) == 0xf0);
-
-impl IsletRealmMetadata {
-    fn realm_id_as_str(&self) -> Option<&str> {
-        let Ok(cstr) = CStr::from_bytes_until_nul(&self.realm_id) else {
-            return None;
-        };
-        let Ok(s) = cstr.to_str() else {
-            return None;
-        };
-        Some(s)
-    }
-
-    pub fn dump(&self) {
-        debug!("fmt_version: {:#010x}", self.fmt_version);
-        debug!(
-            "realm_id: {}",
-            self.realm_id_as_str().unwrap_or("INVALID REALM ID")
-        );
-        debug!("rim: {}", hex::encode(self.rim));
-        debug!("hash_algo: {:#010x}", self.hash_algo);
-        debug!("svn: {:#010x}", self.svn);
-        debug!("version_major: {:#010x}", self.version_major);
-        debug!("version_minor: {:#010x}", self.version_minor);
-        debug!("version_patch: {:#010x}", self.version_patch);
-        debug!("public_key: {}", hex::encode(self.public_key));
-        debug!("signature: {}", hex::encode(self.signature));
-    }
+static TRUST: Mutex<Trust> = Mutex::new(Trust {
+    vendor_key: None,
+    revoked: Vec::new(),
+    require_v2: false,
+});
 
-    fn verifying_key(&self) -> core::result::Result<VerifyingKey, Error> {
-        let point = EncodedPoint::from_untagged_bytes(GenericArray::from_slice(&self.public_key));
-        VerifyingKey::from_encoded_point(&point).or(Err(Error::RmiErrorInput))
-    }
+/// Provisions the vendor key, which the signer chains of v2 metadata are rooted in,
+/// and the hashes of the revoked signer keys. They come from the boot manifest.
+/// With `require_v2`, v1 metadata is rejected, which only takes effect along
+/// with a vendor key.
+pub fn provision_trust(
+    vendor_key: Option<&[u8; P384_PUBLIC_KEY_SIZE]>,
+    revoked: &[[u8; KEY_HASH_SIZE]],
+    require_v2: bool,
+) {
+    let mut trust = TRUST.lock();
+    trust.vendor_key = vendor_key.copied();
+    trust.revoked = revoked.iter().take(MAX_REVOKED_KEYS).copied().collect();
+    trust.require_v2 = require_v2 && trust.vendor_key.is_some();
+}
 
-    fn signature(&self) -> core::result::Result<Signature, Error> {
-        Signature::from_slice(&self.signature).or(Err(Error::RmiErrorInput))
-    }
+fn p384_verifying_key(
+    key: &[u8; P384_PUBLIC_KEY_SIZE],
+) -> core::result::Result<VerifyingKey, Error> {
+    let point = EncodedPoint::from_untagged_bytes(GenericArray::from_slice(key));
+    VerifyingKey::from_encoded_point(&point).or(Err(Error::RmiErrorInput))
+}
 
-    fn header_as_u8_slice(&self) -> &[u8] {
-        let slice = unsafe {
-            core::slice::from_raw_parts(
-                (self as *const Self) as *const u8,
-                core::mem::size_of::<Self>(),
-            )
-        };
-        &slice[..REALM_METADATA_HEADER_SIZE]
+fn is_revoked(key: &[u8; P384_PUBLIC_KEY_SIZE]) -> bool {
+    let hash = Sha384::digest(key);
+    TRUST
+        .lock()
+        .revoked
+        .iter()
+        .any(|revoked| revoked[..] == hash[..])
+}
+
+// Returns the public key of the subject if `cert` is signed by `issuer`
+fn verify_cert(
+    cert: &MetadataCert,
+    issuer: &[u8; P384_PUBLIC_KEY_SIZE],
+) -> core::result::Result<[u8; P384_PUBLIC_KEY_SIZE], Error> {
+    let data = cert.as_bytes().ok_or(Error::RmiErrorInput)?;
+    let sign1 = CoseSign1::from_tagged_slice(data).or(Err(Error::RmiErrorInput))?;
+    if sign1.protected.header.alg != Some(coset::Algorithm::Assigned(iana::Algorithm::ES384)) {
+        error!("Certificate should be signed with ES384");
+        Err(Error::RmiErrorInput)?
     }
 
-    pub fn verify_signature(&self) -> core::result::Result<(), Error> {
-        let verifying_key = self.verifying_key()?;
-        let signature = self.signature()?;
-        let data = self.header_as_u8_slice();
+    let verifying_key = p384_verifying_key(issuer)?;
+    sign1
+        .verify_signature(b"", |sig, data| {
+            let signature = Signature::from_slice(sig)?;
+            verifying_key.verify(data, &signature)
+        })
+        .or(Err(Error::RmiErrorInput))?;
+
+    let payload = sign1.payload.as_ref().ok_or(Error::RmiErrorInput)?;
+    let key = CoseKey::from_slice(payload).or(Err(Error::RmiErrorInput))?;
+    let param = |label: iana::Ec2KeyParameter| {
+        key.params
+            .iter()
+            .find(|(l, _)| *l == Label::Int(label as i64))
+            .map(|(_, value)| value)
+            .ok_or(Error::RmiErrorInput)
+    };
 
-        verifying_key
-            .verify(data, &signature)
-            .or(Err(Error::RmiErrorInput))
+    let crv = param(iana::Ec2KeyParameter::Crv)?.as_integer();
+    if crv != Some((iana::EllipticCurve::P_384 as i64).into()) {
+        error!("Certificate should be of a P-384 key");
+        Err(Error::RmiErrorInput)?
+    }
+    let x = param(iana::Ec2KeyParameter::X)?.as_bytes();
+    let y = param(iana::Ec2KeyParameter::Y)?.as_bytes();
+    let (Some(x), Some(y)) = (x, y) else {
+        return Err(Error::RmiErrorInput);
+    };
+    if x.len() != P384_PUBLIC_KEY_SIZE / 2 || y.len() != P384_PUBLIC_KEY_SIZE / 2 {
+        return Err(Error::RmiErrorInput);
     }
 
-    pub fn validate(&self) -> core::result::Result<(), Error> {
-        if self.fmt_version != FMT_VERSION {
-            error!(
-                "Metadata format version {} is not supported!",
-                self.fmt_version
-            );
-            Err(Error::RmiErrorInput)?
-        }
+    let mut subject = [0u8; P384_PUBLIC_KEY_SIZE];
+    subject[..x.len()].copy_from_slice(x);
+    subject[x.len()..].copy_from_slice(y);
+    Ok(subject)
+}
 
-        if self.svn == 0 {
-            error!("SVN number should be greater than zero");
+// Checks that `public_key` is certified by the vendor key through the chain
+fn verify_chain(metadata: &IsletRealmMetadata) -> core::result::Result<(), Error> {
+    let Some(vendor_key) = TRUST.lock().vendor_key else {
+        error!("No vendor key is provisioned for the metadata v2");
+        return Err(Error::RmiErrorInput);
+    };
+
+    let chain = match metadata.chain() {
+        Some(chain) if !chain.is_empty() => chain,
+        _ => {
+            error!("Invalid length of the signer chain");
             Err(Error::RmiErrorInput)?
         }
+    };
 
-        if ![METADATA_HASH_SHA_256, METADATA_HASH_SHA_512].contains(&self.hash_algo) {
-            error!("Hash algorithm is invalid {}", self.hash_algo);
+    let mut issuer = vendor_key;
+    for cert in chain {
+        issuer = verify_cert(cert, &issuer)?;
+        if is_revoked(&issuer) {
+            error!("The signer chain has a revoked key");
             Err(Error::RmiErrorInput)?
         }
+    }
 
-        let is_printable_ascii = |&c| c >= b' ' && c <= b'~';
+    if issuer != *metadata.public_key() {
+        error!("The signer chain doesn't end with the metadata signing key");
+        Err(Error::RmiErrorInput)?
+    }
+    Ok(())
+}
 
-        if !self
-            .realm_id
-            .iter()
-            .take_while(|&c| *c != b'\0')
-            .all(is_printable_ascii)
-        {
-            error!("Realm id is invalid");
-            Err(Error::RmiErrorInput)?
-        }
+pub fn verify_signature(metadata: &IsletRealmMetadata) -> core::result::Result<(), Error> {
+    if is_revoked(metadata.public_key()) {
+        error!("The metadata signing key is revoked");
+        Err(Error::RmiErrorInput)?
+    }
+
+    if metadata.fmt_version() == FMT_VERSION_2 {
+        verify_chain(metadata)?;
+    }
+
+    let verifying_key = p384_verifying_key(metadata.public_key())?;
+    let signature = Signature::from_slice(metadata.signature()).or(Err(Error::RmiErrorInput))?;
 
-        Ok(())
+    verifying_key
+        .verify(metadata.signed_data(), &signature)
+        .or(Err(Error::RmiErrorInput))
+}
+
+pub fn validate(metadata: &IsletRealmMetadata) -> core::result::Result<(), Error> {
+    if let Err(e) = metadata.validate() {
+        error!("Metadata fields are invalid");
+        Err(e)?
     }
 
-    pub fn equal_rd_rim(&self, rim: &Measurement) -> bool {
-        rim.as_slice() == self.rim
+    if metadata.fmt_version() == FMT_VERSION_1 && TRUST.lock().require_v2 {
+        error!("Metadata v1 is not accepted on this platform");
+        Err(Error::RmiErrorInput)?
     }
 
-    pub fn equal_rd_hash_algo(&self, hash_algo: u8) -> bool {
-        let converted_algo = match hash_algo {
-            HASH_ALGO_SHA256 => METADATA_HASH_SHA_256,
-            HASH_ALGO_SHA512 => METADATA_HASH_SHA_512,
-            _ => unreachable!(),
-        };
+    Ok(())
+}
+
+pub fn equal_rd_rim(metadata: &IsletRealmMetadata, rim: &Measurement) -> bool {
+    rim.as_slice() == metadata.rim()
+}
+
+pub fn equal_rd_hash_algo(metadata: &IsletRealmMetadata, hash_algo: u8) -> bool {
+    let converted_algo = match hash_algo {
+        HASH_ALGO_SHA256 => METADATA_HASH_SHA_256,
+        HASH_ALGO_SHA512 => METADATA_HASH_SHA_512,
+        HASH_ALGO_SHA384 => METADATA_HASH_SHA_384,
+        _ => unreachable!(),
+    };
+
+    converted_algo == metadata.hash_algo()
+}
 
-        converted_algo == self.hash_algo
+pub fn dump(metadata: &IsletRealmMetadata) {
+    for line in alloc::format!("{}", metadata).lines() {
+        debug!("{}", line);
     }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::boxed::Box;
+    use coset::{CoseKeyBuilder, CoseSign1Builder, HeaderBuilder};
+    use p384::ecdsa::{signature::Signer, SigningKey};
 
-    // for sealing key derivation
+    // The tests share the provisioned trust
+    static TRUST_LOCK: Mutex<()> = Mutex::new(());
 
-    pub fn svn(&self) -> usize {
-        self.svn
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_slice(&[seed; 48]).unwrap()
     }
 
-    pub fn public_key(&self) -> &[u8; P384_PUBLIC_KEY_SIZE] {
-        &self.public_key
+    fn public_key(key: &SigningKey) -> [u8; P384_PUBLIC_KEY_SIZE] {
+        let point = key.verifying_key().to_encoded_point(false);
+        point.as_bytes()[1..].try_into().unwrap()
     }
 
-    pub fn realm_id(&self) -> &[u8; REALM_ID_SIZE] {
-        &self.realm_id
+    // The payload of a certificate is the P-384 COSE_Key of the subject
+    fn cert(issuer: &SigningKey, subject: &SigningKey) -> Vec<u8> {
+        let subject = public_key(subject);
+        let (x, y) = subject.split_at(P384_PUBLIC_KEY_SIZE / 2);
+        let payload =
+            CoseKeyBuilder::new_ec2_pub_key(iana::EllipticCurve::P_384, x.to_vec(), y.to_vec())
+                .build()
+                .to_vec()
+                .unwrap();
+        CoseSign1Builder::new()
+            .protected(
+                HeaderBuilder::new()
+                    .algorithm(iana::Algorithm::ES384)
+                    .build(),
+            )
+            .payload(payload)
+            .create_signature(b"", |data| {
+                let signature: Signature = issuer.sign(data);
+                signature.to_vec()
+            })
+            .build()
+            .to_tagged_vec()
+            .unwrap()
     }
-}
 
-// This should work but I don't like this traits
-impl vmsa::guard::Content for IsletRealmMetadata {}
-impl safe_abstraction::raw_ptr::RawPtr for IsletRealmMetadata {}
-impl safe_abstraction::raw_ptr::SafetyChecked for IsletRealmMetadata {}
-impl safe_abstraction::raw_ptr::SafetyAssured for IsletRealmMetadata {
-    fn is_initialized(&self) -> bool {
-        true
+    fn sign(metadata: &mut IsletRealmMetadata, signer: &SigningKey) {
+        let signature: Signature = signer.sign(metadata.signed_data());
+        metadata.set_signature(&signature.to_bytes()[..].try_into().unwrap());
     }
 
-    fn verify_ownership(&self) -> bool {
-        true
+    fn metadata(signer: &SigningKey, chain: &[Vec<u8>]) -> Box<IsletRealmMetadata> {
+        let mut metadata = Box::new(
+            IsletRealmMetadata::new("realm", &[], METADATA_HASH_SHA_256, 1, (1, 0, 0)).unwrap(),
+        );
+        let chain: Vec<&[u8]> = chain.iter().map(Vec::as_slice).collect();
+        metadata.set_signer(&public_key(signer), &chain).unwrap();
+        sign(&mut metadata, signer);
+        metadata
+    }
+
+    #[test]
+    fn metadata_signer_chain() {
+        let _lock = TRUST_LOCK.lock();
+        let (vendor, team, signer) = (signing_key(1), signing_key(2), signing_key(3));
+
+        // v1 is signed by the embedded key only
+        let v1 = metadata(&signer, &[]);
+        assert!(validate(&v1).is_ok());
+        assert!(verify_signature(&v1).is_ok());
+
+        // which is still accepted along with the vendor key, unless v2 is required
+        provision_trust(Some(&public_key(&vendor)), &[], false);
+        assert!(validate(&v1).is_ok());
+        provision_trust(Some(&public_key(&vendor)), &[], true);
+        assert!(validate(&v1).is_err());
+        provision_trust(Some(&public_key(&vendor)), &[], false);
+
+        let v2 = metadata(&signer, &[cert(&vendor, &team), cert(&team, &signer)]);
+        assert!(validate(&v2).is_ok());
+        assert!(verify_signature(&v2).is_ok());
+
+        // The chain should be rooted in the vendor key
+        let rogue = metadata(&signer, &[cert(&team, &signer)]);
+        assert!(verify_signature(&rogue).is_err());
+
+        // The chain should end with the signing key
+        let broken = metadata(&signer, &[cert(&vendor, &team)]);
+        assert!(verify_signature(&broken).is_err());
+
+        // Any key in the chain can be revoked
+        let team_hash: [u8; KEY_HASH_SIZE] = Sha384::digest(public_key(&team)).into();
+        provision_trust(Some(&public_key(&vendor)), &[team_hash], false);
+        assert!(verify_signature(&v2).is_err());
+        assert!(verify_signature(&v1).is_ok());
+
+        let signer_hash: [u8; KEY_HASH_SIZE] = Sha384::digest(public_key(&signer)).into();
+        provision_trust(Some(&public_key(&vendor)), &[signer_hash], false);
+        assert!(verify_signature(&v1).is_err());
+
+        provision_trust(None, &[], false);
+        assert!(verify_signature(&v2).is_err());
+        assert!(validate(&v1).is_ok());
+
+        // v2 can't be required without the vendor key
+        provision_trust(None, &[], true);
+        assert!(validate(&v1).is_ok());
+        provision_trust(None, &[], false);
+    }
+
+    #[test]
+    fn metadata_build() {
+        let _lock = TRUST_LOCK.lock();
+        // Keys other than the ones of metadata_signer_chain, which revokes them
+        let (issuer, signer) = (signing_key(4), signing_key(5));
+
+        assert!(IsletRealmMetadata::new("realm", &[0; 64], 0x4, 1, (1, 0, 0)).is_err());
+        assert!(
+            IsletRealmMetadata::new("realm", &[0; 64], METADATA_HASH_SHA_256, 0, (1, 0, 0))
+                .is_err()
+        );
+
+        let mut metadata =
+            IsletRealmMetadata::new("realm", &[0xAB; 32], METADATA_HASH_SHA_256, 1, (1, 2, 3))
+                .unwrap();
+        assert!(metadata.set_signer(&public_key(&signer), &[]).is_ok());
+        sign(&mut metadata, &signer);
+
+        let decoded = IsletRealmMetadata::from_bytes(metadata.as_bytes()).unwrap();
+        assert!(validate(&decoded).is_ok());
+        assert!(verify_signature(&decoded).is_ok());
+        assert_eq!(decoded.version(), (1, 2, 3));
+        assert!(alloc::format!("{}", decoded).contains("realm_id: realm"));
+        assert!(IsletRealmMetadata::from_bytes(&metadata.as_bytes()[1..]).is_none());
+
+        // The chain makes it v2
+        let cert = cert(&issuer, &signer);
+        assert!(metadata.set_signer(&public_key(&signer), &[&cert]).is_ok());
+        assert_eq!(metadata.fmt_version(), FMT_VERSION_2);
+        assert_eq!(metadata.chain().map(<[_]>::len), Some(1));
+        assert!(metadata
+            .set_signer(&public_key(&signer), &[&cert[..]; MAX_CHAIN_LEN + 1])
+            .is_err());
     }
 }
@@ -6,18 +6,13 @@ use crate::granule::GRANULE_SIZE;
 use crate::granule::{set_granule, GranuleState};
 use crate::host;
 use crate::listen;
-use crate::measurement::{HashContext, MEASUREMENTS_SLOT_RIM};
-use crate::realm::mm::stage2_translation::Stage2Translation;
-use crate::realm::mm::IPATranslation;
-use crate::realm::rd::State;
-use crate::realm::rd::{insert_rtt, Rd};
-use crate::realm::registry::{remove, VMID_SET};
-use crate::rmi::{self, metadata::IsletRealmMetadata};
+use crate::measurement::{self, MEASUREMENTS_SLOT_RIM};
+use crate::realm::rd::{Rd, State};
+use crate::realm::registry::{alloc_mecid, release_mecid, remove, VMID_SET};
+use crate::rmi::{self, metadata, metadata::IsletRealmMetadata};
 use crate::{get_granule, get_granule_if};
 
 use alloc::boxed::Box;
-use alloc::sync::Arc;
-use spin::mutex::Mutex;
 
 extern crate alloc;
 
@@ -33,12 +28,12 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
             let metadata_granule = get_granule_if!(meta, GranuleState::Metadata)?;
             let metadata_obj = metadata_granule.content::<IsletRealmMetadata>()?;
 
-            if !metadata_obj.equal_rd_rim(&rd.measurements[MEASUREMENTS_SLOT_RIM]) {
+            if !metadata::equal_rd_rim(&metadata_obj, &rd.measurements[MEASUREMENTS_SLOT_RIM]) {
                 error!("Calculated rim and those read from metadata are not the same!");
                 return Err(Error::RmiErrorRealm(0));
             }
 
-            if !metadata_obj.equal_rd_hash_algo(rd.hash_algo()) {
+            if !metadata::equal_rd_hash_algo(&metadata_obj, rd.hash_algo()) {
                 error!("Provided measurement hash algorithm and metadata hash algorithm are different!");
                 return Err(Error::RmiErrorRealm(0));
             }
@@ -70,27 +65,25 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
         rmm.page_table.map(params_ptr, false);
         let params = host::copy_from::<Params>(params_ptr).ok_or(Error::RmiErrorInput)?;
         rmm.page_table.unmap(params_ptr);
-        params.verify_compliance(rd)?;
-
-        for i in 0..params.rtt_num_start as usize {
-            let rtt = params.rtt_base as usize + i * GRANULE_SIZE;
-            let _ = get_granule_if!(rtt, GranuleState::Delegated)?;
-            // The below is added to avoid a fault regarding the RTT entry
-            // during the below stage 2 page table creation
-            rmm.page_table.map(rtt, true);
+        params::verify_compliance(&params, rd)?;
+
+        let num_aux_planes = params.num_aux_planes();
+        let rtt_bases = core::iter::once(params.rtt_base)
+            .chain(params.aux_rtt_base[..num_aux_planes].iter().copied());
+        for rtt_base in rtt_bases.clone() {
+            for i in 0..params.rtt_num_start as usize {
+                let rtt = rtt_base as usize + i * GRANULE_SIZE;
+                let _ = get_granule_if!(rtt, GranuleState::Delegated)?;
+                // The below is added to avoid a fault regarding the RTT entry
+                // during the below stage 2 page table creation
+                rmm.page_table.map(rtt, true);
+            }
         }
 
         // revisit rmi.create_realm() (is it necessary?)
-        create_realm(params.vmid as usize).map(|_| {
-            let s2 = Box::new(Stage2Translation::new(
-                params.rtt_base as usize,
-                params.rtt_level_start as usize,
-                params.rtt_num_start as usize,
-            )) as Box<dyn IPATranslation>;
-
-            insert_rtt(params.vmid as usize, Arc::new(Mutex::new(s2)));
-
-            rd_obj.init(
+        create_realm(params.vmid as usize)?;
+        rd_obj
+            .init(
                 params.vmid,
                 params.rtt_base as usize,
                 params.rtt_num_start as usize,
@@ -102,20 +95,59 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
                 params.pmu_en(),
                 params.pmu_num_ctrs as usize,
             )
-        })?;
+            .inspect_err(|_| {
+                remove(params.vmid as usize).expect("Realm should be created before.");
+            })?;
+
+        // Each auxiliary plane tags its own stage 2 table with its own VMID
+        let aux_vmid = &params.aux_vmid[..num_aux_planes];
+        for (i, vmid) in aux_vmid.iter().enumerate() {
+            create_realm(*vmid as usize).inspect_err(|_| {
+                remove(params.vmid as usize).expect("Realm should be created before.");
+                for created in &aux_vmid[..i] {
+                    let _ = remove(*created as usize);
+                }
+            })?;
+        }
+        let aux_rtt_base = params.aux_rtt_base.map(|base| base as usize);
+        rd_obj
+            .set_aux_planes(aux_vmid, &aux_rtt_base[..num_aux_planes])
+            .inspect_err(|_| {
+                remove(params.vmid as usize).expect("Realm should be created before.");
+                for vmid in aux_vmid {
+                    remove(*vmid as usize).expect("Plane should be created before.");
+                }
+            })?;
 
-        let rtt_base = rd_obj.rtt_base();
         rd_obj.set_hash_algo(params.hash_algo);
+        rd_obj.set_da_en(params.da_en());
+        rd_obj.set_lpa2(params.lpa2());
+        rd_obj.set_debug_config(params.num_bps as usize, params.num_wps as usize);
+        rd_obj.set_sme_config(params.sme_en(), params.sme_vl as u64);
+        rd_obj.set_mte_en(params.mte_en());
 
         #[cfg(not(kani))]
         // `rsi` is currently not reachable in model checking harnesses
-        HashContext::new(&mut rd_obj)?.measure_realm_create(&params)?;
+        measurement::rim_context(&mut rd_obj)?.measure_realm_create(&(&params).into())?;
+
+        if params.mec_policy == rmi::MEC_POLICY_PRIVATE {
+            let mecid = alloc_mecid().inspect_err(|_| {
+                remove(params.vmid as usize).expect("Realm should be created before.");
+                for vmid in &params.aux_vmid[..num_aux_planes] {
+                    remove(*vmid as usize).expect("Plane should be created before.");
+                }
+            })?;
+            rd_obj.set_mecid(mecid);
+        }
+        let mecid = rd_obj.mecid();
 
         let mut epilogue = move || {
-            for i in 0..params.rtt_num_start as usize {
-                let rtt = rtt_base + i * GRANULE_SIZE;
-                let mut rtt_granule = get_granule_if!(rtt, GranuleState::Delegated)?;
-                set_granule(&mut rtt_granule, GranuleState::RTT)?;
+            for rtt_base in rtt_bases.clone() {
+                for i in 0..params.rtt_num_start as usize {
+                    let rtt = rtt_base as usize + i * GRANULE_SIZE;
+                    let mut rtt_granule = get_granule_if!(rtt, GranuleState::Delegated)?;
+                    set_granule(&mut rtt_granule, GranuleState::RTT)?;
+                }
             }
             set_granule(&mut rd_granule, GranuleState::RD)
         };
@@ -125,6 +157,10 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
             // `page_table` is currently not reachable in model checking harnesses
             rmm.page_table.unmap(rd);
             remove(params.vmid as usize).expect("Realm should be created before.");
+            for vmid in &params.aux_vmid[..num_aux_planes] {
+                remove(*vmid as usize).expect("Plane should be created before.");
+            }
+            release_mecid(mecid).expect("MECID should be allocated before.");
         })
     });
 
@@ -168,6 +204,18 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
                     return Err(Error::RmiErrorRealm(0));
                 }
             }
+            #[cfg(not(kani))]
+            for plane in 1..=rd.num_aux_planes() {
+                let aux_rtt_base = rd.aux_rtt_base(plane)?;
+                for i in 0..rd.rtt_num_start() {
+                    let rtt = aux_rtt_base + i * GRANULE_SIZE;
+
+                    let rtt_granule = get_granule!(rtt)?;
+                    if rtt_granule.num_children() > 0 {
+                        return Err(Error::RmiErrorRealm(0));
+                    }
+                }
+            }
             #[cfg(kani)]
             {
                 // XXX: we remove the loop and consider only the first iteration
@@ -185,6 +233,15 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
             let mut rtt_granule = get_granule!(rtt)?;
             set_granule(&mut rtt_granule, GranuleState::Delegated)?;
         }
+        #[cfg(not(kani))]
+        for plane in 1..=rd.num_aux_planes() {
+            let aux_rtt_base = rd.aux_rtt_base(plane)?;
+            for i in 0..rd.rtt_num_start() {
+                let rtt = aux_rtt_base + i * GRANULE_SIZE;
+                let mut rtt_granule = get_granule_if!(rtt, GranuleState::RTT)?;
+                set_granule(&mut rtt_granule, GranuleState::Delegated)?;
+            }
+        }
         #[cfg(kani)]
         {
             // XXX: we remove the loop and consider only the first iteration
@@ -200,7 +257,16 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
         rmm.page_table.unmap(arg[0]);
         // TODO: remove the below after modeling `VmidIsFree()`
         #[cfg(not(kani))]
-        remove(vmid)?;
+        {
+            for plane in 1..=rd.num_aux_planes() {
+                remove(rd.plane_vmid(plane)?)?;
+            }
+            remove(vmid)?;
+            release_mecid(rd.mecid())?;
+            rd.destroy_s2_tables();
+        }
+        #[cfg(feature = "stat")]
+        crate::stat::REALM_STATS.lock().remove(vmid);
 
         Ok(())
     });
@@ -220,14 +286,14 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
         let realm_metadata: Box<IsletRealmMetadata> =
             Box::new(host::copy_from(meta_ptr).ok_or(Error::RmiErrorInput)?);
         rmm.page_table.unmap(meta_ptr);
-        realm_metadata.dump();
+        metadata::dump(&realm_metadata);
 
-        if let Err(e) = realm_metadata.verify_signature() {
+        if let Err(e) = metadata::verify_signature(&realm_metadata) {
             error!("Verification of realm metadata signature has failed");
             Err(e)?;
         }
 
-        if let Err(e) = realm_metadata.validate() {
+        if let Err(e) = metadata::validate(&realm_metadata) {
             error!("The content of realm metadata is not valid");
             Err(e)?;
         }
@@ -270,11 +336,26 @@ fn create_realm(vmid: usize) -> Result<(), Error> {
 
 #[cfg(test)]
 mod test {
+    use crate::debug;
+    use crate::event::replay::{encode, Record};
+    use crate::granule::{GranuleState, GRANULE_SIZE};
+    use crate::host::DataPage;
+    use crate::measurement::{
+        rim_context, MEASUREMENTS_SLOT_RIM, MEASURE_DESC_TYPE_DATA, RMI_MEASURE_CONTENT,
+    };
+    use crate::mec::MECID_SHARED;
     use crate::realm::rd::{Rd, State};
-    use crate::rmi::{ERROR_INPUT, REALM_ACTIVATE, REALM_CREATE, SUCCESS};
+    use crate::rmi::realm::Params as RealmParams;
+    use crate::rmi::{
+        ERROR_INPUT, GRANULE_DELEGATE, GRANULE_UNDELEGATE, HASH_ALGO_SHA384, MEC_POLICY_PRIVATE,
+        REALM_ACTIVATE, REALM_CREATE, SUCCESS,
+    };
     use crate::test_utils::*;
 
     use alloc::vec;
+    use sha2::{Digest, Sha384};
+
+    const SHA384_SIZE: usize = 48;
 
     #[test]
     fn rmi_realm_create_positive() {
@@ -293,51 +374,172 @@ mod test {
         miri_teardown();
     }
 
-    // Source: https://github.com/ARM-software/cca-rmm-acs
-    // Test Case: cmd_realm_create
-    /*
-        Check 1 : params_align; rd : 0x88300000 params_ptr : 0x88303001 ret : 1
-        Check 2 : params_bound; rd : 0x88300000 params_ptr : 0x1C0B0000 ret : 1
-        Check 3 : params_bound; rd : 0x88300000 params_ptr : 0x1000000001000 ret : 1
-        Check 4 : params_pas; rd : 0x88300000 params_ptr : 0x88309000 ret : 1
-        Check 5 : params_pas; rd : 0x88300000 params_ptr : 0x6000000 ret : 1
-        Check 6 : params_valid; rd : 0x88300000 params_ptr : 0x8830C000 ret : 1
-        Check 7 : params_valid; rd : 0x88300000 params_ptr : 0x8830F000 ret : 1
-        Check 8 : params_supp
-        Skipping Test case
-        Check 9 : params_supp; rd : 0x88300000 params_ptr : 0x88315000 ret : 1
-        Check 10 : params_supp; rd : 0x88300000 params_ptr : 0x88318000 ret : 1
-        Check 11 : params_supp; rd : 0x88300000 params_ptr : 0x8831B000 ret : 1
-        Check 12 : params_supp; rd : 0x88300000 params_ptr : 0x8831E000 ret : 1
-        Check 13 : params_supp; rd : 0x88300000 params_ptr : 0x88321000 ret : 1
-        Check 14 : alias; rd : 0x88306000 params_ptr : 0x88303000 ret : 1
-        Check 15 : rd_align; rd : 0x88300001 params_ptr : 0x88303000 ret : 1
-        Check 16 : rd_bound; rd : 0x1C0B0000 params_ptr : 0x88303000 ret : 1
-        Check 17 : rd_bound; rd : 0x1000000001000 params_ptr : 0x88303000 ret : 1
-        Check 18 : rd_state; rd : 0x88324000 params_ptr : 0x88303000 ret : 1
-        Check 19 : rd_state; rd : 0x88327000 params_ptr : 0x88303000 ret : 1
-        Check 20 : rd_state; rd : 0x88336000 params_ptr : 0x88303000 ret : 1
-        Check 21 : rd_state; rd : 0x8832A000 params_ptr : 0x88303000 ret : 1
-        Check 22 : rd_state; rd : 0x88372000 params_ptr : 0x88303000 ret : 1
-        Check 23 : rtt_align; rd : 0x88300000 params_ptr : 0x88378000 ret : 1
-        Check 24 : rtt_num_level; rd : 0x88300000 params_ptr : 0x8837B000 ret : 1
-        Check 25 : rtt_state; rd : 0x88300000 params_ptr : 0x8837E000 ret : 1
-        Check 26 : vmid_valid
-        Couldn't create VMID Invalid sequence
-        Skipping Test case
-        Check 27 : vmid_valid; rd : 0x88300000 params_ptr : 0x88387000 ret : 1
-    */
+    #[test]
+    fn rmi_realm_create_aux_planes() {
+        let aux_rtt = alloc_granule(IDX_RTT_AUX_LEVEL0);
+        let ret = rmi::<GRANULE_DELEGATE>(&[aux_rtt]);
+        assert_eq!(ret[0], SUCCESS);
+
+        let set_aux_plane = |params: &mut RealmParams, aux_vmid| {
+            params.num_aux_planes = 1;
+            params.aux_rtt_base[0] = aux_rtt as u64;
+            params.aux_vmid[0] = aux_vmid;
+        };
+
+        // The auxiliary plane can't share the VMID of the primary plane
+        let ret = realm_create_with(|params| set_aux_plane(params, params.vmid));
+        assert_eq!(ret, Err(ERROR_INPUT));
+
+        let rd = realm_create_with(|params| set_aux_plane(params, params.vmid + 1)).unwrap();
+        unsafe {
+            let rd_obj = &*(rd as *const Rd);
+            assert_eq!(rd_obj.num_aux_planes(), 1);
+            assert_eq!(rd_obj.aux_rtt_base(1).unwrap(), aux_rtt);
+        };
+        realm_destroy(rd);
+
+        let ret = rmi::<GRANULE_UNDELEGATE>(&[aux_rtt]);
+        assert_eq!(ret[0], SUCCESS);
+
+        miri_teardown();
+    }
+
+    #[test]
+    fn rmi_realm_create_debug() {
+        // More breakpoints than the PE implements
+        let ret = realm_create_with(|params| params.num_bps = debug::max_num_bps() as u8 + 1);
+        assert_eq!(ret, Err(ERROR_INPUT));
+
+        let rd = realm_create_with(|params| {
+            params.num_bps = 2;
+            params.num_wps = 1;
+        })
+        .unwrap();
+        unsafe {
+            let rd_obj = &*(rd as *const Rd);
+            assert_eq!(rd_obj.debug_config(), (2, 1));
+        };
+        realm_destroy(rd);
+
+        miri_teardown();
+    }
+
+    #[test]
+    fn rmi_realm_create_mec_private() {
+        const MEC_POLICY_INVALID: u8 = 2;
+        let ret = realm_create_with(|params| params.mec_policy = MEC_POLICY_INVALID);
+        assert_eq!(ret, Err(ERROR_INPUT));
+
+        let rd = realm_create_with(|params| params.mec_policy = MEC_POLICY_PRIVATE).unwrap();
+        let mecid = unsafe { (*(rd as *const Rd)).mecid() };
+        assert_ne!(mecid, MECID_SHARED);
+        realm_destroy(rd);
+
+        // A released MECID is given again once its key is refreshed
+        let rd = realm_create_with(|params| params.mec_policy = MEC_POLICY_PRIVATE).unwrap();
+        assert_eq!(unsafe { (*(rd as *const Rd)).mecid() }, mecid);
+        realm_destroy(rd);
+
+        let rd = realm_create();
+        assert_eq!(unsafe { (*(rd as *const Rd)).mecid() }, MECID_SHARED);
+        realm_destroy(rd);
+
+        miri_teardown();
+    }
+
+    #[test]
+    fn rmi_realm_create_sha384() {
+        let rd = realm_create_with(|params| params.hash_algo = HASH_ALGO_SHA384).unwrap();
+
+        // RIM starts from the hash of the params, whose unused fields are zeroed
+        let mut measured_params = [0u8; GRANULE_SIZE];
+        measured_params[0x8] = 40;
+        measured_params[0x30] = HASH_ALGO_SHA384;
+        let rim = Sha384::digest(measured_params);
+
+        let rd_obj = unsafe { &mut *(rd as *mut Rd) };
+        let measurement = rd_obj.measurements[MEASUREMENTS_SLOT_RIM];
+        assert_eq!(&measurement.as_slice()[..SHA384_SIZE], &rim[..]);
+        assert!(measurement.as_slice()[SHA384_SIZE..]
+            .iter()
+            .all(|b| *b == 0));
+
+        // RIM is extended with the measurement descriptor of the data granule
+        let mut data = DataPage::default();
+        data.as_mut_slice().fill(0xab);
+        let ipa: usize = 0x1000;
+
+        let mut desc = [0u8; 0x100];
+        desc[0x0] = MEASURE_DESC_TYPE_DATA;
+        desc[0x8..0x10].copy_from_slice(&0x100u64.to_le_bytes());
+        desc[0x10..0x10 + SHA384_SIZE].copy_from_slice(&rim);
+        desc[0x50..0x58].copy_from_slice(&ipa.to_le_bytes());
+        desc[0x58..0x60].copy_from_slice(&RMI_MEASURE_CONTENT.to_le_bytes());
+        desc[0x60..0x60 + SHA384_SIZE].copy_from_slice(&Sha384::digest(data.as_slice()));
+        let extended = Sha384::digest(desc);
+
+        rim_context(rd_obj)
+            .unwrap()
+            .measure_data_granule(data.as_slice(), ipa, RMI_MEASURE_CONTENT)
+            .unwrap();
+        let measurement = rd_obj.measurements[MEASUREMENTS_SLOT_RIM];
+        assert_eq!(&measurement.as_slice()[..SHA384_SIZE], &extended[..]);
+
+        realm_destroy(rd);
+
+        miri_teardown();
+    }
+
+    #[test]
+    fn rmi_realm_create_mte() {
+        const MTE_FLAG: u64 = 1 << 5;
+
+        let rd = realm_create_with(|params| params.flags = MTE_FLAG).unwrap();
+        assert!(unsafe { (*(rd as *const Rd)).mte_en() });
+        realm_destroy(rd);
+
+        miri_teardown();
+    }
+
     #[test]
     fn rmi_realm_create_negative() {
-        let test_data = vec![
-            // TODO: Cover all test data
-            ((0x88300000 as usize, 0x88303001 as usize), ERROR_INPUT),
+        // TODO: Cover all test data
+        let checks = [
+            (0x88300000, 0x88303001), // params_align
+            (0x88300000, 0x1C0B0000), // params_bound
+            (0x88300000, 0x1000000001000),
+            (0x88300000, 0x88309000), // params_pas
+            (0x88300001, 0x88303000), // rd_align
+            (0x1C0B0000, 0x88303000), // rd_bound
+            (0x1000000001000, 0x88303000),
         ];
 
-        // main test
-        for (input, output) in test_data {
-            let ret = rmi::<REALM_CREATE>(&[input.0, input.1, 0]);
-            assert_eq!(output, ret[0]);
+        let call = |cmd, args: &[usize], status| Record::Rmi {
+            cmd,
+            args: args.to_vec(),
+            ret: vec![status, 0],
+            buffer: None,
+        };
+        let mut records = vec![Record::Dram {
+            base: 0x80000000,
+            size: 0x7C000000,
+        }];
+        for addr in [0x88300000, 0x88309000] {
+            records.push(call(GRANULE_DELEGATE, &[addr], SUCCESS));
+        }
+        for (rd, params_ptr) in checks {
+            records.push(call(REALM_CREATE, &[rd, params_ptr], ERROR_INPUT));
+        }
+        for addr in [0x88300000, 0x88309000] {
+            records.push(call(GRANULE_UNDELEGATE, &[addr], SUCCESS));
         }
+        for addr in [0x88300000, 0x88303000, 0x88309000] {
+            records.push(Record::State {
+                addr,
+                state: GranuleState::Undelegated as u64,
+            });
+        }
+
+        replay(&encode(&records));
     }
 }
@@ -8,6 +8,7 @@ use crate::realm::mm::rtt::RTT_PAGE_LEVEL;
 use crate::realm::mm::stage2_tte::S2TTE;
 use crate::realm::rd::Rd;
 use crate::rec::context::get_reg;
+use crate::rec::mmio::PendingMmio;
 use crate::rec::sea::inject_sea;
 use crate::rec::{
     Rec, RmmRecEmulatableAbort::EmulatableAbort, RmmRecEmulatableAbort::NotEmulatableAbort,
@@ -29,6 +30,8 @@ enum AbortHandleType {
     AddrSizeFaultInject,
     NonEmulatableExit,
     EmulatableExit,
+    // Emulatable if the faulting instruction can be decoded (ISV == 0)
+    DecodeExit,
     DataAbortExit,
 }
 
@@ -39,6 +42,14 @@ pub fn handle_realm_exit(
     run: &mut Run,
 ) -> Result<(bool, usize), Error> {
     let mut return_to_ns = true;
+
+    // Synchronous exits of an auxiliary plane are handled by the primary plane
+    #[cfg(not(kani))]
+    if rec.active_plane() != 0 && crate::rec::plane::exits_to_primary(realm_exit_res[0]) {
+        rsi::plane::exit_to_primary(rec)?;
+        return Ok((false, rmi::SUCCESS));
+    }
+
     let ret = match RecExitReason::from(realm_exit_res[0]) {
         #[cfg(not(kani))]
         // `rsi` is currently not reachable in model checking harnesses
@@ -164,7 +175,7 @@ fn abort_handle_type(
 ) -> Result<AbortHandleType, Error> {
     let is_protected_ipa = rd.addr_in_par(fault_ipa);
     let (s2tte, last_level) =
-        S2TTE::get_s2tte(rd, fault_ipa, RTT_PAGE_LEVEL, Error::RmiErrorRtt(0))?;
+        S2TTE::get_s2tte(&rd.rtts(), fault_ipa, RTT_PAGE_LEVEL, Error::RmiErrorRtt(0))?;
     let esr = EsrEl2::new(esr_el2);
 
     if is_protected_ipa {
@@ -192,7 +203,7 @@ fn abort_handle_type(
             if esr.get_masked_value(EsrEl2::ISV) == 1 {
                 return Ok(AbortHandleType::EmulatableExit);
             } else {
-                return Ok(AbortHandleType::NonEmulatableExit);
+                return Ok(AbortHandleType::DecodeExit);
             }
         }
     }
@@ -214,12 +225,25 @@ fn handle_data_abort(
     let fault_ipa = hpfar_el2 & (HPFAR_EL2::FIPA.mask << HPFAR_EL2::FIPA.shift);
     let fault_ipa = (fault_ipa << 8) as usize;
 
-    let ret = match abort_handle_type(&rd, ExitSyncType::DataAbort, esr_el2, fault_ipa)? {
+    let mut mmio: Option<PendingMmio> = None;
+    let handle_type = match abort_handle_type(&rd, ExitSyncType::DataAbort, esr_el2, fault_ipa)? {
+        AbortHandleType::DecodeExit => {
+            mmio = crate::rec::mmio::decode_abort(&rd, rec, esr_el2);
+            match mmio {
+                Some(_) => AbortHandleType::EmulatableExit,
+                None => AbortHandleType::DecodeExit,
+            }
+        }
+        handle_type => handle_type,
+    };
+
+    let ret = match handle_type {
         AbortHandleType::SeaInject | AbortHandleType::AddrSizeFaultInject => {
             inject_sea(rec, esr_el2, far_el2);
             rmi::SUCCESS_REC_ENTER
         }
-        AbortHandleType::NonEmulatableExit => {
+        // DecodeExit remains when the faulting instruction couldn't be decoded
+        AbortHandleType::NonEmulatableExit | AbortHandleType::DecodeExit => {
             rec.set_emulatable_abort(NotEmulatableAbort);
             if rd.addr_in_par(fault_ipa) {
                 run.set_esr(esr_el2 & NON_EMULATABLE_ABORT_MASK);
@@ -233,11 +257,20 @@ fn handle_data_abort(
             rmi::SUCCESS
         }
         AbortHandleType::EmulatableExit => {
+            // The decoded access is reported as if it had a valid syndrome
+            let (esr_el2, far_el2) = match &mmio {
+                Some(mmio) => (mmio.esr(), mmio.far()),
+                None => (esr_el2, far_el2),
+            };
             if esr_el2 & EsrEl2::WNR != 0 {
-                let write_val = get_write_val(rec, esr_el2)?;
+                let write_val = match &mmio {
+                    Some(mmio) => mmio.write_val(rec)?,
+                    None => get_write_val(rec, esr_el2)?,
+                };
                 run.set_gpr(0, write_val)?;
             }
             rec.set_emulatable_abort(EmulatableAbort);
+            rec.set_pending_mmio(mmio);
             run.set_esr(esr_el2 & EMULATABLE_ABORT_MASK);
             run.set_far(far_el2 & !(GRANULE_MASK as u64));
             rmi::SUCCESS
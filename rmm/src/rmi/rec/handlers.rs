@@ -1,5 +1,5 @@
 use super::mpidr::MPIDR;
-use super::params::Params;
+use super::params::{self, Params};
 use super::run::{EntryFlag, Run};
 use super::vtcr::{activate_stage2_mmu, prepare_vtcr};
 use crate::event::RmiHandle;
@@ -9,9 +9,9 @@ use crate::granule::{set_granule, set_granule_with_parent, GranuleState};
 use crate::granule::{set_granule, GranuleState};
 use crate::host;
 use crate::listen;
-use crate::measurement::HashContext;
 use crate::realm::rd::{Rd, State};
 use crate::rec::context::{set_reg, RegOffset};
+use crate::rec::plane;
 use crate::rec::State as RecState;
 use crate::rec::{max_recs_order, Rec, RmmRecEmulatableAbort::NotEmulatableAbort};
 use crate::rmi;
@@ -23,17 +23,19 @@ use crate::{get_granule, get_granule_if};
 use aarch64_cpu::registers::*;
 use armv9a::bits_in_reg;
 
-extern crate alloc;
-
-fn prepare_args(rd: &mut Rd, mpidr: u64) -> Result<(usize, u64, u64), Error> {
-    let page_table = rd.s2_table().lock().get_base_address() as u64;
-    let vttbr = bits_in_reg(
+fn vttbr_of(rd: &Rd, plane: usize) -> Result<u64, Error> {
+    let page_table = rd.plane_s2_table(plane)?.get_base_address() as u64;
+    Ok(bits_in_reg(
         VTTBR_EL2::VMID.mask << VTTBR_EL2::VMID.shift,
-        rd.id() as u64,
+        rd.plane_vmid(plane)? as u64,
     ) | bits_in_reg(
         VTTBR_EL2::BADDR.mask << VTTBR_EL2::BADDR.shift,
         page_table >> 1,
-    );
+    ))
+}
+
+fn prepare_args(rd: &mut Rd, mpidr: u64) -> Result<(usize, u64, u64), Error> {
+    let vttbr = vttbr_of(rd, 0)?;
     let vmpidr = mpidr | (MPIDR_EL1::RES1.mask << MPIDR_EL1::RES1.shift);
     let vcpuid = rd.vcpu_index;
     rd.vcpu_index += 1;
@@ -55,7 +57,7 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
         rmm.page_table.map(params_ptr, false);
         let params = host::copy_from::<Params>(params_ptr).ok_or(Error::RmiErrorInput)?;
         rmm.page_table.unmap(params_ptr);
-        params.verify_compliance(rec, rd, params_ptr)?;
+        params::verify_compliance(&params, rec, rd, params_ptr)?;
 
         let rec_index = MPIDR::from(params.mpidr).index();
         let mut rd_granule = get_granule_if!(rd, GranuleState::RD)?;
@@ -89,6 +91,13 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
                     set_granule(&mut aux_granule, GranuleState::RecAux)?;
                 }
                 rec.init(owner, vcpuid, params.flags, params.aux, vttbr, vmpidr)?;
+
+                let num_aux = rd.num_aux_planes();
+                let mut aux_vttbr = [0u64; rmi::MAX_AUX_PLANES];
+                for (idx, vttbr) in aux_vttbr[..num_aux].iter_mut().enumerate() {
+                    *vttbr = vttbr_of(&rd, idx + 1)?;
+                }
+                plane::init_planes(&mut rec, &aux_vttbr[..num_aux])?;
             }
             Err(_) => return Err(Error::RmiErrorInput),
         }
@@ -106,7 +115,7 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
         rd.inc_recs();
         #[cfg(not(kani))]
         // `rsi` is currently not reachable in model checking harnesses
-        HashContext::new(&mut rd)?.measure_rec_params(&params)?;
+        crate::measurement::rim_context(&mut rd)?.measure_rec_params(&(&params).into())?;
 
         #[cfg(not(feature = "gst_page_table"))]
         rd_granule.inc_count();
@@ -121,11 +130,15 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
     listen!(rmi, rmi::REC_DESTROY, |arg, _ret, rmm| {
         let mut rec_granule = get_granule_if!(arg[0], GranuleState::Rec)?;
 
-        let rec = rec_granule.content::<Rec<'_>>()?;
+        let mut rec = rec_granule.content::<Rec<'_>>()?;
         if rec.get_state() == RecState::Running {
             return Err(Error::RmiErrorRec);
         }
 
+        #[cfg(not(kani))]
+        // `rsi` is currently not reachable in model checking harnesses
+        crate::rec::attest::wipe_token(&mut rec)?;
+
         #[cfg(not(kani))]
         for i in 0..rmi::MAX_REC_AUX_GRANULES {
             let rec_aux = rec.aux(i) as usize;
@@ -198,6 +211,8 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
                 panic!("Unexpected realm state");
             }
         }
+        #[cfg(feature = "stat")]
+        crate::stat::REALM_STATS.lock().rec_enter(rd.id());
         // XXX: we explicitly release Rd's lock here to avoid a deadlock
         core::mem::drop(rd_granule);
 
@@ -227,7 +242,9 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
 
         #[cfg(not(any(miri, test, fuzzing)))]
         crate::rec::gic::receive_state_from_host(&mut rec, &run)?;
-        crate::rec::mmio::emulate_mmio(&mut rec, &run)?;
+        // The second register of a decoded pair access is emulated
+        // by the host before resuming the Realm.
+        let mmio_pending = crate::rec::mmio::emulate_mmio(&mut rec, &mut run)?;
         crate::rec::sea::host_sea_inject(&mut rec, &run)?;
 
         crate::rsi::ripas::complete_ripas(&mut rec, &run)?;
@@ -252,7 +269,7 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
 
         crate::rec::save_host_state(&rec);
         let mut ret_ns;
-        loop {
+        while !mmio_pending {
             ret_ns = true;
             run.set_imm(0);
 
@@ -282,8 +299,16 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
             #[cfg(any(miri, test))]
             {
                 use crate::test_utils::mock;
-                mock::realm::setup_psci_complete(&mut rec, &mut run);
-                mock::realm::setup_ripas_state(&mut rec, &mut run);
+
+                // Tests pass the realm exit to emulate the way fuzz targets do (see below)
+                if arg.len() >= 3 {
+                    rec.set_emulatable_abort(NotEmulatableAbort);
+                    (_, ret[0]) =
+                        mock::realm::emulate_realm(rmm, &mut rec, &mut run, arg[2], &arg[3..])?;
+                } else {
+                    mock::realm::setup_psci_complete(&mut rec, &mut run);
+                    mock::realm::setup_ripas_state(&mut rec, &mut run);
+                }
             }
 
             #[cfg(fuzzing)]
@@ -353,11 +378,14 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
 
 #[cfg(test)]
 mod test {
-    use crate::event::realmexit::RecExitReason;
-    use crate::rmi::rec::run::Run;
+    use crate::event::realmexit::{ExitSyncType, RecExitReason};
+    use crate::granule::GRANULE_SIZE;
+    use crate::rec::Rec;
+    use crate::rmi::rec::run::{EntryFlag, Run};
     use crate::rmi::*;
     use crate::rsi::PSCI_CPU_ON;
     use crate::test_utils::*;
+    use armv9a::regs::{EsrEl2, ESR_EL2_EC_DATA_ABORT};
 
     // Source: https://github.com/ARM-software/cca-rmm-acs
     // Test Case: cmd_rec_create
@@ -400,4 +428,79 @@ mod test {
 
         miri_teardown();
     }
+
+    // Covered RMIs: REC_ENTER
+    // Related Spec: A7.2.2 Emulatable Data Abort
+    #[test]
+    fn rmi_rec_enter_mmio_pair() {
+        // ldp x1, x2, [x0], #16
+        const LDP_POST_INDEX: u32 = 0xa8c10801;
+        // ldp x3, x4, [x1]
+        const LDP_X1: u32 = 0xa9401023;
+        const MMIO_IPA: usize = (1 << (IPA_WIDTH - 1)) + GRANULE_SIZE;
+        const DFSC_TRANSLATION_L3: usize = 0b000111;
+
+        let rd = realm_create();
+        unsafe {
+            let code = alloc_granule(IDX_SRC1) as *mut u32;
+            *code = LDP_POST_INDEX;
+            *code.add(1) = LDP_X1;
+        }
+        // The REC starts at PC 0
+        data_create(rd, 0, IDX_DATA1, IDX_SRC1);
+        rec_create(rd, IDX_REC1, IDX_REC1_PARAMS, IDX_REC1_AUX);
+        let ret = rmi::<REALM_ACTIVATE>(&[rd]);
+        assert_eq!(ret[0], SUCCESS);
+
+        let (rec1, run1) = (granule_addr(IDX_REC1), granule_addr(IDX_REC1_RUN));
+        let rec = || unsafe { &*(rec1 as *const Rec<'_>) };
+        let run = || unsafe { &mut *(run1 as *mut Run) };
+        let data_abort = [
+            REC_ENTER_EXIT_CMD,
+            ExitSyncType::DataAbort as usize,
+            (ESR_EL2_EC_DATA_ABORT << 26) as usize | DFSC_TRANSLATION_L3,
+            MMIO_IPA >> 8,
+            0,
+        ];
+        let isv = |run: &Run| EsrEl2::new(run.esr()).get_masked_value(EsrEl2::ISV);
+
+        // Without a valid syndrome, the pair is reported one register at a time
+        let ret = rmi::<REC_ENTER>(&[&[rec1, run1][..], &data_abort].concat());
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(run().exit_reason(), EXIT_SYNC);
+        assert_eq!(isv(run()), 1);
+
+        run().set_entry_flags(EntryFlag::EMUL_MMIO);
+        run().set_entry_gpr(0, u64::MAX - 7).unwrap();
+        let ret = rmi::<REC_ENTER>(&[rec1, run1]);
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(run().exit_reason(), EXIT_SYNC);
+        assert_eq!(isv(run()), 1);
+        // Neither register is written until the pair completes
+        assert_eq!(rec().context.gp_regs[1], 0);
+        assert_eq!(rec().context.elr_el2, 0);
+
+        // The Realm resumes after the second register, and faults at the next
+        // pair whose address wraps around, which can't be emulated.
+        run().set_entry_gpr(0, 0x1234).unwrap();
+        let ret = rmi::<REC_ENTER>(&[&[rec1, run1][..], &data_abort].concat());
+        assert_eq!(ret[0], SUCCESS);
+        let regs = &rec().context.gp_regs;
+        assert_eq!((regs[0], regs[1], regs[2]), (16, u64::MAX - 7, 0x1234));
+        assert_eq!(rec().context.elr_el2, 4);
+        assert_eq!(run().exit_reason(), EXIT_SYNC);
+        assert_eq!((isv(run()), run().far()), (0, 0));
+
+        // Cleanup
+        run().set_entry_flags(0);
+        let ret = rmi::<DATA_DESTROY>(&[rd, 0]);
+        assert_eq!(ret[0], SUCCESS);
+        mock::host::unmap(rd, 0, false);
+        let ret = rmi::<GRANULE_UNDELEGATE>(&[granule_addr(IDX_DATA1)]);
+        assert_eq!(ret[0], SUCCESS);
+        rec_destroy(IDX_REC1, IDX_REC1_AUX);
+        realm_destroy(rd);
+
+        miri_teardown();
+    }
 }
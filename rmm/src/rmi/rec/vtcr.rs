@@ -4,6 +4,12 @@ use crate::rmi::error::Error;
 
 use aarch64_cpu::registers::*;
 
+// VTCR_EL2 fields not covered by aarch64_cpu
+// DS: 52-bit output address descriptors (FEAT_LPA2)
+const VTCR_DS: u64 = 1 << 32;
+// SL2: combined with SL0 == 0b00, starts the walk at level -1
+const VTCR_SL2: u64 = 1 << 33;
+
 fn is_feat_vmid16_present() -> bool {
     #[cfg(not(any(miri, test)))]
     let ret = ID_AA64MMFR1_EL1.read(ID_AA64MMFR1_EL1::VMIDBits)
@@ -18,8 +24,9 @@ pub fn prepare_vtcr(rd: &Rd) -> Result<u64, Error> {
     let s2_starting_level = rd.s2_starting_level();
     let ipa_bits = rd.ipa_bits();
 
-    // sl0 consists of 2 bits (2^2 == 4)
-    if !(s2_starting_level >= 0 && s2_starting_level <= 3) {
+    // sl0 consists of 2 bits (2^2 == 4), level -1 needs SL2 from FEAT_LPA2
+    let min_level = if rd.lpa2() { -1 } else { 0 };
+    if !(s2_starting_level >= min_level && s2_starting_level <= 3) {
         return Err(Error::RmiErrorInput);
     }
 
@@ -28,7 +35,13 @@ pub fn prepare_vtcr(rd: &Rd) -> Result<u64, Error> {
         return Err(Error::RmiErrorInput);
     }
 
-    let mut vtcr_val = VTCR_EL2::PS::PA_40B_1TB
+    let ps_val = if rd.lpa2() {
+        VTCR_EL2::PS::PA_52B_4PB
+    } else {
+        VTCR_EL2::PS::PA_40B_1TB
+    };
+
+    let mut vtcr_val = ps_val
         + VTCR_EL2::TG0::Granule4KB
         + VTCR_EL2::SH0::Inner
         + VTCR_EL2::ORGN0::NormalWBRAWA
@@ -46,12 +59,21 @@ pub fn prepare_vtcr(rd: &Rd) -> Result<u64, Error> {
         VTCR_EL2::SL0::Granule4KBLevel2,
         VTCR_EL2::SL0::Granule4KBLevel3,
     ];
-    let sl0_val = sl0_array[s2_starting_level as usize];
     let t0sz_val = (64 - ipa_bits) as u64;
+    vtcr_val += VTCR_EL2::T0SZ.val(t0sz_val);
 
-    vtcr_val += sl0_val + VTCR_EL2::T0SZ.val(t0sz_val);
+    let mut raw_val = 0;
+    if s2_starting_level < 0 {
+        vtcr_val += VTCR_EL2::SL0::Granule4KBLevel0;
+        raw_val |= VTCR_SL2;
+    } else {
+        vtcr_val += sl0_array[s2_starting_level as usize];
+    }
+    if rd.lpa2() {
+        raw_val |= VTCR_DS;
+    }
 
-    Ok(vtcr_val.into())
+    Ok(u64::from(vtcr_val) | raw_val)
 }
 
 pub fn activate_stage2_mmu(rec: &Rec<'_>) {
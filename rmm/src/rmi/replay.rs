@@ -0,0 +1,62 @@
+use super::error::Error;
+use crate::event::replay;
+use crate::event::RmiHandle;
+use crate::granule::GRANULE_SIZE;
+use crate::host;
+use crate::listen;
+use crate::rmi;
+
+use alloc::vec::Vec;
+use spin::mutex::Mutex;
+
+// The maximum number of contiguous NS granules for a chunk of the replay log
+const REPLAY_MAX_GRANULES: usize = 16;
+
+// The log taken at offset 0, which the following reads continue on
+static SNAPSHOT: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+pub fn set_event_handler(rmi: &mut RmiHandle) {
+    // ISLET_REPLAY_READ is a vendor specific RMI for reading the log of RMI commands
+    // to replay them in tests (see test_utils::replay)
+    // Input registers
+    // x0: function id (0xC7000157)
+    // x1: offset - the offset of the log to read from. Reading from 0 takes a snapshot
+    //              of the log along with the current states of the granules it refers to.
+    // x2: buf - a physicall address of the host provided (NS) buffer, granule aligned
+    // x3: num_granules - the number of contiguous granules of the buffer
+    // Output registers
+    // x0: status
+    // x1: len - the length of the snapshot. Nothing is copied from the offset beyond it.
+    listen!(rmi, rmi::ISLET_REPLAY_READ, |arg, ret, rmm| {
+        let offset = arg[0];
+        let buf = arg[1];
+        let num_granules = arg[2];
+
+        if buf % GRANULE_SIZE != 0 || num_granules == 0 || num_granules > REPLAY_MAX_GRANULES {
+            return Err(Error::RmiErrorInput);
+        }
+
+        let mut snapshot = SNAPSHOT.lock();
+        if offset == 0 {
+            *snapshot = replay::snapshot();
+        }
+        ret[1] = snapshot.len();
+
+        let start = offset.min(snapshot.len());
+        let end = offset
+            .saturating_add(num_granules * GRANULE_SIZE)
+            .min(snapshot.len());
+        host::copy_out(rmm, buf, num_granules, &snapshot[start..end])
+            .ok_or(Error::RmiErrorInput)?;
+        Ok(())
+    });
+
+    // ISLET_REPLAY_RESET is a vendor specific RMI for starting the log over
+    // Input registers
+    // x0: function id (0xC7000158)
+    listen!(rmi, rmi::ISLET_REPLAY_RESET, |_arg, _ret, _rmm| {
+        replay::reset();
+        SNAPSHOT.lock().clear();
+        Ok(())
+    });
+}
@@ -1,5 +1,3 @@
-extern crate alloc;
-
 use crate::event::RmiHandle;
 use crate::granule::{
     is_granule_aligned, is_not_in_realm, set_granule, GranuleState, GRANULE_SIZE,
@@ -7,11 +5,11 @@ use crate::granule::{
 use crate::host;
 use crate::host::DataPage;
 use crate::listen;
-use crate::measurement::HashContext;
+use crate::mec::{self, MECID_SHARED};
 use crate::realm::mm::rtt;
 use crate::realm::mm::rtt::{RTT_MIN_BLOCK_LEVEL, RTT_PAGE_LEVEL};
-use crate::realm::mm::stage2_tte::{level_mask, S2TTE};
-use crate::realm::rd::{Rd, State};
+use crate::realm::mm::stage2_tte::{mapping_size, S2TTE};
+use crate::realm::rd::{Rd, RdRef, Rtts, State};
 use crate::rec::Rec;
 use crate::rmi;
 use crate::rmi::error::Error;
@@ -20,16 +18,15 @@ use crate::{get_granule, get_granule_if};
 #[cfg(feature = "gst_page_table")]
 use crate::{get_granule, get_granule_if, set_state_and_get_granule};
 
-fn is_valid_rtt_cmd(rd: &Rd, ipa: usize, level: usize) -> bool {
-    if level > RTT_PAGE_LEVEL {
+fn is_valid_rtt_cmd(rd: &Rtts<'_>, ipa: usize, level: isize) -> bool {
+    if level < rd.s2_starting_level() || level > RTT_PAGE_LEVEL {
         return false;
     }
 
     if ipa >= rd.ipa_size() {
         return false;
     }
-    let mask = level_mask(level).unwrap_or(0);
-    if ipa & mask as usize != ipa {
+    if ipa % mapping_size(level) != 0 {
         return false;
     }
     true
@@ -37,13 +34,13 @@ fn is_valid_rtt_cmd(rd: &Rd, ipa: usize, level: usize) -> bool {
 
 pub fn set_event_handler(rmi: &mut RmiHandle) {
     listen!(rmi, rmi::RTT_CREATE, |arg, _ret, rmm| {
-        let rd_granule = get_granule_if!(arg[0], GranuleState::RD)?;
+        let rd_ref = RdRef::new(arg[0])?;
+        let rd = rd_ref.rtts();
         let rtt_addr = arg[1];
-        let rd = rd_granule.content::<Rd>()?;
         let ipa = arg[2];
-        let level = arg[3];
+        let level = arg[3] as isize;
 
-        let min_level = rd.s2_starting_level() as usize + 1;
+        let min_level = rd.s2_starting_level() + 1;
 
         if (level < min_level) || (level > RTT_PAGE_LEVEL) || !is_valid_rtt_cmd(&rd, ipa, level - 1)
         {
@@ -64,12 +61,12 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
     });
 
     listen!(rmi, rmi::RTT_DESTROY, |arg, ret, _rmm| {
-        let rd_granule = get_granule_if!(arg[0], GranuleState::RD)?;
-        let rd = rd_granule.content::<Rd>()?;
+        let rd_ref = RdRef::new(arg[0])?;
+        let rd = rd_ref.rtts();
         let ipa = arg[1];
-        let level = arg[2];
+        let level = arg[2] as isize;
 
-        let min_level = rd.s2_starting_level() as usize + 1;
+        let min_level = rd.s2_starting_level() + 1;
 
         if (level < min_level) || (level > RTT_PAGE_LEVEL) || !is_valid_rtt_cmd(&rd, ipa, level - 1)
         {
@@ -84,6 +81,7 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
     });
 
     listen!(rmi, rmi::RTT_INIT_RIPAS, |arg, ret, _rmm| {
+        // Unlike the other RTT operations, this holds the RD lock to extend the RIM
         let mut rd_granule = get_granule_if!(arg[0], GranuleState::RD)?;
         let mut rd = rd_granule.content_mut::<Rd>()?;
         let base = arg[1];
@@ -97,8 +95,8 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
             return Err(Error::RmiErrorInput);
         }
 
-        if !is_valid_rtt_cmd(&rd, base, RTT_PAGE_LEVEL)
-            || !is_valid_rtt_cmd(&rd, top, RTT_PAGE_LEVEL)
+        if !is_valid_rtt_cmd(&rd.rtts(), base, RTT_PAGE_LEVEL)
+            || !is_valid_rtt_cmd(&rd.rtts(), top, RTT_PAGE_LEVEL)
             || !rd.addr_in_par(base)
             || !rd.addr_in_par(top - GRANULE_SIZE)
         {
@@ -119,8 +117,8 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
             warn!("Granules of RD and REC shouldn't be identical");
             return Err(Error::RmiErrorInput);
         }
-        let rd_granule = get_granule_if!(arg[0], GranuleState::RD)?;
-        let rd = rd_granule.content::<Rd>()?;
+        let rd_ref = RdRef::new(arg[0])?;
+        let rd = rd_ref.rtts();
         let mut rec_granule = get_granule_if!(arg[1], GranuleState::Rec)?;
         let mut rec = rec_granule.content_mut::<Rec<'_>>()?;
         if rec.realmid()? != rd.id() {
@@ -148,10 +146,10 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
     });
 
     listen!(rmi, rmi::RTT_READ_ENTRY, |arg, ret, _rmm| {
-        let rd_granule = get_granule_if!(arg[0], GranuleState::RD)?;
-        let rd = rd_granule.content::<Rd>()?;
+        let rd_ref = RdRef::new(arg[0])?;
+        let rd = rd_ref.rtts();
         let ipa = arg[1];
-        let level = arg[2];
+        let level = arg[2] as isize;
         if !is_valid_rtt_cmd(&rd, ipa, level) {
             return Err(Error::RmiErrorInput);
         }
@@ -174,7 +172,7 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
             return Err(Error::RmiErrorInput);
         }
 
-        // rd granule lock
+        // rd granule lock, held to extend the RIM
         let mut rd_granule = get_granule_if!(rd, GranuleState::RD)?;
         let mut rd = rd_granule.content_mut::<Rd>()?;
 
@@ -184,7 +182,7 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
             return Err(Error::RmiErrorRealm(0));
         }
 
-        validate_ipa(&rd, ipa)?;
+        validate_ipa(&rd.rtts(), ipa)?;
 
         if !is_not_in_realm(src_pa) {
             return Err(Error::RmiErrorInput);
@@ -194,8 +192,11 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
         let mut target_page_granule = get_granule_if!(target_pa, GranuleState::Delegated)?;
         let mut target_page = target_page_granule.content_mut::<DataPage>()?;
         #[cfg(not(kani))]
-        // `page_table` is currently not reachable in model checking harnesses
-        rmm.page_table.map(target_pa, true);
+        {
+            // `page_table` is currently not reachable in model checking harnesses
+            mec::switch_to(rd.mecid());
+            rmm.page_table.map_realm(target_pa, rd.mecid());
+        }
 
         // copy src to target
         #[cfg(not(kani))]
@@ -205,20 +206,23 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
         rmm.page_table.unmap(src_pa);
 
         // map ipa to taget_pa in S2 table
-        rtt::data_create(&rd, ipa, target_pa, false)?;
+        rtt::data_create(&rd.rtts(), ipa, target_pa, false)?;
 
         #[cfg(not(kani))]
         // `rsi` is currently not reachable in model checking harnesses
-        HashContext::new(&mut rd)?.measure_data_granule(&target_page, ipa, flags)?;
+        crate::measurement::rim_context(&mut rd)?.measure_data_granule(
+            target_page.as_slice(),
+            ipa,
+            flags,
+        )?;
 
         set_granule(&mut target_page_granule, GranuleState::Data)?;
         Ok(())
     });
 
     listen!(rmi, rmi::DATA_CREATE_UNKNOWN, |arg, _ret, rmm| {
-        // rd granule lock
-        let rd_granule = get_granule_if!(arg[0], GranuleState::RD)?;
-        let rd = rd_granule.content::<Rd>()?;
+        let rd_ref = RdRef::new(arg[0])?;
+        let rd = rd_ref.rtts();
 
         // target_phys: location where realm data is created.
         let target_pa = arg[1];
@@ -234,7 +238,7 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
         let mut target_page_granule = get_granule_if!(target_pa, GranuleState::Delegated)?;
         #[cfg(not(kani))]
         // `page_table` is currently not reachable in model checking harnesses
-        rmm.page_table.map(target_pa, true);
+        rmm.page_table.map_realm(target_pa, rd_ref.mecid());
 
         // 1. map ipa to target_pa in S2 table
         rtt::data_create(&rd, ipa, target_pa, true)?;
@@ -245,10 +249,9 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
         Ok(())
     });
 
-    listen!(rmi, rmi::DATA_DESTROY, |arg, ret, _rmm| {
-        // rd granule lock
-        let rd_granule = get_granule_if!(arg[0], GranuleState::RD)?;
-        let rd = rd_granule.content::<Rd>()?;
+    listen!(rmi, rmi::DATA_DESTROY, |arg, ret, rmm| {
+        let rd_ref = RdRef::new(arg[0])?;
+        let rd = rd_ref.rtts();
         let ipa = arg[1];
 
         if !rd.addr_in_par(ipa) || !is_valid_rtt_cmd(&rd, ipa, RTT_PAGE_LEVEL) {
@@ -259,7 +262,8 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
             ret[2] = t;
         })?;
 
-        // data granule lock and change state
+        // data granule lock and change state, which wipes it under the realm's MECID
+        mec::switch_to(rd_ref.mecid());
         #[cfg(feature = "gst_page_table")]
         set_state_and_get_granule!(pa, GranuleState::Delegated)?;
 
@@ -268,6 +272,10 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
             let mut granule = get_granule!(pa)?;
             set_granule(&mut granule, GranuleState::Delegated)?;
         }
+        mec::clean_granule(pa);
+        #[cfg(not(kani))]
+        // `page_table` is currently not reachable in model checking harnesses
+        rmm.page_table.map_realm(pa, MECID_SHARED);
 
         ret[1] = pa;
         ret[2] = top;
@@ -277,18 +285,18 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
     // Map an unprotected IPA to a non-secure PA.
     listen!(rmi, rmi::RTT_MAP_UNPROTECTED, |arg, _ret, _rmm| {
         let ipa = arg[1];
-        let level = arg[2];
+        let level = arg[2] as isize;
         let host_s2tte = arg[3];
         let s2tte = S2TTE::from(host_s2tte);
-        if !s2tte.is_host_ns_valid(level) {
+
+        let rd_ref = RdRef::new(arg[0])?;
+        let rd = rd_ref.rtts();
+
+        if !s2tte.is_host_ns_valid(level, rd.lpa2()) {
             return Err(Error::RmiErrorInput);
         }
 
-        // rd granule lock
-        let rd_granule = get_granule_if!(arg[0], GranuleState::RD)?;
-        let rd = rd_granule.content::<Rd>()?;
-
-        if (level < rd.s2_starting_level() as usize)
+        if (level < rd.s2_starting_level())
             || (level < RTT_MIN_BLOCK_LEVEL)
             || (level > RTT_PAGE_LEVEL)
             || !is_valid_rtt_cmd(&rd, ipa, level)
@@ -302,13 +310,13 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
 
     // Unmap a non-secure PA at an unprotected IPA
     listen!(rmi, rmi::RTT_UNMAP_UNPROTECTED, |arg, ret, _rmm| {
-        let rd_granule = get_granule_if!(arg[0], GranuleState::RD)?;
-        let rd = rd_granule.content::<Rd>()?;
+        let rd_ref = RdRef::new(arg[0])?;
+        let rd = rd_ref.rtts();
 
         let ipa = arg[1];
 
-        let level = arg[2];
-        if (level < rd.s2_starting_level() as usize)
+        let level = arg[2] as isize;
+        if (level < rd.s2_starting_level())
             || (level < RTT_MIN_BLOCK_LEVEL)
             || (level > RTT_PAGE_LEVEL)
             || !is_valid_rtt_cmd(&rd, ipa, level)
@@ -326,12 +334,12 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
 
     // Destroy a homogeneous RTT and map as a bigger block at its parent RTT
     listen!(rmi, rmi::RTT_FOLD, |arg, ret, _rmm| {
-        let rd_granule = get_granule_if!(arg[0], GranuleState::RD)?;
-        let rd = rd_granule.content::<Rd>()?;
+        let rd_ref = RdRef::new(arg[0])?;
+        let rd = rd_ref.rtts();
         let ipa = arg[1];
-        let level = arg[2];
+        let level = arg[2] as isize;
 
-        let min_level = rd.s2_starting_level() as usize + 1;
+        let min_level = rd.s2_starting_level() + 1;
 
         if (level < min_level) || (level > RTT_PAGE_LEVEL) || !is_valid_rtt_cmd(&rd, ipa, level - 1)
         {
@@ -342,9 +350,99 @@ pub fn set_event_handler(rmi: &mut RmiHandle) {
         ret[1] = rtt;
         Ok(())
     });
+
+    // Create an RTT in the auxiliary RTT tree of the given plane
+    listen!(rmi, rmi::RTT_AUX_CREATE, |arg, _ret, rmm| {
+        let rd_ref = RdRef::new(arg[0])?;
+        let rd = rd_ref.rtts();
+        let rtt_addr = arg[1];
+        let ipa = arg[2];
+        let level = arg[3] as isize;
+        let plane = arg[4];
+
+        let min_level = rd.s2_starting_level() + 1;
+
+        if (level < min_level)
+            || (level > RTT_PAGE_LEVEL)
+            || !is_valid_rtt_cmd(&rd, ipa, level - 1)
+            || !is_valid_aux_plane(&rd, plane)
+        {
+            return Err(Error::RmiErrorInput);
+        }
+        if rtt_addr == arg[0] {
+            return Err(Error::RmiErrorInput);
+        }
+        let mut rtt_granule = get_granule_if!(rtt_addr, GranuleState::Delegated)?;
+
+        #[cfg(not(kani))]
+        rmm.page_table.map(rtt_addr, true);
+        rtt::aux_create(&rd, plane, rtt_addr, ipa, level)?;
+        set_granule(&mut rtt_granule, GranuleState::RTT)?;
+        Ok(())
+    });
+
+    // Destroy an RTT in the auxiliary RTT tree of the given plane
+    listen!(rmi, rmi::RTT_AUX_DESTROY, |arg, ret, _rmm| {
+        let rd_ref = RdRef::new(arg[0])?;
+        let rd = rd_ref.rtts();
+        let ipa = arg[1];
+        let level = arg[2] as isize;
+        let plane = arg[3];
+
+        let min_level = rd.s2_starting_level() + 1;
+
+        if (level < min_level)
+            || (level > RTT_PAGE_LEVEL)
+            || !is_valid_rtt_cmd(&rd, ipa, level - 1)
+            || !is_valid_aux_plane(&rd, plane)
+        {
+            return Err(Error::RmiErrorInput);
+        }
+
+        ret[1] = rtt::aux_destroy(&rd, plane, ipa, level)?;
+        Ok(())
+    });
+
+    // Mirror a protected mapping of the primary RTT into the auxiliary RTT of the given plane
+    listen!(rmi, rmi::RTT_AUX_MAP_PROTECTED, |arg, _ret, _rmm| {
+        let rd_ref = RdRef::new(arg[0])?;
+        let rd = rd_ref.rtts();
+        let ipa = arg[1];
+        let plane = arg[2];
+
+        if !rd.addr_in_par(ipa)
+            || !is_valid_rtt_cmd(&rd, ipa, RTT_PAGE_LEVEL)
+            || !is_valid_aux_plane(&rd, plane)
+        {
+            return Err(Error::RmiErrorInput);
+        }
+
+        rtt::aux_map_protected(&rd, plane, ipa)
+    });
+
+    // Remove a protected mapping from the auxiliary RTT of the given plane
+    listen!(rmi, rmi::RTT_AUX_UNMAP_PROTECTED, |arg, _ret, _rmm| {
+        let rd_ref = RdRef::new(arg[0])?;
+        let rd = rd_ref.rtts();
+        let ipa = arg[1];
+        let plane = arg[2];
+
+        if !rd.addr_in_par(ipa)
+            || !is_valid_rtt_cmd(&rd, ipa, RTT_PAGE_LEVEL)
+            || !is_valid_aux_plane(&rd, plane)
+        {
+            return Err(Error::RmiErrorInput);
+        }
+
+        rtt::aux_unmap_protected(&rd, plane, ipa)
+    });
 }
 
-pub fn validate_ipa(rd: &Rd, ipa: usize) -> Result<(), Error> {
+fn is_valid_aux_plane(rd: &Rtts<'_>, plane: usize) -> bool {
+    (1..=rd.num_aux_planes()).contains(&plane)
+}
+
+pub fn validate_ipa(rd: &Rtts<'_>, ipa: usize) -> Result<(), Error> {
     if !is_granule_aligned(ipa) {
         error!("ipa: {:x} is not aligned with {:x}", ipa, GRANULE_SIZE);
         return Err(Error::RmiErrorInput);
@@ -435,6 +533,100 @@ mod test {
         miri_teardown();
     }
 
+    // Covered RMIs: RTT_CREATE, RTT_DESTROY, RTT_READ_ENTRY at level -1 (FEAT_LPA2)
+    #[test]
+    fn rmi_rtt_create_lpa2_positive() {
+        use crate::rmi::realm::params::Params as RealmParams;
+
+        for mocking_addr in &[alloc_granule(IDX_RD), alloc_granule(IDX_RTT_LEVEL0)] {
+            let ret = rmi::<GRANULE_DELEGATE>(&[*mocking_addr]);
+            assert_eq!(ret[0], SUCCESS);
+        }
+
+        let (rd, rtt_root, params_ptr) = (
+            alloc_granule(IDX_RD),
+            alloc_granule(IDX_RTT_LEVEL0),
+            alloc_granule(IDX_REALM_PARAMS),
+        );
+
+        const LPA2_FLAG: u64 = 1 << 0;
+        unsafe {
+            let params = &mut *(params_ptr as *mut RealmParams);
+            params.flags = LPA2_FLAG;
+            params.s2sz = 52;
+            params.rtt_num_start = 1;
+            params.rtt_level_start = -1;
+            params.rtt_base = rtt_root as u64;
+        };
+
+        let ret = rmi::<REALM_CREATE>(&[rd, params_ptr]);
+        assert_eq!(ret[0], SUCCESS);
+
+        let rtt = mock::host::alloc_granule(IDX_RTT_LEVEL1);
+        let ret = rmi::<GRANULE_DELEGATE>(&[rtt]);
+        assert_eq!(ret[0], SUCCESS);
+
+        // Beyond the 48-bit IPA space, only reachable from the level -1 root
+        let (ipa, level) = (1 << 48, 0);
+        let ret = rmi::<RTT_CREATE>(&[rd, rtt, ipa, level]);
+        assert_eq!(ret[0], SUCCESS);
+
+        let ret = rmi::<RTT_READ_ENTRY>(&[rd, ipa, (level as isize - 1) as usize]);
+        assert_eq!(ret[0], SUCCESS);
+
+        let (walk_level, state, desc) = (ret[1], ret[2], ret[3]);
+        const RMI_TABLE: usize = 2;
+        assert_eq!(walk_level, -1isize as usize);
+        assert_eq!(state, RMI_TABLE);
+        assert_eq!(desc, rtt);
+
+        let ret = rmi::<RTT_DESTROY>(&[rd, ipa, level]);
+        assert_eq!(ret[0], SUCCESS);
+
+        let ret = rmi::<GRANULE_UNDELEGATE>(&[rtt]);
+        assert_eq!(ret[0], SUCCESS);
+
+        realm_destroy(rd);
+
+        unsafe {
+            let params = &mut *(params_ptr as *mut RealmParams);
+            params.flags = 0;
+        };
+
+        miri_teardown();
+    }
+
+    // Covered RMIs: RTT_INIT_RIPAS, RTT_READ_ENTRY at level -1 (FEAT_LPA2)
+    #[test]
+    fn rmi_rtt_init_ripas_lpa2() {
+        const LPA2_FLAG: u64 = 1 << 0;
+        let rd = realm_create_with(|params| {
+            params.flags = LPA2_FLAG;
+            params.s2sz = 52;
+            params.rtt_level_start = -1;
+        })
+        .unwrap();
+
+        // A level -1 table covers the 52-bit IPA space, 256TB for each entry
+        const LM1_SIZE: usize = 1 << 48;
+        let (base, top) = (0, 2 * LM1_SIZE);
+        let ret = rmi::<RTT_INIT_RIPAS>(&[rd, base, top]);
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(ret[1], top);
+
+        let ret = rmi::<RTT_READ_ENTRY>(&[rd, LM1_SIZE, -1isize as usize]);
+        assert_eq!(ret[0], SUCCESS);
+
+        let (level, ripas) = (ret[1], ret[4]);
+        const RMI_RAM: usize = 1;
+        assert_eq!(level, -1isize as usize);
+        assert_eq!(ripas, RMI_RAM);
+
+        realm_destroy(rd);
+
+        miri_teardown();
+    }
+
     // Source: https://github.com/ARM-software/cca-rmm-acs
     // Test Case: cmd_rtt_init_ripas
     // Covered RMIs: RTT_INIT_RIPAS, RTT_READ_ENTRY
@@ -675,4 +867,196 @@ mod test {
 
         miri_teardown();
     }
+
+    // Covered RMIs: RTT_AUX_CREATE, RTT_AUX_DESTROY, RTT_AUX_MAP_PROTECTED,
+    //               RTT_AUX_UNMAP_PROTECTED, and DATA_DESTROY, RTT_SET_RIPAS
+    //               of a granule mirrored in an auxiliary RTT
+    #[test]
+    fn rmi_rtt_aux_positive() {
+        use crate::rmi::error::Error;
+        use crate::rsi::IPA_STATE_SET;
+
+        const IPA: usize = 0;
+        const PLANE: usize = 1;
+        const RSI_EMPTY: usize = 0;
+        let rtt_error = |level| usize::from(Error::RmiErrorRtt(level));
+
+        let aux_rtts = [
+            IDX_RTT_AUX_LEVEL0,
+            IDX_RTT_AUX_LEVEL1,
+            IDX_RTT_AUX_LEVEL2,
+            IDX_RTT_AUX_LEVEL3,
+        ]
+        .map(mock::host::alloc_granule);
+        for rtt in &aux_rtts {
+            let ret = rmi::<GRANULE_DELEGATE>(&[*rtt]);
+            assert_eq!(ret[0], SUCCESS);
+        }
+
+        let rd = realm_create_with(|params| {
+            params.num_aux_planes = 1;
+            params.aux_rtt_base[0] = aux_rtts[0] as u64;
+            params.aux_vmid[0] = params.vmid + 1;
+        })
+        .unwrap();
+        data_create(rd, IPA, IDX_DATA1, IDX_SRC1);
+        rec_create(rd, IDX_REC1, IDX_REC1_PARAMS, IDX_REC1_AUX);
+        let ret = rmi::<REALM_ACTIVATE>(&[rd]);
+        assert_eq!(ret[0], SUCCESS);
+
+        // Only the auxiliary planes of the realm have auxiliary RTTs
+        for plane in [0, PLANE + 1] {
+            let ret = rmi::<RTT_AUX_CREATE>(&[rd, aux_rtts[1], IPA, 1, plane]);
+            assert_eq!(ret[0], ERROR_INPUT);
+            let ret = rmi::<RTT_AUX_DESTROY>(&[rd, IPA, 1, plane]);
+            assert_eq!(ret[0], ERROR_INPUT);
+            let ret = rmi::<RTT_AUX_MAP_PROTECTED>(&[rd, IPA, plane]);
+            assert_eq!(ret[0], ERROR_INPUT);
+            let ret = rmi::<RTT_AUX_UNMAP_PROTECTED>(&[rd, IPA, plane]);
+            assert_eq!(ret[0], ERROR_INPUT);
+        }
+
+        // The mirror needs an auxiliary RTT at the page level
+        let ret = rmi::<RTT_AUX_MAP_PROTECTED>(&[rd, IPA, PLANE]);
+        assert_eq!(ret[0], rtt_error(0));
+
+        for level in 1..=3 {
+            let ret = rmi::<RTT_AUX_CREATE>(&[rd, aux_rtts[level], IPA, level, PLANE]);
+            assert_eq!(ret[0], SUCCESS);
+        }
+
+        let ret = rmi::<RTT_AUX_MAP_PROTECTED>(&[rd, IPA, PLANE]);
+        assert_eq!(ret[0], SUCCESS);
+        let ret = rmi::<RTT_AUX_MAP_PROTECTED>(&[rd, IPA, PLANE]);
+        assert_eq!(ret[0], rtt_error(3));
+
+        // While mirrored, neither the granule nor its auxiliary RTT can be destroyed
+        let ret = rmi::<DATA_DESTROY>(&[rd, IPA]);
+        assert_eq!(ret[0], rtt_error(3));
+        let ret = rmi::<RTT_AUX_DESTROY>(&[rd, IPA, 3, PLANE]);
+        assert_eq!(ret[0], rtt_error(3));
+
+        // Nor can its RIPAS change, which would leave the mirror mapped
+        let (rec1, run1) = (granule_addr(IDX_REC1), granule_addr(IDX_REC1_RUN));
+        let set_empty = [rec1, run1, IPA_STATE_SET, IPA, IPA + L3_SIZE, RSI_EMPTY, 0];
+        let ret = rmi::<REC_ENTER>(&set_empty);
+        assert_eq!(ret[0], SUCCESS);
+        let ret = rmi::<RTT_SET_RIPAS>(&[rd, rec1, IPA, IPA + L3_SIZE]);
+        assert_eq!(ret[0], rtt_error(3));
+
+        let ret = rmi::<RTT_AUX_UNMAP_PROTECTED>(&[rd, IPA, PLANE]);
+        assert_eq!(ret[0], SUCCESS);
+        let ret = rmi::<RTT_AUX_UNMAP_PROTECTED>(&[rd, IPA, PLANE]);
+        assert_eq!(ret[0], rtt_error(3));
+
+        let ret = rmi::<RTT_SET_RIPAS>(&[rd, rec1, IPA, IPA + L3_SIZE]);
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(ret[1], IPA + L3_SIZE);
+
+        for level in (1..=3).rev() {
+            let ret = rmi::<RTT_AUX_DESTROY>(&[rd, IPA, level, PLANE]);
+            assert_eq!(ret[0], SUCCESS);
+            assert_eq!(ret[1], aux_rtts[level]);
+        }
+
+        // Cleanup
+        let ret = rmi::<DATA_DESTROY>(&[rd, IPA]);
+        assert_eq!(ret[0], SUCCESS);
+        mock::host::unmap(rd, IPA, false);
+        let ret = rmi::<GRANULE_UNDELEGATE>(&[granule_addr(IDX_DATA1)]);
+        assert_eq!(ret[0], SUCCESS);
+        rec_destroy(IDX_REC1, IDX_REC1_AUX);
+        realm_destroy(rd);
+        for rtt in &aux_rtts {
+            let ret = rmi::<GRANULE_UNDELEGATE>(&[*rtt]);
+            assert_eq!(ret[0], SUCCESS);
+        }
+
+        miri_teardown();
+    }
+
+    // Covered RMIs: RTT_CREATE, RTT_MAP_UNPROTECTED, RTT_READ_ENTRY, RTT_DESTROY,
+    //               REALM_DESTROY while RTT operations are in flight
+    #[test]
+    fn rmi_rtt_concurrent_operations() {
+        extern crate std;
+        use crate::get_granule;
+        use crate::realm::mm::address::GuestPhysAddr;
+        use crate::realm::rd::RdRef;
+        use std::thread;
+
+        let rd = realm_create();
+
+        // Region A is in the unprotected half, under its own level 1 table
+        const IPA_A: usize = 1 << (IPA_WIDTH - 1);
+        const IPA_B: usize = 0x0;
+        let (rtt_a1, rtt_a2, rtt_b1) = (
+            mock::host::alloc_granule(IDX_RTT_LEVEL1),
+            mock::host::alloc_granule(IDX_RTT_LEVEL2),
+            mock::host::alloc_granule(IDX_RTT_OTHER),
+        );
+        for rtt in &[rtt_a1, rtt_a2, rtt_b1] {
+            let ret = rmi::<GRANULE_DELEGATE>(&[*rtt]);
+            assert_eq!(ret[0], SUCCESS);
+        }
+        for (rtt, ipa, level) in &[(rtt_a1, IPA_A, 1), (rtt_a2, IPA_A, 2)] {
+            let ret = rmi::<RTT_CREATE>(&[rd, *rtt, *ipa, *level]);
+            assert_eq!(ret[0], SUCCESS);
+        }
+
+        let num_children = || get_granule!(rd).unwrap().num_children();
+        let rd_ref = RdRef::new(rd).unwrap();
+        let rtts = rd_ref.rtts();
+        // Hold the lock of the level 2 table of region A
+        let table_a = rtts.s2_table().walk(GuestPhysAddr::from(IPA_A), 2).unwrap();
+        assert_eq!(table_a.level(), 2);
+
+        let desc = L1_SIZE | ATTR_NORMAL_WB_WA_RA | ATTR_STAGE2_AP_RW;
+        let op_a = thread::spawn(move || rmi::<RTT_MAP_UNPROTECTED>(&[rd, IPA_A, 2, desc]));
+        // Wait for the operation on region A to reference the RD
+        while num_children() < 2 {
+            thread::yield_now();
+        }
+
+        // The RD isn't locked by the operation on region A, which is stuck on the RTT lock
+        let op_b = thread::spawn(move || rmi::<RTT_CREATE>(&[rd, rtt_b1, IPA_B, 1]));
+        assert_eq!(op_b.join().unwrap()[0], SUCCESS);
+        assert!(!op_a.is_finished());
+
+        // Neither can the realm be destroyed underneath
+        let ret = rmi::<REALM_DESTROY>(&[rd]);
+        assert_eq!(ret[0], RmiStatus::ErrorRealm as usize);
+
+        core::mem::drop(table_a);
+        core::mem::drop(rd_ref);
+        assert_eq!(op_a.join().unwrap()[0], SUCCESS);
+        assert_eq!(num_children(), 0);
+
+        let ret = rmi::<RTT_READ_ENTRY>(&[rd, IPA_A, 2]);
+        assert_eq!(ret[0], SUCCESS);
+        let (level, state, out_desc) = (ret[1], ret[2], ret[3]);
+        assert_eq!(level, 2);
+        assert_eq!(state, rtt_entry_state::RMI_ASSIGNED);
+        assert_eq!(out_desc, desc);
+
+        let ret = rmi::<RTT_READ_ENTRY>(&[rd, IPA_B, 0]);
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(ret[2], rtt_entry_state::RMI_TABLE);
+        assert_eq!(ret[3], rtt_b1);
+
+        let ret = rmi::<RTT_UNMAP_UNPROTECTED>(&[rd, IPA_A, 2]);
+        assert_eq!(ret[0], SUCCESS);
+        for (ipa, level) in &[(IPA_B, 1), (IPA_A, 2), (IPA_A, 1)] {
+            let ret = rmi::<RTT_DESTROY>(&[rd, *ipa, *level]);
+            assert_eq!(ret[0], SUCCESS);
+        }
+        for rtt in &[rtt_a1, rtt_a2, rtt_b1] {
+            let ret = rmi::<GRANULE_UNDELEGATE>(&[*rtt]);
+            assert_eq!(ret[0], SUCCESS);
+        }
+
+        realm_destroy(rd);
+
+        miri_teardown();
+    }
 }
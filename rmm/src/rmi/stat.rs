@@ -0,0 +1,80 @@
+use super::error::Error;
+use crate::event::RmiHandle;
+use crate::granule::GRANULE_SIZE;
+use crate::host;
+use crate::listen;
+use crate::rmi;
+use crate::stat::{REALM_STATS, STATS};
+
+// The maximum number of contiguous NS granules for the statistics buffer
+const STATS_MAX_GRANULES: usize = 16;
+
+pub fn set_event_handler(rmi: &mut RmiHandle) {
+    // ISLET_STATS_READ is a vendor specific RMI for reading the runtime statistics of the RMM
+    // Input registers
+    // x0: function id (0xC7000151)
+    // x1: buf - a physicall address of the host provided (NS) buffer, granule aligned
+    // x2: num_granules - the number of contiguous granules of the buffer
+    // Output registers
+    // x0: status
+    // x1: len - the length of the CBOR encoded statistics.
+    //           It is reported along with RMI_ERROR_INPUT if the buffer is too small.
+    listen!(rmi, rmi::ISLET_STATS_READ, |arg, ret, rmm| {
+        let buf = arg[0];
+        let num_granules = arg[1];
+
+        if buf % GRANULE_SIZE != 0 || num_granules == 0 || num_granules > STATS_MAX_GRANULES {
+            return Err(Error::RmiErrorInput);
+        }
+
+        let encoded = STATS.lock().to_cbor(&REALM_STATS.lock());
+        ret[1] = encoded.len();
+        if encoded.len() > num_granules * GRANULE_SIZE {
+            warn!("The buffer is too small for the stats: {}", encoded.len());
+            return Err(Error::RmiErrorInput);
+        }
+
+        host::copy_out(rmm, buf, num_granules, &encoded).ok_or(Error::RmiErrorInput)?;
+        Ok(())
+    });
+
+    // ISLET_STATS_RESET is a vendor specific RMI for clearing the runtime statistics of the RMM
+    // Input registers
+    // x0: function id (0xC7000152)
+    listen!(rmi, rmi::ISLET_STATS_RESET, |_arg, _ret, _rmm| {
+        STATS.lock().reset();
+        REALM_STATS.lock().reset();
+        Ok(())
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rmi::{ERROR_INPUT, ISLET_STATS_READ, ISLET_STATS_RESET, SUCCESS};
+    use crate::test_utils::*;
+
+    use ciborium::Value;
+
+    #[test]
+    fn rmi_stats_read() {
+        let ret = rmi::<ISLET_STATS_RESET>(&[]);
+        assert_eq!(ret[0], SUCCESS);
+
+        let buf = alloc_granule(IDX_DATA1);
+        let ret = rmi::<ISLET_STATS_READ>(&[buf + 1, 1]);
+        assert_eq!(ret[0], ERROR_INPUT);
+
+        let ret = rmi::<ISLET_STATS_READ>(&[buf, 1]);
+        assert_eq!(ret[0], SUCCESS);
+
+        let len = ret[1];
+        let encoded = unsafe { core::slice::from_raw_parts(buf as *const u8, len) };
+        let stats: Value = ciborium::de::from_reader(encoded).unwrap();
+        let map = stats.as_map().unwrap();
+        assert_eq!(map.len(), 4);
+        assert!(map[3].1.is_array());
+
+        let ret = rmi::<ISLET_STATS_RESET>(&[]);
+        assert_eq!(ret[0], SUCCESS);
+    }
+}
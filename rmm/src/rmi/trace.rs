@@ -0,0 +1,61 @@
+use super::error::Error;
+use crate::event::trace;
+use crate::event::RmiHandle;
+use crate::granule::GRANULE_SIZE;
+use crate::host;
+use crate::listen;
+use crate::rmi;
+
+// The maximum number of contiguous NS granules for the trace buffer
+const TRACE_MAX_GRANULES: usize = 4;
+
+pub fn set_event_handler(rmi: &mut RmiHandle) {
+    // ISLET_TRACE_READ is a vendor specific RMI for reading the RMI/RSI trace of a CPU
+    // Input registers
+    // x0: function id (0xC7000153)
+    // x1: cpu - the index of the CPU whose trace is read
+    // x2: buf - a physicall address of the host provided (NS) buffer, granule aligned
+    // x3: num_granules - the number of contiguous granules of the buffer
+    // Output registers
+    // x0: status
+    // x1: len - the length of the records, from the oldest one.
+    //           It is reported along with RMI_ERROR_INPUT if the buffer is too small.
+    listen!(rmi, rmi::ISLET_TRACE_READ, |arg, ret, rmm| {
+        let cpu = arg[0];
+        let buf = arg[1];
+        let num_granules = arg[2];
+
+        if buf % GRANULE_SIZE != 0 || num_granules == 0 || num_granules > TRACE_MAX_GRANULES {
+            return Err(Error::RmiErrorInput);
+        }
+
+        let records = trace::read(cpu).ok_or(Error::RmiErrorInput)?;
+        ret[1] = records.len();
+        if records.len() > num_granules * GRANULE_SIZE {
+            warn!("The buffer is too small for the trace: {}", records.len());
+            return Err(Error::RmiErrorInput);
+        }
+
+        host::copy_out(rmm, buf, num_granules, &records).ok_or(Error::RmiErrorInput)?;
+        Ok(())
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::NUM_OF_CPU;
+    use crate::rmi::{ERROR_INPUT, ISLET_TRACE_READ, SUCCESS};
+    use crate::test_utils::*;
+
+    #[test]
+    fn rmi_trace_read() {
+        let buf = alloc_granule(IDX_DATA1);
+        let ret = rmi::<ISLET_TRACE_READ>(&[NUM_OF_CPU, buf, 1]);
+        assert_eq!(ret[0], ERROR_INPUT);
+
+        // Commands are traced only on CPU 0 in tests
+        let ret = rmi::<ISLET_TRACE_READ>(&[1, buf, 1]);
+        assert_eq!(ret[0], SUCCESS);
+        assert_eq!(ret[1], 0);
+    }
+}
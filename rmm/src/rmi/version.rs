@@ -2,8 +2,6 @@ use crate::event::RmiHandle;
 use crate::listen;
 use crate::rmi::{self, error::Error};
 
-extern crate alloc;
-
 pub fn decode_version(version: usize) -> (usize, usize) {
     let major = (version & 0x7fff0000) >> 16;
     let minor = version & 0xffff;
@@ -1,4 +1,5 @@
 use crate::cose;
+use crate::rsi::attestation::rak::RealmAttestKey;
 use alloc::vec::Vec;
 use sha2::{Digest, Sha256, Sha384, Sha512};
 
@@ -32,8 +33,8 @@ fn calculate_hash(data: Vec<u8>, algo: HashAlgo) -> Vec<u8> {
 }
 
 pub(super) fn get_realm_public_key_hash(key: Vec<u8>) -> Vec<u8> {
-    let priv_dak = p384::SecretKey::from_slice(&key).unwrap();
-    let public_dak = priv_dak.public_key().to_sec1_bytes().to_vec();
+    let priv_dak = RealmAttestKey::from_slice(&key).unwrap();
+    let public_dak = priv_dak.public_key();
     let public_dak_cose = cose::ec_public_key_sec1_to_cose(&public_dak);
 
     calculate_hash(public_dak_cose, HashAlgo::Sha256)
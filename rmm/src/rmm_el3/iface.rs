@@ -1,7 +1,7 @@
 use super::{digest, utils};
 use super::{
-    ATTEST_KEY_CURVE_ECC_SECP384R1, PLAT_TOKEN, REALM_ATTEST_KEY, RMM_SHARED_BUFFER_LOCK,
-    SHA256_DIGEST_SIZE, VHUK_A, VHUK_M,
+    ATTEST_KEY_CURVE, PLAT_TOKEN, REALM_ATTEST_KEY, RMM_SHARED_BUFFER_LOCK, SHA256_DIGEST_SIZE,
+    VHUK_A, VHUK_M,
 };
 use crate::asm::smc;
 use crate::{config, rmi};
@@ -39,7 +39,7 @@ pub(super) fn get_realm_attest_key() {
 
     let ret = smc(
         rmi::RMM_GET_REALM_ATTEST_KEY,
-        &[*guard, config::PAGE_SIZE, ATTEST_KEY_CURVE_ECC_SECP384R1],
+        &[*guard, config::PAGE_SIZE, ATTEST_KEY_CURVE],
     );
 
     let ret_code = ret[0] as isize;
@@ -107,6 +107,22 @@ pub(super) fn get_vhuks() {
     debug!("VHUK_M: {:02x?}", super::vhuk_m());
 }
 
+pub(super) fn refresh_mec_key(mecid: usize) -> isize {
+    trace!("RMM_MEC_REFRESH");
+
+    let ret = smc(rmi::RMM_MEC_REFRESH, &[mecid]);
+
+    let ret_code = ret[0] as isize;
+    debug!("RMM_MEC_REFRESH returned with: {}", ret_code);
+
+    if ret_code != 0 {
+        let e: RmmEl3IfcError = ret_code.into();
+        error!("RMM_MEC_REFRESH failed with {:?}", e);
+    }
+
+    ret_code
+}
+
 fn get_vhuk(id: usize, out: &Spinlock<[u8; 32]>) -> isize {
     trace!("RMM_ISLET_GET_VHUK");
 
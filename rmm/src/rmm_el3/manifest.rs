@@ -5,6 +5,7 @@ use spinning_top::SpinlockGuard;
 
 use super::RMM_SHARED_BUFFER_LOCK;
 use crate::config;
+use crate::rmi::metadata::{self, KEY_HASH_SIZE, MAX_REVOKED_KEYS, P384_PUBLIC_KEY_SIZE};
 /*
  * Boot Manifest structure illustration, with two dram banks and
  * a single console.
@@ -16,7 +17,7 @@ use crate::config;
  * +--------+----------------+--------------+
  * |   4    |    padding     |  0x00000000  |
  * +--------+----------------+--------------+
- * |   8    |   plat_data    |     NULL     |
+ * |   8    |   plat_data    | IsletPlatData|
  * +--------+----------------+--------------+
  * |   16   |   num_banks    |              |
  * +--------+----------------+              |
@@ -89,6 +90,47 @@ pub struct RmmManifest {
 
 const EL3_IFC_VERSION: u32 = 0x00000003;
 
+// Islet specific platform data, which `plat_data_ptr` points to if present.
+// It provisions the trust in the signers of the realm metadata.
+#[repr(C)]
+pub struct IsletPlatData {
+    pub magic: u64,                                       // ISLET_PLAT_DATA_MAGIC
+    pub flags: u64,                                       // ISLET_PLAT_FLAG_*
+    pub vendor_key: [u8; P384_PUBLIC_KEY_SIZE],           // Root of the metadata signer chains
+    pub num_revoked: u64,                                 // The number of revoked signer keys
+    pub revoked: [[u8; KEY_HASH_SIZE]; MAX_REVOKED_KEYS], // SHA-384 of the revoked keys
+}
+
+const ISLET_PLAT_DATA_MAGIC: u64 = u64::from_le_bytes(*b"ISLETPD1");
+
+// Realms are only created with v2 metadata, whose signer chain is rooted in
+// `vendor_key`. Off by default, in which v1 metadata (signed by the key
+// embedded in it) is accepted as well.
+const ISLET_PLAT_FLAG_REQUIRE_METADATA_V2: u64 = 1 << 0;
+
+fn load_plat_data(ptr: usize) -> core::result::Result<(), Error> {
+    let plat_data = assume_safe::<IsletPlatData>(ptr)?;
+    if plat_data.magic != ISLET_PLAT_DATA_MAGIC {
+        warn!(
+            "Unknown platform data in the manifest: {:#x}",
+            plat_data.magic
+        );
+        return Ok(());
+    }
+
+    let num_revoked = core::cmp::min(plat_data.num_revoked as usize, MAX_REVOKED_KEYS);
+    debug!("Metadata vendor key: {}", hex::encode(plat_data.vendor_key));
+    debug!("Revoked metadata signers: {}", num_revoked);
+    let require_v2 = plat_data.flags & ISLET_PLAT_FLAG_REQUIRE_METADATA_V2 != 0;
+    debug!("Metadata v2 required: {}", require_v2);
+    metadata::provision_trust(
+        Some(&plat_data.vendor_key),
+        &plat_data.revoked[..num_revoked],
+        require_v2,
+    );
+    Ok(())
+}
+
 pub fn load() -> core::result::Result<(), Error> {
     debug!("Configuring RMM with EL3 manifest");
     let guard: SpinlockGuard<'_, _> = RMM_SHARED_BUFFER_LOCK.lock();
@@ -124,6 +166,10 @@ pub fn load() -> core::result::Result<(), Error> {
         }
         max_base = bank.base;
     }
+
+    if manifest.plat_data_ptr != 0 {
+        load_plat_data(manifest.plat_data_ptr as usize)?;
+    }
     Ok(())
 }
 
@@ -154,3 +200,17 @@ impl safe_abstraction::raw_ptr::SafetyAssured for DramBank {
         true
     }
 }
+
+impl safe_abstraction::raw_ptr::RawPtr for IsletPlatData {}
+
+impl safe_abstraction::raw_ptr::SafetyChecked for IsletPlatData {}
+
+impl safe_abstraction::raw_ptr::SafetyAssured for IsletPlatData {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn verify_ownership(&self) -> bool {
+        true
+    }
+}
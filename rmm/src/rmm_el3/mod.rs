@@ -14,6 +14,27 @@ use spinning_top::{Spinlock, SpinlockGuard};
 // TODO: move those consts to a more appropriate place
 const SHA256_DIGEST_SIZE: usize = 32;
 const ATTEST_KEY_CURVE_ECC_SECP384R1: usize = 0;
+// Not defined by the RMM-EL3 interface, which only provides P-384 RAKs.
+// These are for EL3 firmware of other platforms which defines them.
+#[allow(dead_code)]
+const ATTEST_KEY_CURVE_ECC_SECP256R1: usize = 1;
+#[allow(dead_code)]
+const ATTEST_KEY_CURVE_ECC_SECP521R1: usize = 2;
+
+// The curve of the RAK requested from EL3, P-384 unless chosen otherwise
+#[cfg(all(feature = "rak_p256", feature = "rak_p521"))]
+compile_error!("Only one of `rak_p256` and `rak_p521` can be enabled");
+#[cfg(all(
+    any(feature = "fvp", feature = "qemu"),
+    any(feature = "rak_p256", feature = "rak_p521")
+))]
+compile_error!("TF-A only provides P-384 RAKs, `rak_p256` and `rak_p521` are platform-specific");
+#[cfg(feature = "rak_p256")]
+const ATTEST_KEY_CURVE: usize = ATTEST_KEY_CURVE_ECC_SECP256R1;
+#[cfg(feature = "rak_p521")]
+const ATTEST_KEY_CURVE: usize = ATTEST_KEY_CURVE_ECC_SECP521R1;
+#[cfg(not(any(feature = "rak_p256", feature = "rak_p521")))]
+const ATTEST_KEY_CURVE: usize = ATTEST_KEY_CURVE_ECC_SECP384R1;
 
 const VHUK_LENGTH: usize = 32;
 
@@ -65,3 +86,9 @@ pub fn vhuk_a() -> [u8; VHUK_LENGTH] {
 pub fn vhuk_m() -> [u8; VHUK_LENGTH] {
     utils::get_spinlock(&VHUK_M)
 }
+
+/// Requests EL3 to replace the encryption key bound to `mecid`.
+/// The data left by the previous owner of the MECID can't be read afterwards.
+pub fn refresh_mec_key(mecid: usize) -> bool {
+    iface::refresh_mec_key(mecid) == 0
+}
@@ -119,6 +119,7 @@ impl RealmClaims {
         let measurement_size = match measurement_hash_algo.as_str() {
             "sha-256" => 32,
             "sha-512" => 64,
+            "sha-384" => 48,
             _ => panic!("Unexpected hash algo id {}", measurement_hash_algo),
         };
 
@@ -1,17 +1,18 @@
 pub mod claims;
+pub mod rak;
 
 use alloc::{boxed::Box, string::String, vec, vec::Vec};
 use ciborium::{ser, Value};
 use coset::{CoseSign1Builder, HeaderBuilder, TaggedCborSerializable};
-use ecdsa::signature::Signer;
 use tinyvec::ArrayVec;
 
 use crate::{
     measurement::Measurement,
-    rmi::{HASH_ALGO_SHA256, HASH_ALGO_SHA512},
+    rmi::{HASH_ALGO_SHA256, HASH_ALGO_SHA384, HASH_ALGO_SHA512},
 };
 
 use self::claims::RealmClaims;
+use self::rak::{RealmAttestKey, P521_PRIV_LEN};
 use crate::rmm_el3::{plat_token, realm_attest_key};
 
 // Arbitrary number.
@@ -35,8 +36,8 @@ const RAK_PRIV_KEY: [u8; 48] = [
 ];
 
 type PlatformToken = ArrayVec<[u8; MAX_PLATFORM_TOKEN_SIZE]>;
-// 48B - the length of EC-P384 private key
-type RAKPriv = ArrayVec<[u8; 48]>;
+// 66B - the length of EC-P521 private key, the longest one supported
+type RAKPriv = ArrayVec<[u8; P521_PRIV_LEN]>;
 
 #[derive(Debug, Default)]
 pub struct Attestation {
@@ -107,13 +108,14 @@ impl Attestation {
         let hash_algo_id = match hash_algo {
             HASH_ALGO_SHA256 => String::from("sha-256"),
             HASH_ALGO_SHA512 => String::from("sha-512"),
+            HASH_ALGO_SHA384 => String::from("sha-384"),
             _ => panic!("Unrecognized hash algorithm {}", hash_algo),
         };
 
-        let secret_key =
-            p384::SecretKey::from_slice(&self.rak_priv).expect("Failed to import private RAK.");
+        let rak =
+            RealmAttestKey::from_slice(&self.rak_priv).expect("Failed to import private RAK.");
 
-        let public_key = secret_key.public_key().to_sec1_bytes().to_vec();
+        let public_key = rak.public_key();
 
         let claims = RealmClaims::init(
             challenge,
@@ -140,30 +142,18 @@ impl Attestation {
         ser::into_writer(&Value::Map(claims_map), &mut realm_token)
             .expect("Failed to serialize realm token");
 
-        let protected = HeaderBuilder::new()
-            .algorithm(coset::iana::Algorithm::ES384)
-            .build();
+        let protected = HeaderBuilder::new().algorithm(rak.algorithm()).build();
 
         let sign1 = CoseSign1Builder::new()
             .protected(protected)
             .payload(realm_token)
-            .create_signature(b"", |payload| Self::sign(secret_key, payload))
+            .create_signature(b"", |payload| rak.sign(payload))
             .build();
 
         sign1
             .to_tagged_vec()
             .expect("Failed to create tagged signed token")
     }
-
-    fn sign(secret_key: p384::SecretKey, data: &[u8]) -> Vec<u8> {
-        let signing_key = p384::ecdsa::SigningKey::from_bytes(&secret_key.to_bytes())
-            .expect("Failed to generate signing key");
-
-        let signature: p384::ecdsa::Signature = signing_key
-            .try_sign(data)
-            .expect("Failed to create P384 signature");
-        signature.to_vec()
-    }
 }
 
 pub fn get_token(
@@ -186,3 +176,59 @@ pub fn get_token(
         hash_algo,
     )
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::measurement::MEASUREMENTS_SLOT_NR;
+    use coset::{iana, CoseSign1, RegisteredLabelWithPrivate};
+    use ecdsa::signature::Verifier;
+
+    fn realm_token(rak_priv: &[u8]) -> CoseSign1 {
+        let attestation = Attestation::new(b"platform token", rak_priv);
+        let token = attestation.create_realm_token(
+            &[0xAB; MAX_CHALLENGE_SIZE],
+            &[Measurement::default(); MEASUREMENTS_SLOT_NR],
+            &[0; 64],
+            HASH_ALGO_SHA256,
+        );
+        CoseSign1::from_tagged_slice(&token).unwrap()
+    }
+
+    #[test]
+    fn attestation_realm_token_curves() {
+        let mut key = [0x22u8; P521_PRIV_LEN];
+        // Keep the P-521 scalar below the order of the curve
+        key[0] = 0x01;
+
+        for (len, alg) in [
+            (rak::P256_PRIV_LEN, iana::Algorithm::ES256),
+            (rak::P384_PRIV_LEN, iana::Algorithm::ES384),
+            (rak::P521_PRIV_LEN, iana::Algorithm::ES512),
+        ] {
+            let rak_priv = &key[..len];
+            let sign1 = realm_token(rak_priv);
+            assert_eq!(
+                sign1.protected.header.alg,
+                Some(RegisteredLabelWithPrivate::Assigned(alg))
+            );
+
+            let public_key = RealmAttestKey::from_slice(rak_priv).unwrap().public_key();
+            let verified = sign1.verify_signature(b"", |sig, data| match alg {
+                iana::Algorithm::ES256 => {
+                    let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(&public_key)?;
+                    key.verify(data, &p256::ecdsa::Signature::from_slice(sig)?)
+                }
+                iana::Algorithm::ES384 => {
+                    let key = p384::ecdsa::VerifyingKey::from_sec1_bytes(&public_key)?;
+                    key.verify(data, &p384::ecdsa::Signature::from_slice(sig)?)
+                }
+                _ => {
+                    let key = p521::ecdsa::VerifyingKey::from_sec1_bytes(&public_key)?;
+                    key.verify(data, &p521::ecdsa::Signature::from_slice(sig)?)
+                }
+            });
+            assert!(verified.is_ok());
+        }
+    }
+}
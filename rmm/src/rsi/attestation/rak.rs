@@ -0,0 +1,158 @@
+use alloc::vec::Vec;
+use coset::iana;
+use ecdsa::hazmat::{bits2field, sign_prehashed};
+use ecdsa::signature::Signer;
+use p521::elliptic_curve::ff::PrimeField;
+use rfc6979::HmacDrbg;
+use sha2::{Digest, Sha512};
+
+// The lengths of the private keys, which tell the curve of the RAK from EL3
+pub const P256_PRIV_LEN: usize = 32;
+pub const P384_PRIV_LEN: usize = 48;
+pub const P521_PRIV_LEN: usize = 66;
+
+/// Realm Attestation Key
+pub enum RealmAttestKey {
+    P256(p256::SecretKey),
+    P384(p384::SecretKey),
+    P521(p521::SecretKey),
+}
+
+impl RealmAttestKey {
+    pub fn from_slice(key_priv: &[u8]) -> Option<Self> {
+        match key_priv.len() {
+            P256_PRIV_LEN => p256::SecretKey::from_slice(key_priv).ok().map(Self::P256),
+            P384_PRIV_LEN => p384::SecretKey::from_slice(key_priv).ok().map(Self::P384),
+            P521_PRIV_LEN => p521::SecretKey::from_slice(key_priv).ok().map(Self::P521),
+            _ => None,
+        }
+    }
+
+    /// Returns the SEC1 encoded public key.
+    pub fn public_key(&self) -> Vec<u8> {
+        match self {
+            Self::P256(key) => key.public_key().to_sec1_bytes().to_vec(),
+            Self::P384(key) => key.public_key().to_sec1_bytes().to_vec(),
+            Self::P521(key) => key.public_key().to_sec1_bytes().to_vec(),
+        }
+    }
+
+    /// Returns the COSE algorithm of the signatures made by the key.
+    pub fn algorithm(&self) -> iana::Algorithm {
+        match self {
+            Self::P256(_) => iana::Algorithm::ES256,
+            Self::P384(_) => iana::Algorithm::ES384,
+            Self::P521(_) => iana::Algorithm::ES512,
+        }
+    }
+
+    /// Signs `data` with ECDSA, returning the signature as `r || s`.
+    pub fn sign(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::P256(key) => {
+                let signing_key = p256::ecdsa::SigningKey::from_bytes(&key.to_bytes())
+                    .expect("Failed to generate signing key");
+                let signature: p256::ecdsa::Signature = signing_key
+                    .try_sign(data)
+                    .expect("Failed to create P256 signature");
+                signature.to_vec()
+            }
+            Self::P384(key) => {
+                let signing_key = p384::ecdsa::SigningKey::from_bytes(&key.to_bytes())
+                    .expect("Failed to generate signing key");
+                let signature: p384::ecdsa::Signature = signing_key
+                    .try_sign(data)
+                    .expect("Failed to create P384 signature");
+                signature.to_vec()
+            }
+            Self::P521(key) => {
+                let digest = bits2field::<p521::NistP521>(&Sha512::digest(data))
+                    .expect("Failed to convert digest");
+                let (signature, _) = sign_prehashed::<p521::NistP521, _>(
+                    key.to_nonzero_scalar().as_ref(),
+                    p521_nonce(key, &digest),
+                    &digest,
+                )
+                .expect("Failed to create P521 signature");
+                signature.to_vec()
+            }
+        }
+    }
+}
+
+// p521 0.13 signs only with a random nonce, which is not available in RMM,
+// and 0.14 which signs deterministically needs a newer toolchain.
+// This is the nonce of RFC 6979 3.2, whose helper can't be used
+// as SHA-512 is shorter than the P-521 scalar.
+fn p521_nonce(key: &p521::SecretKey, digest: &p521::FieldBytes) -> p521::Scalar {
+    let mut drbg = HmacDrbg::<Sha512>::new(&key.to_bytes(), digest, &[]);
+    loop {
+        let mut k = p521::FieldBytes::default();
+        drbg.fill_bytes(&mut k);
+        // bits2int: the leftmost 521 of the 528 bits
+        for i in (1..k.len()).rev() {
+            k[i] = (k[i] >> 7) | (k[i - 1] << 1);
+        }
+        k[0] >>= 7;
+
+        let k: Option<p521::Scalar> = p521::Scalar::from_repr(k).into();
+        if let Some(k) = k.filter(|k| !bool::from(k.is_zero())) {
+            return k;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ecdsa::signature::Verifier;
+
+    #[test]
+    fn rak_sign_verify() {
+        let data = b"realm token";
+
+        let mut key = [0x11u8; P521_PRIV_LEN];
+        // Keep the P-521 scalar below the order of the curve
+        key[0] = 0x01;
+
+        let rak = RealmAttestKey::from_slice(&key[..P256_PRIV_LEN]).unwrap();
+        assert_eq!(rak.algorithm(), iana::Algorithm::ES256);
+        let verifying_key = p256::ecdsa::VerifyingKey::from_sec1_bytes(&rak.public_key()).unwrap();
+        let signature = p256::ecdsa::Signature::from_slice(&rak.sign(data)).unwrap();
+        assert!(verifying_key.verify(data, &signature).is_ok());
+
+        let rak = RealmAttestKey::from_slice(&key[..P384_PRIV_LEN]).unwrap();
+        assert_eq!(rak.algorithm(), iana::Algorithm::ES384);
+        let verifying_key = p384::ecdsa::VerifyingKey::from_sec1_bytes(&rak.public_key()).unwrap();
+        let signature = p384::ecdsa::Signature::from_slice(&rak.sign(data)).unwrap();
+        assert!(verifying_key.verify(data, &signature).is_ok());
+
+        let rak = RealmAttestKey::from_slice(&key).unwrap();
+        assert_eq!(rak.algorithm(), iana::Algorithm::ES512);
+        let verifying_key = p521::ecdsa::VerifyingKey::from_sec1_bytes(&rak.public_key()).unwrap();
+        let signature = p521::ecdsa::Signature::from_slice(&rak.sign(data)).unwrap();
+        assert!(verifying_key.verify(data, &signature).is_ok());
+
+        assert!(RealmAttestKey::from_slice(&key[..40]).is_none());
+    }
+
+    #[test]
+    fn rak_p521_rfc6979() {
+        // RFC 6979 A.2.7, with SHA-512 and the message "sample"
+        let key = hex::decode(
+            "00fad06daa62ba3b25d2fb40133da757205de67f5bb0018fee8c86e1b68c7e75ca\
+             a896eb32f1f47c70855836a6d16fcc1466f6d8fbec67db89ec0c08b0e996b83538",
+        )
+        .unwrap();
+        let signature = hex::decode(
+            "00c328fafcbd79dd77850370c46325d987cb525569fb63c5d3bc53950e6d4c5f17\
+             4e25a1ee9017b5d450606add152b534931d7d4e8455cc91f9b15bf05ec36e377fa\
+             00617cce7cf5064806c467f678d3b4080d6f1cc50af26ca209417308281b68af28\
+             2623eaa63e5b5c0723d8b8c37ff0777b1a20f8ccb1dccc43997f1ee0e44da4a67a",
+        )
+        .unwrap();
+
+        let rak = RealmAttestKey::from_slice(&key).unwrap();
+        assert_eq!(rak.sign(b"sample"), signature);
+    }
+}
@@ -1,4 +1,3 @@
-use crate::measurement::MeasurementError;
 use crate::rsi::error::Error;
 use crate::rsi::Rd;
 
@@ -15,17 +14,3 @@ pub fn read(
     out.as_mut_slice().copy_from_slice(measurement.as_slice());
     Ok(())
 }
-
-pub fn extend(
-    rd: &mut Rd,
-    index: usize,
-    f: impl Fn(&mut crate::measurement::Measurement) -> Result<(), MeasurementError>,
-) -> Result<(), crate::rsi::error::Error> {
-    let measurement = rd
-        .measurements
-        .get_mut(index)
-        .ok_or(Error::InvalidMeasurementIndex)?;
-
-    f(measurement)?;
-    Ok(())
-}
@@ -3,24 +3,23 @@ pub mod constraint;
 pub mod error;
 pub mod hostcall;
 pub mod measurement;
+pub mod plane;
 pub mod psci;
 pub mod ripas;
 pub mod sealing;
+pub mod vdev;
 pub mod version;
 
-use alloc::vec::Vec;
-
-use crate::define_interface;
 use crate::event::RsiHandle;
 use crate::granule::{GranuleState, GRANULE_SIZE};
 use crate::listen;
-use crate::measurement::{HashContext, Measurement, MEASUREMENTS_SLOT_NR, MEASUREMENTS_SLOT_RIM};
+use crate::measurement::{Measurement, MEASUREMENTS_SLOT_NR, MEASUREMENTS_SLOT_RIM};
 use crate::realm::config::realm_config;
 use crate::realm::mm::address::GuestPhysAddr;
 use crate::realm::mm::rtt::RTT_PAGE_LEVEL;
-use crate::realm::rd::Rd;
+use crate::realm::rd::{Rd, RdRef};
 use crate::rec::context::{get_reg, set_reg};
-use crate::rec::{Rec, RmmRecAttestState};
+use crate::rec::{attest, Rec, RmmRecAttestState};
 use crate::rmi;
 use crate::rmi::error::Error;
 use crate::rmi::rec::run::Run;
@@ -33,43 +32,8 @@ use crate::{get_granule, get_granule_if};
 
 use safe_abstraction::raw_ptr::assume_safe;
 
-define_interface! {
-    command {
-        ABI_VERSION             = 0xc400_0190,
-        FEATURES                = 0xc400_0191,
-        MEASUREMENT_READ        = 0xc400_0192,
-        MEASUREMENT_EXTEND      = 0xc400_0193,
-        ATTEST_TOKEN_INIT       = 0xc400_0194,
-        ATTEST_TOKEN_CONTINUE   = 0xc400_0195,
-        REALM_CONFIG            = 0xc400_0196,
-        IPA_STATE_SET           = 0xc400_0197,
-        IPA_STATE_GET           = 0xc400_0198,
-        HOST_CALL               = 0xc400_0199,
-        // PSCI smcs
-        // XXX: SMCCC_VERSION is not defined in the spec, so remove it if it is not used now
-        SMCCC_VERSION           = 0x8000_0000,
-        PSCI_VERSION            = 0x8400_0000,
-        PSCI_CPU_SUSPEND        = 0xC400_0001,
-        PSCI_CPU_OFF            = 0x8400_0002,
-        PSCI_CPU_ON             = 0xC400_0003,
-        PSCI_AFFINITY_INFO      = 0xC400_0004,
-        PSCI_SYSTEM_OFF         = 0x8400_0008,
-        PSCI_SYSTEM_RESET       = 0x8400_0009,
-        PSCI_FEATURES           = 0x8400_000A,
-        // vendor calls
-        ISLET_REALM_SEALING_KEY = 0xC700_0191,
-    }
-}
-
-pub const SUCCESS: usize = 0;
-pub const ERROR_INPUT: usize = 1;
-pub const ERROR_STATE: usize = 2;
-pub const INCOMPLETE: usize = 3;
-
-pub const ABI_VERSION_MAJOR: usize = 1;
-pub const ABI_VERSION_MINOR: usize = 0;
-
-extern crate alloc;
+// The ABI shared with the Realm
+pub use rmm_spec::rsi::*;
 
 pub fn do_host_call(
     _arg: &[usize],
@@ -78,8 +42,8 @@ pub fn do_host_call(
     rec: &mut Rec<'_>,
     run: &mut Run,
 ) -> core::result::Result<(), Error> {
-    let rd_granule = get_granule_if!(rec.owner()?, GranuleState::RD)?;
-    let rd = rd_granule.content::<Rd>()?;
+    let rd_ref = RdRef::new(rec.owner()?)?;
+    let rd = rd_ref.rtts();
 
     let ipa = get_reg(rec, 1).unwrap_or(0x0);
 
@@ -95,7 +59,6 @@ pub fn do_host_call(
 
     let pa = rd
         .s2_table()
-        .lock()
         .ipa_to_pa(
             crate::realm::mm::address::GuestPhysAddr::from(ipa),
             RTT_PAGE_LEVEL,
@@ -130,31 +93,6 @@ pub fn do_host_call(
     Ok(())
 }
 
-fn get_token_part(
-    rd: &Rd,
-    context: &mut Rec<'_>,
-    size: usize,
-) -> core::result::Result<(Vec<u8>, usize), Error> {
-    let hash_algo = rd.hash_algo();
-    let measurements = rd.measurements;
-
-    // FIXME: This should be stored instead of generating it for every call.
-    let token = crate::rsi::attestation::get_token(
-        context.attest_challenge(),
-        &measurements,
-        rd.personalization_value(),
-        hash_algo,
-    );
-
-    let offset = context.attest_token_offset();
-    let part_size = core::cmp::min(size, token.len() - offset);
-    let part_end = offset + part_size;
-
-    context.set_attest_offset(part_end);
-
-    Ok((token[offset..part_end].to_vec(), token.len() - part_end))
-}
-
 pub fn set_event_handler(rsi: &mut RsiHandle) {
     listen!(rsi, ATTEST_TOKEN_INIT, |_arg, ret, _rmm, rec, _| {
         let mut challenge: [u8; 64] = [0; 64];
@@ -167,8 +105,24 @@ pub fn set_event_handler(rsi: &mut RsiHandle) {
         }
 
         rec.set_attest_challenge(&challenge);
+
+        #[cfg(not(kani))]
+        // `rsi` is currently not reachable in model checking harnesses
+        {
+            let rd_granule = get_granule_if!(rec.owner()?, GranuleState::RD)?;
+            let rd = rd_granule.content::<Rd>()?;
+
+            // The token is signed only once here and streamed from the
+            // auxiliary granules by ATTEST_TOKEN_CONTINUE.
+            let token = attestation::get_token(
+                &challenge,
+                &rd.measurements,
+                rd.personalization_value(),
+                rd.hash_algo(),
+            );
+            attest::store_token(rec, &token)?;
+        }
         rec.set_attest_state(RmmRecAttestState::AttestInProgress);
-        rec.set_attest_offset(0);
 
         set_reg(rec, 0, SUCCESS)?;
         set_reg(rec, 1, attestation::MAX_CCA_TOKEN_SIZE)?;
@@ -178,8 +132,8 @@ pub fn set_event_handler(rsi: &mut RsiHandle) {
     });
 
     listen!(rsi, ATTEST_TOKEN_CONTINUE, |_arg, ret, _rmm, rec, _| {
-        let rd_granule = get_granule_if!(rec.owner()?, GranuleState::RD)?;
-        let rd = rd_granule.content::<Rd>()?;
+        let rd_ref = RdRef::new(rec.owner()?)?;
+        let rd = rd_ref.rtts();
 
         if rec.attest_state() != RmmRecAttestState::AttestInProgress {
             warn!("Calling attest token continue without init");
@@ -198,7 +152,6 @@ pub fn set_event_handler(rsi: &mut RsiHandle) {
 
         let attest_pa: usize = rd
             .s2_table()
-            .lock()
             .ipa_to_pa(GuestPhysAddr::from(attest_ipa), RTT_PAGE_LEVEL)
             .ok_or(Error::RmiErrorInput)?
             .into();
@@ -217,21 +170,22 @@ pub fn set_event_handler(rsi: &mut RsiHandle) {
         #[cfg(not(kani))]
         // `rsi` is currently not reachable in model checking harnesses
         {
-            let (token_part, token_left) = get_token_part(&rd, rec, buffer_size)?;
-
-            unsafe {
-                let pa_ptr = attest_pa as *mut u8;
-                core::ptr::copy(token_part.as_ptr(), pa_ptr.add(pa_offset), token_part.len());
-            }
+            // Safety: the buffer lies within the granule of the realm
+            //         which `attest_ipa` is validated to be mapped to.
+            let buf = unsafe {
+                core::slice::from_raw_parts_mut((attest_pa as *mut u8).add(pa_offset), buffer_size)
+            };
+            let (copied, token_left) = attest::read_token(rec, buf)?;
 
             if token_left == 0 {
+                attest::wipe_token(rec)?;
                 set_reg(rec, 0, SUCCESS)?;
                 rec.set_attest_state(RmmRecAttestState::NoAttestInProgress);
             } else {
                 set_reg(rec, 0, INCOMPLETE)?;
             }
 
-            set_reg(rec, 1, token_part.len())?;
+            set_reg(rec, 1, copied)?;
         }
 
         ret[0] = rmi::SUCCESS_REC_ENTER;
@@ -335,7 +289,7 @@ pub fn set_event_handler(rsi: &mut RsiHandle) {
 
         #[cfg(not(kani))]
         // `rsi` is currently not reachable in model checking harnesses
-        HashContext::new(&mut rd)?.extend_measurement(&buffer[0..size], index)?;
+        crate::measurement::context(&mut rd, index)?.extend_measurement(&buffer[0..size])?;
 
         set_reg(rec, 0, SUCCESS)?;
         ret[0] = rmi::SUCCESS_REC_ENTER;
@@ -348,7 +302,7 @@ pub fn set_event_handler(rsi: &mut RsiHandle) {
         let rd = rd_granule.content::<Rd>()?;
 
         let config_ipa = get_reg(rec, 1)?;
-        if validate_ipa(&rd, config_ipa).is_err() {
+        if validate_ipa(&rd.rtts(), config_ipa).is_err() {
             set_reg(rec, 0, ERROR_INPUT)?;
             ret[0] = rmi::SUCCESS_REC_ENTER;
             return Ok(());
@@ -374,7 +328,9 @@ pub fn set_event_handler(rsi: &mut RsiHandle) {
     //    bit 1: if set, RIM is used as a key metarial
     //    bit 2: if set, the realm_id is used as a key material
     //    bit 3: if set, provided SVN is used as a key material
-    // x2: svn - Security Version Number
+    // x2: svn - Security Version Number, which may be any value up to the current SVN
+    //           of the realm, so that the data sealed by its previous versions can be unsealed.
+    //           RSI_ERROR_INPUT is returned if it is higher than the current one.
     // The resulting key is returned 256 bit long key is returned
     // in x1, x2, x3, x4 registers
     listen!(rsi, ISLET_REALM_SEALING_KEY, |_arg, ret, _rmm, rec, _| {
@@ -0,0 +1,310 @@
+use crate::const_assert_eq;
+use crate::event::RsiHandle;
+use crate::granule::{GranuleState, GRANULE_SIZE};
+use crate::listen;
+use crate::realm::mm::address::GuestPhysAddr;
+use crate::realm::mm::rtt::RTT_PAGE_LEVEL;
+use crate::realm::rd::Rd;
+use crate::rec::context::{get_reg, set_reg, RegOffset};
+use crate::rec::plane::{get_plane_reg, set_plane_reg, switch_to};
+use crate::rec::Rec;
+use crate::rmi;
+use crate::rmi::error::Error;
+use crate::rmi::rtt::validate_ipa;
+use crate::rsi::{ERROR_INPUT, PLANE_ENTER, PLANE_REG_READ, PLANE_REG_WRITE, SUCCESS};
+use crate::{get_granule, get_granule_if};
+
+use autopadding::*;
+use safe_abstraction::raw_ptr::assume_safe;
+
+pub const PLANE_NR_GPRS: usize = 31;
+
+/// The plane exited due to a synchronous exception
+pub const PLANE_EXIT_SYNC: u64 = 0;
+
+pad_struct_and_impl_default!(
+pub struct PlaneEntry {
+    0x0 flags: u64,
+    0x8 pc: u64,
+    0x100 gprs: [u64; PLANE_NR_GPRS],
+    0x800 => @END,
+}
+);
+
+pad_struct_and_impl_default!(
+pub struct PlaneExit {
+    0x0 reason: u64,
+    0x100 elr: u64,
+    0x108 esr: u64,
+    0x110 far: u64,
+    0x118 hpfar: u64,
+    0x120 spsr: u64,
+    0x200 gprs: [u64; PLANE_NR_GPRS],
+    0x800 => @END,
+}
+);
+
+/// The object shared between the primary plane and the RMM
+/// to enter an auxiliary plane and to get the reason of its exit.
+#[repr(C)]
+#[derive(Default)]
+pub struct PlaneRun {
+    entry: PlaneEntry,
+    exit: PlaneExit,
+}
+
+const_assert_eq!(core::mem::size_of::<PlaneRun>(), GRANULE_SIZE);
+
+impl core::fmt::Debug for PlaneRun {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("rsi::PlaneRun")
+            .field("entry.flags", &format_args!("{:#X}", &self.entry.flags))
+            .field("entry.pc", &format_args!("{:#X}", &self.entry.pc))
+            .field("entry.gprs", &self.entry.gprs)
+            .field("exit.reason", &format_args!("{:#X}", &self.exit.reason))
+            .field("exit.elr", &format_args!("{:#X}", &self.exit.elr))
+            .field("exit.esr", &format_args!("{:#X}", &self.exit.esr))
+            .field("exit.far", &format_args!("{:#X}", &self.exit.far))
+            .field("exit.hpfar", &format_args!("{:#X}", &self.exit.hpfar))
+            .field("exit.spsr", &format_args!("{:#X}", &self.exit.spsr))
+            .field("exit.gprs", &self.exit.gprs)
+            .finish()
+    }
+}
+
+impl safe_abstraction::raw_ptr::RawPtr for PlaneRun {}
+
+impl safe_abstraction::raw_ptr::SafetyChecked for PlaneRun {}
+
+impl safe_abstraction::raw_ptr::SafetyAssured for PlaneRun {
+    fn is_initialized(&self) -> bool {
+        // The initialization of this memory is guaranteed
+        // according to the RMM Specification A2.2.4 Granule Wiping.
+        // This instance belongs to a Data Granule and has been initialized.
+        true
+    }
+
+    fn verify_ownership(&self) -> bool {
+        // The instance's ownership is guaranteed while being processed by the RMM.
+        // The primary plane is not running while the RMM accesses the instance,
+        // and auxiliary planes can't access it unless the primary plane
+        // maps it in their RTTs, which only affects the realm's own data.
+        true
+    }
+}
+
+fn plane_run_pa(rd: &Rd, ipa: usize) -> Result<usize, Error> {
+    let pa = rd
+        .s2_table()
+        .ipa_to_pa(GuestPhysAddr::from(ipa), RTT_PAGE_LEVEL)
+        .ok_or(Error::RmiErrorInput)?;
+    Ok(pa.as_usize())
+}
+
+fn is_valid_plane(rd: &Rd, rec: &Rec<'_>, plane: usize) -> bool {
+    rec.active_plane() == 0 && (1..=rd.num_aux_planes()).contains(&plane)
+}
+
+/// Reports the exit of the running auxiliary plane to the primary plane
+/// through the PlaneRun object given on PLANE_ENTER, then resumes the
+/// primary plane on the same REC.
+pub fn exit_to_primary(rec: &mut Rec<'_>) -> Result<(), Error> {
+    let rd_granule = get_granule_if!(rec.owner()?, GranuleState::RD)?;
+    let rd = rd_granule.content::<Rd>()?;
+    let pa = plane_run_pa(&rd, rec.plane_run())?;
+    let mut plane_run = assume_safe::<PlaneRun>(pa)?;
+
+    let exit = &mut plane_run.exit;
+    exit.reason = PLANE_EXIT_SYNC;
+    exit.elr = rec.context.elr_el2;
+    exit.esr = rec.context.sys_regs.esr_el2;
+    exit.far = rec.context.sys_regs.far_el2;
+    exit.hpfar = rec.context.sys_regs.hpfar;
+    exit.spsr = rec.context.spsr_el2;
+    exit.gprs.copy_from_slice(&rec.context.gp_regs);
+
+    switch_to(rec, 0)
+}
+
+pub fn set_event_handler(rsi: &mut RsiHandle) {
+    // PLANE_ENTER
+    // x1: index of the auxiliary plane to enter
+    // x2: IPA of the PlaneRun object
+    listen!(rsi, PLANE_ENTER, |_arg, ret, _rmm, rec, _| {
+        let plane = get_reg(rec, 1)?;
+        let run_ipa = get_reg(rec, 2)?;
+
+        let rd_granule = get_granule_if!(rec.owner()?, GranuleState::RD)?;
+        let rd = rd_granule.content::<Rd>()?;
+
+        if !is_valid_plane(&rd, rec, plane)
+            || run_ipa % GRANULE_SIZE != 0
+            || validate_ipa(&rd.rtts(), run_ipa).is_err()
+        {
+            set_reg(rec, 0, ERROR_INPUT)?;
+            ret[0] = rmi::SUCCESS_REC_ENTER;
+            return Ok(());
+        }
+        let Ok(pa) = plane_run_pa(&rd, run_ipa) else {
+            set_reg(rec, 0, ERROR_INPUT)?;
+            ret[0] = rmi::SUCCESS_REC_ENTER;
+            return Ok(());
+        };
+        core::mem::drop(rd_granule);
+
+        let plane_run = assume_safe::<PlaneRun>(pa)?;
+        trace!("PLANE_ENTER plane: {} {:#X?}", plane, *plane_run);
+
+        // The primary plane gets the result when the auxiliary plane exits.
+        set_reg(rec, 0, SUCCESS)?;
+        rec.set_plane_run(run_ipa);
+        switch_to(rec, plane)?;
+        for (idx, gpr) in plane_run.entry.gprs.iter().enumerate() {
+            set_reg(rec, idx, *gpr as usize)?;
+        }
+        set_reg(rec, RegOffset::PC, plane_run.entry.pc as usize)?;
+
+        ret[0] = rmi::SUCCESS_REC_ENTER;
+        Ok(())
+    });
+
+    // PLANE_REG_READ
+    // x1: index of the auxiliary plane
+    // x2: register encoding (0..=30: gprs, 31: pc, 32: pstate, 40: sctlr)
+    listen!(rsi, PLANE_REG_READ, |_arg, ret, _rmm, rec, _| {
+        let plane = get_reg(rec, 1)?;
+        let register = get_reg(rec, 2)?;
+
+        let rd_granule = get_granule_if!(rec.owner()?, GranuleState::RD)?;
+        let rd = rd_granule.content::<Rd>()?;
+        let valid = is_valid_plane(&rd, rec, plane);
+        core::mem::drop(rd_granule);
+
+        match get_plane_reg(rec, plane, register) {
+            Ok(value) if valid => {
+                set_reg(rec, 0, SUCCESS)?;
+                set_reg(rec, 1, value as usize)?;
+            }
+            _ => set_reg(rec, 0, ERROR_INPUT)?,
+        }
+        ret[0] = rmi::SUCCESS_REC_ENTER;
+        Ok(())
+    });
+
+    // PLANE_REG_WRITE
+    // x1: index of the auxiliary plane
+    // x2: register encoding (0..=30: gprs, 31: pc, 32: pstate, 40: sctlr)
+    // x3: value to write
+    listen!(rsi, PLANE_REG_WRITE, |_arg, ret, _rmm, rec, _| {
+        let plane = get_reg(rec, 1)?;
+        let register = get_reg(rec, 2)?;
+        let value = get_reg(rec, 3)?;
+
+        let rd_granule = get_granule_if!(rec.owner()?, GranuleState::RD)?;
+        let rd = rd_granule.content::<Rd>()?;
+        let valid = is_valid_plane(&rd, rec, plane);
+        core::mem::drop(rd_granule);
+
+        if valid && set_plane_reg(rec, plane, register, value as u64).is_ok() {
+            set_reg(rec, 0, SUCCESS)?;
+        } else {
+            set_reg(rec, 0, ERROR_INPUT)?;
+        }
+        ret[0] = rmi::SUCCESS_REC_ENTER;
+        Ok(())
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rmi::{
+        DATA_DESTROY, GRANULE_DELEGATE, GRANULE_UNDELEGATE, REALM_ACTIVATE, REC_ENTER,
+    };
+    use crate::test_utils::*;
+
+    // Covered RSIs: PLANE_REG_WRITE, PLANE_REG_READ, PLANE_ENTER
+    #[test]
+    fn rsi_plane_enter() {
+        const PLANE_RUN_IPA: usize = 0;
+        const PLANE: usize = 1;
+
+        let aux_rtt = alloc_granule(IDX_RTT_AUX_LEVEL0);
+        let ret = rmi::<GRANULE_DELEGATE>(&[aux_rtt]);
+        assert_eq!(ret[0], rmi::SUCCESS);
+
+        let rd = realm_create_with(|params| {
+            params.num_aux_planes = 1;
+            params.aux_rtt_base[0] = aux_rtt as u64;
+            params.aux_vmid[0] = params.vmid + 1;
+        })
+        .unwrap();
+        // The PlaneRun object, which the data granule is copied from
+        let src = unsafe { &mut *(alloc_granule(IDX_SRC1) as *mut PlaneRun) };
+        *src = PlaneRun::default();
+        src.entry.pc = 0x3000;
+        src.entry.gprs[5] = 0x55;
+        data_create(rd, PLANE_RUN_IPA, IDX_DATA1, IDX_SRC1);
+        rec_create(rd, IDX_REC1, IDX_REC1_PARAMS, IDX_REC1_AUX);
+        let ret = rmi::<REALM_ACTIVATE>(&[rd]);
+        assert_eq!(ret[0], rmi::SUCCESS);
+
+        let (rec1, run1) = (alloc_granule(IDX_REC1), alloc_granule(IDX_REC1_RUN));
+        let rec = || unsafe { &*(rec1 as *const Rec<'_>) };
+        // Returns X0 of the REC after the RSI call
+        let rsi = |cmd, args: &[usize]| {
+            rmi::<REC_ENTER>(&[&[rec1, run1, cmd][..], args].concat());
+            rec().context.gp_regs[0] as usize
+        };
+
+        // Only the auxiliary planes of the realm can be accessed
+        for plane in [0, PLANE + 1] {
+            assert_eq!(rsi(PLANE_REG_WRITE, &[plane, 5, 0xabcd]), ERROR_INPUT);
+            assert_eq!(rsi(PLANE_REG_READ, &[plane, 5]), ERROR_INPUT);
+            assert_eq!(rsi(PLANE_ENTER, &[plane, PLANE_RUN_IPA]), ERROR_INPUT);
+        }
+        const REG_INVALID: usize = 33;
+        assert_eq!(rsi(PLANE_REG_READ, &[PLANE, REG_INVALID]), ERROR_INPUT);
+
+        assert_eq!(rsi(PLANE_REG_WRITE, &[PLANE, 5, 0xabcd]), SUCCESS);
+        assert_eq!(
+            rsi(PLANE_REG_WRITE, &[PLANE, RegOffset::PC, 0x2000]),
+            SUCCESS
+        );
+        assert_eq!(rsi(PLANE_REG_READ, &[PLANE, 5]), SUCCESS);
+        assert_eq!(rec().context.gp_regs[1], 0xabcd);
+        assert_eq!(rsi(PLANE_REG_READ, &[PLANE, RegOffset::PC]), SUCCESS);
+        assert_eq!(rec().context.gp_regs[1], 0x2000);
+
+        assert_eq!(rsi(PLANE_ENTER, &[PLANE, PLANE_RUN_IPA + 1]), ERROR_INPUT);
+
+        // The auxiliary plane starts from the entry of PlaneRun
+        rsi(PLANE_ENTER, &[PLANE, PLANE_RUN_IPA]);
+        assert_eq!(rec().active_plane(), PLANE);
+        assert_eq!(rec().context.elr_el2, 0x3000);
+        assert_eq!(rec().context.gp_regs[5], 0x55);
+        assert_eq!(get_plane_reg(rec(), 0, 0).unwrap(), SUCCESS as u64);
+
+        // Its RSI calls exit to the primary plane, which finds them in PlaneRun
+        rsi(PLANE_REG_READ, &[PLANE, 5]);
+        assert_eq!(rec().active_plane(), 0);
+        assert_eq!(rec().context.gp_regs[0], SUCCESS as u64);
+        let plane_run = unsafe { &*(alloc_granule(IDX_DATA1) as *const PlaneRun) };
+        assert_eq!(plane_run.exit.reason, PLANE_EXIT_SYNC);
+        assert_eq!(plane_run.exit.gprs[0], PLANE_REG_READ as u64);
+        assert_eq!(plane_run.exit.gprs[5], 0x55);
+
+        // Cleanup
+        let ret = rmi::<DATA_DESTROY>(&[rd, PLANE_RUN_IPA]);
+        assert_eq!(ret[0], rmi::SUCCESS);
+        mock::host::unmap(rd, PLANE_RUN_IPA, false);
+        let ret = rmi::<GRANULE_UNDELEGATE>(&[alloc_granule(IDX_DATA1)]);
+        assert_eq!(ret[0], rmi::SUCCESS);
+        rec_destroy(IDX_REC1, IDX_REC1_AUX);
+        realm_destroy(rd);
+        let ret = rmi::<GRANULE_UNDELEGATE>(&[aux_rtt]);
+        assert_eq!(ret[0], rmi::SUCCESS);
+
+        miri_teardown();
+    }
+}
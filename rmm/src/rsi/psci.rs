@@ -32,8 +32,6 @@ const SMCCC_MINOR_VERSION: usize = 2;
 const PSCI_MAJOR_VERSION: usize = 1;
 const PSCI_MINOR_VERSION: usize = 1;
 
-extern crate alloc;
-
 pub fn set_event_handler(rsi: &mut RsiHandle) {
     listen!(rsi, rsi::PSCI_VERSION, |_arg, ret, _rmm, rec, _run| {
         if set_reg(rec, 0, psci_version()).is_err() {
@@ -1,7 +1,7 @@
 use crate::granule::is_granule_aligned;
 use crate::granule::GranuleState;
 use crate::realm::mm::stage2_tte::ripas;
-use crate::realm::rd::Rd;
+use crate::realm::rd::{Rd, RdRef};
 use crate::rec::context::{get_reg, set_reg};
 use crate::rec::Rec;
 use crate::rmi;
@@ -18,8 +18,8 @@ pub fn get_ripas_state(
     rec: &mut Rec<'_>,
     _run: &mut Run,
 ) -> core::result::Result<(), Error> {
-    let rd_granule = get_granule_if!(rec.owner()?, GranuleState::RD)?;
-    let rd = rd_granule.content::<Rd>()?;
+    let rd_ref = RdRef::new(rec.owner()?)?;
+    let rd = rd_ref.rtts();
 
     let base = get_reg(rec, 1)?;
     let top = get_reg(rec, 2)?;
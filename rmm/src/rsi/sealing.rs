@@ -6,13 +6,11 @@ use crate::realm::rd::{Rd, RPV_SIZE};
 use crate::rmi::error::Error;
 use crate::rmi::metadata::{IsletRealmMetadata, P384_PUBLIC_KEY_SIZE, REALM_ID_SIZE};
 use crate::rmm_el3::{vhuk_a, vhuk_m};
+use crate::rsi::{
+    RSI_ISLET_SLK_REALM_ID, RSI_ISLET_SLK_RIM, RSI_ISLET_SLK_SVN, RSI_ISLET_USE_VHUK_M,
+};
 use crate::{get_granule, get_granule_if};
 
-const RSI_ISLET_USE_VHUK_M: usize = 0x1 << 0;
-const RSI_ISLET_SLK_RIM: usize = 0x1 << 1;
-const RSI_ISLET_SLK_REALM_ID: usize = 0x1 << 2;
-const RSI_ISLET_SLK_SVN: usize = 0x1 << 3;
-
 pub const SEALING_KEY_SIZE: usize = 32;
 
 const SALT: [u8; 32] = [
@@ -102,6 +100,11 @@ impl KdfInfo {
     }
 }
 
+/// Derives the sealing key of the realm. With `RSI_ISLET_SLK_SVN`,
+/// the key is bound to the requested `svn`, which may be any value up to
+/// the current SVN of the realm but never higher. This lets realms updated
+/// to a new SVN derive the keys of their previous versions and migrate
+/// the sealed data forward, while the older versions can't derive the newer keys.
 pub fn realm_sealing_key(
     rd: &Rd,
     flags: usize,
@@ -115,12 +118,21 @@ pub fn realm_sealing_key(
     info.rpv.copy_from_slice(rd.personalization_value());
     info.flags = flags;
 
+    // The realms without metadata are regarded to be at SVN 0
+    if flags & RSI_ISLET_SLK_SVN != 0 && rd.metadata().is_none() && svn != 0 {
+        warn!("The SVN parameter is invalid!");
+        Err(Error::RmiErrorInput)?
+    }
+
     if let Some(meta_addr) = rd.metadata() {
         let metadata_granule = get_granule_if!(meta_addr, GranuleState::Metadata)?;
         let metadata_obj = metadata_granule.content::<IsletRealmMetadata>()?;
 
         if flags & RSI_ISLET_SLK_SVN != 0 && metadata_obj.svn() < svn {
-            warn!("The SVN parameter is invalid!");
+            warn!(
+                "The SVN parameter is higher than the current one: {}",
+                metadata_obj.svn()
+            );
             Err(Error::RmiErrorInput)?
         }
 
@@ -160,3 +172,66 @@ pub fn realm_sealing_key(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::granule::set_granule;
+    use crate::rmi::metadata::METADATA_HASH_SHA_256;
+    use crate::rmi::{GRANULE_DELEGATE, GRANULE_UNDELEGATE, SUCCESS};
+    use crate::test_utils::*;
+
+    fn sealing_key(rd: &Rd, flags: usize, svn: usize) -> Option<[u8; SEALING_KEY_SIZE]> {
+        let mut key = [0u8; SEALING_KEY_SIZE];
+        realm_sealing_key(rd, flags, svn, &mut key).ok()?;
+        Some(key)
+    }
+
+    #[test]
+    fn rsi_sealing_key_svn() {
+        let rd_addr = mock::host::realm_setup();
+        let mut rd_granule = get_granule_if!(rd_addr, GranuleState::RD).unwrap();
+        let mut rd = rd_granule.content_mut::<Rd>().unwrap();
+
+        // Without metadata, the realm is at SVN 0
+        assert!(sealing_key(&rd, RSI_ISLET_SLK_SVN, 0).is_some());
+        assert!(sealing_key(&rd, RSI_ISLET_SLK_SVN, 1).is_none());
+
+        let mdg = alloc_granule(IDX_DATA1);
+        let ret = rmi::<GRANULE_DELEGATE>(&[mdg]);
+        assert_eq!(ret[0], SUCCESS);
+        {
+            let mut metadata_granule = get_granule_if!(mdg, GranuleState::Delegated).unwrap();
+            let mut metadata = metadata_granule
+                .content_mut::<IsletRealmMetadata>()
+                .unwrap();
+            *metadata =
+                IsletRealmMetadata::new("realm", &[], METADATA_HASH_SHA_256, 2, (1, 0, 0)).unwrap();
+            set_granule(&mut metadata_granule, GranuleState::Metadata).unwrap();
+        }
+        rd.set_metadata(Some(mdg));
+
+        let flags = RSI_ISLET_SLK_REALM_ID | RSI_ISLET_SLK_SVN;
+        let current = sealing_key(&rd, flags, 2).unwrap();
+        let previous = sealing_key(&rd, flags, 1).unwrap();
+        assert_ne!(current, previous);
+        // The key of a previous SVN stays the same to unseal the old data
+        assert_eq!(sealing_key(&rd, flags, 1).unwrap(), previous);
+        assert!(sealing_key(&rd, flags, 3).is_none());
+
+        // The SVN is not a key material without the flag
+        assert_eq!(
+            sealing_key(&rd, RSI_ISLET_SLK_REALM_ID, 1),
+            sealing_key(&rd, RSI_ISLET_SLK_REALM_ID, 3)
+        );
+
+        drop(rd);
+        drop(rd_granule);
+        mock::host::realm_teardown(rd_addr);
+
+        let ret = rmi::<GRANULE_UNDELEGATE>(&[mdg]);
+        assert_eq!(ret[0], SUCCESS);
+
+        miri_teardown();
+    }
+}
@@ -0,0 +1,141 @@
+use crate::dev::pdev::{Pdev, PDEV_MAX_DIGEST_SIZE};
+use crate::dev::vdev::Vdev;
+use crate::event::RsiHandle;
+use crate::granule::{GranuleState, GRANULE_SIZE};
+use crate::listen;
+use crate::realm::mm::address::GuestPhysAddr;
+use crate::realm::mm::rtt::{self, RTT_PAGE_LEVEL};
+use crate::realm::rd::Rd;
+use crate::rec::context::{get_reg, set_reg};
+use crate::rmi;
+use crate::rmi::error::Error;
+use crate::rmi::rtt::validate_ipa;
+use crate::rsi::{ERROR_INPUT, SUCCESS, VDEV_GET_INFO, VDEV_VALIDATE_MAPPING};
+use crate::{get_granule, get_granule_if};
+
+use autopadding::*;
+use safe_abstraction::raw_ptr::assume_safe;
+
+pad_struct_and_impl_default!(
+pub struct VdevInfo {
+    0x0    flags: u64,
+    0x8    state: u64,
+    0x10   hash_algo: u8,
+    0x40   cert_digest: [u8; PDEV_MAX_DIGEST_SIZE],
+    0x80   meas_digest: [u8; PDEV_MAX_DIGEST_SIZE],
+    0xc0   report_digest: [u8; PDEV_MAX_DIGEST_SIZE],
+    0x200  => @END,
+}
+);
+
+impl VdevInfo {
+    fn init(&mut self, vdev: &Vdev) {
+        self.flags = 0;
+        self.state = vdev.state() as u64;
+        self.hash_algo = vdev.hash_algo();
+        self.cert_digest.copy_from_slice(vdev.cert_digest());
+        self.meas_digest.copy_from_slice(vdev.meas_digest());
+        self.report_digest.copy_from_slice(vdev.report_digest());
+    }
+}
+
+impl safe_abstraction::raw_ptr::RawPtr for VdevInfo {}
+
+impl safe_abstraction::raw_ptr::SafetyChecked for VdevInfo {}
+
+impl safe_abstraction::raw_ptr::SafetyAssured for VdevInfo {
+    fn is_initialized(&self) -> bool {
+        // The instance is filled in by the RMM below,
+        // so its previous content doesn't matter.
+        true
+    }
+
+    fn verify_ownership(&self) -> bool {
+        // The Realm is not running while the RMM accesses the instance,
+        // which belongs to the Realm's own protected memory.
+        true
+    }
+}
+
+fn vdev_info(rd: &Rd, vdev: &Vdev, info_ipa: usize) -> Result<(), Error> {
+    let pa = rd
+        .s2_table()
+        .ipa_to_pa(GuestPhysAddr::from(info_ipa), RTT_PAGE_LEVEL)
+        .ok_or(Error::RmiErrorInput)?;
+    let mut info = assume_safe::<VdevInfo>(pa.into())?;
+    info.init(vdev);
+    Ok(())
+}
+
+pub fn set_event_handler(rsi: &mut RsiHandle) {
+    // VDEV_GET_INFO
+    // x1: vdev_id of the device
+    // x2: IPA of the VdevInfo object
+    listen!(rsi, VDEV_GET_INFO, |_arg, ret, _rmm, rec, _| {
+        let vdev_id = get_reg(rec, 1)? as u64;
+        let info_ipa = get_reg(rec, 2)?;
+
+        let rd_granule = get_granule_if!(rec.owner()?, GranuleState::RD)?;
+        let rd = rd_granule.content::<Rd>()?;
+
+        let vdev = rd.find_vdev(vdev_id);
+        let (Some(vdev), true) = (vdev, validate_ipa(&rd.rtts(), info_ipa).is_ok()) else {
+            set_reg(rec, 0, ERROR_INPUT)?;
+            ret[0] = rmi::SUCCESS_REC_ENTER;
+            return Ok(());
+        };
+
+        let vdev_granule = get_granule_if!(vdev, GranuleState::Vdev)?;
+        let vdev = vdev_granule.content::<Vdev>()?;
+        if vdev_info(&rd, &vdev, info_ipa).is_err() {
+            set_reg(rec, 0, ERROR_INPUT)?;
+        } else {
+            set_reg(rec, 0, SUCCESS)?;
+        }
+        ret[0] = rmi::SUCCESS_REC_ENTER;
+        Ok(())
+    });
+
+    // VDEV_VALIDATE_MAPPING
+    // x1: vdev_id of the device
+    // x2: base of the target IPA region
+    // x3: top of the target IPA region
+    // x4: base of the PA region the IPA region is expected to be mapped to
+    listen!(rsi, VDEV_VALIDATE_MAPPING, |_arg, ret, _rmm, rec, _| {
+        let vdev_id = get_reg(rec, 1)? as u64;
+        let base = get_reg(rec, 2)?;
+        let top = get_reg(rec, 3)?;
+        let pa_base = get_reg(rec, 4)?;
+
+        let rd_granule = get_granule_if!(rec.owner()?, GranuleState::RD)?;
+        let rd = rd_granule.content::<Rd>()?;
+
+        let valid_range = base < top
+            && top % GRANULE_SIZE == 0
+            && pa_base % GRANULE_SIZE == 0
+            && validate_ipa(&rd.rtts(), base).is_ok()
+            && validate_ipa(&rd.rtts(), top - GRANULE_SIZE).is_ok();
+        let vdev = rd.find_vdev(vdev_id);
+        let (Some(vdev), true) = (vdev, valid_range) else {
+            set_reg(rec, 0, ERROR_INPUT)?;
+            ret[0] = rmi::SUCCESS_REC_ENTER;
+            return Ok(());
+        };
+
+        let vdev_granule = get_granule_if!(vdev, GranuleState::Vdev)?;
+        let vdev = vdev_granule.content::<Vdev>()?;
+        let pdev_granule = get_granule_if!(vdev.pdev(), GranuleState::Pdev)?;
+        let pdev = pdev_granule.content::<Pdev>()?;
+        if !vdev.is_mappable() || !pdev.ncoh_contains(pa_base, pa_base + (top - base)) {
+            set_reg(rec, 0, ERROR_INPUT)?;
+            ret[0] = rmi::SUCCESS_REC_ENTER;
+            return Ok(());
+        }
+
+        let next = rtt::validate_dev_mapping(&rd.rtts(), base, top, pa_base)?;
+        set_reg(rec, 0, SUCCESS)?;
+        set_reg(rec, 1, next)?;
+        ret[0] = rmi::SUCCESS_REC_ENTER;
+        Ok(())
+    });
+}
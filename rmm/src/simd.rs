@@ -19,6 +19,10 @@ const QUARD_WORD: u64 = 128;
 // P regs = 2 bytes * 16 regs * 4 vq
 // FFR reg = 2 bytes * 4 vq
 pub const MAX_VQ: u64 = 4;
+// Note: Streaming vectors are stored in the same Z/P register area,
+// and ZA is an array of SVL x SVL bits, which fills up an Aux granule
+// (64 bytes * 64 slices) with svq 4.
+pub const MAX_SVQ: u64 = 4;
 
 #[derive(Default, Debug)]
 // SIMD configuration structure
@@ -31,6 +35,15 @@ pub struct SimdConfig {
 
     // SME enabled flag
     pub sme_en: bool,
+
+    // SME streaming vector length represented in quads
+    pub sme_svq: u64,
+
+    // SME2 enabled flag (ZT0 register)
+    pub sme2_en: bool,
+
+    // Full A64 instruction set in streaming mode (FEAT_SME_FA64)
+    pub sme_fa64: bool,
 }
 
 lazy_static! {
@@ -40,6 +53,9 @@ lazy_static! {
         let mut sve_en: bool = false;
         let mut sve_vq: u64 = 0;
         let mut sme_en: bool = false;
+        let mut sme_svq: u64 = 0;
+        let mut sme2_en: bool = false;
+        let mut sme_fa64: bool = false;
 
         trace!("Reading simd features");
         #[cfg(not(any(test, miri, fuzzing)))]
@@ -71,34 +87,73 @@ lazy_static! {
             trace!("sme_svq_arch_max={:?}", sme_svq_arch_max);
 
             assert!(sme_svq_arch_max <= SVE_VQ_ARCH_MAX);
+            // Get svl in bytes
+            let svl_b = unsafe { get_streaming_vector_length_bytes() };
+            sme_svq = ((svl_b << 3) / QUARD_WORD) - 1;
+            if sme_svq >= MAX_SVQ {
+                sme_svq = MAX_SVQ - 1;
+            }
+            // ID_AA64PFR1_EL1.SME is 0b0010 for FEAT_SME2 (ZT0)
+            sme2_en = ID_AA64PFR1_SME_EL1.read(ID_AA64PFR1_SME_EL1::SME) >= 0b0010;
+            sme_fa64 = armv9a::regs::ID_AA64SMFR0_EL1.is_set(armv9a::regs::ID_AA64SMFR0_EL1::FA64);
             sme_en = true;
+            trace!("sme_svq={:?} sme2={:?} fa64={:?}", sme_svq, sme2_en, sme_fa64);
         }
 
         SimdConfig {
             sve_en,
             sve_vq,
             sme_en,
+            sme_svq,
+            sme2_en,
+            sme_fa64,
         }
     };
 }
 
 /// Get the SVE vector length in bytes using the RDVL instruction
-#[target_feature(enable = "sve")]
+#[cfg_attr(target_arch = "aarch64", target_feature(enable = "sve"))]
 unsafe fn get_vector_length_bytes() -> u64 {
-    let vl_b: u64;
+    match () {
+        #[cfg(target_arch = "aarch64")]
+        () => {
+            let vl_b: u64;
+            unsafe {
+                asm!("rdvl {}, #1", out(reg) vl_b);
+            }
+            vl_b
+        }
+
+        #[cfg(not(target_arch = "aarch64"))]
+        () => unimplemented!(),
+    }
+}
+
+/// Get the streaming SVE vector length in bytes using the RDSVL instruction
+#[cfg(not(any(test, miri, fuzzing)))]
+unsafe fn get_streaming_vector_length_bytes() -> u64 {
+    let svl_b: u64;
     unsafe {
-        asm!("rdvl {}, #1", out(reg) vl_b);
+        asm!(".arch_extension sme", "rdsvl {}, #1", out(reg) svl_b);
     }
-    vl_b
+    svl_b
 }
 
-pub fn validate(en: bool, sve_vl: u64) -> bool {
+pub fn validate(en: bool, sve_vl: u64, sme_en: bool, sme_vl: u64) -> bool {
     if en && !SIMD_CONFIG.sve_en {
         return false;
     }
     if sve_vl > SIMD_CONFIG.sve_vq {
         return false;
     }
+    // Streaming mode shares the SVE context management,
+    // so SME is only provided along with SVE.
+    if sme_en && (!SIMD_CONFIG.sme_en || !en) {
+        return false;
+    }
+    if sme_vl > SIMD_CONFIG.sme_svq {
+        return false;
+    }
     true
 }
 
@@ -113,3 +168,15 @@ pub fn max_sve_vl() -> u64 {
 pub fn sme_en() -> bool {
     SIMD_CONFIG.sme_en
 }
+
+pub fn max_sme_svl() -> u64 {
+    SIMD_CONFIG.sme_svq
+}
+
+pub fn sme2_en() -> bool {
+    SIMD_CONFIG.sme2_en
+}
+
+pub fn sme_fa64() -> bool {
+    SIMD_CONFIG.sme_fa64
+}
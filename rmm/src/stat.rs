@@ -1,26 +1,47 @@
+#[cfg(not(any(test, fuzzing)))]
 use crate::allocator;
 use crate::rmi;
 use crate::rsi;
 use spin::mutex::Mutex;
 
-use crate::alloc::string::ToString;
-use alloc::string::String;
+use alloc::vec::Vec;
+use ciborium::{ser, Value};
 
 //NOTE: RMI, RSI_CMD_MAX are should be updated whenever there is a new command
 //      which is bigger than the current max value of the commands.
 //      But if RMI, RSI commands are handled by 'Enum', then it can be fixed
 //      by using the max enum value like MAX_KIND
 const RMI_CMD_MIN: usize = rmi::VERSION;
-const RMI_CMD_MAX: usize = rmi::RTT_SET_RIPAS;
+const RMI_CMD_MAX: usize = rmi::VDEV_STOP;
 const RMI_CMD_CNT: usize = RMI_CMD_MAX - RMI_CMD_MIN + 1;
 
 const RSI_CMD_MIN: usize = rsi::ABI_VERSION;
-const RSI_CMD_MAX: usize = rsi::HOST_CALL;
+const RSI_CMD_MAX: usize = rsi::PLANE_REG_WRITE;
 const RSI_CMD_CNT: usize = RSI_CMD_MAX - RSI_CMD_MIN + 1;
 
 const MAX_CMD_CNT: usize = max(RMI_CMD_CNT, RSI_CMD_CNT);
 const MAX_KIND: usize = Kind::Undefined as usize;
 
+// The number of realms whose statistics are kept at the same time
+const MAX_REALM_STATS: usize = 16;
+
+// CBOR labels of the statistics read by ISLET_STATS_READ
+pub const STATS_RMI_LABEL: u64 = 1;
+pub const STATS_RSI_LABEL: u64 = 2;
+pub const STATS_HEAP_USED_LABEL: u64 = 3;
+pub const STATS_REALMS_LABEL: u64 = 4;
+
+pub const CMD_FID_LABEL: u64 = 1;
+pub const CMD_CALL_CNT_LABEL: u64 = 2;
+pub const CMD_MIN_CYCLES_LABEL: u64 = 3;
+pub const CMD_MAX_CYCLES_LABEL: u64 = 4;
+pub const CMD_AVG_CYCLES_LABEL: u64 = 5;
+pub const CMD_AVG_MEM_USED_LABEL: u64 = 6;
+
+pub const REALM_VMID_LABEL: u64 = 1;
+pub const REALM_REC_ENTER_CNT_LABEL: u64 = 2;
+pub const REALM_RSI_LABEL: u64 = 3;
+
 const fn max(a: usize, b: usize) -> usize {
     if a >= b {
         a
@@ -31,6 +52,31 @@ const fn max(a: usize, b: usize) -> usize {
 
 lazy_static! {
     pub static ref STATS: Mutex<Stats> = Mutex::new(Stats::new());
+    // Kept apart from STATS, which is held while a command is measured
+    pub static ref REALM_STATS: Mutex<RealmStats> = Mutex::new(RealmStats::new());
+}
+
+/// Returns the system counter, which the cycles of the statistics count.
+#[cfg(not(any(test, miri, fuzzing)))]
+pub fn cycles() -> u64 {
+    use aarch64_cpu::registers::{Readable, CNTPCT_EL0};
+    CNTPCT_EL0.get()
+}
+
+#[cfg(any(test, miri, fuzzing))]
+pub fn cycles() -> u64 {
+    0
+}
+
+fn heap_used() -> usize {
+    #[cfg(not(any(test, fuzzing)))]
+    return allocator::get_used_size();
+    #[cfg(any(test, fuzzing))]
+    0
+}
+
+fn label(label: u64) -> Value {
+    Value::Integer(label.into())
 }
 
 #[inline(always)]
@@ -54,7 +100,7 @@ impl Stats {
     fn new() -> Self {
         let mut stats = Stats {
             collected_stat_cnt: 0,
-            list: [Stat::default(); MAX_KIND],
+            list: [Stat::new(Kind::Undefined); MAX_KIND],
         };
 
         for i in 0..MAX_KIND {
@@ -63,6 +109,10 @@ impl Stats {
         stats
     }
 
+    pub fn reset(&mut self) {
+        *self = Stats::new();
+    }
+
     fn get_stat(&mut self, cmd: usize) -> Result<&mut Stat, Error> {
         let kind = Kind::get_kind(cmd)?;
         Ok(&mut self.list[kind as usize])
@@ -114,16 +164,37 @@ impl Stats {
             }
         }
 
-        info!(
-            "TOTAL MemUsed in RMM HEAP: {: >12} byte",
-            allocator::get_used_size()
-        );
+        info!("TOTAL MemUsed in RMM HEAP: {: >12} byte", heap_used());
         info!("=============================================== STATS::PRINT() END ===============================================");
     }
+
+    /// Serializes the statistics of the commands and the per-realm
+    /// breakdowns as a CBOR map.
+    pub fn to_cbor(&self, realms: &RealmStats) -> Vec<u8> {
+        let map = Value::Map(alloc::vec![
+            (
+                label(STATS_RMI_LABEL),
+                self.list[Kind::RMI as usize].cbor_value()
+            ),
+            (
+                label(STATS_RSI_LABEL),
+                self.list[Kind::RSI as usize].cbor_value()
+            ),
+            (
+                label(STATS_HEAP_USED_LABEL),
+                Value::Integer((heap_used() as u64).into())
+            ),
+            (label(STATS_REALMS_LABEL), realms.cbor_value()),
+        ]);
+
+        let mut buf = Vec::new();
+        ser::into_writer(&map, &mut buf).expect("Failed to serialize stats");
+        buf
+    }
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug)]
 struct Stat {
     kind: Kind,
     overflowed: bool,
@@ -132,20 +203,79 @@ struct Stat {
 
     mem_used_before: Option<i64>,
     total_mem_used: [i64; MAX_CMD_CNT],
+
+    cycles_before: Option<u64>,
+    min_cycles: [u64; MAX_CMD_CNT],
+    max_cycles: [u64; MAX_CMD_CNT],
+    total_cycles: [u64; MAX_CMD_CNT],
 }
 
 impl Stat {
     fn new(kind: Kind) -> Self {
         Stat {
             kind,
-            ..Default::default()
+            overflowed: false,
+            cur_cmd: None,
+            call_cnt: [0; MAX_CMD_CNT],
+            mem_used_before: None,
+            total_mem_used: [0; MAX_CMD_CNT],
+            cycles_before: None,
+            min_cycles: [u64::MAX; MAX_CMD_CNT],
+            max_cycles: [0; MAX_CMD_CNT],
+            total_cycles: [0; MAX_CMD_CNT],
+        }
+    }
+
+    fn cmd_min(&self) -> Option<usize> {
+        match self.kind {
+            Kind::RMI => Some(RMI_CMD_MIN),
+            Kind::RSI => Some(RSI_CMD_MIN),
+            _ => None,
         }
     }
-    fn cmd_to_str(&self, cmd: usize) -> String {
+
+    // An array of the commands called at least once
+    fn cbor_value(&self) -> Value {
+        let Some(cmd_min) = self.cmd_min() else {
+            return Value::Array(Vec::new());
+        };
+
+        let entries = (0..MAX_CMD_CNT)
+            .filter(|idx| self.call_cnt[*idx] != 0)
+            .map(|idx| {
+                let call_cnt = self.call_cnt[idx];
+                Value::Map(alloc::vec![
+                    (
+                        label(CMD_FID_LABEL),
+                        Value::Integer(((cmd_min + idx) as u64).into())
+                    ),
+                    (label(CMD_CALL_CNT_LABEL), Value::Integer(call_cnt.into())),
+                    (
+                        label(CMD_MIN_CYCLES_LABEL),
+                        Value::Integer(self.min_cycles[idx].into())
+                    ),
+                    (
+                        label(CMD_MAX_CYCLES_LABEL),
+                        Value::Integer(self.max_cycles[idx].into())
+                    ),
+                    (
+                        label(CMD_AVG_CYCLES_LABEL),
+                        Value::Integer((self.total_cycles[idx] / call_cnt).into())
+                    ),
+                    (
+                        label(CMD_AVG_MEM_USED_LABEL),
+                        Value::Integer((self.total_mem_used[idx] / call_cnt as i64).into())
+                    ),
+                ])
+            })
+            .collect();
+        Value::Array(entries)
+    }
+    fn cmd_to_str(&self, cmd: usize) -> &'static str {
         match self.kind {
             Kind::RMI => rmi::to_str(cmd),
             Kind::RSI => rsi::to_str(cmd),
-            _ => "Undefined".to_string(),
+            _ => "Undefined",
         }
     }
 
@@ -183,10 +313,8 @@ impl Stat {
     }
 
     fn print_all(&self) -> Result<(), Error> {
-        let cmd_min = match self.kind {
-            Kind::RMI => RMI_CMD_MIN,
-            Kind::RSI => RSI_CMD_MIN,
-            _ => return Ok(()),
+        let Some(cmd_min) = self.cmd_min() else {
+            return Ok(());
         };
 
         for i in 0..MAX_CMD_CNT {
@@ -199,7 +327,8 @@ impl Stat {
         if self.mem_used_before.is_some() || self.cur_cmd.is_some() {
             return Err(Error::MismatchedSequence);
         }
-        self.mem_used_before = Some(allocator::get_used_size() as i64);
+        self.mem_used_before = Some(heap_used() as i64);
+        self.cycles_before = Some(cycles());
         self.cur_cmd = Some(cmd);
 
         Ok(())
@@ -228,7 +357,7 @@ impl Stat {
         }
 
         let idx = self.get_idx(cmd)?;
-        let mem_used_after = allocator::get_used_size() as i64;
+        let mem_used_after = heap_used() as i64;
         let mem_used_before = self.mem_used_before.unwrap();
         if mem_used_after.checked_sub(mem_used_before).is_none() {
             error!(
@@ -257,18 +386,135 @@ impl Stat {
         }
         self.call_cnt[idx] += 1;
 
+        let cur_cycles = cycles().saturating_sub(self.cycles_before.unwrap_or(0));
+        self.min_cycles[idx] = self.min_cycles[idx].min(cur_cycles);
+        self.max_cycles[idx] = self.max_cycles[idx].max(cur_cycles);
+        self.total_cycles[idx] = self.total_cycles[idx].saturating_add(cur_cycles);
+
         if let Err(e) = self.print(cmd) {
             error!("error to print Stat {:?}, {}", e, self.cmd_to_str(cmd));
         }
 
         // clean up after updating
         self.mem_used_before = None;
+        self.cycles_before = None;
         self.cur_cmd = None;
 
         Ok(())
     }
 }
 
+#[derive(Copy, Clone, Debug, Default)]
+struct RealmStat {
+    vmid: usize,
+    rec_enter_cnt: u64,
+    rsi_call_cnt: [u64; RSI_CMD_CNT],
+    rsi_cycles: [u64; RSI_CMD_CNT],
+}
+
+impl RealmStat {
+    fn cbor_value(&self) -> Value {
+        let rsi = (0..RSI_CMD_CNT)
+            .filter(|idx| self.rsi_call_cnt[*idx] != 0)
+            .map(|idx| {
+                let call_cnt = self.rsi_call_cnt[idx];
+                Value::Map(alloc::vec![
+                    (
+                        label(CMD_FID_LABEL),
+                        Value::Integer(((RSI_CMD_MIN + idx) as u64).into())
+                    ),
+                    (label(CMD_CALL_CNT_LABEL), Value::Integer(call_cnt.into())),
+                    (
+                        label(CMD_AVG_CYCLES_LABEL),
+                        Value::Integer((self.rsi_cycles[idx] / call_cnt).into())
+                    ),
+                ])
+            })
+            .collect();
+
+        Value::Map(alloc::vec![
+            (
+                label(REALM_VMID_LABEL),
+                Value::Integer((self.vmid as u64).into())
+            ),
+            (
+                label(REALM_REC_ENTER_CNT_LABEL),
+                Value::Integer(self.rec_enter_cnt.into())
+            ),
+            (label(REALM_RSI_LABEL), Value::Array(rsi)),
+        ])
+    }
+}
+
+/// Statistics of each realm, keyed by its VMID
+pub struct RealmStats {
+    list: [Option<RealmStat>; MAX_REALM_STATS],
+}
+
+impl RealmStats {
+    fn new() -> Self {
+        RealmStats {
+            list: [None; MAX_REALM_STATS],
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = RealmStats::new();
+    }
+
+    fn get_or_insert(&mut self, vmid: usize) -> Option<&mut RealmStat> {
+        let idx = match self
+            .list
+            .iter()
+            .position(|s| s.is_some_and(|s| s.vmid == vmid))
+        {
+            Some(idx) => idx,
+            None => {
+                let Some(idx) = self.list.iter().position(|s| s.is_none()) else {
+                    trace!("No room for the stats of realm {}", vmid);
+                    return None;
+                };
+                self.list[idx] = Some(RealmStat {
+                    vmid,
+                    ..Default::default()
+                });
+                idx
+            }
+        };
+        self.list[idx].as_mut()
+    }
+
+    pub fn rec_enter(&mut self, vmid: usize) {
+        if let Some(stat) = self.get_or_insert(vmid) {
+            stat.rec_enter_cnt = stat.rec_enter_cnt.saturating_add(1);
+        }
+    }
+
+    pub fn rsi_called(&mut self, vmid: usize, cmd: usize, cycles: u64) {
+        if !is_rsi_cmd(cmd) {
+            return;
+        }
+        if let Some(stat) = self.get_or_insert(vmid) {
+            let idx = cmd - RSI_CMD_MIN;
+            stat.rsi_call_cnt[idx] = stat.rsi_call_cnt[idx].saturating_add(1);
+            stat.rsi_cycles[idx] = stat.rsi_cycles[idx].saturating_add(cycles);
+        }
+    }
+
+    // VMIDs are reused by the realms created later
+    pub fn remove(&mut self, vmid: usize) {
+        for stat in self.list.iter_mut() {
+            if stat.is_some_and(|s| s.vmid == vmid) {
+                *stat = None;
+            }
+        }
+    }
+
+    fn cbor_value(&self) -> Value {
+        Value::Array(self.list.iter().flatten().map(|s| s.cbor_value()).collect())
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum Kind {
     RMI = 0,
@@ -1,4 +1,3 @@
-use crate::event::Context;
 use crate::event::Mainloop;
 use crate::granule::GRANULE_SIZE;
 use crate::monitor::Monitor;
@@ -11,17 +10,192 @@ pub use mock::host::{alloc_granule, granule_addr};
 use alloc::vec::Vec;
 
 pub fn rmi<const COMMAND: usize>(arg: &[usize]) -> Vec<usize> {
+    rmi_dyn(COMMAND, arg)
+}
+
+fn rmi_dyn(cmd: usize, arg: &[usize]) -> Vec<usize> {
     let monitor = Monitor::new();
 
-    let mut ctx = Context::new(COMMAND);
-    ctx.init_arg(&arg);
-    ctx.init_ret(&[0; 8]);
+    let mut ret = [0; 8];
+    let handler = monitor.rmi.on_event.get(cmd).unwrap();
+    if let Err(code) = handler(arg, &mut ret, &monitor) {
+        ret[0] = code.into();
+    }
+    ret.to_vec()
+}
+
+/// Handles an RMI command decided at runtime, the way the main loop does.
+/// Arguments beyond the ones of the command are dropped, except for REC_ENTER
+/// where they describe the realm exit to emulate (see `mock::realm::emulate_realm`).
+/// Returns X0-X4 of the result.
+#[cfg(fuzzing)]
+pub fn rmi_call(cmd: usize, arg: &[usize]) -> Vec<usize> {
+    let mut monitor = Monitor::new();
+
+    let mut regs = [0; 7];
+    let len = arg.len().min(regs.len());
+    regs[..len].copy_from_slice(&arg[..len]);
+
+    let mut ctx = crate::rmi::constraint::validate(cmd, &regs);
+    if ctx.cmd == REC_ENTER {
+        ctx.init_arg(arg);
+    }
+    monitor.handle_rmi(&mut ctx);
+    let mut ret = ctx.arg_slice().to_vec();
+    ret.resize(5, 0);
+    ret
+}
+
+/// Reads the RMM's view of granules, for fuzz targets checking it against
+/// the commands they issued. Every function returns None for an invalid address.
+#[cfg(fuzzing)]
+pub mod inspect {
+    use crate::get_granule;
+    use crate::granule::GranuleState;
+    use crate::rec::Rec;
+
+    pub fn granule_state(addr: usize) -> Option<u8> {
+        Some(get_granule!(addr).ok()?.state())
+    }
+
+    pub fn num_children(addr: usize) -> Option<usize> {
+        Some(get_granule!(addr).ok()?.num_children())
+    }
+
+    /// Returns the RD which the REC at `rec` belongs to.
+    pub fn rec_owner(rec: usize) -> Option<usize> {
+        let granule = get_granule!(rec).ok()?;
+        if granule.state() != GranuleState::Rec {
+            return None;
+        }
+        let rec = granule.content::<Rec<'_>>().ok()?;
+        rec.owner().ok()
+    }
+}
+
+/// Replays the RMI commands of a log read with ISLET_REPLAY_READ (see `event::replay`).
+/// Granules of the captured NS DRAM are relocated to the mock ones in the order
+/// they show up, along with the granule addresses in RmiRealmParams and RmiRecParams.
+/// Each command should return the captured status, and each granule should end up
+/// in the captured state.
+/// The RSI commands made during a REC_ENTER are emulated one REC_ENTER each,
+/// and each should leave the captured X0 in the REC.
+pub fn replay(log: &[u8]) {
+    use crate::event::replay::{decode, granule_args, Record};
+    use crate::get_granule;
+    use crate::rec::Rec;
+
+    let records = decode(log).expect("Malformed replay log");
+    let mut relocator = Relocator::default();
+    let mut rsi_calls = Vec::new();
+    for record in records {
+        match record {
+            Record::Dram { base, size } => relocator.dram.push(base..base + size),
+            Record::Rsi { cmd, args, ret } => rsi_calls.push((cmd, args, ret)),
+            Record::Rmi {
+                cmd,
+                mut args,
+                ret,
+                buffer,
+            } => {
+                for idx in granule_args(cmd) {
+                    if let Some(arg) = args.get_mut(*idx) {
+                        *arg = relocator.relocate(*arg);
+                    }
+                }
+                if let Some(buffer) = buffer {
+                    relocator.write_buffer(cmd, buffer.addr, &buffer.data);
+                }
+
+                let mut replayed = Vec::new();
+                for (rsi, rsi_args, rsi_ret) in rsi_calls.drain(..) {
+                    assert_eq!(cmd, REC_ENTER, "RSI out of REC_ENTER");
+                    replayed = rmi_dyn(cmd, &[&args[..2], &[rsi], &rsi_args[..]].concat());
+                    let rec = unsafe { &*(args[0] as *const Rec<'_>) };
+                    assert_eq!(
+                        rec.context.gp_regs[0] as usize,
+                        rsi_ret[0],
+                        "{} {:x?}",
+                        crate::rsi::to_str(rsi),
+                        rsi_args
+                    );
+                }
+                if replayed.is_empty() {
+                    replayed = rmi_dyn(cmd, &args);
+                } else if replayed[0] == SUCCESS_REC_ENTER {
+                    // The realm was resumed after the last RSI command,
+                    // and exited to the host for a reason which isn't recorded.
+                    replayed[0] = SUCCESS;
+                }
+                assert_eq!(
+                    replayed[0],
+                    ret[0],
+                    "{} {:x?}",
+                    crate::rmi::to_str(cmd),
+                    args
+                );
+            }
+            Record::State { addr, state } => {
+                let addr = relocator.relocate(addr);
+                let granule = get_granule!(addr).unwrap();
+                assert_eq!(granule.state() as u64, state, "granule {:x}", addr);
+            }
+        }
+    }
+}
+
+// Maps the granules of a captured machine to the mock ones
+#[derive(Default)]
+struct Relocator {
+    dram: Vec<core::ops::Range<usize>>,
+    granules: alloc::collections::BTreeMap<usize, usize>,
+}
+
+impl Relocator {
+    fn in_dram(&self, addr: usize) -> bool {
+        self.dram.iter().any(|region| region.contains(&addr))
+    }
+
+    fn relocate(&mut self, addr: usize) -> usize {
+        if !self.in_dram(addr) {
+            return addr;
+        }
+        let base = addr & !(GRANULE_SIZE - 1);
+        let idx = self.granules.len();
+        let mock = *self.granules.entry(base).or_insert_with(|| {
+            // The last granule of the region is not aligned
+            assert!(idx + 1 < crate::granule::GRANULE_STATUS_TABLE_SIZE);
+            alloc_granule(idx)
+        });
+        mock + (addr - base)
+    }
 
-    let handler = monitor.rmi.on_event.get(&COMMAND).unwrap();
-    if let Err(code) = handler(&ctx.arg, &mut ctx.ret, &monitor) {
-        ctx.ret[0] = code.into();
+    fn write_buffer(&mut self, cmd: usize, addr: usize, data: &[u8]) {
+        if !self.in_dram(addr) {
+            return;
+        }
+        let addr = self.relocate(addr);
+        unsafe {
+            core::ptr::copy_nonoverlapping(data.as_ptr(), addr as *mut u8, GRANULE_SIZE);
+        }
+        match cmd {
+            REALM_CREATE => {
+                let params = unsafe { &mut *(addr as *mut RealmParams) };
+                params.rtt_base = self.relocate(params.rtt_base as usize) as u64;
+                for rtt in params.aux_rtt_base.iter_mut() {
+                    *rtt = self.relocate(*rtt as usize) as u64;
+                }
+            }
+            REC_CREATE => {
+                let params = unsafe { &mut *(addr as *mut RecParams) };
+                let num_aux = (params.num_aux as usize).min(params.aux.len());
+                for aux in params.aux[..num_aux].iter_mut() {
+                    *aux = self.relocate(*aux as usize) as u64;
+                }
+            }
+            _ => {}
+        }
     }
-    ctx.ret.to_vec()
 }
 
 pub fn extract_bits(value: usize, start: u32, end: u32) -> usize {
@@ -35,6 +209,13 @@ pub fn extract_bits(value: usize, start: u32, end: u32) -> usize {
 }
 
 pub fn realm_create() -> usize {
+    realm_create_with(|_| {}).unwrap()
+}
+
+/// Creates a realm like `realm_create`, with the params `set_params` changes.
+/// Returns RD, or the status of REALM_CREATE after undelegating the granules.
+/// The changes are reverted afterwards, for the other tests sharing the params granule.
+pub fn realm_create_with<F: FnOnce(&mut RealmParams)>(set_params: F) -> Result<usize, usize> {
     for mocking_addr in &[alloc_granule(IDX_RD), alloc_granule(IDX_RTT_LEVEL0)] {
         let ret = rmi::<GRANULE_DELEGATE>(&[*mocking_addr]);
         assert_eq!(ret[0], SUCCESS);
@@ -46,18 +227,25 @@ pub fn realm_create() -> usize {
         alloc_granule(IDX_REALM_PARAMS),
     );
 
-    unsafe {
-        let params = &mut *(params_ptr as *mut RealmParams);
-        params.s2sz = 40;
-        params.rtt_num_start = 1;
-        params.rtt_level_start = 0;
-        params.rtt_base = rtt as u64;
-    };
+    let params = unsafe { &mut *(params_ptr as *mut RealmParams) };
+    params.s2sz = 40;
+    params.rtt_num_start = 1;
+    params.rtt_level_start = 0;
+    params.rtt_base = rtt as u64;
 
+    let saved = unsafe { core::ptr::read(params) };
+    set_params(params);
     let ret = rmi::<REALM_CREATE>(&[rd, params_ptr]);
-    assert_eq!(ret[0], SUCCESS);
+    unsafe { core::ptr::write(params, saved) };
 
-    rd
+    if ret[0] != SUCCESS {
+        for mocking_addr in &[rd, rtt] {
+            let ret = rmi::<GRANULE_UNDELEGATE>(&[*mocking_addr]);
+            assert_eq!(ret[0], SUCCESS);
+        }
+        return Err(ret[0]);
+    }
+    Ok(rd)
 }
 
 pub fn realm_destroy(rd: usize) {
@@ -178,6 +366,21 @@ pub const IDX_DATA3: usize = 48;
 pub const IDX_DATA4: usize = 49;
 pub const IDX_SRC1: usize = 50;
 pub const IDX_SRC2: usize = 51;
+pub const IDX_RTT_AUX_LEVEL0: usize = 52;
+pub const IDX_PDEV: usize = 53;
+pub const IDX_PDEV_AUX: usize = 54;
+pub const IDX_VDEV: usize = 55;
+pub const IDX_DEV_PARAMS: usize = 56;
+pub const IDX_DEV_COMM_DATA: usize = 57;
+pub const IDX_DEV_REQ: usize = 58;
+pub const IDX_DEV_RESP: usize = 59;
+pub const IDX_RTT_AUX_LEVEL1: usize = 60;
+pub const IDX_RTT_AUX_LEVEL2: usize = 61;
+pub const IDX_RTT_AUX_LEVEL3: usize = 62;
+
+// Device memory (MMIO) of the software PDEV, which is out of the granule region
+pub const DEV_MMIO_BASE: usize = 0x1c0b_0000;
+pub const DEV_MMIO_SIZE: usize = 0x10000;
 
 #[cfg(fuzzing)]
 pub const IDX_L2_ALIGNED_DATA: usize = 0;
@@ -206,7 +409,7 @@ pub fn miri_teardown() {
     use core::sync::atomic::AtomicUsize;
     use core::sync::atomic::Ordering;
 
-    const TEST_TOTAL: usize = 11;
+    const TEST_TOTAL: usize = 20;
     static TEST_COUNT: AtomicUsize = AtomicUsize::new(0);
     TEST_COUNT.fetch_add(1, Ordering::SeqCst);
 
@@ -219,7 +422,10 @@ pub mod mock {
     pub mod host {
         use super::super::*;
 
+        use crate::dev::soft::SoftPdev;
+        use crate::dev::{COMM_EXIT_FLAG_SEND, COMM_STATUS_NONE, COMM_STATUS_RESPONSE};
         use crate::granule::{GRANULE_REGION, GRANULE_SIZE};
+        use crate::rmi::dev::params::DevCommData;
         use crate::rmi::{RTT_CREATE, RTT_DESTROY, RTT_READ_ENTRY};
 
         pub use alloc_granule as granule_addr;
@@ -309,6 +515,38 @@ pub mod mock {
             }
         }
 
+        /// Relays the messages of PDEV/VDEV_COMMUNICATE between the RMM and
+        /// the software PDEV until the RMM has nothing more to send.
+        pub fn dev_communicate<const COMMAND: usize>(obj: usize, dev: &mut SoftPdev) {
+            let (data_ptr, req, resp) = (
+                alloc_granule(IDX_DEV_COMM_DATA),
+                alloc_granule(IDX_DEV_REQ),
+                alloc_granule(IDX_DEV_RESP),
+            );
+            let data = unsafe { &mut *(data_ptr as *mut DevCommData) };
+            *data = DevCommData::default();
+            data.enter.status = COMM_STATUS_NONE;
+            data.enter.req_addr = req as u64;
+            data.enter.resp_addr = resp as u64;
+
+            loop {
+                let ret = rmi::<COMMAND>(&[obj, data_ptr]);
+                assert_eq!(ret[0], SUCCESS);
+                if data.exit.flags & COMM_EXIT_FLAG_SEND == 0 {
+                    break;
+                }
+
+                let (req_buf, resp_buf) = unsafe {
+                    (
+                        core::slice::from_raw_parts(req as *const u8, data.exit.req_len as usize),
+                        core::slice::from_raw_parts_mut(resp as *mut u8, GRANULE_SIZE),
+                    )
+                };
+                data.enter.resp_len = dev.respond(req_buf, resp_buf) as u64;
+                data.enter.status = COMM_STATUS_RESPONSE;
+            }
+        }
+
         pub fn realm_setup() -> usize {
             let rd = realm_create();
             rec_create(rd, IDX_REC1, IDX_REC1_PARAMS, IDX_REC1_AUX);
@@ -377,6 +615,10 @@ pub mod mock {
 
             if cmd == REC_ENTER_EXIT_CMD {
                 let realm_exit_res: [usize; 4] = [args[0], args[1], args[2], args[3]];
+                // Saved along with the exit, as the realm exit path does
+                rec.context.sys_regs.esr_el2 = args[1] as u64;
+                rec.context.sys_regs.hpfar = args[2] as u64;
+                rec.context.sys_regs.far_el2 = args[3] as u64;
                 handle_realm_exit(realm_exit_res, rmm, rec, run)
             } else {
                 set_reg(rec, 0, cmd)?;
@@ -0,0 +1,28 @@
+//! Pretty-prints the RMI/RSI trace which the host read with ISLET_TRACE_READ.
+//!
+//! The input is the raw buffer filled by the RMM, as many of them as CPUs
+//! concatenated in any order. Records are sorted by their timestamps.
+//!
+//! Like the fuzz targets, it links the rmm as a hosted library:
+//!   RUSTFLAGS="--cfg fuzzing" cargo run -p rmm_trace -- trace.bin
+
+use islet_rmm::trace::{decode, Record};
+
+use std::io::Read;
+
+fn main() -> std::io::Result<()> {
+    let mut buf = Vec::new();
+    match std::env::args().nth(1) {
+        Some(path) => buf = std::fs::read(path)?,
+        None => {
+            std::io::stdin().read_to_end(&mut buf)?;
+        }
+    }
+
+    let mut records: Vec<Record> = decode(&buf).collect();
+    records.sort_by_key(|r| r.timestamp);
+    for record in records {
+        println!("{}", record);
+    }
+    Ok(())
+}
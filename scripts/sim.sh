@@ -0,0 +1,18 @@
+#!/bin/bash
+
+ROOT=$(git rev-parse --show-toplevel)
+
+mkdir -p $ROOT/out
+
+cd $ROOT/rmm/sim
+
+if [ $? -ne 0 ]; then
+	exit 1
+fi
+
+export RUSTFLAGS="--cfg fuzzing -A warnings"
+
+# The workspace defaults to the RMM's bare-metal target
+HOST=$(rustc -vV | sed -n 's/^host: //p')
+
+cargo run --release --target $HOST -- $@
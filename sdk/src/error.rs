@@ -11,6 +11,7 @@ pub enum Error {
     Sealing,
     SealingKey,
     Serialize,
+    Signature,
 }
 
 impl From<TokenError> for Error {
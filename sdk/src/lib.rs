@@ -47,4 +47,45 @@ mod tests {
         let unsealed = unseal(&sealed).unwrap();
         assert_eq!(plaintext, &unsealed[..]);
     }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn unsealing_earlier_layout() {
+        use super::sealing::unseal;
+        // "Plaintext" sealed with the simulated key by the earlier SDKs,
+        // the tag and the IV followed by the ciphertext
+        let sealed = [
+            0x3b, 0x1b, 0xfe, 0x1b, 0xa3, 0xee, 0xbe, 0x6c, 0xed, 0xb5, 0xf9, 0x10, 0x2c, 0x41,
+            0xb0, 0x91, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c,
+            0x09, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x3f, 0xaf, 0xde, 0x8f, 0xf7, 0x3f,
+            0x61, 0xf3, 0xe4,
+        ];
+        assert_eq!(b"Plaintext", &unseal(&sealed).unwrap()[..]);
+    }
+
+    #[test]
+    fn sealing_svn() {
+        use super::sealing::{seal, seal_with_svn, unseal, unseal_with_svn};
+        let plaintext = b"Plaintext";
+        let sealed_v1 = seal_with_svn(plaintext, 1).unwrap();
+        let sealed_v2 = seal_with_svn(plaintext, 2).unwrap();
+        assert_eq!(plaintext, &unseal_with_svn(&sealed_v1).unwrap()[..]);
+        assert_eq!(plaintext, &unseal_with_svn(&sealed_v2).unwrap()[..]);
+
+        // The keys of different SVNs are not interchangeable.
+        // The SVN follows the tag and the IV of the header.
+        let mut tampered = sealed_v1.clone();
+        tampered[16 + 12] = 2;
+        assert!(unseal_with_svn(&tampered).is_err());
+
+        // Nor are they with the key of seal()
+        let sealed = seal(plaintext).unwrap();
+        assert!(unseal_with_svn(&sealed).is_err());
+        assert_eq!(plaintext, &unseal(&sealed).unwrap()[..]);
+
+        // unseal() takes either of them
+        assert_eq!(plaintext, &unseal(&sealed_v1).unwrap()[..]);
+        assert_eq!(plaintext, &unseal(&sealed_v2).unwrap()[..]);
+        assert!(unseal(&tampered).is_err());
+    }
 }
@@ -2,5 +2,5 @@ pub use crate::attester::attest;
 pub use crate::error::Error;
 pub use crate::parser::{parse, print_claims};
 pub use crate::report::Report;
-pub use crate::sealing::{seal, unseal};
+pub use crate::sealing::{seal, seal_with_svn, unseal, unseal_with_svn};
 pub use crate::verifier::verify;
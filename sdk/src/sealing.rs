@@ -2,6 +2,8 @@ use crate::error::Error;
 
 use openssl::rand::rand_bytes;
 use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+#[cfg(target_arch = "aarch64")]
+use rmm_spec::rsi;
 use serde::{Deserialize, Serialize};
 use zeroize::Zeroizing;
 
@@ -14,12 +16,22 @@ use zeroize::Zeroizing;
 //   the header and the ciphertext, the header contains the IV and the authentication TAG.
 //   The whole structure is serialized using serde and bincode crates to produce binary object, that then can
 //   be saved in a file on the host side.
+// - With seal_with_svn(), the key is bound to the realm metadata and the given Security Version Number (SVN)
+//   instead of the RIM, and the SVN is recorded next to the header. A realm updated to a higher SVN can still derive
+//   the keys of the lower ones, so unseal_with_svn() takes the recorded SVN to unseal the data of its previous versions.
+// - unseal() takes the data sealed by either of them. The authentication TAG tells which layout the data has,
+//   as the data doesn't decrypt with a wrongly parsed header or key.
 
 // We take VHUK_M (Measurement based Virtual Hardware Unique Key) and RIM
 // as a key material during the sealing key derivation process
 #[cfg(target_arch = "aarch64")]
-const UNIQUE_SEALING_KEY: u64 =
-    rust_rsi::RSI_SEALING_KEY_FLAGS_KEY | rust_rsi::RSI_SEALING_KEY_FLAGS_RIM;
+const UNIQUE_SEALING_KEY: u64 = (rsi::RSI_ISLET_USE_VHUK_M | rsi::RSI_ISLET_SLK_RIM) as u64;
+
+// We take VHUK_M, the realm id and the SVN of the realm metadata
+// as a key material, which stay the same over the updates of a realm image
+#[cfg(target_arch = "aarch64")]
+const SVN_SEALING_KEY: u64 =
+    (rsi::RSI_ISLET_USE_VHUK_M | rsi::RSI_ISLET_SLK_REALM_ID | rsi::RSI_ISLET_SLK_SVN) as u64;
 
 const AES_GCM_256_IV_LEN: usize = 12;
 const AES_GCM_256_TAG_LEN: usize = 16;
@@ -32,6 +44,8 @@ const SEALING_KEY: [u8; SEALING_KEY_LEN] = [
     0x4a, 0x93, 0x9d, 0x55, 0xb9, 0x89, 0x15, 0x44, 0x45, 0xa3, 0x86, 0x1e, 0x1f, 0xa1, 0xe2, 0xce,
 ];
 
+// The layout of the header and the sealed data should stay the same,
+// so that the data sealed by the earlier SDKs can be unsealed.
 #[derive(Serialize, Deserialize)]
 struct Header {
     tag: [u8; AES_GCM_256_TAG_LEN],
@@ -55,21 +69,89 @@ struct SealedData {
     ciphertext: Vec<u8>,
 }
 
-fn sealing_key() -> Result<[u8; SEALING_KEY_LEN], Error> {
+// The data sealed with seal_with_svn() has the SVN the key is bound to
+// next to the header.
+#[derive(Serialize, Deserialize)]
+struct SvnSealedData {
+    header: Header,
+    svn: u64,
+    ciphertext: Vec<u8>,
+}
+
+fn sealing_key(svn: Option<u64>) -> Result<[u8; SEALING_KEY_LEN], Error> {
     cfg_if::cfg_if! {
-        // Return the embedded sealing key for simulated platform
+        // Return the embedded sealing key for simulated platform,
+        // mixed with the SVN if requested
         if #[cfg(target_arch="x86_64")] {
-            Ok(SEALING_KEY)
+            match svn {
+                Some(svn) => {
+                    let mut material = SEALING_KEY.to_vec();
+                    material.extend_from_slice(&svn.to_le_bytes());
+                    Ok(openssl::sha::sha256(&material))
+                }
+                None => Ok(SEALING_KEY),
+            }
         } else {
-            rust_rsi::sealing_key(UNIQUE_SEALING_KEY, 0).or(Err(Error::SealingKey))
+            match svn {
+                Some(svn) => rust_rsi::sealing_key(SVN_SEALING_KEY, svn),
+                None => rust_rsi::sealing_key(UNIQUE_SEALING_KEY, 0),
+            }
+            .or(Err(Error::SealingKey))
         }
     }
 }
 
 pub fn seal(plaintext: &[u8]) -> Result<Vec<u8>, Error> {
     let mut header = Header::new()?;
+    let ciphertext = encrypt(plaintext, None, &mut header)?;
+    let sealed_data = SealedData {
+        header: header,
+        ciphertext: ciphertext,
+    };
+
+    bincode::serialize(&sealed_data).map_err(|_| Error::Sealing)
+}
+
+/// Unseals the data sealed by seal() or seal_with_svn().
+pub fn unseal(sealed: &[u8]) -> Result<Vec<u8>, Error> {
+    let unsealed = bincode::deserialize::<SealedData>(sealed)
+        .map_err(|_| Error::Sealing)
+        .and_then(|sealed_data| decrypt(&sealed_data.ciphertext, None, &sealed_data.header));
+    match unsealed {
+        // Fall back to the layout of seal_with_svn()
+        Err(Error::Sealing) => unseal_with_svn(sealed),
+        unsealed => unsealed,
+    }
+}
+
+/// Seals `plaintext` with the key bound to `svn`, which should not be
+/// higher than the current SVN of the realm.
+pub fn seal_with_svn(plaintext: &[u8], svn: u64) -> Result<Vec<u8>, Error> {
+    let mut header = Header::new()?;
+    let ciphertext = encrypt(plaintext, Some(svn), &mut header)?;
+    let sealed_data = SvnSealedData {
+        header: header,
+        svn: svn,
+        ciphertext: ciphertext,
+    };
+
+    bincode::serialize(&sealed_data).map_err(|_| Error::Sealing)
+}
+
+/// Unseals the data sealed by seal_with_svn(), with the key of the SVN
+/// recorded in it.
+pub fn unseal_with_svn(sealed: &[u8]) -> Result<Vec<u8>, Error> {
+    let sealed_data: SvnSealedData = bincode::deserialize(sealed).map_err(|_| Error::Sealing)?;
+    decrypt(
+        &sealed_data.ciphertext,
+        Some(sealed_data.svn),
+        &sealed_data.header,
+    )
+}
+
+fn encrypt(plaintext: &[u8], svn: Option<u64>, header: &mut Header) -> Result<Vec<u8>, Error> {
     let cipher = Cipher::aes_256_gcm();
-    let sealing_key = Zeroizing::new(sealing_key().map_err(|_| Error::SealingKey)?);
+    let sealing_key = Zeroizing::new(sealing_key(svn).map_err(|_| Error::SealingKey)?);
 
     let enc_res = encrypt_aead(
         cipher,
@@ -80,26 +162,20 @@ pub fn seal(plaintext: &[u8]) -> Result<Vec<u8>, Error> {
         &mut header.tag,
     );
 
-    let sealed_data = SealedData {
-        header: header,
-        ciphertext: enc_res.map_err(|_| Error::Sealing)?,
-    };
-
-    bincode::serialize(&sealed_data).map_err(|_| Error::Sealing)
+    enc_res.map_err(|_| Error::Sealing)
 }
 
-pub fn unseal(sealed: &[u8]) -> Result<Vec<u8>, Error> {
-    let sealed_data: SealedData = bincode::deserialize(sealed).map_err(|_| Error::Sealing)?;
+fn decrypt(ciphertext: &[u8], svn: Option<u64>, header: &Header) -> Result<Vec<u8>, Error> {
     let cipher = Cipher::aes_256_gcm();
-    let sealing_key = Zeroizing::new(sealing_key().map_err(|_| Error::SealingKey)?);
+    let sealing_key = Zeroizing::new(sealing_key(svn).map_err(|_| Error::SealingKey)?);
 
     let dec_res = decrypt_aead(
         cipher,
         sealing_key.as_ref(),
-        Some(&sealed_data.header.iv),
+        Some(&header.iv),
         &[],
-        &sealed_data.ciphertext,
-        &sealed_data.header.tag,
+        ciphertext,
+        &header.tag,
     );
 
     dec_res.map_err(|_| Error::Sealing)
@@ -1,9 +1,24 @@
+use crate::error::Error;
 use crate::report::Report;
 use crate::AttestationClaims;
 
-use rust_rsi::{verify_token, TokenError};
+use ciborium::Value;
+use coset::{iana, CborSerializable, CoseKey, CoseSign1, Label, TaggedCborSerializable};
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::{EcGroup, EcKey, EcPoint};
+use openssl::ecdsa::EcdsaSig;
+use openssl::hash::{hash, MessageDigest};
+use openssl::nid::Nid;
+use rust_rsi::verify_token;
 
-pub fn verify(report: &Report) -> Result<AttestationClaims, TokenError> {
+const CCA_TOKEN_COLLECTION: u64 = 399;
+const CCA_REALM_DELEGATED_TOKEN: u64 = 44241;
+const CCA_REALM_PUB_KEY: u64 = 44237;
+
+pub fn verify(report: &Report) -> Result<AttestationClaims, Error> {
+    // The realm signature is checked here for any RAK curve,
+    // ahead of rust_rsi which parses the claims out of the token.
+    verify_realm_signature(&extract_realm_token(&report.buffer)?)?;
     let claims = verify_token(&report.buffer, None)?;
 
     cfg_if::cfg_if! {
@@ -17,3 +32,225 @@ pub fn verify(report: &Report) -> Result<AttestationClaims, TokenError> {
         }
     }
 }
+
+// Finds the realm token in the CCA token collection of a report.
+fn extract_realm_token(report: &[u8]) -> Result<Vec<u8>, Error> {
+    let collection: Value = ciborium::de::from_reader(report).or(Err(Error::Decoding))?;
+    let Value::Tag(CCA_TOKEN_COLLECTION, collection) = collection else {
+        return Err(Error::Decoding);
+    };
+    collection
+        .as_map()
+        .and_then(|map| {
+            map.iter()
+                .find(|(label, _)| label.as_integer() == Some(CCA_REALM_DELEGATED_TOKEN.into()))
+        })
+        .and_then(|(_, token)| token.as_bytes())
+        .cloned()
+        .ok_or(Error::Claims)
+}
+
+/// Verifies the signature of a realm token with the RAK in its claims.
+/// The RAK can be on any of the P-256, P-384 and P-521 curves,
+/// which are told by the COSE algorithm of the token.
+pub fn verify_realm_signature(realm_token: &[u8]) -> Result<(), Error> {
+    let sign1 = CoseSign1::from_tagged_slice(realm_token).or(Err(Error::Decoding))?;
+
+    let (curve, digest) = match &sign1.protected.header.alg {
+        Some(coset::Algorithm::Assigned(iana::Algorithm::ES256)) => {
+            (Nid::X9_62_PRIME256V1, MessageDigest::sha256())
+        }
+        Some(coset::Algorithm::Assigned(iana::Algorithm::ES384)) => {
+            (Nid::SECP384R1, MessageDigest::sha384())
+        }
+        Some(coset::Algorithm::Assigned(iana::Algorithm::ES512)) => {
+            (Nid::SECP521R1, MessageDigest::sha512())
+        }
+        _ => return Err(Error::NotSupported),
+    };
+
+    let payload = sign1.payload.as_ref().ok_or(Error::Decoding)?;
+    let claims: Value = ciborium::de::from_reader(&payload[..]).or(Err(Error::Decoding))?;
+    let rak_pub = claims
+        .as_map()
+        .and_then(|map| {
+            map.iter()
+                .find(|(label, _)| label.as_integer() == Some(CCA_REALM_PUB_KEY.into()))
+        })
+        .and_then(|(_, value)| value.as_bytes())
+        .ok_or(Error::Claims)?;
+    let rak_pub = ec_public_key(rak_pub, curve)?;
+
+    sign1.verify_signature(b"", |signature, data| {
+        // The signature is `r || s` of the same length
+        let (r, s) = signature.split_at(signature.len() / 2);
+        let signature = EcdsaSig::from_private_components(
+            BigNum::from_slice(r).or(Err(Error::Decoding))?,
+            BigNum::from_slice(s).or(Err(Error::Decoding))?,
+        )
+        .or(Err(Error::Decoding))?;
+        let digest = hash(digest, data).or(Err(Error::Decoding))?;
+        match signature.verify(&digest, &rak_pub) {
+            Ok(true) => Ok(()),
+            _ => Err(Error::Signature),
+        }
+    })
+}
+
+// Loads the EC2 public `key`, which should be on the `curve`.
+// The key is COSE encoded, or SEC1 encoded as in the tokens of the older RMMs.
+fn ec_public_key(key: &[u8], curve: Nid) -> Result<EcKey<openssl::pkey::Public>, Error> {
+    let group = EcGroup::from_curve_name(curve).or(Err(Error::Decoding))?;
+    let mut ctx = BigNumContext::new().or(Err(Error::Decoding))?;
+
+    let sec1 = match CoseKey::from_slice(key) {
+        Ok(key) => cose_key_to_sec1(&key, curve)?,
+        Err(_) => key.to_vec(),
+    };
+    let point = EcPoint::from_bytes(&group, &sec1, &mut ctx).or(Err(Error::InvalidArgument))?;
+    EcKey::from_public_key(&group, &point).or(Err(Error::Decoding))
+}
+
+fn cose_key_to_sec1(key: &CoseKey, curve: Nid) -> Result<Vec<u8>, Error> {
+    let param = |label: iana::Ec2KeyParameter| {
+        key.params
+            .iter()
+            .find(|(l, _)| *l == Label::Int(label as i64))
+            .map(|(_, value)| value)
+            .ok_or(Error::Claims)
+    };
+
+    let crv = match curve {
+        Nid::X9_62_PRIME256V1 => iana::EllipticCurve::P_256,
+        Nid::SECP384R1 => iana::EllipticCurve::P_384,
+        _ => iana::EllipticCurve::P_521,
+    };
+    if param(iana::Ec2KeyParameter::Crv)?.as_integer() != Some((crv as i64).into()) {
+        return Err(Error::InvalidArgument);
+    }
+    let x = param(iana::Ec2KeyParameter::X)?
+        .as_bytes()
+        .ok_or(Error::Claims)?;
+    let y = param(iana::Ec2KeyParameter::Y)?
+        .as_bytes()
+        .ok_or(Error::Claims)?;
+
+    let mut sec1 = vec![0x04];
+    sec1.extend_from_slice(x);
+    sec1.extend_from_slice(y);
+    Ok(sec1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coset::{CoseKeyBuilder, CoseSign1Builder, HeaderBuilder};
+    use openssl::pkey::Private;
+
+    fn realm_token(curve: Nid, alg: iana::Algorithm, digest: MessageDigest) -> Vec<u8> {
+        let group = EcGroup::from_curve_name(curve).unwrap();
+        let rak: EcKey<Private> = EcKey::generate(&group).unwrap();
+
+        let mut ctx = BigNumContext::new().unwrap();
+        let (mut x, mut y) = (BigNum::new().unwrap(), BigNum::new().unwrap());
+        rak.public_key()
+            .affine_coordinates(&group, &mut x, &mut y, &mut ctx)
+            .unwrap();
+        let size = (group.degree() as i32 + 7) / 8;
+        let crv = match curve {
+            Nid::X9_62_PRIME256V1 => iana::EllipticCurve::P_256,
+            Nid::SECP384R1 => iana::EllipticCurve::P_384,
+            _ => iana::EllipticCurve::P_521,
+        };
+        let rak_pub = CoseKeyBuilder::new_ec2_pub_key(
+            crv,
+            x.to_vec_padded(size).unwrap(),
+            y.to_vec_padded(size).unwrap(),
+        )
+        .build()
+        .to_vec()
+        .unwrap();
+
+        let claims = Value::Map(vec![
+            (Value::Integer(10.into()), Value::Bytes(vec![0xAB; 64])),
+            (
+                Value::Integer(CCA_REALM_PUB_KEY.into()),
+                Value::Bytes(rak_pub),
+            ),
+        ]);
+        let mut payload = Vec::new();
+        ciborium::ser::into_writer(&claims, &mut payload).unwrap();
+
+        CoseSign1Builder::new()
+            .protected(HeaderBuilder::new().algorithm(alg).build())
+            .payload(payload)
+            .create_signature(b"", |data| {
+                let digest = hash(digest, data).unwrap();
+                let signature = EcdsaSig::sign(&digest, &rak).unwrap();
+                let mut raw = signature.r().to_vec_padded(size).unwrap();
+                raw.extend(signature.s().to_vec_padded(size).unwrap());
+                raw
+            })
+            .build()
+            .to_tagged_vec()
+            .unwrap()
+    }
+
+    #[test]
+    fn verify_realm_signature_curves() {
+        for (curve, alg, digest) in [
+            (
+                Nid::X9_62_PRIME256V1,
+                iana::Algorithm::ES256,
+                MessageDigest::sha256(),
+            ),
+            (
+                Nid::SECP384R1,
+                iana::Algorithm::ES384,
+                MessageDigest::sha384(),
+            ),
+            (
+                Nid::SECP521R1,
+                iana::Algorithm::ES512,
+                MessageDigest::sha512(),
+            ),
+        ] {
+            let token = realm_token(curve, alg, digest);
+            assert!(verify_realm_signature(&token).is_ok());
+
+            // The signature breaks with any change of the token
+            let mut tampered = token.clone();
+            let last = tampered.len() - 1;
+            tampered[last] ^= 0x1;
+            assert!(verify_realm_signature(&tampered).is_err());
+        }
+
+        // The algorithm should match the curve of the RAK
+        let token = realm_token(
+            Nid::X9_62_PRIME256V1,
+            iana::Algorithm::ES384,
+            MessageDigest::sha384(),
+        );
+        assert!(verify_realm_signature(&token).is_err());
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn verify_realm_signature_mock() {
+        let realm_token = extract_realm_token(&crate::mock::REPORT).unwrap();
+        assert!(verify_realm_signature(&realm_token).is_ok());
+
+        // verify() refuses a report whose realm signature is broken
+        let mut report = crate::mock::REPORT.to_vec();
+        let offset = report
+            .windows(realm_token.len())
+            .position(|window| window == &realm_token[..])
+            .unwrap();
+        report[offset + realm_token.len() - 1] ^= 0x1;
+        let report = Report {
+            buffer: report,
+            user_data: Vec::new(),
+        };
+        assert!(matches!(verify(&report), Err(Error::Signature)));
+    }
+}